@@ -336,6 +336,7 @@ impl OakAbiTestService for FrontendNode {
             "NodePrivilegeReadRaw",
             (Self::test_node_privilege_read_raw, Count::Unchanged),
         );
+        tests.insert("NodeCount", (Self::test_node_count, Count::Unchanged));
         tests.insert("NodePanic", (Self::test_node_panic, Count::Unchanged));
         tests.insert(
             "RandomGetRaw",
@@ -1941,6 +1942,19 @@ impl FrontendNode {
         Ok(())
     }
 
+    fn test_node_count(&mut self) -> TestResult {
+        // At least this Node is live.
+        let wasm_count = oak::node_count(Some("wasm"));
+        expect_matches!(wasm_count, Ok(_));
+        let wasm_count = wasm_count.unwrap();
+        expect!(wasm_count >= 1);
+        let total_count = oak::node_count(None);
+        expect_matches!(total_count, Ok(_));
+        expect!(total_count.unwrap() >= wasm_count);
+        expect_eq!(Ok(0), oak::node_count(Some("no-such-node-type")));
+        Ok(())
+    }
+
     fn test_node_privilege_read(&mut self) -> TestResult {
         let privilege = oak::node_privilege_read();
         expect_matches!(privilege, Ok(_));
@@ -78,6 +78,7 @@ async fn test_set_intersection() {
             values: hashmap! {
                 hex::encode(&signature.hash) => vec![signature.clone()]
             },
+            ..Default::default()
         },
     );
     let runtime =
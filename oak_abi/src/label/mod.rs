@@ -39,6 +39,26 @@ impl crate::proto::oak::label::Label {
         bytes
     }
 
+    /// Convert a label to a canonical byte representation.
+    ///
+    /// Unlike [`Label::serialize`], the result does not depend on the order in which tags were
+    /// added to the label, nor on duplicate tags: both tag sets are deduplicated and sorted by
+    /// their serialized form before encoding. Two labels that contain the same tags therefore
+    /// always produce identical bytes, which makes the result suitable for hashing and comparing
+    /// labels.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        self.canonical().serialize()
+    }
+
+    /// Returns a copy of the label in canonical form, i.e. with both tag sets deduplicated and
+    /// sorted as for [`Label::canonical_bytes`].
+    pub fn canonical(&self) -> Self {
+        Label {
+            confidentiality_tags: canonical_tags(&self.confidentiality_tags),
+            integrity_tags: canonical_tags(&self.integrity_tags),
+        }
+    }
+
     /// Build the label from bytes.
     pub fn deserialize(bytes: &[u8]) -> Option<Self> {
         Self::decode(bytes).ok()
@@ -58,25 +78,135 @@ impl crate::proto::oak::label::Label {
     pub fn flows_to(&self, other: &Self) -> bool {
         #![allow(clippy::mutable_key_type)]
 
-        let self_confidentiality_tags: HashSet<_> = self.confidentiality_tags.iter().collect();
         let other_confidentiality_tags: HashSet<_> = other.confidentiality_tags.iter().collect();
         let self_integrity_tags: HashSet<_> = self.integrity_tags.iter().collect();
-        let other_integrity_tags: HashSet<_> = other.integrity_tags.iter().collect();
 
         // The target label must have (compared to the self label):
         // - same or more confidentiality tags
         // - same or fewer integrity tags
-        // A special case is the `top` tag that may represent the set of all confidentiality tags,
-        // or the set of all integrity tags. Therefore, to be more accurate:
-        // - the target label must be `top`, or have same or more confidentiality tags than the self
-        //   label, and
-        // - the self label must be `top`, or the target label must have same or fewer integrity
-        //   tags than the self label
-        (other_confidentiality_tags.contains(&top())
-            || self_confidentiality_tags.is_subset(&other_confidentiality_tags))
-            && (self_integrity_tags.contains(&top())
-                || other_integrity_tags.is_subset(&self_integrity_tags))
+        // A special case is the `top` tag of a sub-lattice, that may represent the set of all the
+        // confidentiality tags, or the set of all the integrity tags, of that sub-lattice (or of
+        // all the sub-lattices for the plain `top` tag). Therefore, to be more accurate:
+        // - each confidentiality tag of the self label must be covered by the target label, and
+        // - each integrity tag of the target label must be covered by the self label
+        // where a tag is covered by a set of tags if it is in the set, or if the set contains a
+        // `top` tag for its sub-lattice.
+        self.confidentiality_tags
+            .iter()
+            .all(|tag| covers(&other_confidentiality_tags, tag))
+            && other
+                .integrity_tags
+                .iter()
+                .all(|tag| covers(&self_integrity_tags, tag))
+    }
+
+    /// Returns the least upper bound of two labels: the least restrictive label that both labels
+    /// flow to.
+    ///
+    /// Its confidentiality tags are the union of the confidentiality tags of both labels, and its
+    /// integrity tags are their intersection.
+    pub fn join(&self, other: &Self) -> Self {
+        Label {
+            confidentiality_tags: union_tags(
+                &self.confidentiality_tags,
+                &other.confidentiality_tags,
+            ),
+            integrity_tags: intersection_tags(&self.integrity_tags, &other.integrity_tags),
+        }
+    }
+
+    /// Returns the greatest lower bound of two labels: the most restrictive label that flows to
+    /// both labels.
+    ///
+    /// Its confidentiality tags are the intersection of the confidentiality tags of both labels,
+    /// and its integrity tags are their union.
+    pub fn meet(&self, other: &Self) -> Self {
+        Label {
+            confidentiality_tags: intersection_tags(
+                &self.confidentiality_tags,
+                &other.confidentiality_tags,
+            ),
+            integrity_tags: union_tags(&self.integrity_tags, &other.integrity_tags),
+        }
+    }
+}
+
+/// Returns the canonical union of two sets of tags.
+fn union_tags(a: &[Tag], b: &[Tag]) -> Vec<Tag> {
+    canonical_tags(&[a, b].concat())
+}
+
+/// Returns the canonical intersection of two sets of tags, where a `top` tag stands for the set of
+/// all the tags of its sub-lattice: each tag of either set is kept if it is covered by the other
+/// set.
+fn intersection_tags(a: &[Tag], b: &[Tag]) -> Vec<Tag> {
+    #![allow(clippy::mutable_key_type)]
+
+    let a_tags: HashSet<_> = a.iter().collect();
+    let b_tags: HashSet<_> = b.iter().collect();
+    let tags: Vec<Tag> = a
+        .iter()
+        .filter(|tag| covers(&b_tags, tag))
+        .chain(b.iter().filter(|tag| covers(&a_tags, tag)))
+        .cloned()
+        .collect();
+    canonical_tags(&tags)
+}
+
+/// Returns whether `tag` is in `tags`, or belongs to a sub-lattice that `tags` contains the `top`
+/// tag of.
+#[allow(clippy::mutable_key_type)]
+fn covers(tags: &HashSet<&Tag>, tag: &Tag) -> bool {
+    tags.contains(tag) || tags.iter().any(|top_tag| top_tag.is_top_of(tag))
+}
+
+impl Tag {
+    /// Returns the namespace of the sub-lattice that the tag belongs to, or `None` if the tag is
+    /// empty.
+    ///
+    /// The namespace of a `top` tag is that of the sub-lattice it is the top element of, i.e.
+    /// [`TagNamespace::All`] for the plain [`top`] tag.
+    pub fn namespace(&self) -> Option<TagNamespace> {
+        match self.tag.as_ref()? {
+            tag::Tag::WebAssemblyModuleTag(_) => Some(TagNamespace::WebAssemblyModule),
+            tag::Tag::WebAssemblyModuleSignatureTag(_) => {
+                Some(TagNamespace::WebAssemblyModuleSignature)
+            }
+            tag::Tag::TlsEndpointTag(_) => Some(TagNamespace::TlsEndpoint),
+            tag::Tag::PublicKeyIdentityTag(_) => Some(TagNamespace::PublicKeyIdentity),
+            tag::Tag::TlsClientCertificateTag(_) => Some(TagNamespace::TlsClientCertificate),
+            tag::Tag::TopTag(top) => Some(top.namespace()),
+        }
     }
+
+    /// Returns whether the tag is the `top` tag of a sub-lattice that `other` belongs to, i.e.
+    /// whether the tag stands for a set of tags that includes `other`.
+    pub fn is_top_of(&self, other: &Tag) -> bool {
+        match &self.tag {
+            Some(tag::Tag::TopTag(top)) => {
+                top.namespace() == TagNamespace::All || other.namespace() == Some(top.namespace())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns a sorted and deduplicated copy of `tags`, ordered by their serialized form.
+fn canonical_tags(tags: &[Tag]) -> Vec<Tag> {
+    let mut encoded_tags: Vec<(Vec<u8>, &Tag)> = tags
+        .iter()
+        .map(|tag| {
+            let mut bytes = Vec::new();
+            tag.encode(&mut bytes).expect("could not serialize tag");
+            (bytes, tag)
+        })
+        .collect();
+    encoded_tags.sort_by(|(a, _), (b, _)| a.cmp(b));
+    encoded_tags.dedup_by(|(a, _), (b, _)| a == b);
+    encoded_tags
+        .into_iter()
+        .map(|(_, tag)| tag.clone())
+        .collect()
 }
 
 /// Convenience function for creating labels with a single confidentiality tag.
@@ -134,8 +264,32 @@ pub fn public_key_identity_tag(public_key: &[u8]) -> Tag {
     }
 }
 
+/// Creates a [`Tag`] having as principal the client identified by the provided TLS client
+/// certificate `subject` and `subject_alternative_names`.
+///
+/// See https://github.com/project-oak/oak/blob/main/oak_abi/proto/label.proto
+pub fn tls_client_certificate_tag(subject: &str, subject_alternative_names: &[String]) -> Tag {
+    Tag {
+        tag: Some(tag::Tag::TlsClientCertificateTag(TlsClientCertificateTag {
+            subject: subject.to_string(),
+            subject_alternative_names: subject_alternative_names.to_vec(),
+        })),
+    }
+}
+
 /// Convenience function for creating the top tag.
 pub fn top() -> Tag {
-    let tag = Some(tag::Tag::TopTag(Top {}));
+    namespace_top(TagNamespace::All)
+}
+
+/// Creates the top tag of the sub-lattice of tags in the provided `namespace`.
+///
+/// As a confidentiality tag or a privilege, it only covers the tags in `namespace`, so that e.g. a
+/// Node may be allowed to declassify any user identity without being allowed to declassify data
+/// from other sub-lattices.
+pub fn namespace_top(namespace: TagNamespace) -> Tag {
+    let tag = Some(tag::Tag::TopTag(Top {
+        namespace: namespace as i32,
+    }));
     Tag { tag }
 }
@@ -15,6 +15,7 @@
 //
 
 use super::*;
+use quickcheck::{quickcheck, Arbitrary, Gen};
 
 #[test]
 fn serialize_deserialize() {
@@ -55,6 +56,48 @@ fn serialize_deserialize() {
     }
 }
 
+#[test]
+fn canonical_bytes_ignore_tag_order() {
+    let tag_0 = public_key_identity_tag(&[0, 0, 0]);
+    let tag_1 = public_key_identity_tag(&[1, 1, 1]);
+    let tag_2 = tls_endpoint_tag("localhost");
+
+    let label_0_1 = Label {
+        confidentiality_tags: vec![tag_0.clone(), tag_1.clone()],
+        integrity_tags: vec![tag_2.clone(), tag_0.clone()],
+    };
+    let label_1_0 = Label {
+        confidentiality_tags: vec![tag_1.clone(), tag_0.clone()],
+        integrity_tags: vec![tag_0.clone(), tag_2.clone()],
+    };
+    // The plain serialization depends on the order of the tags.
+    assert_ne!(label_0_1.serialize(), label_1_0.serialize());
+    // The canonical serialization does not.
+    assert_eq!(label_0_1.canonical_bytes(), label_1_0.canonical_bytes());
+
+    // Duplicate tags do not affect the canonical serialization either.
+    let label_with_duplicates = Label {
+        confidentiality_tags: vec![tag_1.clone(), tag_0.clone(), tag_1.clone()],
+        integrity_tags: vec![tag_2.clone(), tag_0.clone(), tag_2],
+    };
+    assert_eq!(
+        label_0_1.canonical_bytes(),
+        label_with_duplicates.canonical_bytes()
+    );
+
+    // Labels with different tags have different canonical serializations.
+    let label_0 = Label {
+        confidentiality_tags: vec![tag_0],
+        integrity_tags: vec![],
+    };
+    assert_ne!(label_0.canonical_bytes(), label_0_1.canonical_bytes());
+
+    // The canonical serialization is still a valid serialized label.
+    let deserialized = Label::deserialize(&label_1_0.canonical_bytes()).unwrap();
+    assert!(deserialized.flows_to(&label_1_0));
+    assert!(label_1_0.flows_to(&deserialized));
+}
+
 #[test]
 fn label_flow() {
     let tag_0 = public_key_identity_tag(&[0, 0, 0]);
@@ -137,3 +180,157 @@ fn label_flow() {
     assert!(!label_0_1.flows_to(&label_0));
     assert!(!label_0_1.flows_to(&label_1));
 }
+
+#[test]
+fn label_flow_namespace_top() {
+    let identity_tag = public_key_identity_tag(&[0, 0, 0]);
+    let endpoint_tag = tls_endpoint_tag("localhost");
+    let identity_top = namespace_top(TagNamespace::PublicKeyIdentity);
+
+    let identity_label = confidentiality_label(identity_tag.clone());
+    let endpoint_label = confidentiality_label(endpoint_tag.clone());
+    let identity_top_label = confidentiality_label(identity_top.clone());
+
+    // The top of a sub-lattice is more confidential than any tag in that sub-lattice, but not
+    // than tags in other sub-lattices, nor than the top of all the sub-lattices.
+    assert!(identity_label.flows_to(&identity_top_label));
+    assert!(!identity_top_label.flows_to(&identity_label));
+    assert!(!endpoint_label.flows_to(&identity_top_label));
+    assert!(identity_top_label.flows_to(&confidentiality_label(top())));
+    assert!(!confidentiality_label(top()).flows_to(&identity_top_label));
+
+    // Likewise for integrity, where the top of a sub-lattice stands for all the integrity tags of
+    // that sub-lattice.
+    let trusted_identities = Label {
+        confidentiality_tags: vec![],
+        integrity_tags: vec![identity_top],
+    };
+    let trusted_by_identity = Label {
+        confidentiality_tags: vec![],
+        integrity_tags: vec![identity_tag],
+    };
+    let trusted_by_endpoint = Label {
+        confidentiality_tags: vec![],
+        integrity_tags: vec![endpoint_tag],
+    };
+    assert!(trusted_identities.flows_to(&trusted_by_identity));
+    assert!(!trusted_identities.flows_to(&trusted_by_endpoint));
+}
+
+#[test]
+fn label_join_meet() {
+    let tag_0 = public_key_identity_tag(&[0, 0, 0]);
+    let tag_1 = public_key_identity_tag(&[1, 1, 1]);
+
+    let label_0 = Label {
+        confidentiality_tags: vec![tag_0.clone()],
+        integrity_tags: vec![tag_0.clone(), tag_1.clone()],
+    };
+    let label_1 = Label {
+        confidentiality_tags: vec![tag_1.clone()],
+        integrity_tags: vec![tag_1.clone()],
+    };
+
+    let join = label_0.join(&label_1);
+    assert_eq!(
+        join.canonical_bytes(),
+        Label {
+            confidentiality_tags: vec![tag_0.clone(), tag_1.clone()],
+            integrity_tags: vec![tag_1.clone()],
+        }
+        .canonical_bytes()
+    );
+
+    let meet = label_0.meet(&label_1);
+    assert_eq!(
+        meet.canonical_bytes(),
+        Label {
+            confidentiality_tags: vec![],
+            integrity_tags: vec![tag_0, tag_1],
+        }
+        .canonical_bytes()
+    );
+
+    // The `top` integrity tag stands for all the integrity tags, so it does not restrict the
+    // integrity of the join.
+    let trusted = Label {
+        confidentiality_tags: vec![],
+        integrity_tags: vec![top()],
+    };
+    assert_eq!(
+        label_1.join(&trusted).canonical_bytes(),
+        label_1.canonical_bytes()
+    );
+}
+
+/// Generates labels from a small set of tags, including `top`, so that generated labels often
+/// share tags.
+impl Arbitrary for Label {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let tags = [
+            top(),
+            public_key_identity_tag(&[0, 0, 0]),
+            public_key_identity_tag(&[1, 1, 1]),
+            tls_endpoint_tag("localhost"),
+        ];
+        let arbitrary_tags = |g: &mut Gen| {
+            (0..usize::arbitrary(g) % 4)
+                .map(|_| g.choose(&tags).unwrap().clone())
+                .collect()
+        };
+        Label {
+            confidentiality_tags: arbitrary_tags(g),
+            integrity_tags: arbitrary_tags(g),
+        }
+    }
+}
+
+#[test]
+fn label_join_is_commutative() {
+    fn property(a: Label, b: Label) -> bool {
+        a.join(&b).canonical_bytes() == b.join(&a).canonical_bytes()
+    }
+    quickcheck(property as fn(Label, Label) -> bool);
+}
+
+#[test]
+fn label_join_is_associative() {
+    fn property(a: Label, b: Label, c: Label) -> bool {
+        a.join(&b).join(&c).canonical_bytes() == a.join(&b.join(&c)).canonical_bytes()
+    }
+    quickcheck(property as fn(Label, Label, Label) -> bool);
+}
+
+#[test]
+fn labels_flow_to_their_join() {
+    fn property(a: Label, b: Label) -> bool {
+        let join = a.join(&b);
+        a.flows_to(&join) && b.flows_to(&join)
+    }
+    quickcheck(property as fn(Label, Label) -> bool);
+}
+
+#[test]
+fn label_meet_is_commutative() {
+    fn property(a: Label, b: Label) -> bool {
+        a.meet(&b).canonical_bytes() == b.meet(&a).canonical_bytes()
+    }
+    quickcheck(property as fn(Label, Label) -> bool);
+}
+
+#[test]
+fn label_meet_is_associative() {
+    fn property(a: Label, b: Label, c: Label) -> bool {
+        a.meet(&b).meet(&c).canonical_bytes() == a.meet(&b.meet(&c)).canonical_bytes()
+    }
+    quickcheck(property as fn(Label, Label, Label) -> bool);
+}
+
+#[test]
+fn meet_flows_to_labels() {
+    fn property(a: Label, b: Label) -> bool {
+        let meet = a.meet(&b);
+        meet.flows_to(&a) && meet.flows_to(&b)
+    }
+    quickcheck(property as fn(Label, Label) -> bool);
+}
@@ -273,6 +273,18 @@ extern "C" {
     pub fn node_privilege_read(label_buf: *mut u8, label_size: usize, actual_size: *mut u32)
         -> u32;
 
+    /// Returns the number of Nodes currently live in the Runtime.
+    ///
+    /// If `type_len` is non-zero, only Nodes whose type matches the UTF-8 string in the memory
+    /// area given by `type_buf` and `type_len` (e.g. `"wasm"`) are counted.
+    ///
+    /// The count is stored into the 8-byte location given by `count`, as a little-endian `u64`.
+    ///
+    /// Returns the status of the operation, as an [`OakStatus`] value.
+    ///
+    /// [`OakStatus`]: crate::OakStatus
+    pub fn node_count(type_buf: *const u8, type_len: usize, count: *mut u64) -> u32;
+
     /// Creates a new Node instance running code identified by a serialized [`NodeConfiguration`].
     ///
     /// The name of the new Node is provided in the memory area given by `name_buf` and `name_len`.
@@ -27,18 +27,19 @@ use openssl::{
     x509::{X509NameBuilder, X509ReqBuilder},
 };
 use tonic::{
-    transport::{Certificate, Channel, ClientTlsConfig, Identity},
+    transport::{Certificate, Channel, ClientTlsConfig},
     Request,
 };
 
 const RSA_KEY_SIZE: u32 = 2048;
 
 /// Creates an X.509 certificate signing request and sends it to the Proxy Attestation Service.
-/// After receiving back a signed certificate, creates TLS [`Identity`].
+/// After receiving back a signed certificate, returns it along with the private key, both PEM
+/// encoded.
 pub async fn get_tls_identity_from_proxy(
     uri: &Uri,
     root_tls_certificate: &[u8],
-) -> anyhow::Result<Identity> {
+) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
     // Create certificate signing request and a corresponding private/public key pair.
     let rsa_key_pair = Rsa::generate(RSA_KEY_SIZE)?;
     let private_key = rsa_key_pair.private_key_to_pem()?.to_vec();
@@ -63,7 +64,7 @@ pub async fn get_tls_identity_from_proxy(
     let certificate = response.get_ref().certificate.to_vec();
     info!("Received signed certificate from Proxy Attestation Service");
 
-    Ok(Identity::from_pem(&certificate, &private_key))
+    Ok((certificate, private_key))
 }
 
 /// Creates an X.509 certificate signing request.
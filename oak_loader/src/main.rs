@@ -30,7 +30,7 @@
 use anyhow::Context;
 #[cfg(feature = "oak-attestation")]
 pub mod attestation;
-use log::info;
+use log::{info, warn};
 mod options;
 use oak_runtime::config::configure_and_run;
 use options::create_runtime_config;
@@ -74,8 +74,12 @@ async fn main() -> anyhow::Result<()> {
     }
 
     info!("stop Runtime");
-    runtime.stop();
+    let report = runtime.stop_with_report();
+    let panicked_nodes = report.panicked_nodes();
+    if !panicked_nodes.is_empty() {
+        warn!("Nodes panicked while stopping: {:?}", panicked_nodes);
+    }
 
-    info!("Runtime stopped");
+    info!("Runtime stopped: {:?}", report.status);
     Ok(())
 }
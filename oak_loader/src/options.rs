@@ -24,15 +24,16 @@ use core::str::FromStr;
 use log::debug;
 use oak_abi::proto::oak::application::{ApplicationConfiguration, ConfigMap};
 use oak_runtime::{
-    auth::oidc_utils::{parse_client_info_json, ClientInfo},
+    auth::oidc_utils::{parse_client_info_json, ClientInfo, TokenValidationConfiguration},
     permissions::PermissionsConfiguration,
-    tls::Certificate,
-    SignatureTable,
+    tls::{Certificate, TlsConfig, TlsPolicy, TlsVersion},
+    HeartbeatConfiguration, MetricsPushConfiguration, NodeCreationRateLimit, SignaturePolicy,
+    SignatureTable, StartupOrder, StuckNodeAction, WatchdogConfiguration,
 };
 use oak_sign::SignatureBundle;
 use prost::Message;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{read, read_to_string},
 };
 use structopt::StructOpt;
@@ -50,6 +51,25 @@ pub struct Opt {
         structopt(long, help = "Permissions configuration file.")
     )]
     permissions: Option<String>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_module_hash),
+        help = "Hex encoded SHA-256 hashes of revoked Wasm modules, which cannot be run even if \
+        they have valid signatures."
+    )]
+    revoked_module_hashes: Vec<String>,
+    #[structopt(
+        long,
+        help = "Files containing the PEM encoded public keys of the signers trusted to sign Wasm \
+        modules, used with --module-signature-threshold."
+    )]
+    trusted_signer_public_keys: Vec<String>,
+    #[structopt(
+        long,
+        help = "Minimum number of distinct trusted signers that must have signed a Wasm module \
+        for it to be run. Wasm modules can be run without any signature if not provided."
+    )]
+    module_signature_threshold: Option<usize>,
     #[structopt(long, help = "Private RSA key file used by gRPC server pseudo-Nodes.")]
     grpc_tls_private_key: Option<String>,
     #[structopt(
@@ -87,6 +107,52 @@ pub struct Opt {
         OpenID Connect authentication will not be available if this parameter is not specified."
     )]
     oidc_client: Option<String>,
+    #[structopt(
+        long,
+        help = "Interval in seconds after which the cached keys of the OpenID Connect provider, \
+        used for validating identity tokens, are downloaded again. Defaults to one hour if not \
+        provided."
+    )]
+    oidc_jwks_refresh_interval_seconds: Option<u64>,
+    #[structopt(
+        long,
+        help = "Clock skew in seconds allowed when checking the expiration time of OpenID Connect \
+        identity tokens. Defaults to one minute if not provided."
+    )]
+    oidc_clock_skew_seconds: Option<u64>,
+    #[structopt(
+        long,
+        help = "Files containing encoded FileDescriptorSets (e.g. as produced by `protoc \
+        --descriptor_set_out`) describing the gRPC services of the application. gRPC server \
+        pseudo-Nodes only serve the gRPC server reflection service if this parameter is specified."
+    )]
+    grpc_reflection_descriptor_sets: Vec<String>,
+    #[structopt(
+        long,
+        help = "PEM encoded X.509 TLS root certificate file used by gRPC server pseudo-Nodes to \
+        verify the certificates presented by clients. Client certificates are not requested if \
+        this parameter is not specified."
+    )]
+    grpc_client_ca_certificate: Option<String>,
+    #[structopt(
+        long,
+        help = "Reject gRPC clients that do not present a certificate verified by \
+        `grpc-client-ca-certificate` (i.e. require mutual TLS)."
+    )]
+    require_grpc_client_certificate: bool,
+    #[structopt(
+        long,
+        help = "Minimum TLS version (1.2 or 1.3) accepted by gRPC and HTTP server pseudo-Nodes. \
+        TLS 1.2 and later are accepted if not specified."
+    )]
+    tls_min_version: Option<TlsVersion>,
+    #[structopt(
+        long,
+        help = "Names of the TLS cipher suites accepted by gRPC and HTTP server pseudo-Nodes, in \
+        order of preference (e.g. TLS13_AES_256_GCM_SHA384). All the supported cipher suites are \
+        accepted if not specified."
+    )]
+    tls_cipher_suites: Vec<String>,
     #[structopt(long, help = "Private RSA key file used by HTTP server pseudo-Nodes.")]
     http_tls_private_key: Option<String>,
     #[structopt(
@@ -96,16 +162,73 @@ pub struct Opt {
     http_tls_certificate: Option<String>,
     #[structopt(long, default_value = "9090", help = "Metrics server port number.")]
     metrics_port: u16,
+    #[structopt(
+        long,
+        default_value = "::",
+        help = "IP address of the interface to bind the metrics server to, e.g. 127.0.0.1. \
+        Binds to all interfaces by default."
+    )]
+    metrics_host: std::net::IpAddr,
     #[structopt(long, help = "Starts the Runtime without a metrics server.")]
     no_metrics: bool,
+    #[structopt(
+        long,
+        help = "UDP address of a StatsD server to periodically push metrics to, e.g. \
+        127.0.0.1:8125. Metrics are not pushed if not provided."
+    )]
+    metrics_push_address: Option<std::net::SocketAddr>,
+    #[structopt(
+        long,
+        default_value = "10000",
+        help = "Interval in milliseconds between two pushes of the metrics to the StatsD server."
+    )]
+    metrics_push_interval_ms: u64,
+    #[structopt(
+        long,
+        default_value = "",
+        help = "Prefix prepended to the names of the metrics pushed to the StatsD server."
+    )]
+    metrics_push_prefix: String,
     #[structopt(
         long,
         default_value = "1909",
         help = "Introspection server port number."
     )]
     introspect_port: u16,
+    #[structopt(
+        long,
+        default_value = "::",
+        help = "IP address of the interface to bind the introspection server to, e.g. \
+        127.0.0.1. Binds to all interfaces by default."
+    )]
+    introspect_host: std::net::IpAddr,
     #[structopt(long, help = "Starts the Runtime without an introspection server.")]
     no_introspect: bool,
+    #[structopt(
+        long,
+        help = "Shows labels as canonical JSON rather than in their debug format in the \
+        introspection server."
+    )]
+    introspect_json_labels: bool,
+    #[structopt(
+        long,
+        help = "File containing a token that requests to the introspection server must carry \
+        in their Authorization header, either as a bearer token or as a basic authentication \
+        password. Requests are not authenticated if not provided."
+    )]
+    introspect_auth_token_file: Option<String>,
+    #[structopt(
+        long,
+        help = "Private RSA key file used by the metrics and introspection servers, which serve \
+        HTTPS rather than HTTP if provided, along with a certificate."
+    )]
+    aux_server_tls_private_key: Option<String>,
+    #[structopt(
+        long,
+        help = "PEM encoded X.509 TLS certificate file used by the metrics and introspection \
+        servers."
+    )]
+    aux_server_tls_certificate: Option<String>,
     #[structopt(long, help = "Filename for KMS credentials.")]
     kms_credentials: Option<String>,
     #[structopt(
@@ -113,6 +236,141 @@ pub struct Opt {
         help = "Configuration files to expose to the Oak Application, each in key=filename format."
     )]
     config_files: Vec<ConfigEntry>,
+    #[structopt(
+        long,
+        help = "Validates that all Node configurations reference existing Wasm modules before \
+        starting the Runtime."
+    )]
+    strict_config_validation: bool,
+    #[structopt(
+        long,
+        help = "Maximum interval in milliseconds between heartbeats from a Node, after which it is \
+        considered stuck. Stuck Node detection is disabled if not provided."
+    )]
+    heartbeat_interval_ms: Option<u64>,
+    #[structopt(
+        long,
+        help = "Asks Nodes considered stuck to terminate, instead of only reporting them."
+    )]
+    terminate_stuck_nodes: bool,
+    #[structopt(
+        long,
+        help = "Time in milliseconds a Node may spend waiting on channels before it is reported \
+        as potentially stuck (e.g. deadlocked). Such Nodes are only reported, in the logs and in \
+        the `runtime_stuck_waiting_nodes` gauge, and are not interrupted. Reporting is disabled if \
+        not provided."
+    )]
+    watchdog_threshold_ms: Option<u64>,
+    #[structopt(
+        long,
+        default_value = "unordered",
+        parse(try_from_str = parse_startup_order),
+        help = "Order in which auxiliary servers and Nodes are started: unordered, \
+        aux-servers-first (waiting for the servers to bind before starting Nodes) or nodes-first."
+    )]
+    startup_order: StartupOrder,
+    #[structopt(
+        long,
+        help = "Maximum number of downgrades recorded in the history carried by each message. \
+        Downgrade histories are not tracked if not provided."
+    )]
+    downgrade_history_limit: Option<usize>,
+    #[structopt(
+        long,
+        help = "Maximum number of handles that a single message written to a channel may contain. \
+        Defaults to 1024 if not provided."
+    )]
+    max_handles_per_message: Option<usize>,
+    #[structopt(
+        long,
+        help = "Maximum number of live Nodes, counting Nodes of all types. Creating further Nodes \
+        fails until a Node exits. The number of Nodes is unlimited if not provided."
+    )]
+    max_nodes: Option<usize>,
+    #[structopt(
+        long,
+        help = "Number of Node creations per second that each Node is allowed in the long run. \
+        Node creation is not rate limited if not provided."
+    )]
+    node_creation_rate: Option<f64>,
+    #[structopt(
+        long,
+        help = "Number of Node creations that each Node is allowed in a burst, when rate limiting \
+        Node creation."
+    )]
+    node_creation_burst: Option<u32>,
+    #[structopt(
+        long,
+        help = "Minimum interval in milliseconds between two log messages of the same kind emitted \
+        for the same Node on hot paths, such as repeated permission denials. Defaults to 10 \
+        seconds if not provided, and 0 disables rate limiting."
+    )]
+    log_rate_limit_interval_ms: Option<u64>,
+    #[structopt(
+        long,
+        help = "Maximum number of channels reported with their own label value by the \
+        runtime_channel_queue_length metric, with further channels being aggregated. The metric \
+        is disabled if not provided."
+    )]
+    channel_queue_length_metric_limit: Option<usize>,
+    #[structopt(
+        long,
+        help = "Maximum number of channels reported with their own label value by the \
+        runtime_channel_message_rate metric, with further channels being aggregated. The metric \
+        is disabled if not provided."
+    )]
+    channel_message_rate_metric_limit: Option<usize>,
+    #[structopt(
+        long,
+        help = "Requires the names of live channels to be unique, rejecting the creation of a \
+        channel with the same name as an existing one. Channels created by pseudo-Nodes are \
+        exempt, so that they can handle requests concurrently."
+    )]
+    require_unique_channel_names: bool,
+    #[structopt(
+        long,
+        help = "Period in milliseconds during which the Runtime remembers Nodes that have been \
+        removed, so that Nodes terminating while being started are not reported as errors."
+    )]
+    node_tombstone_grace_period_ms: Option<u64>,
+    #[structopt(
+        long,
+        help = "Seed for the random number generators used to allocate handles and by random \
+        pseudo-Nodes, so that runs performing the same operations allocate the same handles and \
+        read the same random bytes. Fresh entropy is used if not provided."
+    )]
+    rng_seed: Option<u64>,
+    #[structopt(
+        long,
+        help = "Derives the IDs of Nodes from their names rather than from their creation order, \
+        so that the same application gets the same Node IDs across runs."
+    )]
+    stable_node_ids: bool,
+    #[structopt(
+        long,
+        help = "Maximum number of introspection events kept by the Runtime, after which the \
+        oldest events are dropped. Defaults to 100000 if not provided."
+    )]
+    introspection_event_queue_capacity: Option<usize>,
+    #[structopt(
+        long,
+        help = "Tags each message written to a channel with the ID of the Node that wrote it, so \
+        that the Node reading the message can retrieve it for auditing."
+    )]
+    tag_message_writers: bool,
+    #[structopt(
+        long,
+        help = "File to append all introspection events to, for offline analysis. Only used if \
+        the oak-unsafe feature is enabled."
+    )]
+    introspection_event_log_path: Option<String>,
+    #[structopt(
+        long,
+        help = "Maximum time in milliseconds to wait for Nodes to terminate when stopping the \
+        Runtime, after which the remaining Nodes are abandoned. Waits indefinitely if not \
+        provided."
+    )]
+    shutdown_timeout_ms: Option<u64>,
 }
 
 /// A specification of a configuration entry as human readable key and a path to a file whose
@@ -135,6 +393,24 @@ impl FromStr for ConfigEntry {
     }
 }
 
+/// Parses a [`StartupOrder`] from its command line representation.
+fn parse_startup_order(v: &str) -> anyhow::Result<StartupOrder> {
+    match v {
+        "unordered" => Ok(StartupOrder::Unordered),
+        "aux-servers-first" => Ok(StartupOrder::AuxServersFirst),
+        "nodes-first" => Ok(StartupOrder::NodesFirst),
+        _ => Err(anyhow!("could not parse startup order: {}", v)),
+    }
+}
+
+/// Parses a hex encoded SHA-256 hash of a Wasm module, and normalizes it to lowercase, as used in
+/// the [`SignatureTable`].
+pub fn parse_module_hash(v: &str) -> anyhow::Result<String> {
+    let hash = hex::decode(v).with_context(|| format!("could not parse module hash {}", v))?;
+    anyhow::ensure!(hash.len() == 32, "module hash {} is not a SHA-256 hash", v);
+    Ok(hex::encode(hash))
+}
+
 /// Parse input options and create a `RuntimeConfiguration`.
 pub async fn create_runtime_config() -> anyhow::Result<oak_runtime::RuntimeConfiguration> {
     let opt = Opt::from_args();
@@ -156,28 +432,114 @@ pub async fn create_runtime_config() -> anyhow::Result<oak_runtime::RuntimeConfi
     // Create the overall gRPC configuration.
     let secure_server_configuration = create_secure_server_config(&opt).await?;
 
+    let aux_server_tls_config =
+        create_aux_server_tls_config(&opt).context("could not create aux server TLS config")?;
+
+    let introspect_auth_token = match &opt.introspect_auth_token_file {
+        Some(path) => {
+            let token = std::fs::read_to_string(path)
+                .with_context(|| format!("could not read introspection auth token {}", path))?;
+            if token.trim().is_empty() {
+                return Err(anyhow!("introspection auth token {} is empty", path));
+            }
+            Some(token.trim().to_string())
+        }
+        None => None,
+    };
+
     // Create signature table.
-    let sign_table = create_sign_table(&app_config)?;
+    let sign_table = create_sign_table(&app_config, &opt)?;
     debug!("parsed signatures: {:?}", sign_table);
 
+    let stuck_node_action = if opt.terminate_stuck_nodes {
+        StuckNodeAction::Terminate
+    } else {
+        StuckNodeAction::Report
+    };
+    let heartbeat_config = opt
+        .heartbeat_interval_ms
+        .map(|interval_ms| HeartbeatConfiguration {
+            interval: std::time::Duration::from_millis(interval_ms),
+            action: stuck_node_action,
+        });
+    let watchdog_config = opt
+        .watchdog_threshold_ms
+        .map(|threshold_ms| WatchdogConfiguration {
+            threshold: std::time::Duration::from_millis(threshold_ms),
+        });
+
+    let metrics_push = match opt.metrics_push_address {
+        Some(address) if cfg!(feature = "oak-unsafe") && !opt.no_metrics => {
+            Some(MetricsPushConfiguration {
+                address,
+                flush_interval: std::time::Duration::from_millis(opt.metrics_push_interval_ms),
+                prefix: opt.metrics_push_prefix.clone(),
+            })
+        }
+        _ => None,
+    };
+
+    let node_creation_rate_limit = create_node_creation_rate_limit(&opt)
+        .context("could not create node creation rate limit")?;
+
     // Create Runtime config.
     let runtime_configuration = oak_runtime::RuntimeConfiguration {
-        metrics_port: if cfg!(feature = "oak-unsafe") && !opt.no_metrics {
-            Some(opt.metrics_port)
+        metrics_address: if cfg!(feature = "oak-unsafe") && !opt.no_metrics {
+            Some(std::net::SocketAddr::new(
+                opt.metrics_host,
+                opt.metrics_port,
+            ))
         } else {
             None
         },
-        introspect_port: if cfg!(feature = "oak-unsafe") && !opt.no_introspect {
-            Some(opt.introspect_port)
+        introspect_address: if cfg!(feature = "oak-unsafe") && !opt.no_introspect {
+            Some(std::net::SocketAddr::new(
+                opt.introspect_host,
+                opt.introspect_port,
+            ))
         } else {
             None
         },
+        aux_server_tls_config,
+        metrics_push,
         kms_credentials: opt.kms_credentials.map(std::path::PathBuf::from),
         secure_server_configuration,
         app_config,
         permissions_config,
         sign_table,
         config_map,
+        strict_config_validation: opt.strict_config_validation,
+        heartbeat_config,
+        watchdog_config,
+        startup_order: opt.startup_order,
+        downgrade_history_limit: opt.downgrade_history_limit,
+        max_handles_per_message: opt.max_handles_per_message,
+        node_config_defaults: HashMap::new(),
+        log_rate_limit_interval: opt
+            .log_rate_limit_interval_ms
+            .map(std::time::Duration::from_millis),
+        channel_queue_length_metric_limit: opt.channel_queue_length_metric_limit,
+        channel_message_rate_metric_limit: opt.channel_message_rate_metric_limit,
+        require_unique_channel_names: opt.require_unique_channel_names,
+        node_tombstone_grace_period: opt
+            .node_tombstone_grace_period_ms
+            .map(std::time::Duration::from_millis),
+        rng_seed: opt.rng_seed,
+        stable_node_ids: opt.stable_node_ids,
+        introspection_event_queue_capacity: opt.introspection_event_queue_capacity,
+        tag_message_writers: opt.tag_message_writers,
+        introspection_event_log_path: opt
+            .introspection_event_log_path
+            .map(std::path::PathBuf::from),
+        channel_label_buckets: Vec::new(),
+        shutdown_timeout: opt
+            .shutdown_timeout_ms
+            .map(std::time::Duration::from_millis),
+        dead_letter_handle: None,
+        introspect_json_labels: opt.introspect_json_labels,
+        introspect_auth_token,
+        max_nodes: opt.max_nodes,
+        node_creation_rate_limit,
     };
 
     Ok(runtime_configuration)
@@ -207,10 +569,11 @@ pub fn parse_config_map(config_entries: &[ConfigEntry]) -> anyhow::Result<Config
 async fn create_secure_server_config(
     opt: &Opt,
 ) -> anyhow::Result<oak_runtime::SecureServerConfiguration> {
-    let grpc_config = create_grpc_config(opt)
+    let tls_policy = create_tls_policy(opt).context("Couldn't create TLS policy")?;
+    let grpc_config = create_grpc_config(opt, &tls_policy)
         .await
         .context("Couldn't create gRPC config")?;
-    let http_config = create_http_config(opt)
+    let http_config = create_http_config(opt, &tls_policy)
         .map_err(|e| log::warn!("{}", e))
         .ok();
 
@@ -220,28 +583,108 @@ async fn create_secure_server_config(
     })
 }
 
+/// Create the [`TlsPolicy`] restricting the TLS parameters accepted by server nodes, if any
+/// restrictions were specified.
+fn create_tls_policy(opt: &Opt) -> anyhow::Result<Option<TlsPolicy>> {
+    if opt.tls_min_version.is_none() && opt.tls_cipher_suites.is_empty() {
+        return Ok(None);
+    }
+    TlsPolicy::new(
+        opt.tls_min_version.unwrap_or_default(),
+        opt.tls_cipher_suites.clone(),
+    )
+    .map(Some)
+}
+
 /// Create the overall [`oak_runtime::GrpcConfiguration`] from the TLS certificate and private key
 /// files or using the Proxy Attestation Service.
-async fn create_grpc_config(opt: &Opt) -> anyhow::Result<oak_runtime::GrpcConfiguration> {
-    let tls_identity = get_tls_identity(opt).await?;
+async fn create_grpc_config(
+    opt: &Opt,
+    tls_policy: &Option<TlsPolicy>,
+) -> anyhow::Result<oak_runtime::GrpcConfiguration> {
+    let (tls_certificate, tls_private_key) = get_tls_identity(opt).await?;
+    let tls_identity = Identity::from_pem(&tls_certificate, &tls_private_key);
+    let client_ca_certificate = get_grpc_client_ca_certificate(opt)?;
+    // The TLS identity can only be restricted to a TLS policy, or verify client certificates,
+    // through a full TLS configuration.
+    let tls_config = if tls_policy.is_some() || client_ca_certificate.is_some() {
+        let tls_config = TlsConfig::from_pem(&tls_certificate, &tls_private_key)
+            .ok_or_else(|| anyhow!("Could not create TLS configuration for gRPC server nodes."))?;
+        Some(match tls_policy {
+            Some(tls_policy) => tls_config.with_policy(tls_policy.clone()),
+            None => tls_config,
+        })
+    } else {
+        None
+    };
 
     let root_tls_certificate = get_root_tls_certificate_or_default(opt).ok();
     let oidc_client_info = get_oidc_client_info(opt)?;
+    let grpc_reflection_file_descriptor_sets = get_grpc_reflection_file_descriptor_sets(opt)?;
 
     let grpc_config = oak_runtime::GrpcConfiguration {
         grpc_server_tls_identity: Some(tls_identity),
+        grpc_server_tls_config: tls_config,
+        grpc_server_client_ca_certificate: client_ca_certificate,
+        grpc_server_require_client_certificate: opt.require_grpc_client_certificate,
         grpc_client_root_tls_certificate: root_tls_certificate,
         oidc_client_info,
+        oidc_token_validation_config: create_oidc_token_validation_config(opt),
+        grpc_reflection_file_descriptor_sets,
     };
 
     Ok(grpc_config)
 }
 
+/// Create the options for validating OpenID Connect identity tokens, using the defaults for the
+/// options that were not specified.
+fn create_oidc_token_validation_config(opt: &Opt) -> TokenValidationConfiguration {
+    let default_config = TokenValidationConfiguration::default();
+    TokenValidationConfiguration {
+        jwks_refresh_interval: opt.oidc_jwks_refresh_interval_seconds.map_or(
+            default_config.jwks_refresh_interval,
+            std::time::Duration::from_secs,
+        ),
+        clock_skew: opt
+            .oidc_clock_skew_seconds
+            .map_or(default_config.clock_skew, std::time::Duration::from_secs),
+        ..default_config
+    }
+}
+
 /// Create a signature table for Oak runtime.
 /// Returns an [`SignatureTable`] that maps each module hash to a vector of [`SignatureBundle`].
 /// Returned signatures are not verified yet, they are supposed to be verified by the `oak_runtime`.
-fn create_sign_table(app_config: &ApplicationConfiguration) -> anyhow::Result<SignatureTable> {
-    let mut sign_table = SignatureTable::default();
+fn create_sign_table(
+    app_config: &ApplicationConfiguration,
+    opt: &Opt,
+) -> anyhow::Result<SignatureTable> {
+    let signature_policy = match opt.module_signature_threshold {
+        Some(threshold) => {
+            let trusted_public_keys = opt
+                .trusted_signer_public_keys
+                .iter()
+                .map(|path| oak_sign::read_pem_file(path))
+                .collect::<anyhow::Result<HashSet<_>>>()
+                .context("could not read trusted signer public keys")?;
+            anyhow::ensure!(
+                threshold <= trusted_public_keys.len(),
+                "module signature threshold {} exceeds the number of trusted signers {}",
+                threshold,
+                trusted_public_keys.len()
+            );
+            Some(SignaturePolicy {
+                threshold,
+                trusted_public_keys,
+            })
+        }
+        None => None,
+    };
+    let mut sign_table = SignatureTable {
+        revoked_hashes: opt.revoked_module_hashes.iter().cloned().collect(),
+        signature_policy,
+        ..Default::default()
+    };
     for signature in app_config.module_signatures.iter() {
         let bundle: SignatureBundle = signature.to_owned().into();
         bundle.verify().context("Signature verification failed")?;
@@ -259,7 +702,10 @@ fn create_sign_table(app_config: &ApplicationConfiguration) -> anyhow::Result<Si
 
 /// Create the overall [`oak_runtime::HttpConfiguration`] from the TLS certificate and private key
 /// files.
-fn create_http_config(opt: &Opt) -> anyhow::Result<oak_runtime::HttpConfiguration> {
+fn create_http_config(
+    opt: &Opt,
+    tls_policy: &Option<TlsPolicy>,
+) -> anyhow::Result<oak_runtime::HttpConfiguration> {
     let http_tls_private_key_path = match &opt.http_tls_private_key {
         Some(path) => path,
         None => {
@@ -277,8 +723,12 @@ fn create_http_config(opt: &Opt) -> anyhow::Result<oak_runtime::HttpConfiguratio
         }
     };
 
-    match oak_runtime::tls::TlsConfig::new(http_tls_certificate_path, http_tls_private_key_path) {
+    match TlsConfig::new(http_tls_certificate_path, http_tls_private_key_path) {
         Some(tls_config) => {
+            let tls_config = match tls_policy {
+                Some(tls_policy) => tls_config.with_policy(tls_policy.clone()),
+                None => tls_config,
+            };
             let root_tls_certificate = get_root_tls_certificate_or_default(opt).ok();
             Ok(oak_runtime::HttpConfiguration {
                 tls_config,
@@ -291,6 +741,42 @@ fn create_http_config(opt: &Opt) -> anyhow::Result<oak_runtime::HttpConfiguratio
     }
 }
 
+/// Create the TLS identity of the auxiliary servers from the TLS certificate and private key
+/// files, if provided.
+fn create_aux_server_tls_config(opt: &Opt) -> anyhow::Result<Option<oak_runtime::tls::TlsConfig>> {
+    match (
+        &opt.aux_server_tls_certificate,
+        &opt.aux_server_tls_private_key,
+    ) {
+        (None, None) => Ok(None),
+        (Some(certificate_path), Some(private_key_path)) => {
+            oak_runtime::tls::TlsConfig::new(certificate_path, private_key_path)
+                .map(Some)
+                .ok_or_else(|| anyhow!("Could not create TLS identity for aux servers."))
+        }
+        _ => Err(anyhow!(
+            "Missing configuration for TLS identity for aux servers."
+        )),
+    }
+}
+
+/// Creates the rate limit of Node creation from the rate and burst provided on the command line,
+/// which must either be both provided or both omitted.
+fn create_node_creation_rate_limit(opt: &Opt) -> anyhow::Result<Option<NodeCreationRateLimit>> {
+    match (opt.node_creation_rate, opt.node_creation_burst) {
+        (None, None) => Ok(None),
+        (Some(rate), Some(burst)) if rate > 0.0 && burst > 0 => {
+            Ok(Some(NodeCreationRateLimit { rate, burst }))
+        }
+        (Some(_), Some(_)) => Err(anyhow!(
+            "Node creation rate and burst must both be positive."
+        )),
+        _ => Err(anyhow!(
+            "Missing configuration for node creation rate limit."
+        )),
+    }
+}
+
 /// If `oak-unsafe` is enabled, reads root TLS certificate from the specified file into a byte
 /// array. Otherwise, loads the default root TLS certificate from the embedded byte array.
 /// Parses the byte array into a [`Certificate`], or returns an error if the byte array does not
@@ -312,9 +798,30 @@ fn get_root_tls_certificate_or_default(opt: &Opt) -> anyhow::Result<Certificate>
     Certificate::parse(certificate_bytes)
 }
 
-/// Creates [`Identity`] from the TLS certificate and private key files or using the Proxy
-/// Attestation Service.
-async fn get_tls_identity(opt: &Opt) -> anyhow::Result<Identity> {
+/// Reads the root TLS certificate used by gRPC server nodes to verify client certificates, if
+/// specified.
+fn get_grpc_client_ca_certificate(opt: &Opt) -> anyhow::Result<Option<Certificate>> {
+    match &opt.grpc_client_ca_certificate {
+        Some(certificate_path) => {
+            let certificate_bytes =
+                read(certificate_path).context("could not read gRPC client CA certificate")?;
+            Certificate::parse(certificate_bytes).map(Some)
+        }
+        None => {
+            if opt.require_grpc_client_certificate {
+                anyhow::bail!(
+                    "Specifying `require-grpc-client-certificate` requires \
+                    `grpc-client-ca-certificate`."
+                );
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Gets the PEM encoded TLS certificate and private key of the gRPC server nodes from files or
+/// using the Proxy Attestation Service.
+async fn get_tls_identity(opt: &Opt) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
     match (&opt.proxy_uri, &opt.proxy_root_tls_certificate) {
         #[cfg(feature = "oak-attestation")]
         (Some(proxy_uri_string), Some(proxy_root_tls_certificate_path)) => {
@@ -346,9 +853,9 @@ async fn get_tls_identity(opt: &Opt) -> anyhow::Result<Identity> {
                 None => Err(anyhow!("No gRPC TLS certificate file provided.")),
             }?;
 
-            Ok(Identity::from_pem(
-                &grpc_tls_certificate,
-                &grpc_tls_private_key,
+            Ok((
+                grpc_tls_certificate.into_bytes(),
+                grpc_tls_private_key.into_bytes(),
             ))
         }
     }
@@ -368,6 +875,20 @@ fn get_oidc_client_info(opt: &Opt) -> anyhow::Result<Option<ClientInfo>> {
     }
 }
 
+/// Reads the encoded `FileDescriptorSet`s to serve with gRPC server reflection, if any.
+fn get_grpc_reflection_file_descriptor_sets(opt: &Opt) -> anyhow::Result<Option<Vec<Vec<u8>>>> {
+    if opt.grpc_reflection_descriptor_sets.is_empty() {
+        return Ok(None);
+    }
+    opt.grpc_reflection_descriptor_sets
+        .iter()
+        .map(|path| {
+            read(path).with_context(|| format!("could not read FileDescriptorSet file {}", path))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(Some)
+}
+
 /// Gets the default root TLS certificates from the embedded byte array.
 fn get_default_root_tls_certs() -> Vec<u8> {
     include_bytes!("certs/roots.pem").to_vec()
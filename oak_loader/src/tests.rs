@@ -14,7 +14,7 @@
 // limitations under the License.
 //
 
-use crate::options::{parse_config_map, ConfigEntry};
+use crate::options::{parse_config_map, parse_module_hash, ConfigEntry};
 use maplit::hashmap;
 use oak_abi::proto::oak::application::ConfigMap;
 
@@ -41,6 +41,22 @@ fn parse_config_entry_missing_equals_err() {
     assert!("/dev/null".parse::<ConfigEntry>().is_err());
 }
 
+#[test]
+fn parse_module_hash_normalizes_case() {
+    assert_eq!(
+        "1aa18df5f34d9268db0a880e64154c08f0cd695f48abef487b997c7e8422e673",
+        parse_module_hash("1AA18DF5F34D9268DB0A880E64154C08F0CD695F48ABEF487B997C7E8422E673")
+            .expect("could not parse module hash")
+    );
+}
+
+#[test]
+fn parse_module_hash_invalid_err() {
+    assert!(parse_module_hash("not hex").is_err());
+    // Valid hex, but too short for a SHA-256 hash.
+    assert!(parse_module_hash("1aa18df5").is_err());
+}
+
 #[test]
 fn parse_config_map_multiple_ok() {
     let result = parse_config_map(&[
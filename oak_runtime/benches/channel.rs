@@ -0,0 +1,83 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Benchmarks for the channel hot paths of the Runtime, run with logging disabled so that they
+//! measure the overhead that remains when no log output is produced.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use maplit::hashmap;
+use oak_abi::{label::Label, proto::oak::application::ApplicationConfiguration};
+use oak_io::Message as NodeMessage;
+use oak_runtime::{
+    permissions::PermissionsConfiguration, RuntimeProxy, SecureServerConfiguration, SignatureTable,
+};
+
+fn create_runtime() -> RuntimeProxy {
+    RuntimeProxy::create_runtime(
+        &ApplicationConfiguration {
+            wasm_modules: hashmap! {},
+            initial_node_configuration: None,
+            module_signatures: vec![],
+        },
+        &PermissionsConfiguration::default(),
+        &SecureServerConfiguration::default(),
+        &SignatureTable::default(),
+        None,
+    )
+}
+
+fn channel_round_trip(c: &mut Criterion) {
+    log::set_max_level(log::LevelFilter::Off);
+    let runtime = create_runtime();
+    let (write_handle, read_handle) = runtime
+        .channel_create("bench", &Label::public_untrusted())
+        .expect("could not create channel");
+    let message = NodeMessage {
+        bytes: vec![0; 1024],
+        handles: vec![],
+    };
+
+    c.bench_function("channel_write_read", |b| {
+        b.iter(|| {
+            runtime
+                .channel_write(write_handle, message.clone())
+                .expect("could not write message");
+            black_box(
+                runtime
+                    .channel_read(read_handle)
+                    .expect("could not read message"),
+            )
+        })
+    });
+
+    runtime
+        .channel_write(write_handle, message)
+        .expect("could not write message");
+    c.bench_function("wait_on_channels_ready", |b| {
+        b.iter(|| {
+            black_box(
+                runtime
+                    .wait_on_channels(&[read_handle])
+                    .expect("could not wait on channel"),
+            )
+        })
+    });
+
+    runtime.runtime.stop();
+}
+
+criterion_group!(benches, channel_round_trip);
+criterion_main!(benches);
@@ -0,0 +1,116 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Benchmarks for the [`WakeStrategy`] of a channel shared by many waiting readers, measuring the
+//! time taken to deliver each written message to one of them. With [`WakeStrategy::WakeAll`],
+//! every message wakes all the readers, which then contend for it.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use maplit::hashmap;
+use oak_abi::{label::Label, proto::oak::application::ApplicationConfiguration, ChannelReadStatus};
+use oak_io::Message as NodeMessage;
+use oak_runtime::{
+    permissions::PermissionsConfiguration, ChannelOptions, RuntimeProxy, SecureServerConfiguration,
+    SignatureTable, WakeStrategy,
+};
+use std::sync::mpsc;
+
+/// Number of reader threads waiting on the shared channel.
+const WAITERS: usize = 32;
+
+fn create_runtime() -> RuntimeProxy {
+    RuntimeProxy::create_runtime(
+        &ApplicationConfiguration {
+            wasm_modules: hashmap! {},
+            initial_node_configuration: None,
+            module_signatures: vec![],
+        },
+        &PermissionsConfiguration::default(),
+        &SecureServerConfiguration::default(),
+        &SignatureTable::default(),
+        None,
+    )
+}
+
+fn shared_channel_delivery(c: &mut Criterion) {
+    log::set_max_level(log::LevelFilter::Off);
+    let runtime = create_runtime();
+    let message = NodeMessage {
+        bytes: vec![0; 64],
+        handles: vec![],
+    };
+
+    let mut group = c.benchmark_group("shared_channel_delivery");
+    for (name, wake_strategy) in &[
+        ("wake_all", WakeStrategy::WakeAll),
+        ("wake_one", WakeStrategy::WakeOne),
+    ] {
+        let (write_handle, read_handle) = runtime
+            .channel_create_with_options(
+                "bench",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    wake_strategy: *wake_strategy,
+                    ..ChannelOptions::default()
+                },
+            )
+            .expect("could not create channel");
+
+        // Each reader acknowledges the messages it reads, until the channel is orphaned.
+        let (ack_sender, ack_receiver) = mpsc::channel();
+        let readers: Vec<_> = (0..WAITERS)
+            .map(|_| {
+                let runtime = runtime.clone();
+                let ack_sender = ack_sender.clone();
+                std::thread::spawn(move || {
+                    while let Ok(statuses) = runtime.wait_on_channels(&[read_handle]) {
+                        if statuses != vec![ChannelReadStatus::ReadReady] {
+                            break;
+                        }
+                        // Another reader may have read the message first.
+                        if let Ok(Some(_)) = runtime.channel_read(read_handle) {
+                            ack_sender.send(()).expect("could not acknowledge message");
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        group.bench_function(BenchmarkId::new(*name, WAITERS), |b| {
+            b.iter(|| {
+                runtime
+                    .channel_write(write_handle, message.clone())
+                    .expect("could not write message");
+                ack_receiver
+                    .recv()
+                    .expect("could not receive acknowledgement");
+            })
+        });
+
+        runtime
+            .channel_close(write_handle)
+            .expect("could not close channel");
+        for reader in readers {
+            reader.join().expect("reader thread panicked");
+        }
+    }
+    group.finish();
+
+    runtime.runtime.stop();
+}
+
+criterion_group!(benches, shared_channel_delivery);
+criterion_main!(benches);
@@ -242,7 +242,9 @@ function renderEventDescription(
 
   if (event.getEventDetailsCase() === EventDetailsCase.MESSAGE_DEQUEUED) {
     return {
-      title: `Message Dequeued`,
+      title: event.getMessageDequeued()!.getExpired()
+        ? `Message Expired`
+        : `Message Dequeued`,
       description: (
         <>
           channel{event.getMessageDequeued()!.getChannelId()} to{' '}
@@ -70,6 +70,7 @@ export interface NodeInfo {
   name: string;
   abiHandles: Map<AbiHandle, ChannelHalf>;
   label: Label;
+  privilege?: Label;
 }
 
 export type ChannelID = string;
@@ -123,6 +124,22 @@ function eventReducer(
         label: event!.getNodeCreated()!.getLabel()!.toObject(),
       });
 
+      break;
+    case EventDetailsCase.NODE_PRIVILEGE_ASSIGNED:
+      {
+        const details = event.getNodePrivilegeAssigned()!;
+        const nodeId = details.getNodeId();
+        const node = applicationState.nodeInfos.get(nodeId);
+
+        if (node === undefined) {
+          throw new Error(
+            `Couldn't get Node with id "${nodeId}", as it does not exist.`
+          );
+        }
+
+        node.privilege = details.getPrivilege()!.toObject();
+      }
+
       break;
     case EventDetailsCase.NODE_DESTROYED:
       {
@@ -158,6 +175,23 @@ function eventReducer(
         }
       }
 
+      break;
+    case EventDetailsCase.CHANNEL_MIGRATED:
+      {
+        const details = event!.getChannelMigrated()!;
+        const oldChannel = applicationState.channels.get(
+          details.getOldChannelId()
+        )!;
+        const newChannel = applicationState.channels.get(
+          details.getNewChannelId()
+        )!;
+        newChannel.messages = [...oldChannel.messages, ...newChannel.messages];
+        oldChannel.messages = [];
+      }
+
+      break;
+    case EventDetailsCase.CHANNEL_ORPHANED:
+      // The handles of the orphaned side are reported as destroyed separately.
       break;
     case EventDetailsCase.HANDLE_CREATED:
       {
@@ -228,6 +262,17 @@ function eventReducer(
         .get(event.getMessageDequeued()!.getChannelId())!
         .messages.shift();
 
+      break;
+    case EventDetailsCase.NODE_STUCK:
+      // Stuck Nodes are only reported, and do not change the application state.
+      break;
+    case EventDetailsCase.MESSAGE_REJECTED:
+      // Rejected messages are not enqueued, so do not change the application state.
+      break;
+    case EventDetailsCase.LABEL_DOWNGRADED:
+    case EventDetailsCase.PERMISSION_DENIED:
+      // Downgrades and denials only affect individual operations, and do not change the
+      // application state.
       break;
     default:
       // This should never happen
@@ -245,16 +290,19 @@ function useEvents() {
   // Index of the event representing the last state change. Setting it lower
   // than the number of events enables inspecting past application states.
   const [presentEventIndex, setPresentEventIndex] = React.useState<number>(0);
+  // Number of events dropped by the Oak runtime before the first event in the
+  // list, in which case the reconstructed application state may be incomplete.
+  const [droppedEvents, setDroppedEvents] = React.useState<string>('0');
 
   const sessionStorageKeyRef = React.useRef<undefined | string>();
 
   React.useEffect(() => {
     async function loadEvents() {
       const serializedEvents: Uint8Array = await loadSerializedEvents();
-      const events =
-        introspectionEventsProto.Events.deserializeBinary(
-          serializedEvents
-        ).getEventsList();
+      const eventsMessage = introspectionEventsProto.Events.deserializeBinary(
+        serializedEvents
+      );
+      const events = eventsMessage.getEventsList();
 
       if (events.length > 0) {
         // Key the session storage to the first event, preventing state from
@@ -275,6 +323,7 @@ function useEvents() {
       }
 
       setEvents(events);
+      setDroppedEvents(eventsMessage.getDroppedEvents());
     }
 
     loadEvents();
@@ -291,7 +340,12 @@ function useEvents() {
     }
   }, [presentEventIndex, sessionStorageKeyRef.current]);
 
-  return { totalEvents: events, presentEventIndex, setPresentEventIndex };
+  return {
+    totalEvents: events,
+    droppedEvents,
+    presentEventIndex,
+    setPresentEventIndex,
+  };
 }
 
 const useStyles = makeStyles((theme: Theme) =>
@@ -332,7 +386,12 @@ const useStyles = makeStyles((theme: Theme) =>
 
 export default function Root() {
   // The entirety of introspection events
-  const { totalEvents, presentEventIndex, setPresentEventIndex } = useEvents();
+  const {
+    totalEvents,
+    droppedEvents,
+    presentEventIndex,
+    setPresentEventIndex,
+  } = useEvents();
 
   const classes = useStyles();
 
@@ -352,6 +411,11 @@ export default function Root() {
           <Typography variant="h6" className={classes.title}>
             Oak Introspection
           </Typography>
+          {droppedEvents !== '0' && (
+            <Typography variant="body2">
+              {droppedEvents} earlier events were dropped by the runtime
+            </Typography>
+          )}
         </Toolbar>
       </AppBar>
       <BrowserRouter basename="/dynamic">
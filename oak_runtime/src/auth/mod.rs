@@ -17,3 +17,6 @@
 //! Authentication functionality.
 
 pub mod oidc_utils;
+
+#[cfg(test)]
+mod tests;
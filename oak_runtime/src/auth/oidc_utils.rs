@@ -20,10 +20,20 @@
 //! For reference, see:
 //! https://developers.google.com/identity/protocols/oauth2/openid-connect#exchangecode
 
-use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Header, Validation};
+use jsonwebtoken::{
+    decode, decode_header, errors::ErrorKind, Algorithm, DecodingKey, Header, Validation,
+};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::{error, fmt};
+use std::{
+    error, fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Location of the JSON Web Key Set of the Google Identity Platform.
+// TODO(#923): Get certificate location from the discovery document.
+const GOOGLE_JWKS_URI: &str = "https://www.googleapis.com/oauth2/v3/certs";
 
 /// Claims encoded into the identity token. Reference:
 /// https://developers.google.com/identity/protocols/oauth2/openid-connect#an-id-tokens-payload
@@ -45,6 +55,7 @@ pub struct Claims {
     #[serde(rename = "sub")]
     _subject: String,
     /// The email address used for logging into the account.
+    #[serde(rename = "email")]
     _email: String,
     // TODO(#922): Add nonce
 }
@@ -103,6 +114,162 @@ struct Key {
     modulus: String,
 }
 
+/// JSON Web Key Set downloaded from the OpenID Connect provider, along with the time it was
+/// downloaded.
+struct CachedKeySet {
+    key_set: Arc<KeySet>,
+    fetched_at: Instant,
+}
+
+/// State of a [`TokenValidator`] shared by the validation of all tokens.
+#[derive(Default)]
+struct KeySetCache {
+    cached_key_set: Option<CachedKeySet>,
+    /// Time at which the JSON Web Key Set was last downloaded again because a token was signed by
+    /// a key missing from the cached one.
+    unknown_key_refetched_at: Option<Instant>,
+}
+
+/// Options for validating identity tokens.
+#[derive(Clone, Debug)]
+pub struct TokenValidationConfiguration {
+    /// Interval after which the cached JSON Web Key Set of the OpenID Connect provider is
+    /// downloaded again, so that rotated signing keys are eventually used.
+    pub jwks_refresh_interval: Duration,
+    /// Minimum interval between downloads of the JSON Web Key Set triggered by tokens signed by a
+    /// key missing from the cached one, so that tokens with unknown key IDs cannot be used to
+    /// flood the OpenID Connect provider with requests.
+    pub jwks_unknown_key_refetch_interval: Duration,
+    /// Clock skew allowed between the Runtime and the OpenID Connect provider when checking the
+    /// expiration time of identity tokens.
+    pub clock_skew: Duration,
+}
+
+impl Default for TokenValidationConfiguration {
+    fn default() -> Self {
+        TokenValidationConfiguration {
+            jwks_refresh_interval: Duration::from_secs(60 * 60),
+            jwks_unknown_key_refetch_interval: Duration::from_secs(60),
+            clock_skew: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Validates identity tokens, caching the JSON Web Key Set used for verifying their signatures so
+/// that it is not downloaded for every token.
+pub struct TokenValidator {
+    /// Location of the JSON Web Key Set of the OpenID Connect provider.
+    jwks_uri: String,
+    config: TokenValidationConfiguration,
+    /// Only held briefly, and never while downloading the JSON Web Key Set, so that a slow
+    /// provider does not block the validation of tokens signed by already cached keys.
+    cache: Mutex<KeySetCache>,
+}
+
+impl TokenValidator {
+    /// Creates a validator for identity tokens issued by the Google Identity Platform.
+    pub fn new(config: TokenValidationConfiguration) -> Self {
+        Self::with_jwks_uri(GOOGLE_JWKS_URI, config)
+    }
+
+    /// Creates a validator for identity tokens signed by the keys in the JSON Web Key Set at
+    /// `jwks_uri`.
+    pub(crate) fn with_jwks_uri(jwks_uri: &str, config: TokenValidationConfiguration) -> Self {
+        TokenValidator {
+            jwks_uri: jwks_uri.to_owned(),
+            config,
+            cache: Mutex::new(KeySetCache::default()),
+        }
+    }
+
+    /// Decodes and validates an identity token. Tokens that expired longer ago than the allowed
+    /// clock skew are rejected with a [`TokenError`] of kind [`TokenErrorKind::Expired`].
+    pub async fn validate(&self, token: &str) -> Result<Claims, Box<dyn error::Error>> {
+        let key = self.get_key(decode_header(token)?).await?;
+        let mut validation = Validation::new(key.algorithm.parse()?);
+        validation.validate_exp = true;
+        validation.leeway = self.config.clock_skew.as_secs();
+        let key = DecodingKey::from_rsa_components(&key.modulus, &key.exponent);
+        match decode::<Claims>(token, &key, &validation) {
+            Ok(data) => Ok(data.claims),
+            Err(err) if matches!(err.kind(), ErrorKind::ExpiredSignature) => {
+                Err(Box::new(TokenError::expired()))
+            }
+            Err(err) => Err(Box::new(err)),
+        }
+    }
+
+    /// Gets the public key that matches the header's key ID and algorithm, downloading the JSON
+    /// Web Key Set again if the cached one is older than the refresh interval.
+    ///
+    /// If the key is missing from a cached JSON Web Key Set, the provider may have rotated its
+    /// keys since, so the JSON Web Key Set is downloaded again once, unless this was already done
+    /// within the unknown key refetch interval.
+    async fn get_key(&self, header: Header) -> Result<Key, Box<dyn error::Error>> {
+        let cached_key_set = {
+            let cache = self.cache.lock().unwrap();
+            cache
+                .cached_key_set
+                .as_ref()
+                .filter(|cached_key_set| {
+                    cached_key_set.fetched_at.elapsed() < self.config.jwks_refresh_interval
+                })
+                .map(|cached_key_set| cached_key_set.key_set.clone())
+        };
+        let is_cached = cached_key_set.is_some();
+        let key_set = match cached_key_set {
+            Some(key_set) => key_set,
+            None => self.fetch_key_set().await?,
+        };
+        if let Some(key) = find_key(&key_set, &header) {
+            return Ok(key);
+        }
+        if is_cached && self.start_unknown_key_refetch() {
+            let key_set = self.fetch_key_set().await?;
+            if let Some(key) = find_key(&key_set, &header) {
+                return Ok(key);
+            }
+        }
+        Err(Box::new(TokenError::new("No matching key found.")))
+    }
+
+    /// Checks whether the JSON Web Key Set may be downloaded again because of a token signed by an
+    /// unknown key, and if so records the time of this download.
+    fn start_unknown_key_refetch(&self) -> bool {
+        let mut cache = self.cache.lock().unwrap();
+        let may_refetch = cache.unknown_key_refetched_at.map_or(true, |refetched_at| {
+            refetched_at.elapsed() >= self.config.jwks_unknown_key_refetch_interval
+        });
+        if may_refetch {
+            cache.unknown_key_refetched_at = Some(Instant::now());
+        }
+        may_refetch
+    }
+
+    /// Downloads the JSON Web Key Set from the OpenID Connect provider, and caches it.
+    async fn fetch_key_set(&self) -> Result<Arc<KeySet>, Box<dyn error::Error>> {
+        let response = reqwest::get(&self.jwks_uri).await?.text().await?;
+        let key_set: Arc<KeySet> = Arc::new(serde_json::from_str(&response)?);
+        self.cache.lock().unwrap().cached_key_set = Some(CachedKeySet {
+            key_set: key_set.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(key_set)
+    }
+}
+
+/// Finds the key in `key_set` that matches the header's key ID and algorithm.
+fn find_key(key_set: &KeySet, header: &Header) -> Option<Key> {
+    key_set
+        .keys
+        .iter()
+        .find(|key| {
+            key.algorithm.parse::<Algorithm>().ok() == Some(header.alg)
+                && header.kid.as_deref() == Some(&key.key_id[..])
+        })
+        .cloned()
+}
+
 /// Top-level container for the downloaded OpenID Connect JSON client secret file.
 ///
 /// See: https://developers.google.com/api-client-library/dotnet/guide/aaa_client_secrets
@@ -136,6 +303,7 @@ pub async fn exchange_code_for_token(
     code: &str,
     client_id: &str,
     client_secret: &str,
+    token_validator: &TokenValidator,
 ) -> Result<String, Box<dyn error::Error>> {
     let body = TokenRequest {
         code: code.to_owned(),
@@ -157,48 +325,44 @@ pub async fn exchange_code_for_token(
         .await?;
     let result: TokenResponse = serde_json::from_str(&response)?;
     // Validate token.
-    decode_identity_token(&result.id_token).await?;
+    token_validator.validate(&result.id_token).await?;
     Ok(result.id_token)
 }
 
-// Decodes and validates the identity token.
-async fn decode_identity_token(token: &str) -> Result<Claims, Box<dyn error::Error>> {
-    let key = get_key(decode_header(token)?).await?;
-    let validation = Validation::new(key.algorithm.parse()?);
-    let key = DecodingKey::from_rsa_components(&key.modulus, &key.exponent);
-    let data = decode::<Claims>(token, &key, &validation)?;
-    Ok(data.claims)
-}
-
-/// Gets the public key that matches the header's key ID and algorithm.
-async fn get_key(header: Header) -> Result<Key, Box<dyn std::error::Error>> {
-    // TODO(#923): Get certificate location from the discovery document.
-    let cert_location = "https://www.googleapis.com/oauth2/v3/certs";
-    // TODO(#924): Cache certificates based on the max-age value of the cache-control response
-    // header.
-    let response = reqwest::get(cert_location).await?.text().await?;
-    let key_list: KeySet = serde_json::from_str(&response)?;
-    match key_list.keys.iter().find(|key| {
-        key.algorithm.parse::<Algorithm>().unwrap() == header.alg
-            && key.key_id[..] == header.kid.as_ref().unwrap()[..]
-    }) {
-        Some(key) => Ok(key.clone()),
-        None => Err(Box::new(TokenError::new("No matching key found."))),
-    }
+/// The kind of a [`TokenError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenErrorKind {
+    /// The token has expired.
+    Expired,
+    /// The token is invalid for any other reason.
+    Invalid,
 }
 
 /// Represents a token validation error.
 #[derive(Debug, Clone)]
 pub struct TokenError {
+    kind: TokenErrorKind,
     message: String,
 }
 
 impl TokenError {
     pub fn new(message: &str) -> TokenError {
         TokenError {
+            kind: TokenErrorKind::Invalid,
             message: message.to_owned(),
         }
     }
+
+    pub fn expired() -> TokenError {
+        TokenError {
+            kind: TokenErrorKind::Expired,
+            message: "Token has expired.".to_owned(),
+        }
+    }
+
+    pub fn kind(&self) -> TokenErrorKind {
+        self.kind
+    }
 }
 
 impl fmt::Display for TokenError {
@@ -0,0 +1,187 @@
+//
+// Copyright 2021 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::auth::oidc_utils::{
+    TokenError, TokenErrorKind, TokenValidationConfiguration, TokenValidator,
+};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Response, Server,
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const FIRST_KEY_SET: &str = include_str!("../../testdata/oidc_jwks_1.json");
+const SECOND_KEY_SET: &str = include_str!("../../testdata/oidc_jwks_2.json");
+const FIRST_SIGNING_KEY: &[u8] = include_bytes!("../../testdata/oidc_signing_1.key");
+const SECOND_SIGNING_KEY: &[u8] = include_bytes!("../../testdata/oidc_signing_2.key");
+
+/// Serves the JSON Web Key Set currently in `key_set` over HTTP, and returns its URI.
+fn serve_key_set(key_set: Arc<Mutex<&'static str>>) -> String {
+    let make_service = make_service_fn(move |_conn| {
+        let key_set = key_set.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_request| {
+                let body = Body::from(*key_set.lock().unwrap());
+                async move { Ok::<_, Infallible>(Response::new(body)) }
+            }))
+        }
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let uri = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    uri
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Couldn't get current time")
+        .as_secs()
+}
+
+/// Creates an identity token expiring at `expiry` (in Unix time), signed by `signing_key` with
+/// key ID `key_id`.
+fn create_token(signing_key: &[u8], key_id: &str, expiry: u64) -> String {
+    let header = Header {
+        kid: Some(key_id.to_string()),
+        ..Header::new(Algorithm::RS256)
+    };
+    let claims = serde_json::json!({
+        "aud": "test-client-id",
+        "exp": expiry,
+        "iat": now_seconds() - 3600,
+        "iss": "https://accounts.google.com",
+        "sub": "1234567890",
+        "email": "test@example.com",
+    });
+    encode(
+        &header,
+        &claims,
+        &EncodingKey::from_rsa_pem(signing_key).expect("Couldn't parse signing key"),
+    )
+    .expect("Couldn't create token")
+}
+
+fn error_kind(error: Box<dyn std::error::Error>) -> Option<TokenErrorKind> {
+    error
+        .downcast_ref::<TokenError>()
+        .map(|token_error| token_error.kind())
+}
+
+#[tokio::test]
+async fn test_token_validator_rejects_expired_token() {
+    let jwks_uri = serve_key_set(Arc::new(Mutex::new(FIRST_KEY_SET)));
+    let validator = TokenValidator::with_jwks_uri(
+        &jwks_uri,
+        TokenValidationConfiguration {
+            clock_skew: Duration::from_secs(60),
+            ..Default::default()
+        },
+    );
+
+    let valid_token = create_token(FIRST_SIGNING_KEY, "oidc-test-key-1", now_seconds() + 600);
+    assert!(validator.validate(&valid_token).await.is_ok());
+
+    // Tokens that expired within the allowed clock skew are still accepted.
+    let recently_expired_token =
+        create_token(FIRST_SIGNING_KEY, "oidc-test-key-1", now_seconds() - 10);
+    assert!(validator.validate(&recently_expired_token).await.is_ok());
+
+    let expired_token = create_token(FIRST_SIGNING_KEY, "oidc-test-key-1", now_seconds() - 600);
+    let error = validator
+        .validate(&expired_token)
+        .await
+        .expect_err("Expired token was accepted");
+    assert_eq!(Some(TokenErrorKind::Expired), error_kind(error));
+}
+
+#[tokio::test]
+async fn test_token_validator_accepts_rotated_key_after_refresh() {
+    let key_set = Arc::new(Mutex::new(FIRST_KEY_SET));
+    let jwks_uri = serve_key_set(key_set.clone());
+    let refresh_interval = Duration::from_millis(500);
+    let validator = TokenValidator::with_jwks_uri(
+        &jwks_uri,
+        TokenValidationConfiguration {
+            jwks_refresh_interval: refresh_interval,
+            jwks_unknown_key_refetch_interval: Duration::from_secs(60 * 60),
+            ..Default::default()
+        },
+    );
+    let expiry = now_seconds() + 600;
+    let first_token = create_token(FIRST_SIGNING_KEY, "oidc-test-key-1", expiry);
+    let second_token = create_token(SECOND_SIGNING_KEY, "oidc-test-key-2", expiry);
+
+    assert!(validator.validate(&first_token).await.is_ok());
+
+    // The provider rotates its signing key, but the cached key set is still used until it is
+    // refreshed, since it was already downloaded again for an unknown key recently.
+    assert!(validator
+        .validate(&create_token(
+            FIRST_SIGNING_KEY,
+            "oidc-test-key-unknown",
+            expiry
+        ))
+        .await
+        .is_err());
+    *key_set.lock().unwrap() = SECOND_KEY_SET;
+    let error = validator
+        .validate(&second_token)
+        .await
+        .expect_err("Token signed by an unknown key was accepted");
+    assert_eq!(Some(TokenErrorKind::Invalid), error_kind(error));
+
+    tokio::time::sleep(refresh_interval).await;
+    assert!(validator.validate(&second_token).await.is_ok());
+    assert!(validator.validate(&first_token).await.is_err());
+}
+
+#[tokio::test]
+async fn test_token_validator_refetches_key_set_once_for_unknown_key() {
+    let key_set = Arc::new(Mutex::new(FIRST_KEY_SET));
+    let jwks_uri = serve_key_set(key_set.clone());
+    let validator = TokenValidator::with_jwks_uri(
+        &jwks_uri,
+        TokenValidationConfiguration {
+            jwks_unknown_key_refetch_interval: Duration::from_secs(60 * 60),
+            ..Default::default()
+        },
+    );
+    let expiry = now_seconds() + 600;
+    let first_token = create_token(FIRST_SIGNING_KEY, "oidc-test-key-1", expiry);
+    let second_token = create_token(SECOND_SIGNING_KEY, "oidc-test-key-2", expiry);
+
+    assert!(validator.validate(&first_token).await.is_ok());
+
+    // A token signed by the rotated key is accepted without waiting for the refresh interval,
+    // since its unknown key ID causes the key set to be downloaded again.
+    *key_set.lock().unwrap() = SECOND_KEY_SET;
+    assert!(validator.validate(&second_token).await.is_ok());
+
+    // The key set is not downloaded again for another unknown key ID within the refetch interval.
+    *key_set.lock().unwrap() = FIRST_KEY_SET;
+    let error = validator
+        .validate(&first_token)
+        .await
+        .expect_err("Key set was downloaded again within the refetch interval");
+    assert_eq!(Some(TokenErrorKind::Invalid), error_kind(error));
+}
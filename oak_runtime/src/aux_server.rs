@@ -0,0 +1,146 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Listening for connections to the auxiliary servers (e.g. the metrics and introspection
+//! servers), which are served over TLS if a [`TlsConfig`] is provided, and in plaintext otherwise,
+//! and running the auxiliary servers that instead perform periodic checks of the Runtime.
+
+use crate::tls::TlsConfig;
+use core::task::Poll;
+use futures_util::stream::{Stream, StreamExt, TryStreamExt};
+use log::warn;
+use std::{io, net::SocketAddr, pin::Pin, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::{mpsc, oneshot},
+};
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+
+/// A connection accepted by an auxiliary server, either plaintext or over TLS.
+pub(crate) trait Connection: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Connection for T {}
+
+type ConnectionStream = Pin<Box<dyn Stream<Item = io::Result<Box<dyn Connection>>> + Send>>;
+
+/// Incoming connections of an auxiliary server, to be served by a [`hyper::Server`].
+pub(crate) struct AuxIncoming {
+    connections: ConnectionStream,
+}
+
+impl hyper::server::accept::Accept for AuxIncoming {
+    type Conn = Box<dyn Connection>;
+    type Error = io::Error;
+
+    fn poll_accept(
+        mut self: Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        self.connections.as_mut().poll_next(cx)
+    }
+}
+
+/// Binds to `address`, and returns the incoming connections, over TLS if `tls_config` is
+/// provided.
+///
+/// Each TLS handshake is performed on its own task, so that a slow client does not delay accepting
+/// other connections. Connections for which the TLS handshake fails are dropped, so that a single
+/// misbehaving client does not stop the server.
+pub(crate) async fn bind(
+    address: SocketAddr,
+    tls_config: Option<TlsConfig>,
+) -> io::Result<AuxIncoming> {
+    let tcp = TcpListenerStream::new(TcpListener::bind(address).await?);
+    let connections = match tls_config {
+        None => tcp
+            .map_ok(|stream| Box::new(stream) as Box<dyn Connection>)
+            .boxed(),
+        Some(tls_config) => {
+            let tls_acceptor = TlsAcceptor::from(crate::tls::to_server_config(tls_config));
+            let (sender, receiver) = mpsc::unbounded_channel();
+            tokio::spawn(accept_tls(tcp, tls_acceptor, sender));
+            UnboundedReceiverStream::new(receiver).boxed()
+        }
+    };
+    Ok(AuxIncoming { connections })
+}
+
+/// Accepts the TCP connections from `tcp`, and sends each of them to `sender` once its TLS
+/// handshake has completed, until the receiver is dropped along with the server.
+async fn accept_tls(
+    mut tcp: TcpListenerStream,
+    tls_acceptor: TlsAcceptor,
+    sender: mpsc::UnboundedSender<io::Result<Box<dyn Connection>>>,
+) {
+    loop {
+        let stream = tokio::select! {
+            stream = tcp.next() => stream,
+            _ = sender.closed() => return,
+        };
+        match stream {
+            None => return,
+            Some(Err(err)) => {
+                if sender.send(Err(err)).is_err() {
+                    return;
+                }
+            }
+            Some(Ok(stream)) => {
+                let tls_acceptor = tls_acceptor.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    match tls_acceptor.accept(stream).await {
+                        Ok(stream) => {
+                            // The server may have stopped during the handshake.
+                            let _ = sender.send(Ok(Box::new(stream) as Box<dyn Connection>));
+                        }
+                        Err(err) => warn!("dropping connection to auxiliary server: {}", err),
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Calls `check` once every `period`, until notified on `termination_notification_receiver`.
+///
+/// The notification is waited on between checks, rather than sleeping, so that the auxiliary
+/// server stops promptly however long `period` is.
+pub(crate) fn run_periodically<F: FnMut()>(
+    period: Duration,
+    mut termination_notification_receiver: oneshot::Receiver<()>,
+    mut check: F,
+) {
+    let termination_waiter = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("could not create runtime");
+    // Avoid spinning if configured with a zero period.
+    let period = std::cmp::max(period, Duration::from_millis(1));
+    loop {
+        check();
+        if termination_waiter
+            .block_on(tokio::time::timeout(
+                period,
+                &mut termination_notification_receiver,
+            ))
+            .is_ok()
+        {
+            return;
+        }
+    }
+}
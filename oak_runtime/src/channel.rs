@@ -16,18 +16,22 @@
 
 use crate::{
     message::Message,
-    proto::oak::introspection_events::{event::EventDetails, ChannelDestroyed},
-    Runtime,
+    proto::oak::introspection_events::{
+        event::EventDetails, ChannelDestroyed, ChannelOrphaned, Direction,
+    },
+    serialize_label, NodeId, Runtime,
 };
 use log::{debug, error};
 use oak_abi::OakStatus;
 use std::{
+    cmp::Reverse,
     collections::{HashMap, VecDeque},
     sync::{
         atomic::{AtomicU64, Ordering::SeqCst},
         Arc, Mutex, RwLock, RwLockReadGuard, Weak,
     },
-    thread::{Thread, ThreadId},
+    thread::{self, Thread, ThreadId},
+    time::{Duration, Instant},
 };
 
 type Messages = VecDeque<Message>;
@@ -50,6 +54,225 @@ pub trait DotIdentifier {
 /// as soon as it is resumed.)
 type WaitingThreads = Mutex<HashMap<ThreadId, Weak<Thread>>>;
 
+/// Strategy used to decide which waiting threads are woken when new messages are written to a
+/// [`Channel`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakeStrategy {
+    /// Wake all the threads waiting on the channel, and let them race for the available messages.
+    WakeAll,
+    /// Wake at most as many waiting threads as there are messages available on the channel.
+    ///
+    /// This avoids waking a thundering herd of waiters when many Nodes share a channel, but it
+    /// relies on each woken waiter actually reading from the channel; if a woken waiter does not,
+    /// the remaining waiters are only woken by subsequent writes. It is therefore best suited to
+    /// channels whose readers only wait on that single channel.
+    WakeOne,
+}
+
+impl Default for WakeStrategy {
+    fn default() -> Self {
+        WakeStrategy::WakeAll
+    }
+}
+
+/// Policy used to decide which of the threads waiting to read from a [`Channel`] are woken first
+/// when new messages are written to it.
+///
+/// Policies only decide which waiting threads are woken, not which thread reads each message: a
+/// woken thread may find that the message has already been read by another thread (e.g. one that
+/// read without waiting), in which case it just waits again. Policies other than
+/// [`ConsumerPolicy::FirstCome`] therefore only distribute messages as described when all the
+/// readers of the channel wait on it before reading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsumerPolicy {
+    /// Wake waiting threads according to the [`WakeStrategy`] of the channel, and let whichever is
+    /// scheduled first read the messages.
+    FirstCome,
+    /// Wake at most one waiting thread per available message, rotating through the consumers of
+    /// the channel (i.e. the threads that have waited on it) in the order in which they first
+    /// waited, so that messages are distributed evenly across them.
+    RoundRobin,
+    /// Wake at most one waiting thread per available message, preferring the consumers of the
+    /// channel whose Node has the highest priority (see
+    /// [`crate::RuntimeProxy::channel_set_consumer_priority`]), and then the consumers that first
+    /// waited on the channel.
+    Priority,
+    /// Wake at most one waiting thread per available message, in the order in which the threads
+    /// started waiting on the channel, so that a single message wakes a single waiter and every
+    /// waiter is eventually woken while messages keep arriving.
+    ///
+    /// A thread keeps its place in the queue for as long as it waits, even across spurious
+    /// wakeups, and leaves it once it stops waiting. A woken thread that starts a new wait without
+    /// having read a message gives up its claim on the message, which then wakes the next waiter.
+    Fifo,
+}
+
+impl Default for ConsumerPolicy {
+    fn default() -> Self {
+        ConsumerPolicy::FirstCome
+    }
+}
+
+/// Reason given by a writer for closing its handle to a [`Channel`].
+///
+/// The reason given by the last writer is recorded on the [`Channel`] when it becomes orphaned, so
+/// that readers can distinguish graceful completion from failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The writer completed normally.
+    Completed,
+    /// The writer failed, with an application-specific error code.
+    Error(u32),
+    /// The writer was canceled before completing.
+    Canceled,
+}
+
+impl Default for CloseReason {
+    fn default() -> Self {
+        CloseReason::Completed
+    }
+}
+
+/// A message that could not be appended to a [`Channel`], handed back to the writer along with the
+/// reason.
+#[derive(Debug)]
+pub enum RejectedMessage {
+    /// The [`Channel`] already holds as many messages as its capacity allows.
+    ChannelFull(Message),
+    /// The [`Channel`] has no readers left.
+    ChannelClosed(Message),
+}
+
+/// Options that govern the behaviour of a newly created [`Channel`].
+#[derive(Clone, Debug, Default)]
+pub struct ChannelOptions {
+    /// Strategy for waking waiting threads when messages are written to the channel.
+    pub wake_strategy: WakeStrategy,
+    /// Policy for choosing which waiting threads to wake when messages are written to the channel.
+    /// Policies other than [`ConsumerPolicy::FirstCome`] wake at most one thread per available
+    /// message regardless of the [`WakeStrategy`], so they come with the same caveats as
+    /// [`WakeStrategy::WakeOne`].
+    pub consumer_policy: ConsumerPolicy,
+    /// Maximum number of messages that may be queued on the channel, or `None` if the channel is
+    /// unbounded. Writes to a channel that is at capacity fail until a reader drains it.
+    pub capacity: Option<usize>,
+    /// Validator for the contents of the messages written to the channel, if provided. Writes of
+    /// messages rejected by the validator fail, and the messages are not enqueued.
+    pub validator: Option<MessageValidator>,
+}
+
+/// A check of the contents of the messages written to a channel, returning a description of the
+/// problem for invalid messages.
+///
+/// The check is performed on every write to the channel, so it should be cheap.
+#[derive(Clone)]
+pub struct MessageValidator(Arc<ValidatorFn>);
+
+type ValidatorFn = dyn Fn(&[u8]) -> Result<(), String> + Send + Sync;
+
+impl MessageValidator {
+    pub fn new<F: Fn(&[u8]) -> Result<(), String> + Send + Sync + 'static>(validator: F) -> Self {
+        MessageValidator(Arc::new(validator))
+    }
+}
+
+impl std::fmt::Debug for MessageValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MessageValidator")
+    }
+}
+
+/// The consumers of a [`Channel`] with a [`ConsumerPolicy`] other than
+/// [`ConsumerPolicy::FirstCome`].
+#[derive(Default)]
+struct Consumers {
+    /// Threads that have waited to read from the channel and the Nodes they belong to, in the
+    /// order in which they first waited. Threads are unregistered once their Node no longer holds
+    /// a read half of the channel.
+    threads: Vec<(ThreadId, NodeId)>,
+    /// Position in `threads` of the next consumer to wake under [`ConsumerPolicy::RoundRobin`].
+    next: usize,
+    /// Priorities of the Nodes reading from the channel under [`ConsumerPolicy::Priority`].
+    /// Nodes default to priority zero.
+    priorities: HashMap<NodeId, i32>,
+    /// Threads waiting to read from the channel under [`ConsumerPolicy::Fifo`], in the order in
+    /// which they started waiting, along with the sequence number of their wait. Entries of waits
+    /// that are no longer in `queued` are stale, and are skipped and dropped lazily.
+    queue: VecDeque<(ThreadId, u64)>,
+    /// Sequence number of the current wait of each thread in `queue`, and the Node the thread
+    /// belongs to.
+    queued: HashMap<ThreadId, (u64, NodeId)>,
+    /// Sequence number of the next wait added to `queue`.
+    next_sequence_number: u64,
+    /// Threads woken under [`ConsumerPolicy::Fifo`] that have not read a message from the channel
+    /// yet. Each of them has a message promised, which is not used to wake other threads.
+    woken: HashMap<ThreadId, WokenConsumer>,
+}
+
+/// A thread woken under [`ConsumerPolicy::Fifo`] that has not read a message from the channel yet.
+struct WokenConsumer {
+    /// Node the thread belongs to.
+    node_id: NodeId,
+    /// Whether the wait during which the thread was woken has ended. The thread keeps its claim on
+    /// a message while it is still checking the channels of that wait.
+    wait_ended: bool,
+}
+
+impl Consumers {
+    /// Unregisters the threads of the Node identified by `node_id`, along with its priority.
+    /// Returns whether any of these threads had a message promised under [`ConsumerPolicy::Fifo`].
+    fn remove_node(&mut self, node_id: NodeId) -> bool {
+        let node_threads: Vec<ThreadId> = self
+            .queued
+            .iter()
+            .filter(|(_, (_, consumer_node_id))| *consumer_node_id == node_id)
+            .map(|(thread_id, _)| *thread_id)
+            .collect();
+        for thread_id in node_threads {
+            self.dequeue(thread_id);
+        }
+        let woken_count = self.woken.len();
+        self.woken.retain(|_, consumer| consumer.node_id != node_id);
+        let released = self.woken.len() < woken_count;
+
+        self.priorities.remove(&node_id);
+        // Keep the round-robin position on the same consumer, or on the one following it if it is
+        // removed.
+        let next = self.next;
+        let mut index = 0;
+        let mut removed_before_next = 0;
+        self.threads.retain(|(_, consumer_node_id)| {
+            let keep = *consumer_node_id != node_id;
+            if !keep && index < next {
+                removed_before_next += 1;
+            }
+            index += 1;
+            keep
+        });
+        let count = self.threads.len();
+        self.next = if count == 0 {
+            0
+        } else {
+            (next - removed_before_next) % count
+        };
+        released
+    }
+
+    /// Removes the current wait of the thread identified by `thread_id` from the FIFO queue, if
+    /// any, dropping stale entries once they make up most of the queue.
+    fn dequeue(&mut self, thread_id: ThreadId) {
+        if self.queued.remove(&thread_id).is_none() {
+            return;
+        }
+        if self.queue.len() > 2 * self.queued.len() {
+            let queued = &self.queued;
+            self.queue.retain(|(thread_id, sequence_number)| {
+                matches!(queued.get(thread_id), Some((current, _)) if current == sequence_number)
+            });
+        }
+    }
+}
+
 /// The internal implementation of a channel representation backed by a `VecDeque<Message>`.
 ///
 /// Channels are reference counted using `Arc<Channel>`, which are always in the form of a
@@ -72,6 +295,11 @@ pub struct Channel {
     /// Threads can be woken up spuriously without issue.
     waiting_threads: WaitingThreads,
 
+    /// Threads waiting for the channel to drop below its capacity, in the same form as
+    /// `waiting_threads`. They are woken when a message is read from the channel, or when the
+    /// channel loses its last reader.
+    waiting_writers: WaitingThreads,
+
     /// The `Label` associated with this channel.
     ///
     /// This is set at channel creation time and does not change after that.
@@ -79,12 +307,50 @@ pub struct Channel {
     /// See https://github.com/project-oak/oak/blob/main/docs/concepts.md#labels
     pub label: oak_abi::label::Label,
 
+    /// The serialized form of [`Channel::label`], computed once at channel creation time so that
+    /// its size is stable across the calls needed to retrieve it through the ABI.
+    serialized_label: Vec<u8>,
+
     /// The name for the channel.
     ///
     /// The name does not have to be unique and can be empty. In logs it is
     /// combined with the id to form a unique debug_ig to identify channels.
     name: String,
 
+    /// Strategy for waking waiting threads when messages are written to the channel.
+    wake_strategy: WakeStrategy,
+
+    /// Policy for choosing which waiting threads to wake when messages are written to the channel.
+    consumer_policy: ConsumerPolicy,
+
+    /// The consumers of the channel, only tracked for policies other than
+    /// [`ConsumerPolicy::FirstCome`].
+    consumers: Mutex<Consumers>,
+
+    /// Maximum number of messages that may be queued on the channel, or `None` if unbounded.
+    capacity: Option<usize>,
+
+    /// Validator for the contents of the messages written to the channel, if any.
+    validator: Option<MessageValidator>,
+
+    /// Reason given by the last writer when closing its handle, recorded once the channel becomes
+    /// orphaned. The lock is also held while closing writer halves with an explicit reason, so
+    /// that concurrent closes agree on which of them is the last writer.
+    close_reason: Mutex<Option<CloseReason>>,
+
+    /// Time at which the channel was created. For debugging/introspection purposes.
+    created: Instant,
+
+    /// Time of the most recent read or write on the channel, or of its creation if there has been
+    /// none, in nanoseconds since [`Channel::created`]. This is updated on every read and write,
+    /// so it is stored as an atomic rather than behind a lock. For debugging/introspection
+    /// purposes.
+    last_activity_nanos: AtomicU64,
+
+    /// Number of messages written to the channel since its creation. For debugging/introspection
+    /// purposes.
+    messages_written: AtomicU64,
+
     /// Weak reference to the Runtime used for sending introspection events.
     runtime_weak: Weak<Runtime>,
 }
@@ -126,17 +392,56 @@ impl ChannelHalf {
         self.channel.id
     }
 
+    /// Returns the name of the underlying channel. For debugging/introspection purposes.
+    pub fn get_channel_name(&self) -> &str {
+        &self.channel.name
+    }
+
     /// Returns a unique debug_id used to identify the channel in debug output.
     pub fn get_channel_debug_id(&self) -> String {
         self.channel.get_debug_id()
     }
 
+    /// Returns the [`oak_abi::label::Label`] of the underlying channel. For debugging/introspection
+    /// purposes.
+    pub fn get_label(&self) -> &oak_abi::label::Label {
+        &self.channel.label
+    }
+
+    /// Checks the contents of a message to be written to the underlying channel using its
+    /// [`MessageValidator`], if any, returning a description of the problem if it is invalid.
+    pub fn validate_message(&self, bytes: &[u8]) -> Result<(), String> {
+        match &self.channel.validator {
+            Some(MessageValidator(validator)) => validator(bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the serialized [`oak_abi::label::Label`] of the underlying channel.
+    pub fn get_serialized_label(&self) -> &[u8] {
+        &self.channel.serialized_label
+    }
+
     /// Get read-only access to the channel's messages.  For debugging/introspection
     /// purposes.
     pub fn get_messages(&self) -> RwLockReadGuard<Messages> {
         self.channel.messages.read().unwrap()
     }
 
+    /// Returns the time elapsed since the underlying channel was last read from or written to.
+    /// For debugging/introspection purposes.
+    pub fn get_idle_time(&self) -> Duration {
+        let last_activity = self.channel.created
+            + Duration::from_nanos(self.channel.last_activity_nanos.load(SeqCst));
+        last_activity.elapsed()
+    }
+
+    /// Returns the number of messages written to the underlying channel since its creation. For
+    /// debugging/introspection purposes.
+    pub fn get_messages_written(&self) -> u64 {
+        self.channel.messages_written.load(SeqCst)
+    }
+
     /// Visit all channel halves that are reachable via this `ChannelHalf`, starting with
     /// `self`. The `visitor` function should return a boolean indicating whether the provided half
     /// needs to be further explored.  For debugging/introspection purposes.
@@ -161,9 +466,78 @@ impl ChannelHalf {
         }
     }
 
-    /// Wake any threads waiting on the underlying channel.
+    /// Returns a new [`ChannelHalf`] with the same direction as `self`, referring to the channel
+    /// underlying `other` instead.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn rebind(&self, other: &ChannelHalf) -> ChannelHalf {
+        ChannelHalf::new(other.channel.clone(), self.direction)
+    }
+
+    /// Moves all the messages queued on the underlying channel to the front of the queue of the
+    /// channel underlying `target`, preserving their order, and returns how many were moved.
+    ///
+    /// The messages of both channels are locked in order of channel ID, so that concurrent moves
+    /// between the same channels cannot deadlock. The capacity of the target channel is not
+    /// enforced, so it may end up over capacity until enough messages are read from it.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn move_messages_to(&self, target: &ChannelHalf) -> usize {
+        if Arc::ptr_eq(&self.channel, &target.channel) {
+            return 0;
+        }
+        let count = {
+            let (mut source_messages, mut target_messages) = if self.channel.id < target.channel.id
+            {
+                let source_messages = self.channel.messages.write().unwrap();
+                (source_messages, target.channel.messages.write().unwrap())
+            } else {
+                let target_messages = target.channel.messages.write().unwrap();
+                (self.channel.messages.write().unwrap(), target_messages)
+            };
+            let count = source_messages.len();
+            for message in source_messages.drain(..).rev() {
+                target_messages.push_front(message);
+            }
+            count
+        };
+        if count > 0 {
+            target.channel.record_activity();
+            target.channel.wake_waiters_for_messages();
+            self.channel.wake_writer_waiters();
+        }
+        count
+    }
+
+    /// Records that the thread identified by `thread_id` has stopped waiting on the underlying
+    /// channel.
+    pub fn remove_waiter(&self, thread_id: ThreadId) {
+        self.channel.remove_waiter(thread_id);
+    }
+
+    /// Unregisters the Node identified by `node_id` as a consumer of the underlying channel, once
+    /// it no longer holds a read half of it.
+    pub fn remove_consumer(&self, node_id: NodeId) {
+        self.channel.remove_consumer(node_id);
+    }
+
+    /// Wake any threads waiting on the underlying channel, either for messages or for capacity.
     pub fn wake_waiters(&self) {
         self.channel.wake_waiters();
+        self.channel.wake_writer_waiters();
+    }
+
+    /// Drop this [`ChannelHalf`], recording `reason` on the underlying channel if this is the
+    /// last write half, so that it becomes the reason the channel was orphaned.
+    ///
+    /// The reason is ignored for read halves, and for write halves that are not the last one.
+    pub fn close(self, reason: CloseReason) {
+        let channel = self.channel.clone();
+        let mut close_reason = channel.close_reason.lock().unwrap();
+        if self.direction == ChannelHalfDirection::Write && channel.writer_count.load(SeqCst) == 1 {
+            *close_reason = Some(reason);
+        }
+        // Drop the half while holding the lock, so that the writer count cannot be observed
+        // between recording the reason and orphaning the channel.
+        drop(self);
     }
 }
 
@@ -179,10 +553,21 @@ impl Clone for ChannelHalf {
 /// sync.
 impl Drop for ChannelHalf {
     fn drop(&mut self) {
-        match self.direction {
-            ChannelHalfDirection::Write => self.channel.dec_writer_count(),
-            ChannelHalfDirection::Read => self.channel.dec_reader_count(),
+        let orphaned = match self.direction {
+            ChannelHalfDirection::Write => self.channel.dec_writer_count() == 1,
+            ChannelHalfDirection::Read => self.channel.dec_reader_count() == 1,
         };
+        if orphaned {
+            if let Some(runtime) = self.channel.runtime_weak.upgrade() {
+                runtime.introspection_event(EventDetails::ChannelOrphaned(ChannelOrphaned {
+                    channel_id: self.channel.id,
+                    direction: match self.direction {
+                        ChannelHalfDirection::Read => Direction::Read as i32,
+                        ChannelHalfDirection::Write => Direction::Write as i32,
+                    },
+                }));
+            }
+        }
         if self.direction == ChannelHalfDirection::Write && !self.channel.has_writers() {
             // This was the last writer to the channel: wake any waiters so they
             // can be aware that the channel is orphaned.
@@ -192,6 +577,15 @@ impl Drop for ChannelHalf {
             );
             self.wake_waiters();
         }
+        if self.direction == ChannelHalfDirection::Read && !self.channel.has_readers() {
+            // This was the last reader of the channel: wake any writers blocked on its capacity so
+            // they can be aware that the channel is orphaned.
+            debug!(
+                "last reader for channel {} gone, wake writers",
+                self.channel.id
+            );
+            self.channel.wake_writer_waiters();
+        }
     }
 }
 
@@ -271,6 +665,9 @@ impl Drop for Channel {
                 runtime.introspection_event(EventDetails::ChannelDestroyed(ChannelDestroyed {
                     channel_id: self.id,
                 }));
+                runtime.remove_channel_queue_length(self.id);
+                runtime.remove_channel_message_rate(self.id);
+                runtime.release_channel_name(&self.name, self.id);
             }
             None => {
                 error!("Couldn't send ChannelDestroyed event, since the runtime has been dropped. {:?}", self);
@@ -282,6 +679,7 @@ impl Drop for Channel {
         // channel to existence) so no need to `wake_waiters()`.
         // Deliberately clear the HashMap under the lock to avoid a TSAN report.
         self.waiting_threads.lock().unwrap().clear();
+        self.waiting_writers.lock().unwrap().clear();
     }
 }
 
@@ -290,6 +688,7 @@ impl Channel {
         id: ChannelId,
         name: &str,
         label: &oak_abi::label::Label,
+        options: &ChannelOptions,
         runtime_weak: Weak<Runtime>,
     ) -> Arc<Channel> {
         debug!("create new Channel object with ID {}", id);
@@ -299,8 +698,19 @@ impl Channel {
             writer_count: AtomicU64::new(0),
             reader_count: AtomicU64::new(0),
             waiting_threads: Mutex::new(HashMap::new()),
+            waiting_writers: Mutex::new(HashMap::new()),
             label: label.clone(),
+            serialized_label: serialize_label(label),
             name: name.to_string(),
+            wake_strategy: options.wake_strategy,
+            consumer_policy: options.consumer_policy,
+            consumers: Mutex::new(Consumers::default()),
+            capacity: options.capacity,
+            validator: options.validator.clone(),
+            close_reason: Mutex::new(None),
+            created: Instant::now(),
+            last_activity_nanos: AtomicU64::new(0),
+            messages_written: AtomicU64::new(0),
             runtime_weak,
         })
     }
@@ -311,6 +721,23 @@ impl Channel {
         format!("{}({})", &self.name, self.id)
     }
 
+    /// Record that the channel has just been read from or written to.
+    pub fn record_activity(&self) {
+        self.last_activity_nanos
+            .fetch_max(self.created.elapsed().as_nanos() as u64, SeqCst);
+    }
+
+    /// Record that a message has just been written to the channel.
+    pub fn record_message_written(&self) {
+        self.messages_written.fetch_add(1, SeqCst);
+    }
+
+    /// Returns the maximum number of messages that may be queued on the channel, or `None` if the
+    /// channel is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
     /// Determine whether there are any readers of the channel.
     pub fn has_readers(&self) -> bool {
         self.reader_count.load(SeqCst) > 0
@@ -321,18 +748,35 @@ impl Channel {
         self.writer_count.load(SeqCst) > 0
     }
 
-    /// Decrement the [`Channel`] writer counter.
-    fn dec_writer_count(&self) {
-        if self.writer_count.fetch_sub(1, SeqCst) == 0 {
+    /// Returns the reason the channel was orphaned, or `None` if the channel still has writers.
+    ///
+    /// Writers that went away without giving an explicit reason (e.g. because their Node exited)
+    /// are treated as having completed normally.
+    pub fn close_reason(&self) -> Option<CloseReason> {
+        let close_reason = self.close_reason.lock().unwrap();
+        if self.has_writers() {
+            None
+        } else {
+            Some(close_reason.unwrap_or_default())
+        }
+    }
+
+    /// Decrement the [`Channel`] writer counter, returning its previous value.
+    fn dec_writer_count(&self) -> u64 {
+        let count = self.writer_count.fetch_sub(1, SeqCst);
+        if count == 0 {
             panic!("remove_reader: Writer count was already 0, something is very wrong!")
         }
+        count
     }
 
-    /// Decrement the [`Channel`] reader counter.
-    fn dec_reader_count(&self) {
-        if self.reader_count.fetch_sub(1, SeqCst) == 0 {
+    /// Decrement the [`Channel`] reader counter, returning its previous value.
+    fn dec_reader_count(&self) -> u64 {
+        let count = self.reader_count.fetch_sub(1, SeqCst);
+        if count == 0 {
             panic!("remove_reader: Reader count was already 0, something is very wrong!")
         }
+        count
     }
 
     /// Increment the [`Channel`] writer counter.
@@ -345,14 +789,126 @@ impl Channel {
         self.reader_count.fetch_add(1, SeqCst)
     }
 
-    /// Add the given [`Thread`] reference into the collection of [`Thread`]s waiting on this
-    /// [`Channel`]'s readability.  Threads waiting on the [`Channel`] will be woken when
-    /// data is available, or if the [`Channel`] becomes orphaned (no writers left).
-    pub fn add_waiter(&self, thread: &Arc<Thread>) {
+    /// Add the given [`Thread`] reference, belonging to the Node identified by `node_id`, into the
+    /// collection of [`Thread`]s waiting on this [`Channel`]'s readability.  Threads waiting on the
+    /// [`Channel`] will be woken when data is available, or if the [`Channel`] becomes orphaned
+    /// (no writers left).
+    pub fn add_waiter(&self, node_id: NodeId, thread: &Arc<Thread>) {
         self.waiting_threads
             .lock()
             .unwrap()
             .insert(thread.id(), Arc::downgrade(thread));
+        match self.consumer_policy {
+            ConsumerPolicy::FirstCome => {}
+            ConsumerPolicy::Fifo => {
+                let thread_id = thread.id();
+                let released = {
+                    let mut consumers = self.consumers.lock().unwrap();
+                    if !consumers.queued.contains_key(&thread_id) {
+                        let sequence_number = consumers.next_sequence_number;
+                        consumers.next_sequence_number += 1;
+                        consumers
+                            .queued
+                            .insert(thread_id, (sequence_number, node_id));
+                        consumers.queue.push_back((thread_id, sequence_number));
+                    }
+                    // A woken thread starting a new wait without having read a message gives up
+                    // its claim on it.
+                    match consumers.woken.get(&thread_id) {
+                        Some(consumer) if consumer.wait_ended => {
+                            consumers.woken.remove(&thread_id);
+                            true
+                        }
+                        _ => false,
+                    }
+                };
+                if released {
+                    self.wake_consumers();
+                }
+            }
+            _ => {
+                let mut consumers = self.consumers.lock().unwrap();
+                if !consumers.threads.iter().any(|(id, _)| *id == thread.id()) {
+                    consumers.threads.push((thread.id(), node_id));
+                }
+            }
+        }
+    }
+
+    /// Records that the thread identified by `thread_id` has stopped waiting on the [`Channel`],
+    /// so that it leaves the queue of waiters under [`ConsumerPolicy::Fifo`].
+    pub fn remove_waiter(&self, thread_id: ThreadId) {
+        if self.consumer_policy == ConsumerPolicy::Fifo {
+            let mut consumers = self.consumers.lock().unwrap();
+            consumers.dequeue(thread_id);
+            if let Some(consumer) = consumers.woken.get_mut(&thread_id) {
+                consumer.wait_ended = true;
+            }
+        }
+    }
+
+    /// Records that the current thread has read a message from the [`Channel`], fulfilling the
+    /// claim it had on a message if it was woken under [`ConsumerPolicy::Fifo`].
+    pub fn record_message_read(&self) {
+        if self.consumer_policy == ConsumerPolicy::Fifo {
+            self.consumers
+                .lock()
+                .unwrap()
+                .woken
+                .remove(&thread::current().id());
+        }
+    }
+
+    /// Sets the priority of the Node identified by `node_id` as a consumer of the [`Channel`],
+    /// used under [`ConsumerPolicy::Priority`].
+    pub fn set_consumer_priority(&self, node_id: NodeId, priority: i32) {
+        self.consumers
+            .lock()
+            .unwrap()
+            .priorities
+            .insert(node_id, priority);
+    }
+
+    /// Unregisters the threads of the Node identified by `node_id` as consumers of the [`Channel`],
+    /// along with its priority.
+    fn remove_consumer(&self, node_id: NodeId) {
+        if self.consumer_policy == ConsumerPolicy::FirstCome {
+            return;
+        }
+        let released = self.consumers.lock().unwrap().remove_node(node_id);
+        // Messages promised to the threads of the Node are available to other waiters again.
+        if released {
+            self.wake_consumers();
+        }
+    }
+
+    /// Returns the threads woken under [`ConsumerPolicy::Fifo`] that have not read a message yet.
+    #[cfg(test)]
+    pub(crate) fn woken_consumers(&self) -> Vec<ThreadId> {
+        self.consumers
+            .lock()
+            .unwrap()
+            .woken
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Returns the number of threads registered as consumers of the [`Channel`].
+    #[cfg(test)]
+    pub(crate) fn consumer_count(&self) -> usize {
+        self.consumers.lock().unwrap().threads.len()
+    }
+
+    /// Returns the number of threads currently waiting on the [`Channel`]'s readability.
+    #[cfg(test)]
+    pub(crate) fn waiter_count(&self) -> usize {
+        self.waiting_threads
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|thread| thread.upgrade().is_some())
+            .count()
     }
 
     /// Wake any [`Thread`]s that are waiting on the [`Channel`].
@@ -375,4 +931,149 @@ impl Channel {
         }
         waiting_threads.clear();
     }
+
+    /// Add the given [`Thread`] reference into the collection of [`Thread`]s waiting for this
+    /// [`Channel`] to drop below its capacity. Threads waiting on the [`Channel`] will be woken
+    /// when a message is read from it, or if the [`Channel`] loses its last reader.
+    pub fn add_writer_waiter(&self, thread: &Arc<Thread>) {
+        self.waiting_writers
+            .lock()
+            .unwrap()
+            .insert(thread.id(), Arc::downgrade(thread));
+    }
+
+    /// Wake any [`Thread`]s that are waiting for the [`Channel`] to drop below its capacity.
+    pub fn wake_writer_waiters(&self) {
+        let mut waiting_writers = self.waiting_writers.lock().unwrap();
+        for thread in waiting_writers.values() {
+            if let Some(thread) = thread.upgrade() {
+                thread.unpark();
+            }
+        }
+        waiting_writers.clear();
+    }
+
+    /// Appends `msg` to the messages queued on the [`Channel`] and wakes waiting readers, unless
+    /// the [`Channel`] is at capacity or has no readers left, in which case `msg` is handed back to
+    /// the caller.
+    pub fn try_push_message(&self, msg: Message) -> Result<(), RejectedMessage> {
+        if !self.has_readers() {
+            return Err(RejectedMessage::ChannelClosed(msg));
+        }
+        {
+            let mut messages = self.messages.write().unwrap();
+            if let Some(capacity) = self.capacity {
+                if messages.len() >= capacity {
+                    return Err(RejectedMessage::ChannelFull(msg));
+                }
+            }
+            self.record_activity();
+            self.record_message_written();
+            messages.push_back(msg);
+        }
+        self.wake_waiters_for_messages();
+        Ok(())
+    }
+
+    /// Wake [`Thread`]s waiting on the [`Channel`] after new messages have been written to it,
+    /// according to the [`ConsumerPolicy`] and the [`WakeStrategy`] of the [`Channel`].
+    pub fn wake_waiters_for_messages(&self) {
+        if self.consumer_policy != ConsumerPolicy::FirstCome {
+            self.wake_consumers();
+            return;
+        }
+        match self.wake_strategy {
+            WakeStrategy::WakeAll => self.wake_waiters(),
+            WakeStrategy::WakeOne => {
+                let available = self.messages.read().unwrap().len();
+                let mut woken = 0;
+                // Stale references are dropped along the way, but waiters beyond the number of
+                // available messages are left in place to be woken by subsequent writes.
+                self.waiting_threads.lock().unwrap().retain(|_, thread| {
+                    if woken >= available {
+                        return true;
+                    }
+                    if let Some(thread) = thread.upgrade() {
+                        thread.unpark();
+                        woken += 1;
+                    }
+                    false
+                });
+            }
+        }
+    }
+
+    /// Wake at most as many waiting consumers as there are messages available on the [`Channel`],
+    /// in the order given by its [`ConsumerPolicy`].
+    fn wake_consumers(&self) {
+        let available = self.messages.read().unwrap().len();
+        let mut consumers = self.consumers.lock().unwrap();
+        let mut waiting_threads = self.waiting_threads.lock().unwrap();
+        if self.consumer_policy == ConsumerPolicy::Fifo {
+            // Messages already promised to woken threads are not available to wake others.
+            let mut to_wake = available.saturating_sub(consumers.woken.len());
+            while to_wake > 0 {
+                let (thread_id, sequence_number) = match consumers.queue.pop_front() {
+                    Some(entry) => entry,
+                    None => break,
+                };
+                // Stale entries of waits that have ended are dropped along the way.
+                let node_id = match consumers.queued.get(&thread_id) {
+                    Some((current, node_id)) if *current == sequence_number => *node_id,
+                    _ => continue,
+                };
+                consumers.queued.remove(&thread_id);
+                if let Some(thread) = waiting_threads
+                    .remove(&thread_id)
+                    .and_then(|thread| thread.upgrade())
+                {
+                    thread.unpark();
+                    consumers.woken.insert(
+                        thread_id,
+                        WokenConsumer {
+                            node_id,
+                            wait_ended: false,
+                        },
+                    );
+                    to_wake -= 1;
+                }
+            }
+            return;
+        }
+        let count = consumers.threads.len();
+        let order: Vec<usize> = match self.consumer_policy {
+            ConsumerPolicy::Priority => {
+                let mut order: Vec<usize> = (0..count).collect();
+                // The sort is stable, so consumers with the same priority stay in the order in
+                // which they first waited.
+                order.sort_by_key(|&i| {
+                    Reverse(
+                        consumers
+                            .priorities
+                            .get(&consumers.threads[i].1)
+                            .copied()
+                            .unwrap_or_default(),
+                    )
+                });
+                order
+            }
+            _ => (0..count).map(|i| (consumers.next + i) % count).collect(),
+        };
+        let mut woken = 0;
+        for i in order {
+            if woken >= available {
+                break;
+            }
+            // Consumers that are not currently waiting are skipped, and stale references are
+            // dropped along the way.
+            if let Some(thread) = waiting_threads
+                .remove(&consumers.threads[i].0)
+                .and_then(|thread| thread.upgrade())
+            {
+                thread.unpark();
+                woken += 1;
+                consumers.next = (i + 1) % count;
+            }
+        }
+    }
 }
@@ -0,0 +1,123 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Migration of the queued messages and handles of a channel to a replacement channel, e.g. in
+//! order to change the capacity of a channel without losing the messages in flight.
+
+use crate::{
+    proto::oak::introspection_events::{
+        event::EventDetails, ChannelMigrated, Direction, HandleCreated, HandleDestroyed,
+    },
+    ChannelHalfDirection, Runtime,
+};
+use log::{info, warn};
+use oak_abi::OakStatus;
+
+// Channel migration related methods for the Runtime.
+impl Runtime {
+    /// Moves all the messages queued on the channel identified by `old_channel_id` to the front of
+    /// the queue of the channel identified by `new_channel_id`, preserving their order, and
+    /// rebinds all the handles of Nodes that refer to the old channel to the new channel, keeping
+    /// their values and directions. Returns the number of messages moved.
+    ///
+    /// Both channels must be referred to by the handle of at least one Node, and must have the
+    /// same label, since the migrated messages would otherwise become accessible at a different
+    /// label. Handles to the old channel that are held in queued messages are not rebound.
+    ///
+    /// The handle tables of all Nodes are locked for the duration of the migration, so Nodes
+    /// cannot observe the old and new channels in an intermediate state. Operations that are
+    /// already in progress on the old channel (e.g. a Node blocked waiting on it) are not
+    /// redirected, and so will observe the old channel without its messages.
+    pub fn migrate_channel(
+        &self,
+        old_channel_id: u64,
+        new_channel_id: u64,
+    ) -> Result<usize, OakStatus> {
+        if old_channel_id == new_channel_id {
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+        // Halves of the old channel that are replaced, which are only dropped once the handle
+        // tables are unlocked, since dropping them may destroy the old channel.
+        let mut replaced_halves = Vec::new();
+        let message_count = {
+            let mut node_infos = self.node_infos.write().unwrap();
+            let find_half = |channel_id: u64| {
+                node_infos
+                    .values()
+                    .flat_map(|node_info| node_info.abi_handles.values())
+                    .find(|half| half.get_channel_id() == channel_id)
+                    .cloned()
+                    .ok_or(OakStatus::ErrInvalidArgs)
+            };
+            let old_half = find_half(old_channel_id)?;
+            let new_half = find_half(new_channel_id)?;
+            // Labels with the same tags in a different order are equal.
+            if old_half.get_label().canonical() != new_half.get_label().canonical() {
+                warn!(
+                    "cannot migrate channel {} to channel {} with a different label",
+                    old_channel_id, new_channel_id
+                );
+                return Err(OakStatus::ErrPermissionDenied);
+            }
+
+            let message_count = old_half.move_messages_to(&new_half);
+            self.introspection_event(EventDetails::ChannelMigrated(ChannelMigrated {
+                old_channel_id,
+                new_channel_id,
+                message_count: message_count as u64,
+            }));
+
+            for (node_id, node_info) in node_infos.iter_mut() {
+                for (handle, half) in node_info.abi_handles.iter_mut() {
+                    if half.get_channel_id() != old_channel_id {
+                        continue;
+                    }
+                    let direction = match half.direction {
+                        ChannelHalfDirection::Read => Direction::Read as i32,
+                        ChannelHalfDirection::Write => Direction::Write as i32,
+                    };
+                    self.introspection_event(EventDetails::HandleDestroyed(HandleDestroyed {
+                        node_id: node_id.0,
+                        handle: *handle,
+                        channel_id: old_channel_id,
+                        direction,
+                    }));
+                    let new_half = half.rebind(&new_half);
+                    replaced_halves.push(std::mem::replace(half, new_half));
+                    self.introspection_event(EventDetails::HandleCreated(HandleCreated {
+                        node_id: node_id.0,
+                        handle: *handle,
+                        channel_id: new_channel_id,
+                        direction,
+                    }));
+                }
+            }
+
+            self.update_channel_queue_length(&old_half);
+            self.update_channel_queue_length(&new_half);
+            message_count
+        };
+        info!(
+            "migrated {} messages and {} handles from channel {} to channel {}",
+            message_count,
+            replaced_halves.len(),
+            old_channel_id,
+            new_channel_id
+        );
+        drop(replaced_halves);
+        Ok(message_count)
+    }
+}
@@ -16,27 +16,45 @@
 
 //! Functionality covering configuration of a Runtime instance.
 
-use crate::{io::SenderExt, Runtime, RuntimeConfiguration, RuntimeProxy};
+use crate::{
+    io::SenderExt,
+    node::{find_wasm_module, ConfigurationError},
+    NodeMessage, Runtime, RuntimeConfiguration, RuntimeProxy,
+};
 use log::{error, info};
+use oak_abi::{
+    proto::oak::application::{node_configuration::ConfigType, ApplicationConfiguration},
+    OakStatus,
+};
 use oak_io::{handle::WriteHandle, OakError};
 use std::sync::Arc;
 
+/// Checks that the Node configurations known at startup only reference WebAssembly modules that
+/// are present in the provided [`ApplicationConfiguration`].
+pub(crate) fn validate_app_config(
+    app_config: &ApplicationConfiguration,
+) -> Result<(), ConfigurationError> {
+    if let Some(ConfigType::WasmConfig(config)) = app_config
+        .initial_node_configuration
+        .as_ref()
+        .and_then(|node_config| node_config.config_type.as_ref())
+    {
+        find_wasm_module(app_config, &config.wasm_module_name)?;
+    }
+    Ok(())
+}
+
 /// Configures a [`Runtime`] from the given [`RuntimeConfiguration`] and begins execution.
 ///
 /// Returns a [`RuntimeProxy`] for an initial implicit Node, and a writeable [`oak_abi::Handle`] to
 /// send messages into the Runtime. Creating a new channel and passing the write [`oak_abi::Handle`]
 /// into the runtime will enable messages to be read back out from the [`RuntimeProxy`].
+///
+/// If [`RuntimeConfiguration::strict_config_validation`] is set, the application configuration is
+/// validated before anything is started.
 pub fn configure_and_run(config: RuntimeConfiguration) -> Result<Arc<Runtime>, OakError> {
-    let proxy = RuntimeProxy::create_runtime(
-        &config.app_config,
-        &config.permissions_config,
-        &config.secure_server_configuration,
-        &config.sign_table,
-        config.kms_credentials.as_ref(),
-    );
-    proxy.set_as_current();
     let config_map = config.config_map.clone();
-    let handle = proxy.start_runtime(config)?;
+    let (proxy, handle) = configure_and_start(config)?;
 
     // Pass in the config map over the initial channel.
     let sender = crate::io::Sender::new(WriteHandle { handle });
@@ -51,3 +69,66 @@ pub fn configure_and_run(config: RuntimeConfiguration) -> Result<Arc<Runtime>, O
     // Application's `ConfigMap`, drop all reference to it.
     Ok(proxy.runtime)
 }
+
+/// Same as [`configure_and_run`], but injects the provided raw `bytes` over the initial channel
+/// instead of the prost-encoded [`RuntimeConfiguration::config_map`], which is ignored.
+///
+/// This allows running Applications whose initial Node expects its configuration in a different
+/// format, e.g. JSON.
+pub fn configure_and_run_raw(
+    config: RuntimeConfiguration,
+    bytes: Vec<u8>,
+) -> Result<Arc<Runtime>, OakError> {
+    let (proxy, handle) = configure_and_start(config)?;
+    info!("Send in initial raw configuration");
+    inject_raw_bytes(&proxy, handle, bytes)?;
+
+    // Now that the implicit initial Node has been used to inject the
+    // Application's configuration, drop all reference to it.
+    Ok(proxy.runtime)
+}
+
+/// Creates and starts a [`Runtime`] from the given [`RuntimeConfiguration`], returning a
+/// [`RuntimeProxy`] for the initial implicit Node and the write [`oak_abi::Handle`] of the initial
+/// channel.
+fn configure_and_start(
+    config: RuntimeConfiguration,
+) -> Result<(RuntimeProxy, oak_abi::Handle), OakError> {
+    if config.strict_config_validation {
+        validate_app_config(&config.app_config).map_err(|err| {
+            error!("Invalid application configuration: {}", err);
+            OakError::OakStatus(OakStatus::ErrInvalidArgs)
+        })?;
+    }
+    let proxy = RuntimeProxy::create_runtime(
+        &config.app_config,
+        &config.permissions_config,
+        &config.secure_server_configuration,
+        &config.sign_table,
+        config.kms_credentials.as_ref(),
+    );
+    proxy.set_as_current();
+    let handle = proxy.start_runtime(config)?;
+    Ok((proxy, handle))
+}
+
+/// Writes `bytes` as a single message without handles to the channel identified by `handle`, and
+/// then closes `handle`.
+pub(crate) fn inject_raw_bytes(
+    proxy: &RuntimeProxy,
+    handle: oak_abi::Handle,
+    bytes: Vec<u8>,
+) -> Result<(), OakError> {
+    proxy.channel_write(
+        handle,
+        NodeMessage {
+            bytes,
+            handles: vec![],
+        },
+    )?;
+
+    if let Err(err) = proxy.channel_close(handle) {
+        error!("Failed to close initial handle {:?}: {:?}", handle, err);
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Forwarding of undeliverable messages to a dead-letter channel.
+//!
+//! When enabled, a message written to a channel that has no readers left is forwarded to the
+//! dead-letter channel as a [`DeadLetter`] message, identifying the Node that wrote it and the
+//! channel it was written to, rather than being dropped. The write still fails with
+//! [`OakStatus::ErrChannelClosed`](oak_abi::OakStatus::ErrChannelClosed).
+//!
+//! As for any other write, the message is only forwarded if the writing Node is allowed to write
+//! to the dead-letter channel.
+
+use crate::{
+    channel::{with_writer_channel, RejectedMessage},
+    message::Message,
+    proto::oak::dead_letter::DeadLetter,
+    ChannelHalf, Downgrading, NodeId, Runtime,
+};
+use log::warn;
+use prost::Message as _;
+
+// Dead-letter channel related methods for the Runtime.
+impl Runtime {
+    /// Forwards undeliverable messages to the channel that the write `half` refers to, or drops
+    /// them if `half` is `None`.
+    pub(crate) fn set_dead_letter_channel(&self, half: Option<ChannelHalf>) {
+        *self.dead_letter_channel.write().unwrap() = half;
+    }
+
+    /// Forwards the `data` of a message written by the Node identified by `node_id` to the
+    /// orphaned channel identified by `channel_id` to the dead-letter channel, if any.
+    pub(crate) fn forward_dead_letter(&self, node_id: NodeId, channel_id: u64, data: Vec<u8>) {
+        let half = match self.dead_letter_channel.read().unwrap().clone() {
+            Some(half) => half,
+            None => return,
+        };
+        if self
+            .validate_can_write_to_channel(node_id, &half, Downgrading::No)
+            .is_err()
+        {
+            return;
+        }
+        let dead_letter = DeadLetter {
+            source_node_id: node_id.0,
+            channel_id,
+            data,
+        };
+        let msg = Message {
+            data: dead_letter.encode_to_vec(),
+            channels: Vec::new(),
+            downgrade_history: Vec::new(),
+            writer: None,
+            expiry: None,
+        };
+        match with_writer_channel(&half, |channel| Ok(channel.try_push_message(msg))) {
+            Ok(Ok(())) => {
+                self.update_channel_queue_length(&half);
+                self.record_channel_message(&half);
+            }
+            Ok(Err(RejectedMessage::ChannelFull(_msg))) => warn!(
+                "{:?}: dead-letter channel full, dropping message for channel {}",
+                self.get_node_debug_id(node_id),
+                channel_id
+            ),
+            Ok(Err(RejectedMessage::ChannelClosed(_msg))) => warn!(
+                "{:?}: dead-letter channel closed, dropping message for channel {}",
+                self.get_node_debug_id(node_id),
+                channel_id
+            ),
+            Err(status) => warn!(
+                "{:?}: could not forward message for channel {} to dead-letter channel: {:?}",
+                self.get_node_debug_id(node_id),
+                channel_id,
+                status
+            ),
+        }
+    }
+}
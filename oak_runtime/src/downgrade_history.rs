@@ -0,0 +1,135 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Tracking of the downgrades applied to data as it flows through privileged Nodes.
+//!
+//! When enabled, each message carries the history of the downgrades that were applied to the data
+//! it was derived from. The Runtime cannot observe how a Node derives the messages it writes from
+//! the messages it reads, so it associates each message written by a Node with the history of the
+//! message most recently read by that Node. Reading a message using the Node's privilege appends
+//! a [`DowngradeRecord`] to that history, if the privilege actually changed the label of the
+//! channel.
+
+use crate::{ChannelHalf, Downgrading, NodeId, Runtime};
+use oak_abi::label::{Label, Tag};
+use std::sync::atomic::Ordering::SeqCst;
+
+/// A downgrade applied by a Node when reading data using its privilege.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DowngradeRecord {
+    /// The Node that applied the downgrade.
+    pub node_id: NodeId,
+    /// Confidentiality tags removed from the label of the data.
+    pub removed_tags: Vec<Tag>,
+    /// Integrity tags added to the label of the data.
+    pub added_tags: Vec<Tag>,
+}
+
+// Downgrade history related methods for the Runtime.
+impl Runtime {
+    /// Enables downgrade history tracking, keeping at most `limit` of the most recent entries in
+    /// the history of each message, or disables it if `limit` is `None`.
+    pub(crate) fn set_downgrade_history_limit(&self, limit: Option<usize>) {
+        self.downgrade_history_limit
+            .store(limit.unwrap_or(0), SeqCst);
+    }
+
+    /// Returns the maximum length of downgrade histories, or `None` if tracking is disabled.
+    fn get_downgrade_history_limit(&self) -> Option<usize> {
+        match self.downgrade_history_limit.load(SeqCst) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Returns the downgrade history of the message most recently read by the Node identified by
+    /// `node_id`, including any downgrade applied by the Node itself when reading it.
+    ///
+    /// This is also the history attached to messages subsequently written by the Node.
+    pub(crate) fn downgrade_history(&self, node_id: NodeId) -> Vec<DowngradeRecord> {
+        if self.get_downgrade_history_limit().is_none() {
+            return Vec::new();
+        }
+        let node_infos = self.node_infos.read().unwrap();
+        node_infos
+            .get(&node_id)
+            .expect("Invalid node_id")
+            .downgrade_history
+            .clone()
+    }
+
+    /// Records that the Node identified by `node_id` has read a message with the provided
+    /// `history` from the channel read half `half`, appending an entry for any downgrade applied
+    /// by the Node.
+    pub(crate) fn record_downgrade_history(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        downgrade: Downgrading,
+        mut history: Vec<DowngradeRecord>,
+    ) {
+        let limit = match self.get_downgrade_history_limit() {
+            Some(limit) => limit,
+            None => return,
+        };
+        if let Downgrading::Yes = downgrade {
+            if let Ok(channel_label) = self.get_reader_channel_label(half) {
+                // The downgrade was already recorded when checking the read.
+                let effective_label = self.get_node_downgraded_label(node_id, &channel_label);
+                if let Some(record) = downgrade_record(node_id, &channel_label, &effective_label) {
+                    history.push(record);
+                }
+            }
+        }
+        if history.len() > limit {
+            history.drain(..history.len() - limit);
+        }
+        let mut node_infos = self.node_infos.write().unwrap();
+        if let Some(node_info) = node_infos.get_mut(&node_id) {
+            node_info.downgrade_history = history;
+        }
+    }
+}
+
+/// Returns the [`DowngradeRecord`] describing how `original_label` was downgraded to
+/// `downgraded_label` by the Node identified by `node_id`, or `None` if the labels are the same.
+fn downgrade_record(
+    node_id: NodeId,
+    original_label: &Label,
+    downgraded_label: &Label,
+) -> Option<DowngradeRecord> {
+    let removed_tags: Vec<Tag> = original_label
+        .confidentiality_tags
+        .iter()
+        .filter(|tag| !downgraded_label.confidentiality_tags.contains(tag))
+        .cloned()
+        .collect();
+    let added_tags: Vec<Tag> = downgraded_label
+        .integrity_tags
+        .iter()
+        .filter(|tag| !original_label.integrity_tags.contains(tag))
+        .cloned()
+        .collect();
+    if removed_tags.is_empty() && added_tags.is_empty() {
+        None
+    } else {
+        Some(DowngradeRecord {
+            node_id,
+            removed_tags,
+            added_tags,
+        })
+    }
+}
@@ -19,7 +19,14 @@
 use crate::{channel::DotIdentifier, ChannelHalf, ChannelHalfDirection, NodeId, Runtime};
 use itertools::Itertools;
 use log::info;
-use std::{collections::HashSet, fmt::Write, string::String};
+use oak_abi::{label::Label, OakStatus};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    string::String,
+    sync::atomic::Ordering::SeqCst,
+    time::Duration,
+};
 
 /// Trait that returns the path at which the debug introspection server will
 /// show a page for a data structure.
@@ -51,6 +58,130 @@ impl HtmlPath for (NodeId, oak_abi::Handle) {
     }
 }
 
+/// A channel that has not been read from or written to for a while, as reported by
+/// [`Runtime::idle_channels`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdleChannel {
+    /// Internal identifier of the channel.
+    pub channel_id: u64,
+    /// Debug identifier of the channel, which includes its name.
+    pub debug_id: String,
+    /// Time elapsed since the channel was last read from or written to.
+    pub idle_time: Duration,
+}
+
+/// A channel through which a Node may send messages to another Node, as reported by
+/// [`Runtime::trace_link`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkChannel {
+    /// Internal identifier of the channel.
+    pub channel_id: u64,
+    /// Debug identifier of the channel, which includes its name.
+    pub debug_id: String,
+    /// Number of messages written to the channel since its creation, by any writer.
+    pub messages_written: u64,
+    /// Number of messages currently waiting in the channel to be read.
+    pub messages_pending: u64,
+}
+
+/// The state of a Node, as recorded in a [`StateSnapshot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeSnapshot {
+    /// Name of the Node.
+    pub name: String,
+    /// Name of the type of the Node.
+    pub node_type: &'static str,
+    /// Label of the Node.
+    pub label: Label,
+    /// ABI handle passed to the Node when starting it, if any.
+    pub initial_handle: Option<oak_abi::Handle>,
+}
+
+/// The state of a channel, as recorded in a [`StateSnapshot`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelSnapshot {
+    /// Label of the channel.
+    pub label: Label,
+    /// Number of messages currently waiting in the channel to be read.
+    pub depth: usize,
+}
+
+/// The state of a handle held by a Node, as recorded in a [`StateSnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandleSnapshot {
+    /// Internal identifier of the channel the handle refers to.
+    pub channel_id: u64,
+    /// Direction of the channel half the handle refers to.
+    pub direction: ChannelHalfDirection,
+}
+
+/// A snapshot of the Nodes, channels and handles of the [`Runtime`], as returned by
+/// [`Runtime::state_snapshot`].
+///
+/// Snapshots can be compared with each other, either directly or with [`StateSnapshot::diff`],
+/// e.g. to check in tests which objects an operation created or destroyed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateSnapshot {
+    /// Nodes, indexed by Node ID.
+    pub nodes: BTreeMap<NodeId, NodeSnapshot>,
+    /// Channels reachable from Node handles, indexed by channel ID.
+    pub channels: BTreeMap<u64, ChannelSnapshot>,
+    /// Handles held by Nodes, indexed by Node ID and handle.
+    pub handles: BTreeMap<(NodeId, oak_abi::Handle), HandleSnapshot>,
+}
+
+/// The objects added and removed between two [`StateSnapshot`]s, as returned by
+/// [`StateSnapshot::diff`]. All the lists are ordered.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// IDs of the Nodes only present in the later snapshot.
+    pub added_nodes: Vec<NodeId>,
+    /// IDs of the Nodes only present in the earlier snapshot.
+    pub removed_nodes: Vec<NodeId>,
+    /// IDs of the channels only present in the later snapshot.
+    pub added_channels: Vec<u64>,
+    /// IDs of the channels only present in the earlier snapshot.
+    pub removed_channels: Vec<u64>,
+    /// Handles (with the ID of the Node holding them) only present in the later snapshot.
+    pub added_handles: Vec<(NodeId, oak_abi::Handle)>,
+    /// Handles (with the ID of the Node holding them) only present in the earlier snapshot.
+    pub removed_handles: Vec<(NodeId, oak_abi::Handle)>,
+}
+
+impl StateDiff {
+    /// Returns whether no object was added or removed.
+    pub fn is_empty(&self) -> bool {
+        self == &StateDiff::default()
+    }
+}
+
+/// Returns the keys of `to` that are not in `from`, in order.
+fn added_keys<K: Ord + Copy, V>(from: &BTreeMap<K, V>, to: &BTreeMap<K, V>) -> Vec<K> {
+    to.keys()
+        .filter(|key| !from.contains_key(key))
+        .copied()
+        .collect()
+}
+
+impl StateSnapshot {
+    /// Returns the Nodes, channels and handles that are present in `other` but not in `self`
+    /// (added), and those that are present in `self` but not in `other` (removed), treating `self`
+    /// as the earlier of the two snapshots.
+    ///
+    /// Objects present in both snapshots are not reported, even if their state (e.g. the depth
+    /// of a channel) differs.
+    pub fn diff(&self, other: &StateSnapshot) -> StateDiff {
+        StateDiff {
+            added_nodes: added_keys(&self.nodes, &other.nodes),
+            removed_nodes: added_keys(&other.nodes, &self.nodes),
+            added_channels: added_keys(&self.channels, &other.channels),
+            removed_channels: added_keys(&other.channels, &self.channels),
+            added_handles: added_keys(&self.handles, &other.handles),
+            removed_handles: added_keys(&other.handles, &self.handles),
+        }
+    }
+}
+
 // Graph-related methods for the Runtime.
 impl Runtime {
     /// Generate a Graphviz dot graph that shows the current shape of the Nodes and Channels in
@@ -94,13 +225,21 @@ impl Runtime {
             let node_infos = self.node_infos.read().unwrap();
             for node_id in node_infos.keys().sorted() {
                 let node_info = node_infos.get(node_id).unwrap();
+                let initial_handle = node_info.initial_handle.map(|(handle, _)| handle);
                 for handle in node_info.abi_handles.keys() {
+                    // Mark the handle through which the Node received its initial channel half.
+                    let suffix = if initial_handle == Some(*handle) {
+                        " (initial)"
+                    } else {
+                        ""
+                    };
                     writeln!(
                         &mut s,
-                        r###"    {} [label="{}:{}" URL="{}"]"###,
+                        r###"    {} [label="{}:{}{}" URL="{}"]"###,
                         (*node_id, *handle).dot_id(),
                         node_id.0,
                         handle,
+                        suffix,
                         (*node_id, *handle).html_path(),
                     )
                     .unwrap();
@@ -208,6 +347,70 @@ impl Runtime {
         s
     }
 
+    /// Generate a Graphviz dot graph of the topology of the [`Runtime`], with a vertex for each
+    /// Node and a single edge for each channel that a Node holds a handle to, from the Nodes that
+    /// hold its write halves to the Nodes that hold its read halves.
+    ///
+    /// Unlike [`Runtime::graph`], handles and queued messages are not shown, so that the output
+    /// only changes when the topology changes. Channels that are not held by any Node in one of
+    /// the directions are connected to a point vertex on that side instead.
+    pub fn export_graph_dot(&self) -> String {
+        let mut s = String::new();
+        writeln!(&mut s, "digraph Runtime {{").unwrap();
+        writeln!(&mut s, "  node [shape=box]").unwrap();
+        // Debug ID, writer Nodes and reader Nodes of each channel.
+        let mut channels: BTreeMap<u64, (String, BTreeSet<NodeId>, BTreeSet<NodeId>)> =
+            BTreeMap::new();
+        let node_infos = self.node_infos.read().unwrap();
+        for node_id in node_infos.keys().sorted() {
+            let node_info = node_infos.get(node_id).unwrap();
+            writeln!(
+                &mut s,
+                r###"  {} [label="{}"]"###,
+                node_id.dot_id(),
+                node_info.get_debug_id(*node_id),
+            )
+            .unwrap();
+            for half in node_info.abi_handles.values() {
+                let (_, writers, readers) =
+                    channels.entry(half.get_channel_id()).or_insert_with(|| {
+                        (
+                            half.get_channel_debug_id(),
+                            BTreeSet::new(),
+                            BTreeSet::new(),
+                        )
+                    });
+                match half.direction {
+                    ChannelHalfDirection::Write => writers.insert(*node_id),
+                    ChannelHalfDirection::Read => readers.insert(*node_id),
+                };
+            }
+        }
+        for (channel_id, (debug_id, writers, readers)) in &channels {
+            let mut endpoints = Vec::new();
+            for (node_ids, side) in &[(writers, "writer"), (readers, "reader")] {
+                if node_ids.is_empty() {
+                    let point = format!("channel{}_{}", channel_id, side);
+                    writeln!(&mut s, "  {} [shape=point]", point).unwrap();
+                    endpoints.push(point);
+                } else {
+                    endpoints.push(format!(
+                        "{{{}}}",
+                        node_ids.iter().map(|node_id| node_id.dot_id()).join(" ")
+                    ));
+                }
+            }
+            writeln!(
+                &mut s,
+                r###"  {} -> {} [label="{}" taillabel="write" headlabel="read"]"###,
+                endpoints[0], endpoints[1], debug_id,
+            )
+            .unwrap();
+        }
+        writeln!(&mut s, "}}").unwrap();
+        s
+    }
+
     /// Generate an HTML page that describes the internal state of the [`Runtime`].
     pub fn html(&self) -> String {
         let mut s = String::new();
@@ -274,6 +477,167 @@ impl Runtime {
         (node_count as u32, channel_ids.len() as u32)
     }
 
+    /// Return the channels that are reachable from Node handles, but which have not been read from
+    /// or written to for longer than `threshold`, ordered by channel ID.
+    ///
+    /// Such channels are not closed automatically, as a Node may still legitimately use them
+    /// later, but a Node holding a handle it never uses typically indicates a logic bug.
+    /// May be slow to generate, as it involves exploring reachable channels recursively.
+    pub fn idle_channels(&self, threshold: Duration) -> Vec<IdleChannel> {
+        let mut channel_ids = HashSet::new();
+        let mut idle_channels = Vec::new();
+        let mut visitor = |half: &ChannelHalf| {
+            let channel_id = half.get_channel_id();
+            if !channel_ids.insert(channel_id) {
+                return false;
+            }
+            let idle_time = half.get_idle_time();
+            if idle_time > threshold {
+                idle_channels.push(IdleChannel {
+                    channel_id,
+                    debug_id: half.get_channel_debug_id(),
+                    idle_time,
+                });
+            }
+            true
+        };
+        {
+            let node_infos = self.node_infos.read().unwrap();
+            for node_info in node_infos.values() {
+                for half in node_info.abi_handles.values() {
+                    half.visit_halves(&mut visitor);
+                }
+            }
+        }
+        idle_channels.sort_by_key(|idle_channel| idle_channel.channel_id);
+        idle_channels
+    }
+
+    /// Return the channels through which `from_node` may send messages to `to_node`, i.e. the
+    /// channels for which `from_node` holds a write half and `to_node` holds a read half, ordered
+    /// by channel ID, together with their message counts.
+    ///
+    /// Only handles held directly by the Nodes are considered, not those in pending messages.
+    /// Fails with [`OakStatus::ErrInvalidArgs`] if either of the Nodes does not exist.
+    pub fn trace_link(
+        &self,
+        from_node: NodeId,
+        to_node: NodeId,
+    ) -> Result<Vec<LinkChannel>, OakStatus> {
+        let node_infos = self.node_infos.read().unwrap();
+        let from_node_info = node_infos
+            .get(&from_node)
+            .ok_or(OakStatus::ErrInvalidArgs)?;
+        let to_node_info = node_infos.get(&to_node).ok_or(OakStatus::ErrInvalidArgs)?;
+
+        let halves_with_direction =
+            |halves: &HashMap<oak_abi::Handle, ChannelHalf>, direction: ChannelHalfDirection| {
+                halves
+                    .values()
+                    .filter(|half| half.direction == direction)
+                    .map(|half| (half.get_channel_id(), half.clone()))
+                    .collect::<HashMap<u64, ChannelHalf>>()
+            };
+        let write_halves =
+            halves_with_direction(&from_node_info.abi_handles, ChannelHalfDirection::Write);
+        let read_halves =
+            halves_with_direction(&to_node_info.abi_handles, ChannelHalfDirection::Read);
+
+        let mut link_channels: Vec<LinkChannel> = read_halves
+            .iter()
+            .filter(|(channel_id, _)| write_halves.contains_key(channel_id))
+            .map(|(channel_id, half)| LinkChannel {
+                channel_id: *channel_id,
+                debug_id: half.get_channel_debug_id(),
+                messages_written: half.get_messages_written(),
+                messages_pending: half.get_messages().len() as u64,
+            })
+            .collect();
+        link_channels.sort_by_key(|link_channel| link_channel.channel_id);
+        Ok(link_channels)
+    }
+
+    /// Return the fan-in and fan-out degree of the channel identified by `channel_id`, as the
+    /// number of distinct Nodes holding a write half of it and the number of distinct Nodes
+    /// holding a read half of it, in that order.
+    ///
+    /// Only handles held directly by the Nodes are considered, not those in pending messages, so
+    /// both counts are zero for a channel that does not exist (any more).
+    pub fn channel_degree(&self, channel_id: u64) -> (usize, usize) {
+        let mut writer_nodes = HashSet::new();
+        let mut reader_nodes = HashSet::new();
+        let node_infos = self.node_infos.read().unwrap();
+        for (node_id, node_info) in node_infos.iter() {
+            for half in node_info.abi_handles.values() {
+                if half.get_channel_id() != channel_id {
+                    continue;
+                }
+                match half.direction {
+                    ChannelHalfDirection::Write => writer_nodes.insert(*node_id),
+                    ChannelHalfDirection::Read => reader_nodes.insert(*node_id),
+                };
+            }
+        }
+        (writer_nodes.len(), reader_nodes.len())
+    }
+
+    /// Return the ABI handle passed to the Node identified by `node_id` when starting it, together
+    /// with the ID of the channel it refers to.
+    ///
+    /// Returns `None` if the Node does not exist, or was not started with an initial handle (e.g.
+    /// the implicit initial Node). The handle may have been closed by the Node since.
+    pub fn node_initial_handle(&self, node_id: NodeId) -> Option<(oak_abi::Handle, u64)> {
+        let node_infos = self.node_infos.read().unwrap();
+        node_infos.get(&node_id)?.initial_handle
+    }
+
+    /// Return a snapshot of the Nodes, channels and handles of the `Runtime`.
+    ///
+    /// Channels are included if they are reachable from Node handles, either directly or via
+    /// pending messages. May be slow to generate, as it involves exploring reachable channels
+    /// recursively.
+    pub fn state_snapshot(&self) -> StateSnapshot {
+        let mut snapshot = StateSnapshot::default();
+        let channels = &mut snapshot.channels;
+        let mut visitor = |half: &ChannelHalf| {
+            let channel_id = half.get_channel_id();
+            if channels.contains_key(&channel_id) {
+                return false;
+            }
+            channels.insert(
+                channel_id,
+                ChannelSnapshot {
+                    label: half.get_label().clone(),
+                    depth: half.get_messages().len(),
+                },
+            );
+            true
+        };
+        let node_infos = self.node_infos.read().unwrap();
+        for (node_id, node_info) in node_infos.iter() {
+            snapshot.nodes.insert(
+                *node_id,
+                NodeSnapshot {
+                    name: node_info.name.clone(),
+                    node_type: node_info.node_type,
+                    label: node_info.label.clone(),
+                    initial_handle: node_info.initial_handle.map(|(handle, _)| handle),
+                },
+            );
+            for (handle, half) in node_info.abi_handles.iter() {
+                snapshot.handles.insert(
+                    (*node_id, *handle),
+                    HandleSnapshot {
+                        channel_id: half.get_channel_id(),
+                        direction: half.direction,
+                    },
+                );
+                half.visit_halves(&mut visitor);
+            }
+        }
+        snapshot
+    }
+
     /// Generate an HTML page that describes the internal state of a specific Node.
     pub(crate) fn html_for_node(&self, id: u64) -> Option<String> {
         let node_id = NodeId(id);
@@ -291,7 +655,21 @@ impl Runtime {
         } else {
             write!(&mut s, "<p>No current thread for Node.").unwrap();
         }
-        write!(&mut s, "<p>Label={:?}<p>Handles:<ul>", node_info.label).unwrap();
+        write!(
+            &mut s,
+            "<p>Label={}",
+            self.introspection_label(&node_info.label)
+        )
+        .unwrap();
+        if let Some((handle, channel_id)) = node_info.initial_handle {
+            write!(
+                &mut s,
+                "<p>Initial handle={} (channel {})",
+                handle, channel_id
+            )
+            .unwrap();
+        }
+        write!(&mut s, "<p>Handles:<ul>").unwrap();
         for (handle, half) in &node_info.abi_handles {
             write!(
                 &mut s,
@@ -322,6 +700,23 @@ impl Runtime {
         )
         .unwrap();
         write!(&mut s, r###"<p>Maps to {:?}"###, half).unwrap();
+        write!(
+            &mut s,
+            "<p>Label={}",
+            self.introspection_label(half.get_label())
+        )
+        .unwrap();
         Some(s)
     }
+
+    /// Formats `label` for display by the introspection server, as JSON if
+    /// [`RuntimeConfiguration::introspect_json_labels`](crate::RuntimeConfiguration) is set, or in
+    /// its debug format otherwise.
+    fn introspection_label(&self, label: &Label) -> String {
+        if self.introspect_json_labels.load(SeqCst) {
+            crate::label_json(label)
+        } else {
+            format!("{:?}", label)
+        }
+    }
 }
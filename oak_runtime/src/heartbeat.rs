@@ -0,0 +1,193 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Node liveness tracking, based on heartbeats emitted by Nodes.
+//!
+//! Nodes opt in to liveness tracking by calling [`RuntimeProxy::heartbeat`]. From then on, a Node
+//! that goes longer than the configured interval without a heartbeat, while not being blocked in
+//! [`RuntimeProxy::wait_on_channels`], is considered stuck (e.g. in an infinite loop), as opposed
+//! to legitimately waiting for input.
+//!
+//! [`RuntimeProxy::heartbeat`]: crate::RuntimeProxy::heartbeat
+//! [`RuntimeProxy::wait_on_channels`]: crate::RuntimeProxy::wait_on_channels
+
+use crate::{
+    proto::oak::introspection_events::{event::EventDetails, NodeStuck},
+    NodeId, Runtime,
+};
+use log::warn;
+use oak_abi::OakStatus;
+use std::{
+    sync::{atomic::Ordering::SeqCst, Arc},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// Action taken by the Runtime for a Node that is considered stuck.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StuckNodeAction {
+    /// Only report the Node, via metrics, introspection events and logs.
+    Report,
+    /// Report the Node, and also ask it to terminate.
+    ///
+    /// Termination is cooperative: the Node is sent the same notification it receives when the
+    /// Runtime stops, and any subsequent heartbeat from it fails with
+    /// [`OakStatus::ErrTerminated`].
+    Terminate,
+}
+
+/// Configuration options for detecting stuck Nodes.
+#[derive(Clone, Debug)]
+pub struct HeartbeatConfiguration {
+    /// Maximum time allowed between heartbeats from a Node before it is considered stuck.
+    pub interval: Duration,
+    /// Action to take for a Node that is considered stuck.
+    pub action: StuckNodeAction,
+}
+
+/// Per-Node liveness state, stored in the Node's `NodeInfo`.
+#[derive(Debug, Default)]
+pub(crate) struct NodeLiveness {
+    /// Time of the most recent heartbeat from the Node, or `None` if the Node has never emitted
+    /// one, in which case it is not monitored.
+    last_heartbeat: Option<Instant>,
+    /// Time since which the Node has been blocked in `wait_on_channels`, if it currently is.
+    waiting_since: Option<Instant>,
+    /// Whether the Node has already been reported as stuck since its last heartbeat.
+    stuck: bool,
+    /// Whether the Node has been asked to terminate because it was stuck.
+    terminate_requested: bool,
+}
+
+impl NodeLiveness {
+    /// Returns the time since which the Node has been blocked in `wait_on_channels`, if it
+    /// currently is.
+    pub(crate) fn waiting_since(&self) -> Option<Instant> {
+        self.waiting_since
+    }
+}
+
+// Liveness related methods for the Runtime.
+impl Runtime {
+    /// Record a heartbeat from the Node identified by `node_id`.
+    ///
+    /// Fails with [`OakStatus::ErrTerminated`] if the Runtime is terminating, or if the Node has
+    /// been asked to terminate because it was stuck.
+    pub(crate) fn heartbeat(&self, node_id: NodeId) -> Result<(), OakStatus> {
+        if self.is_terminating() {
+            return Err(OakStatus::ErrTerminated);
+        }
+        let mut node_infos = self.node_infos.write().unwrap();
+        let liveness = &mut node_infos
+            .get_mut(&node_id)
+            .expect("Invalid node_id")
+            .liveness;
+        if liveness.terminate_requested {
+            return Err(OakStatus::ErrTerminated);
+        }
+        liveness.last_heartbeat = Some(Instant::now());
+        liveness.stuck = false;
+        Ok(())
+    }
+
+    /// Record whether the Node identified by `node_id` is blocked waiting on channels.
+    ///
+    /// Leaving `wait_on_channels` counts as a heartbeat for Nodes that have opted in to liveness
+    /// tracking, so that time spent legitimately waiting for input is not held against them.
+    ///
+    /// Does nothing unless heartbeats or the watchdog are enabled, so that waits do not contend on
+    /// the `node_infos` lock otherwise.
+    pub(crate) fn set_node_waiting(&self, node_id: NodeId, waiting: bool) {
+        if !self.track_waiting_nodes.load(SeqCst) {
+            return;
+        }
+        let mut node_infos = self.node_infos.write().unwrap();
+        if let Some(node_info) = node_infos.get_mut(&node_id) {
+            let liveness = &mut node_info.liveness;
+            liveness.waiting_since = if waiting { Some(Instant::now()) } else { None };
+            if !waiting && liveness.last_heartbeat.is_some() {
+                liveness.last_heartbeat = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Find the Nodes that have not emitted a heartbeat within the configured interval while not
+    /// waiting on channels, and apply the configured action to them.
+    ///
+    /// Each Node is only reported once until it emits another heartbeat. Returns the
+    /// [`NodeId`]s of the newly reported Nodes, in increasing order.
+    pub(crate) fn check_heartbeats(&self, config: &HeartbeatConfiguration) -> Vec<NodeId> {
+        let now = Instant::now();
+        let mut stuck_nodes = Vec::new();
+        {
+            let mut node_infos = self.node_infos.write().unwrap();
+            for (node_id, node_info) in node_infos.iter_mut() {
+                let liveness = &node_info.liveness;
+                let last_heartbeat = match liveness.last_heartbeat {
+                    Some(last_heartbeat) if liveness.waiting_since.is_none() && !liveness.stuck => {
+                        last_heartbeat
+                    }
+                    _ => continue,
+                };
+                let elapsed = now.duration_since(last_heartbeat);
+                if elapsed <= config.interval {
+                    continue;
+                }
+                node_info.liveness.stuck = true;
+                warn!(
+                    "{:?}: no heartbeat for {:?}, node considered stuck",
+                    node_info.get_debug_id(*node_id),
+                    elapsed
+                );
+                self.metrics_data
+                    .runtime_metrics
+                    .runtime_stuck_nodes_total
+                    .with_label_values(&[node_info.node_type])
+                    .inc();
+                if config.action == StuckNodeAction::Terminate {
+                    node_info.liveness.terminate_requested = true;
+                    if let Some(node_stopper) = node_info.node_stopper.as_mut() {
+                        node_stopper.notify(*node_id);
+                    }
+                }
+                stuck_nodes.push((*node_id, elapsed));
+            }
+        }
+
+        stuck_nodes.sort_by_key(|(node_id, _)| *node_id);
+        stuck_nodes
+            .into_iter()
+            .map(|(node_id, elapsed)| {
+                self.introspection_event(EventDetails::NodeStuck(NodeStuck {
+                    node_id: node_id.0,
+                    millis_since_heartbeat: elapsed.as_millis() as u64,
+                }));
+                node_id
+            })
+            .collect()
+    }
+}
+
+/// Periodically checks the liveness of the Nodes in the `runtime`, until notified to terminate.
+pub(crate) fn monitor_heartbeats(
+    config: HeartbeatConfiguration,
+    runtime: Arc<Runtime>,
+    termination_notification_receiver: oneshot::Receiver<()>,
+) {
+    crate::aux_server::run_periodically(config.interval, termination_notification_receiver, || {
+        runtime.check_heartbeats(&config);
+    });
+}
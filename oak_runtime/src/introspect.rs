@@ -14,16 +14,17 @@
 // limitations under the License.
 //
 
-use crate::{proto::oak::introspection_events::Events, Runtime};
+use crate::{proto::oak::introspection_events::Events, tls::TlsConfig, Runtime};
 use hyper::{
-    header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE},
+    header::{ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
     service::{make_service_fn, service_fn},
     Body, Error, Method, Request, Response, Server, StatusCode,
 };
-use log::info;
+use log::{error, info};
 use prost::Message;
 use regex::Regex;
 use std::{net::SocketAddr, sync::Arc};
+use subtle::ConstantTimeEq;
 
 #[cfg(not(feature = "oak-introspection-client"))]
 mod introspection_client {
@@ -167,11 +168,50 @@ fn find_ids(path: &str, kind: &str) -> Option<(u64, u64)> {
     Some((id, subid))
 }
 
+/// Returns whether the `Authorization` header of `req` carries `token`, either as a bearer token,
+/// or as the password of basic authentication credentials with any user name, so that browsers can
+/// prompt for it.
+fn is_authorized(req: &Request<Body>, token: &str) -> bool {
+    let credentials = match req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+    {
+        Some((scheme, credentials)) => (scheme.to_ascii_lowercase(), credentials.trim()),
+        None => return false,
+    };
+    let provided = match credentials {
+        (scheme, bearer) if scheme == "bearer" => bearer.as_bytes().to_vec(),
+        (scheme, basic) if scheme == "basic" => {
+            let decoded = base64::decode(basic).unwrap_or_default();
+            match decoded.iter().position(|&byte| byte == b':') {
+                Some(separator) => decoded[separator + 1..].to_vec(),
+                None => return false,
+            }
+        }
+        _ => return false,
+    };
+    // Compare in constant time, so that the token cannot be guessed from response times.
+    provided.ct_eq(token.as_bytes()).into()
+}
+
 // Handler for a single HTTP request to the introspection server.
 fn handle_request(
     req: Request<Body>,
     runtime: Arc<Runtime>,
 ) -> Result<Response<Body>, hyper::Error> {
+    if let Some(token) = runtime.introspect_auth_token.read().unwrap().as_deref() {
+        if !is_authorized(&req, token) {
+            let mut unauthorized = Response::default();
+            *unauthorized.status_mut() = StatusCode::UNAUTHORIZED;
+            unauthorized.headers_mut().insert(
+                WWW_AUTHENTICATE,
+                "Basic realm=\"oak-introspection\"".parse().unwrap(),
+            );
+            return Ok(unauthorized);
+        }
+    }
     if req.method() != Method::GET {
         let mut method_not_allowed = Response::default();
         *method_not_allowed.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
@@ -196,8 +236,14 @@ fn handle_request(
         ))));
     // Endpoint to load the list introspection events, serialized via protobuf
     } else if path == "/introspection-events" {
-        let events_message = Events {
-            events: Vec::from(runtime.introspection_event_queue.lock().unwrap().clone()),
+        // Read the dropped event count while holding the lock, so that it is consistent with the
+        // events returned.
+        let events_message = {
+            let introspection_event_queue = runtime.introspection_event_queue.lock().unwrap();
+            Events {
+                events: Vec::from(introspection_event_queue.clone()),
+                dropped_events: runtime.introspection_events_dropped(),
+            }
         };
 
         let mut buffer: Vec<u8> = Vec::new();
@@ -219,6 +265,13 @@ fn handle_request(
         );
 
         return Ok(response);
+    // Endpoint to load the introspection events as a trace in the Chrome Trace Event format.
+    } else if path == "/trace" {
+        let mut response = Response::new(Body::from(runtime.chrome_trace()));
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        return Ok(response);
     } else if let Some(response) = introspection_client::find_client_file(path) {
         return Ok(response);
     } else if let Some(node_id) = find_id(path, "node") {
@@ -242,12 +295,12 @@ fn handle_request(
 }
 
 async fn make_server(
-    port: u16,
+    addr: SocketAddr,
+    tls_config: Option<TlsConfig>,
     runtime: Arc<Runtime>,
     termination_notification_receiver: tokio::sync::oneshot::Receiver<()>,
+    bound_notification_sender: std::sync::mpsc::Sender<()>,
 ) {
-    // Initialize SocketAddr to listen on.
-    let addr = SocketAddr::from((std::net::Ipv6Addr::UNSPECIFIED, port));
     info!("starting introspection server on {:?}", addr);
 
     // Initialize MakeService to handle each connection.
@@ -265,7 +318,15 @@ async fn make_server(
     });
 
     // Bind an address and serve incoming connections.
-    let server = Server::bind(&addr).serve(make_service);
+    let server = match crate::aux_server::bind(addr, tls_config).await {
+        Ok(incoming) => Server::builder(incoming).serve(make_service),
+        Err(err) => {
+            error!("could not bind introspection server to {}: {}", addr, err);
+            return;
+        }
+    };
+    // The Runtime may not be waiting for the notification, so ignore errors when sending it.
+    let _ = bound_notification_sender.send(());
     let graceful = server.with_graceful_shutdown(async {
         // Treat notification failure the same as a notification.
         let _ = termination_notification_receiver.await;
@@ -276,17 +337,22 @@ async fn make_server(
     info!("introspection server terminated with {:?}", result);
 }
 
-// Start running an introspection server on the given port, running until the
-// `termination_notificiation_receiver` is triggered.
+// Start running an introspection server on the given address, over TLS if `tls_config` is
+// provided, running until the `termination_notificiation_receiver` is triggered. A notification
+// is sent on `bound_notification_sender` once the server has bound to the address.
 pub fn serve(
-    port: u16,
+    addr: SocketAddr,
+    tls_config: Option<TlsConfig>,
     runtime: Arc<Runtime>,
     termination_notificiation_receiver: tokio::sync::oneshot::Receiver<()>,
+    bound_notification_sender: std::sync::mpsc::Sender<()>,
 ) {
     let tokio_runtime = tokio::runtime::Runtime::new().expect("Couldn't create Tokio runtime");
     tokio_runtime.block_on(make_server(
-        port,
+        addr,
+        tls_config,
         runtime,
         termination_notificiation_receiver,
+        bound_notification_sender,
     ));
 }
@@ -0,0 +1,129 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Persistence of introspection events to a file, for post-mortem analysis.
+//!
+//! Events are appended to the file as length-delimited protobuf-encoded [`Event`] messages by a
+//! background thread, so that the threads generating events are not blocked on I/O. The file can
+//! be read back with [`read_introspection_event_log`].
+
+use crate::{proto::oak::introspection_events::Event, Runtime};
+use log::{error, info};
+use prost::Message;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::mpsc,
+    thread,
+};
+
+/// A background thread appending introspection events to a file.
+pub(crate) struct IntrospectionEventLog {
+    sender: mpsc::Sender<Event>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl IntrospectionEventLog {
+    /// Opens the file at `path` for appending, and starts the thread writing events to it.
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (sender, receiver) = mpsc::channel();
+        let path = path.to_path_buf();
+        let thread = thread::Builder::new()
+            .name("introspection-event-log".to_string())
+            .spawn(move || write_events(file, receiver, &path))?;
+        Ok(Self { sender, thread })
+    }
+
+    /// Writes all the events sent so far to the file, then closes it.
+    fn close(self) {
+        // Dropping the sender makes the thread exit once it has received all the pending events.
+        drop(self.sender);
+        if self.thread.join().is_err() {
+            error!("introspection event log thread panicked");
+        }
+    }
+}
+
+/// Appends the events received on `receiver` to `file` until all senders are dropped.
+fn write_events(file: File, receiver: mpsc::Receiver<Event>, path: &Path) {
+    let mut writer = BufWriter::new(file);
+    let mut buffer = Vec::new();
+    for event in receiver {
+        buffer.clear();
+        event
+            .encode_length_delimited(&mut buffer)
+            .expect("could not encode introspection event");
+        if let Err(err) = writer.write_all(&buffer) {
+            error!(
+                "could not write introspection event to {}: {:?}",
+                path.display(),
+                err
+            );
+            return;
+        }
+    }
+    if let Err(err) = writer.flush() {
+        error!(
+            "could not flush introspection events to {}: {:?}",
+            path.display(),
+            err
+        );
+    }
+}
+
+/// Reads back all the events written to the file at `path` by the Runtime, in chronological order.
+pub fn read_introspection_event_log(path: &Path) -> std::io::Result<Vec<Event>> {
+    let contents = std::fs::read(path)?;
+    let mut remaining = contents.as_slice();
+    let mut events = Vec::new();
+    while !remaining.is_empty() {
+        let event = Event::decode_length_delimited(&mut remaining)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+// Introspection event log related methods for the Runtime.
+impl Runtime {
+    /// Starts appending all the introspection events generated from now on to the file at `path`.
+    pub(crate) fn open_introspection_event_log(&self, path: &Path) -> std::io::Result<()> {
+        let log = IntrospectionEventLog::open(path)?;
+        info!("writing introspection events to {}", path.display());
+        if let Some(previous) = self.introspection_event_log.lock().unwrap().replace(log) {
+            previous.close();
+        }
+        Ok(())
+    }
+
+    /// Sends `event` to be written to the introspection event log, if enabled.
+    pub(crate) fn log_introspection_event(&self, event: &Event) {
+        if let Some(log) = self.introspection_event_log.lock().unwrap().as_ref() {
+            // The thread only exits early on write errors, which are logged there.
+            let _ = log.sender.send(event.clone());
+        }
+    }
+
+    /// Writes all the pending events to the introspection event log, if enabled, and closes it.
+    pub(crate) fn close_introspection_event_log(&self) {
+        let log = self.introspection_event_log.lock().unwrap().take();
+        if let Some(log) = log {
+            log.close();
+        }
+    }
+}
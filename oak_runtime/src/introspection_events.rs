@@ -14,7 +14,62 @@
 // limitations under the License.
 //
 
+#[cfg(feature = "oak-unsafe")]
+use crate::proto::oak::introspection_events::Event;
 use crate::{proto::oak::introspection_events::event::EventDetails, Runtime};
+#[cfg(feature = "oak-unsafe")]
+use core::sync::atomic::Ordering::SeqCst;
+
+/// Category of introspection events, corresponding to a kind of [`EventDetails`]. The generation of
+/// the events of each category can be enabled or disabled separately.
+#[cfg(feature = "oak-unsafe")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntrospectionEventCategory {
+    NodeCreated,
+    NodeDestroyed,
+    NodePrivilegeAssigned,
+    NodeStuck,
+    LabelDowngraded,
+    PermissionDenied,
+    ChannelCreated,
+    ChannelDestroyed,
+    ChannelMigrated,
+    ChannelOrphaned,
+    HandleCreated,
+    HandleDestroyed,
+    MessageEnqueued,
+    MessageDequeued,
+    MessageRejected,
+}
+
+#[cfg(feature = "oak-unsafe")]
+impl IntrospectionEventCategory {
+    /// Returns the category of events with the provided details.
+    fn of(event_details: &EventDetails) -> Self {
+        match event_details {
+            EventDetails::NodeCreated(_) => Self::NodeCreated,
+            EventDetails::NodeDestroyed(_) => Self::NodeDestroyed,
+            EventDetails::NodePrivilegeAssigned(_) => Self::NodePrivilegeAssigned,
+            EventDetails::NodeStuck(_) => Self::NodeStuck,
+            EventDetails::LabelDowngraded(_) => Self::LabelDowngraded,
+            EventDetails::PermissionDenied(_) => Self::PermissionDenied,
+            EventDetails::ChannelCreated(_) => Self::ChannelCreated,
+            EventDetails::ChannelDestroyed(_) => Self::ChannelDestroyed,
+            EventDetails::ChannelMigrated(_) => Self::ChannelMigrated,
+            EventDetails::ChannelOrphaned(_) => Self::ChannelOrphaned,
+            EventDetails::HandleCreated(_) => Self::HandleCreated,
+            EventDetails::HandleDestroyed(_) => Self::HandleDestroyed,
+            EventDetails::MessageEnqueued(_) => Self::MessageEnqueued,
+            EventDetails::MessageDequeued(_) => Self::MessageDequeued,
+            EventDetails::MessageRejected(_) => Self::MessageRejected,
+        }
+    }
+
+    /// Returns the bit representing the category in the bitmask of disabled categories.
+    fn bit(self) -> u64 {
+        1 << self as u32
+    }
+}
 
 #[cfg(feature = "oak-unsafe")]
 fn current_timestamp() -> prost_types::Timestamp {
@@ -34,15 +89,81 @@ impl Runtime {
     /// internal data structures
     #[cfg(feature = "oak-unsafe")]
     pub fn introspection_event(&self, event_details: EventDetails) {
-        let event = crate::proto::oak::introspection_events::Event {
+        let category = IntrospectionEventCategory::of(&event_details);
+        if self.disabled_introspection_event_categories.load(SeqCst) & category.bit() != 0 {
+            return;
+        }
+
+        let event = Event {
             timestamp: Some(current_timestamp()),
             event_details: Some(event_details),
         };
 
-        self.introspection_event_queue
+        let mut introspection_event_queue = self.introspection_event_queue.lock().unwrap();
+        // Notify subscribers while holding the lock on the queue, so that they receive events in
+        // the same order as they are queued. Sending never blocks, and subscribers whose receiver
+        // has been dropped are removed.
+        self.introspection_event_subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        self.log_introspection_event(&event);
+        introspection_event_queue.push_back(event);
+        // Drop the oldest events once the queue is full, keeping track of how many were dropped so
+        // that consumers can tell that the queue no longer starts with the first event.
+        let capacity = self.introspection_event_queue_capacity.load(SeqCst);
+        while introspection_event_queue.len() > capacity {
+            introspection_event_queue.pop_front();
+            self.introspection_events_dropped.fetch_add(1, SeqCst);
+        }
+    }
+
+    /// Returns the number of introspection events dropped so far because the queue of events was
+    /// full, see [`crate::RuntimeConfiguration::introspection_event_queue_capacity`].
+    #[cfg(feature = "oak-unsafe")]
+    pub fn introspection_events_dropped(&self) -> u64 {
+        self.introspection_events_dropped.load(SeqCst)
+    }
+
+    /// Returns a receiver for all the introspection events generated from now on, in
+    /// chronological order. Dropping the receiver cancels the subscription.
+    ///
+    /// Events are buffered without limit until received, so the receiver should be drained
+    /// promptly.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn subscribe_events(&self) -> std::sync::mpsc::Receiver<Event> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.introspection_event_subscribers
             .lock()
             .unwrap()
-            .push_back(event);
+            .push(sender);
+        receiver
+    }
+
+    /// Enables or disables the generation of introspection events of the provided category. All
+    /// categories are enabled by default.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn set_introspection_event_category_enabled(
+        &self,
+        category: IntrospectionEventCategory,
+        enabled: bool,
+    ) {
+        if enabled {
+            self.disabled_introspection_event_categories
+                .fetch_and(!category.bit(), SeqCst);
+        } else {
+            self.disabled_introspection_event_categories
+                .fetch_or(category.bit(), SeqCst);
+        }
+    }
+
+    /// Returns whether the generation of introspection events of the provided category is enabled.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn is_introspection_event_category_enabled(
+        &self,
+        category: IntrospectionEventCategory,
+    ) -> bool {
+        self.disabled_introspection_event_categories.load(SeqCst) & category.bit() == 0
     }
 
     /// no-op implementation, introspection events are a debugging feature.
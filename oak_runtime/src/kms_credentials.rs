@@ -0,0 +1,201 @@
+//
+// Copyright 2021 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Reloading of the KMS credentials while the Runtime is running.
+//!
+//! KMS clients are created from the credentials when first needed by a crypto pseudo-Node, and are
+//! then shared by all crypto pseudo-Nodes. Reloading the credentials discards these clients, so
+//! that KMS clients created afterwards use the new credentials. This allows rotating the
+//! credentials without restarting the Runtime.
+
+use crate::Runtime;
+use log::{debug, info, warn};
+use oak_abi::OakStatus;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use tink_core::registry::KmsClient;
+
+/// KMS credentials shared by the crypto pseudo-Nodes, along with the KMS clients created from
+/// them.
+pub struct KmsCredentials {
+    state: RwLock<KmsCredentialsState>,
+}
+
+struct KmsCredentialsState {
+    /// Credentials file, or `None` to use the default credentials of each KMS.
+    path: Option<PathBuf>,
+    /// KMS clients created with the credentials, indexed by key URI.
+    clients: HashMap<String, Arc<dyn KmsClient>>,
+}
+
+impl KmsCredentials {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        KmsCredentials {
+            state: RwLock::new(KmsCredentialsState {
+                path,
+                clients: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the current credentials file, if any.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.state.read().unwrap().path.clone()
+    }
+
+    /// Replaces the credentials with the ones in the file at `path`, and discards the KMS clients
+    /// created with the previous credentials.
+    ///
+    /// Fails, leaving the credentials unchanged, if the file cannot be read.
+    fn reload(&self, path: &Path) -> std::io::Result<()> {
+        // The KMS clients read the file themselves, but checking it can be read beforehand avoids
+        // discarding working clients in favour of unusable credentials.
+        std::fs::read(path)?;
+        let mut state = self.state.write().unwrap();
+        state.path = Some(path.to_path_buf());
+        state.clients.clear();
+        Ok(())
+    }
+
+    /// Returns a KMS client for the key identified by `key_uri`, creating it with the current
+    /// credentials if needed.
+    pub fn get_kms_client(
+        &self,
+        key_uri: &str,
+    ) -> Result<Arc<dyn KmsClient>, tink_core::TinkError> {
+        if let Some(client) = self.state.read().unwrap().clients.get(key_uri) {
+            return Ok(client.clone());
+        }
+        // Hold the lock while creating the client, so that it cannot be created with credentials
+        // that are being replaced.
+        let mut state = self.state.write().unwrap();
+        // Another thread may have created the client while the lock was released.
+        if let Some(client) = state.clients.get(key_uri) {
+            return Ok(client.clone());
+        }
+        debug!(
+            "create KMS client for {} using credentials in {:?}",
+            key_uri, state.path
+        );
+        let client = create_kms_client(key_uri, state.path.as_deref())?;
+        state.clients.insert(key_uri.to_string(), client.clone());
+        Ok(client)
+    }
+
+    /// Returns the key URIs of the KMS clients created with the current credentials, in increasing
+    /// order.
+    #[cfg(test)]
+    pub(crate) fn client_key_uris(&self) -> Vec<String> {
+        let mut key_uris = self
+            .state
+            .read()
+            .unwrap()
+            .clients
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        key_uris.sort();
+        key_uris
+    }
+}
+
+/// Creates a KMS client for the key identified by `key_uri`, using the credentials in the file at
+/// `path` if provided.
+#[allow(unused_variables)]
+fn create_kms_client(
+    key_uri: &str,
+    path: Option<&Path>,
+) -> Result<Arc<dyn KmsClient>, tink_core::TinkError> {
+    #[cfg(feature = "awskms")]
+    if key_uri.starts_with(tink_awskms::AWS_PREFIX) {
+        let client = if let Some(kms_creds) = path {
+            tink_awskms::AwsClient::new_with_credentials(key_uri, kms_creds)?
+        } else {
+            tink_awskms::AwsClient::new(key_uri)?
+        };
+        return Ok(Arc::new(client));
+    }
+    // TODO(#745): sort out clashing dependencies
+    #[cfg(feature = "gcpkms")]
+    if key_uri.starts_with(tink_gcpkms::GCP_PREFIX) {
+        let client = if let Some(kms_creds) = path {
+            tink_gcpkms::GcpClient::new_with_credentials(key_uri, kms_creds)?
+        } else {
+            tink_gcpkms::GcpClient::new(key_uri)?
+        };
+        return Ok(Arc::new(client));
+    }
+    #[cfg(test)]
+    if key_uri.starts_with(TEST_KMS_PREFIX) {
+        return Ok(Arc::new(TestKmsClient {
+            path: path.map(Path::to_path_buf),
+        }));
+    }
+    Err("Unrecognized key URI".into())
+}
+
+/// Prefix of the key URIs handled by [`TestKmsClient`].
+#[cfg(test)]
+pub(crate) const TEST_KMS_PREFIX: &str = "test-kms://";
+
+/// KMS client that does not provide any key, but reports the credentials it was created with when
+/// asked for one.
+#[cfg(test)]
+struct TestKmsClient {
+    path: Option<PathBuf>,
+}
+
+#[cfg(test)]
+impl KmsClient for TestKmsClient {
+    fn supported(&self, key_uri: &str) -> bool {
+        key_uri.starts_with(TEST_KMS_PREFIX)
+    }
+
+    fn get_aead(&self, _key_uri: &str) -> Result<Box<dyn tink_core::Aead>, tink_core::TinkError> {
+        Err(format!("test KMS client using credentials in {:?}", self.path).into())
+    }
+}
+
+// KMS credentials related methods for the Runtime.
+impl Runtime {
+    /// Reloads the KMS credentials from the file at `path`. Existing KMS clients are discarded and
+    /// created again with the new credentials when next needed, so that KMS keys obtained by crypto
+    /// pseudo-Nodes from now on use the new credentials.
+    ///
+    /// KMS keys already obtained, and registered as handles by the crypto pseudo-Nodes, keep using
+    /// the credentials they were obtained with.
+    ///
+    /// Fails with [`OakStatus::ErrInvalidArgs`], leaving the credentials unchanged, if the file
+    /// cannot be read.
+    pub fn reload_kms_credentials(&self, path: &Path) -> Result<(), OakStatus> {
+        let previous_path = self.node_factory.kms_credentials.path();
+        self.node_factory
+            .kms_credentials
+            .reload(path)
+            .map_err(|err| {
+                warn!("could not reload KMS credentials from {:?}: {}", path, err);
+                OakStatus::ErrInvalidArgs
+            })?;
+        info!(
+            "reloaded KMS credentials from {:?}, replacing {:?}",
+            path, previous_path
+        );
+        Ok(())
+    }
+}
@@ -24,24 +24,27 @@
 //! platform by providing easy channels for the exfiltration of private data.
 
 use crate::{
-    channel::{with_reader_channel, with_writer_channel, Channel},
+    channel::{with_reader_channel, with_writer_channel, Channel, RejectedMessage},
+    heartbeat::NodeLiveness,
     message::Message,
     metrics::Metrics,
     node::NodeIsolation,
     permissions::PermissionsConfiguration,
     proto::oak::introspection_events::{
         event::EventDetails, ChannelCreated, Direction, Event, HandleCreated, HandleDestroyed,
-        MessageDequeued, MessageEnqueued, NodeCreated, NodeDestroyed,
+        LabelDowngraded, MessageDequeued, MessageEnqueued, MessageRejected, NodeCreated,
+        NodeDestroyed, NodePrivilegeAssigned, PermissionDenied,
     },
-    tls::Certificate,
+    tls::{Certificate, TlsConfig},
 };
-use auth::oidc_utils::ClientInfo;
-use core::sync::atomic::{AtomicBool, AtomicU64, Ordering::SeqCst};
+use auth::oidc_utils::{ClientInfo, TokenValidationConfiguration};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::SeqCst};
 use itertools::Itertools;
-use log::{debug, error, info, trace, warn};
+use log::{debug, error, info, log_enabled, trace, warn, Level};
+use metrics::node_cpu_time::ThreadCpuTimer;
 use node::{CreatedNode, NodeFactory};
 use oak_abi::{
-    label::{top, Label, Tag},
+    label::{namespace_top, Label, Tag, TagNamespace},
     proto::oak::application::{ApplicationConfiguration, ConfigMap, NodeConfiguration},
     ChannelReadStatus, OakStatus,
 };
@@ -49,50 +52,127 @@ use oak_io::Message as NodeMessage;
 use oak_sign::SignatureBundle;
 use prometheus::proto::MetricFamily;
 use prost::Message as _;
-use rand::RngCore;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
 use std::{
+    any::Any,
     collections::{HashMap, HashSet, VecDeque},
+    net::{Ipv6Addr, SocketAddr},
+    panic::{self, AssertUnwindSafe},
     string::String,
-    sync::{Arc, Mutex, RwLock},
+    sync::{mpsc, Arc, Mutex, RwLock, RwLockWriteGuard},
     thread,
     thread::JoinHandle,
+    time::{Duration, Instant},
 };
 use tokio::sync::oneshot;
 use tonic::transport::Identity;
 
-pub use channel::{ChannelHalf, ChannelHalfDirection};
-pub use config::configure_and_run;
+pub use channel::{
+    ChannelHalf, ChannelHalfDirection, ChannelOptions, CloseReason, ConsumerPolicy,
+    MessageValidator, WakeStrategy,
+};
+pub use config::{configure_and_run, configure_and_run_raw};
+pub use downgrade_history::DowngradeRecord;
+#[cfg(feature = "oak-unsafe")]
+pub use graph::{
+    ChannelSnapshot, HandleSnapshot, IdleChannel, LinkChannel, NodeSnapshot, StateDiff,
+    StateSnapshot,
+};
+pub use heartbeat::{HeartbeatConfiguration, StuckNodeAction};
+#[cfg(feature = "oak-unsafe")]
+pub use introspection_event_log::read_introspection_event_log;
+#[cfg(feature = "oak-unsafe")]
+pub use introspection_events::IntrospectionEventCategory;
+pub use log_limiter::DEFAULT_LOG_RATE_LIMIT_INTERVAL;
+pub use metrics::{channel_label::LabelBucket, push::MetricsPushConfiguration};
+pub use node_creation_limiter::NodeCreationRateLimit;
+pub use proto::oak::dead_letter::DeadLetter;
 pub use proxy::RuntimeProxy;
+pub use subgraph::{SubgraphEdge, SubgraphNode, SubgraphSpec};
+pub use topology::{ChannelTopology, NodeTopology, TopologySnapshot};
+pub use watchdog::WatchdogConfiguration;
 
 pub mod auth;
+mod aux_server;
 mod channel;
+#[cfg(feature = "oak-unsafe")]
+mod channel_migration;
 pub mod config;
+mod dead_letter;
+mod downgrade_history;
 #[cfg(feature = "oak-unsafe")]
 mod graph;
+mod heartbeat;
 #[cfg(feature = "oak-unsafe")]
 mod introspect;
+#[cfg(feature = "oak-unsafe")]
+mod introspection_event_log;
 mod introspection_events;
 mod io;
+mod kms_credentials;
+mod log_limiter;
 mod message;
+mod message_expiry;
+mod message_writers;
 mod metrics;
+mod module_replacement;
 mod node;
+mod node_creation_limiter;
+mod node_ids;
+mod node_tombstones;
 pub mod permissions;
 mod proto;
 mod proxy;
+mod subgraph;
 #[cfg(test)]
 mod tests;
 pub mod time;
 pub mod tls;
+mod topology;
+#[cfg(feature = "oak-unsafe")]
+mod trace_export;
+mod watchdog;
+
+/// Default maximum number of handles that a single message written to a channel may contain.
+pub const DEFAULT_MAX_HANDLES_PER_MESSAGE: usize = 1024;
+
+/// Default maximum number of introspection events kept by the Runtime.
+pub const DEFAULT_INTROSPECTION_EVENT_QUEUE_CAPACITY: usize = 100_000;
+
+/// Interval at which the Runtime checks on its progress while stopping, i.e. whether all channels
+/// have been drained (see [`Runtime::stop_drain`]) or whether Node threads have terminated (see
+/// [`RuntimeConfiguration::shutdown_timeout`]).
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Maximum number of failures of Node threads joined before stopping the Runtime that are kept
+/// for its [`ShutdownReport`].
+pub const MAX_EARLY_JOINED_NODE_FAILURES: usize = 100;
+
+/// Maximum length in bytes of a thread name on Linux, excluding the terminating NUL byte.
+const MAX_LINUX_THREAD_NAME_LEN: usize = 15;
+
+/// Returns the address that auxiliary servers listen on when only a port is provided, which
+/// accepts connections on all interfaces.
+pub fn aux_server_address(port: u16) -> SocketAddr {
+    SocketAddr::from((Ipv6Addr::UNSPECIFIED, port))
+}
 
 /// Configuration options that govern the behaviour of the Runtime and the Oak Application running
 /// inside it.
 #[derive(Default, Clone)]
 pub struct RuntimeConfiguration {
-    /// Port to run a metrics server on, if provided.
-    pub metrics_port: Option<u16>,
-    /// Port to run an introspection server on, if provided.
-    pub introspect_port: Option<u16>,
-    /// Credentials filename for KMS integration, if provided.
+    /// Address to run a metrics server on, if provided. The server also exposes a health check on
+    /// the `/healthz` path.
+    pub metrics_address: Option<SocketAddr>,
+    /// Address to run an introspection server on, if provided.
+    pub introspect_address: Option<SocketAddr>,
+    /// TLS identity of the metrics and introspection servers, which serve HTTPS rather than
+    /// plaintext HTTP if provided.
+    pub aux_server_tls_config: Option<tls::TlsConfig>,
+    /// Configuration for periodically pushing metrics to a StatsD server, if provided.
+    pub metrics_push: Option<MetricsPushConfiguration>,
+    /// Credentials filename for KMS integration, if provided. The credentials can be replaced
+    /// while the Runtime is running with [`Runtime::reload_kms_credentials`].
     pub kms_credentials: Option<std::path::PathBuf>,
     /// Security options for server pseudo-nodes.
     pub secure_server_configuration: SecureServerConfiguration,
@@ -104,6 +184,132 @@ pub struct RuntimeConfiguration {
     pub sign_table: SignatureTable,
     /// Start-of-day configuration to feed to the running Application.
     pub config_map: ConfigMap,
+    /// Whether to validate, before running anything, that the Node configurations known at
+    /// startup only reference WebAssembly modules present in `app_config`.
+    pub strict_config_validation: bool,
+    /// Configuration for detecting stuck Nodes based on their heartbeats, if provided.
+    pub heartbeat_config: Option<HeartbeatConfiguration>,
+    /// Configuration for reporting Nodes blocked waiting on channels for a long time, if provided.
+    /// Such Nodes are logged, and their number is exported as the `runtime_stuck_waiting_nodes`
+    /// gauge.
+    pub watchdog_config: Option<WatchdogConfiguration>,
+    /// Order in which auxiliary servers and application Nodes are started.
+    pub startup_order: StartupOrder,
+    /// Maximum number of entries in the downgrade history carried by each message, if provided.
+    /// Downgrade histories are not tracked if not provided, as they add overhead to every read
+    /// and write.
+    pub downgrade_history_limit: Option<usize>,
+    /// Maximum number of handles that a single message written to a channel may contain, which
+    /// bounds the fan-out of capabilities passed in each message. Defaults to
+    /// [`DEFAULT_MAX_HANDLES_PER_MESSAGE`] if not provided.
+    pub max_handles_per_message: Option<usize>,
+    /// Default [`NodeConfiguration`]s for each type of Node, keyed by the name of the
+    /// corresponding `config_type` field (e.g. `"grpc_client_config"`).
+    ///
+    /// The defaults for a type of Node are merged into each [`NodeConfiguration`] of that type
+    /// when creating the Node. Settings explicitly specified for the Node take precedence over the
+    /// defaults, while settings left unspecified (i.e. with their protobuf default value) are
+    /// taken from the defaults.
+    pub node_config_defaults: HashMap<String, NodeConfiguration>,
+    /// Minimum interval between two log messages of the same kind emitted for the same Node on
+    /// hot paths (e.g. repeated permission denials). Defaults to
+    /// [`DEFAULT_LOG_RATE_LIMIT_INTERVAL`] if not provided, and a zero interval disables rate
+    /// limiting.
+    pub log_rate_limit_interval: Option<Duration>,
+    /// Maximum number of channels reported with their own label value by the
+    /// `runtime_channel_queue_length` metric, if provided. The queue lengths of any further
+    /// channels are aggregated under a single label value. The metric is disabled if not provided,
+    /// as channel IDs are not reused.
+    pub channel_queue_length_metric_limit: Option<usize>,
+    /// Maximum number of channels reported with their own label value by the
+    /// `runtime_channel_message_rate` metric, if provided. The message rates of any further
+    /// channels are aggregated under a single label value. The metric is disabled if not provided,
+    /// as channel IDs are not reused.
+    pub channel_message_rate_metric_limit: Option<usize>,
+    /// Whether to require the names of live channels to be unique, so that they can be relied
+    /// upon as identifiers, e.g. by tooling. Creating a channel with the same name as an existing
+    /// channel then fails with [`OakStatus::ErrInvalidArgs`]. Empty names are exempt.
+    ///
+    /// Channels created by pseudo-Nodes are also exempt: they create channels with a fixed name
+    /// for each request they handle (e.g. `"gRPC request"`), which would otherwise prevent them
+    /// from handling requests concurrently.
+    pub require_unique_channel_names: bool,
+    /// Period during which the Runtime remembers Nodes that have been removed, if provided.
+    ///
+    /// A Node may terminate before the Runtime has finished starting it, in which case the late
+    /// steps of starting it are only expected (rather than reported as errors) if it was removed
+    /// within this period.
+    pub node_tombstone_grace_period: Option<Duration>,
+    /// Seed for the random number generators used to allocate handles and by random pseudo-Nodes,
+    /// if provided, so that runs performing the same operations allocate the same handles and
+    /// read the same random bytes (e.g. for fuzzing or replay). Fresh entropy is used if not
+    /// provided.
+    pub rng_seed: Option<u64>,
+    /// Whether to derive the [`NodeId`]s of Nodes from their names rather than from the order in
+    /// which they are created, so that the same application gets the same [`NodeId`]s across
+    /// runs, e.g. to compare graph snapshots. [`NodeId`]s are allocated from a counter if not set.
+    pub stable_node_ids: bool,
+    /// Maximum number of introspection events kept by the Runtime, after which the oldest events
+    /// are dropped. Defaults to [`DEFAULT_INTROSPECTION_EVENT_QUEUE_CAPACITY`] if not provided.
+    /// Only relevant with the `oak-unsafe` feature.
+    pub introspection_event_queue_capacity: Option<usize>,
+    /// Whether to tag each message written to a channel with the [`NodeId`] of the Node that
+    /// wrote it, so that the Node reading the message can retrieve it (e.g. to find out which
+    /// Node sent a malformed message). Messages are not tagged if not set.
+    pub tag_message_writers: bool,
+    /// File to append all introspection events to, if provided, as length-delimited
+    /// protobuf-encoded messages that can be read back with `read_introspection_event_log`. Only
+    /// relevant with the `oak-unsafe` feature.
+    pub introspection_event_log_path: Option<std::path::PathBuf>,
+    /// Buckets of channel labels, in order of precedence, by which the
+    /// `runtime_channel_queue_length_by_label` and `runtime_channel_messages_by_label_total`
+    /// metrics are aggregated. Each channel is counted in the first bucket whose label its own
+    /// label flows to, or in a catch-all bucket if none. The metrics are disabled if empty.
+    pub channel_label_buckets: Vec<LabelBucket>,
+    /// Maximum time that stopping the Runtime waits for Node threads to terminate, if provided,
+    /// after which the remaining threads are abandoned so that a Node ignoring the termination
+    /// notification cannot prevent the Runtime from stopping. The time spent stopping the
+    /// auxiliary servers counts towards it. Stopping waits indefinitely if not provided.
+    pub shutdown_timeout: Option<Duration>,
+    /// Write handle of a channel to which messages written to channels without readers left are
+    /// forwarded as [`DeadLetter`] messages, rather than being dropped, if provided. The handle
+    /// must belong to the Node of the [`RuntimeProxy`] starting the Runtime.
+    pub dead_letter_handle: Option<oak_abi::Handle>,
+    /// Whether the introspection server shows labels as JSON (see [`serialize_label_json`])
+    /// rather than in their debug format.
+    pub introspect_json_labels: bool,
+    /// Token that requests to the introspection server must carry in their `Authorization` header,
+    /// if provided. Requests without a matching token are rejected with `401 Unauthorized`.
+    pub introspect_auth_token: Option<String>,
+    /// Maximum number of live Nodes, if provided, counting Nodes of all types (including the
+    /// implicit initial Node). Creating a Node beyond this limit fails with
+    /// [`OakStatus::ErrResourceExhausted`] until another Node exits. The number of Nodes is
+    /// unlimited if not provided.
+    pub max_nodes: Option<usize>,
+    /// Rate at which each Node may create other Nodes, if provided. Creating a Node beyond this
+    /// rate fails with [`OakStatus::ErrResourceExhausted`], and may be retried later. Node
+    /// creation is not rate limited if not provided.
+    pub node_creation_rate_limit: Option<NodeCreationRateLimit>,
+}
+
+/// Order in which the Runtime starts its auxiliary servers (metrics, introspection) relative to the
+/// application Nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartupOrder {
+    /// Start the auxiliary servers, then the initial Node, without waiting for the servers to be
+    /// ready.
+    Unordered,
+    /// Start the auxiliary servers and wait for them to bind to their ports before starting the
+    /// initial Node, so that e.g. health and metrics endpoints are available during Node startup.
+    AuxServersFirst,
+    /// Start the initial Node before starting the auxiliary servers.
+    NodesFirst,
+}
+
+impl Default for StartupOrder {
+    fn default() -> Self {
+        StartupOrder::Unordered
+    }
 }
 
 /// Configuration options related to gRPC pseudo-Nodes.
@@ -114,11 +320,33 @@ pub struct GrpcConfiguration {
     /// TLS identity to use for all gRPC Server Nodes.
     pub grpc_server_tls_identity: Option<Identity>,
 
+    /// TLS certificates and private key to use for all gRPC Server Nodes instead of
+    /// [`GrpcConfiguration::grpc_server_tls_identity`], if provided, so that the TLS versions and
+    /// cipher suites they accept can be restricted with a [`crate::tls::TlsPolicy`].
+    pub grpc_server_tls_config: Option<TlsConfig>,
+
+    /// PEM formatted root TLS certificate used by all gRPC Server Nodes to verify the certificates
+    /// presented by clients, which requires [`GrpcConfiguration::grpc_server_tls_config`].
+    pub grpc_server_client_ca_certificate: Option<Certificate>,
+
+    /// Whether all gRPC Server Nodes reject clients that do not present a certificate verified by
+    /// [`GrpcConfiguration::grpc_server_client_ca_certificate`] (i.e. mutual TLS), instead of
+    /// treating them as unauthenticated.
+    pub grpc_server_require_client_certificate: bool,
+
     /// OpenID Connect Authentication client information.
     pub oidc_client_info: Option<ClientInfo>,
 
+    /// Options for validating the identity tokens obtained with OpenID Connect Authentication.
+    pub oidc_token_validation_config: TokenValidationConfiguration,
+
     /// PEM formatted root TLS certificate to use for all gRPC Client Nodes.
     pub grpc_client_root_tls_certificate: Option<Certificate>,
+
+    /// Encoded `FileDescriptorSet`s describing the gRPC services of the application. If provided,
+    /// all gRPC Server Nodes also serve the standard gRPC server reflection service, so that
+    /// clients can discover these services without a copy of their `.proto` files.
+    pub grpc_reflection_file_descriptor_sets: Option<Vec<Vec<u8>>>,
 }
 
 /// Configuration options table related to Wasm module signatures.
@@ -126,6 +354,22 @@ pub struct GrpcConfiguration {
 pub struct SignatureTable {
     /// Map from Oak module hashes to corresponding signatures.
     pub values: HashMap<String, Vec<SignatureBundle>>,
+    /// Hex encoded SHA-256 hashes of revoked Oak modules, which cannot be run by Wasm Nodes even
+    /// if they have valid signatures.
+    pub revoked_hashes: HashSet<String>,
+    /// Signatures required for Oak modules to be run by Wasm Nodes, if provided. Otherwise, Oak
+    /// modules can be run without any signature.
+    pub signature_policy: Option<SignaturePolicy>,
+}
+
+/// Policy requiring Oak modules to be signed by a minimum number of distinct trusted signers.
+#[derive(Default, Clone, Debug)]
+pub struct SignaturePolicy {
+    /// Minimum number of distinct trusted signers that must have validly signed an Oak module.
+    pub threshold: usize,
+    /// Public keys of the trusted signers, as binary DER-encoded `SubjectPublicKeyInfo`s.
+    /// Signatures by other keys are ignored.
+    pub trusted_public_keys: HashSet<Vec<u8>>,
 }
 
 /// Configuration options related to HTTP pseudo-Nodes.
@@ -152,28 +396,67 @@ struct NodeStopper {
     /// Handle used for joining the Node thread.
     join_handle: JoinHandle<()>,
 
+    /// Set by the Node thread once it has finished running the Node, so that it can be joined
+    /// without blocking.
+    finished: Arc<AtomicBool>,
+
     /// A notification sender object whose receiver is sent to the Node.
     /// The agreement is that the Runtime will notify the Node upon termination
     /// and then start waiting on the join handle. It's up to the Node to figure
     /// out how to actually terminate when receiving a notification.
-    notify_sender: oneshot::Sender<()>,
+    ///
+    /// Set to `None` once the notification has been sent.
+    notify_sender: Option<oneshot::Sender<()>>,
 }
 
 impl NodeStopper {
+    /// Sends a termination notification to the Node, without waiting for it to terminate.
+    ///
+    /// Does nothing if the Node has already been notified.
+    fn notify(&mut self, node_id: NodeId) {
+        if let Some(notify_sender) = self.notify_sender.take() {
+            notify_sender
+                .send(())
+                // Notification errors are discarded since not all of the Nodes save
+                // and use the [`oneshot::Receiver`].
+                .unwrap_or_else(|()| {
+                    debug!(
+                        "{} already dropped `notify_receiver`.",
+                        self.get_debug_id(node_id)
+                    );
+                });
+        }
+    }
+
+    /// Returns whether the Node thread has finished running the Node.
+    fn is_finished(&self) -> bool {
+        self.finished.load(SeqCst)
+    }
+
     /// Sends a notification to the Node and joins its thread.
-    fn stop_node(self, node_id: NodeId) -> thread::Result<()> {
+    ///
+    /// If a `deadline` is provided and the thread has not terminated by then, the thread is
+    /// abandoned and `None` is returned.
+    fn stop_node(
+        mut self,
+        node_id: NodeId,
+        deadline: Option<Instant>,
+    ) -> Option<thread::Result<()>> {
         let node_debug_id = self.get_debug_id(node_id);
-        self.notify_sender
-            .send(())
-            // Notification errors are discarded since not all of the Nodes save
-            // and use the [`oneshot::Receiver`].
-            .unwrap_or_else(|()| {
-                debug!("{} already dropped `notify_receiver`.", node_debug_id);
-            });
+        self.notify(node_id);
         debug!("join thread for node {}...", node_debug_id);
+        if let Some(deadline) = deadline {
+            while !self.is_finished() {
+                if Instant::now() >= deadline {
+                    debug!("join thread for node {}...abandoned", node_debug_id);
+                    return None;
+                }
+                thread::sleep(STOP_POLL_INTERVAL);
+            }
+        }
         let result = self.join_handle.join();
         debug!("join thread for node {}...done", node_debug_id);
-        result
+        Some(result)
     }
 
     /// Returns a unique debug_id used in the debug output, consisting out of
@@ -193,6 +476,37 @@ impl std::fmt::Debug for NodeStopper {
     }
 }
 
+/// Capacity left for a Node being added to the [`Runtime`].
+#[derive(Clone, Copy)]
+enum NodeCapacity<'a> {
+    /// The Node may only be added if the [`Runtime`] runs, or has reserved capacity for, fewer
+    /// than this number of Nodes.
+    Limit(usize),
+    /// The Node uses capacity reserved beforehand.
+    Reserved(&'a NodeReservation<'a>),
+}
+
+/// Capacity reserved with [`Runtime::reserve_node_capacity`]. The capacity that has not been used
+/// when this is dropped is returned to the [`Runtime`].
+struct NodeReservation<'a> {
+    runtime: &'a Runtime,
+    /// Number of Nodes that can still be added with this reservation. Only updated while holding
+    /// the [`Runtime::node_infos`] write lock.
+    remaining: AtomicUsize,
+}
+
+impl Drop for NodeReservation<'_> {
+    fn drop(&mut self) {
+        let _node_infos = self
+            .runtime
+            .node_infos
+            .write()
+            .expect("could not acquire lock on node_infos");
+        let remaining = self.remaining.swap(0, SeqCst);
+        self.runtime.reserved_nodes.fetch_sub(remaining, SeqCst);
+    }
+}
+
 struct NodeInfo {
     /// The name for the Node.
     ///
@@ -213,13 +527,63 @@ struct NodeInfo {
     /// The downgrading privilege of this Node.
     privilege: NodePrivilege,
 
+    /// The serialized forms of [`NodeInfo::label`] and of [`NodeInfo::privilege`] (converted to a
+    /// [`Label`]), computed once at Node creation time so that their sizes are stable across the
+    /// calls needed to retrieve them through the ABI.
+    serialized_label: Vec<u8>,
+    serialized_privilege: Vec<u8>,
+
     /// Map of ABI handles to channels.
     abi_handles: HashMap<oak_abi::Handle, ChannelHalf>,
 
+    /// The ABI handle passed to the Node when starting it, together with the ID of the channel it
+    /// refers to. This is set when registering the Node, and is `None` for Nodes that were not
+    /// started with an initial handle (e.g. the implicit initial Node).
+    initial_handle: Option<(oak_abi::Handle, u64)>,
+
+    /// Types of Node (as returned by [`Node::node_type`]) that this Node may create, in addition
+    /// to the restrictions of the [`PermissionsConfiguration`], or `None` if the Node may create
+    /// Nodes of any type. This is set when registering the Node, and does not change after that.
+    creatable_node_types: Option<HashSet<String>>,
+
+    /// Configuration the Node was created from, with the defaults for its type applied, or `None`
+    /// if the Node was registered as an instance. This is set when registering the Node, and is
+    /// used to respawn it, see [`Runtime::respawn_wasm_module_nodes`].
+    configuration: Option<NodeConfiguration>,
+
     /// If the Node is currently running, holds the [`NodeStopper`] (with one
     /// small exception, when the Runtime is in the process of closing down and
     /// the [`NodeStopper`] is held by the shutdown processing code).
     node_stopper: Option<NodeStopper>,
+
+    /// Liveness state of the Node, including the time of its last heartbeat.
+    liveness: NodeLiveness,
+
+    /// Downgrade history of the message most recently read by the Node, which is attached to the
+    /// messages it writes. Only tracked if enabled in the [`RuntimeConfiguration`].
+    downgrade_history: Vec<DowngradeRecord>,
+
+    /// Writers of the messages returned by the most recent read of the Node. Only tracked if
+    /// enabled in the [`RuntimeConfiguration`].
+    last_read_message_writers: Vec<Option<NodeId>>,
+
+    /// CPU time spent by the main thread of the Node, recorded once the Node has finished running.
+    cpu_time: Duration,
+
+    /// Whether the main thread of the Node panicked.
+    crashed: bool,
+
+    /// Seeded random number generator used to allocate the handles of the Node, if
+    /// [`RuntimeConfiguration::rng_seed`] was provided, see [`Runtime::handle_rng`].
+    handle_rng: Option<StdRng>,
+
+    /// Whether the Node is sandboxed, as opposed to a pseudo-Node provided by the Runtime. This is
+    /// set when registering the Node, and is `true` for Nodes that were not registered.
+    sandboxed: bool,
+
+    /// Whether the handles of the Node have been transferred to another Node, see
+    /// [`Runtime::transfer_handles`]. The Node is then asked to terminate as soon as it runs.
+    transferred: bool,
 }
 
 /// Returns a unique debug_id consisting out of the provided name and [`NodeId`].
@@ -227,12 +591,61 @@ pub fn construct_debug_id(name: &str, node_id: NodeId) -> String {
     format!("{}({})", name, node_id.0)
 }
 
+/// Returns the name of the thread running the Node with the provided name and [`NodeId`], based
+/// on [`construct_debug_id`].
+///
+/// Linux truncates thread names, so there the Node name is shortened instead, in order to keep the
+/// [`NodeId`] that tells apart Nodes with the same name.
+fn node_thread_name(name: &str, node_id: NodeId) -> String {
+    if !cfg!(target_os = "linux") {
+        return construct_debug_id(name, node_id);
+    }
+    let suffix = format!("({})", node_id.0);
+    let mut name_len = MAX_LINUX_THREAD_NAME_LEN
+        .saturating_sub(suffix.len())
+        .min(name.len());
+    while !name.is_char_boundary(name_len) {
+        name_len -= 1;
+    }
+    format!("{}{}", &name[..name_len], suffix)
+}
+
 impl NodeInfo {
     /// Returns a unique debug_id used to identify the node in the debug output,
     /// consisting out of the provided [`NodeId`], and the node's name.
     fn get_debug_id(&self, node_id: NodeId) -> String {
         construct_debug_id(&self.name, node_id)
     }
+
+    /// Returns whether the Node is allowed to hold the provided channel half, according to the
+    /// [`Label`]s of the Node and of the underlying channel.
+    ///
+    /// This is equivalent to the checks performed by `Runtime::validate_can_read_from_channel` and
+    /// `Runtime::validate_can_write_to_channel`, but can be used while a lock on
+    /// [`Runtime::node_infos`] is already held.
+    fn validate_can_use_half(
+        &self,
+        half: &ChannelHalf,
+        downgrade: Downgrading,
+    ) -> Result<(), OakStatus> {
+        let effective_label = |label: &Label| match downgrade {
+            Downgrading::Yes => self.privilege.downgrade_label(label),
+            Downgrading::No => label.clone(),
+        };
+        let allowed = match half.direction {
+            ChannelHalfDirection::Read => with_reader_channel(half, |channel| {
+                Ok(effective_label(&channel.label).flows_to(&self.label))
+            })?,
+            ChannelHalfDirection::Write => with_writer_channel(half, |channel| {
+                Ok(effective_label(&self.label).flows_to(&channel.label))
+            })?,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(OakStatus::ErrPermissionDenied)
+        }
+    }
 }
 
 /// The downgrading (declassification + endorsement) privilege associated with a Node instance.
@@ -259,13 +672,59 @@ impl NodePrivilege {
         }
     }
 
+    /// Returns the tags that may be declassified by the Node.
+    pub fn can_declassify_confidentiality_tags(&self) -> &HashSet<Tag> {
+        &self.can_declassify_confidentiality_tags
+    }
+
+    /// Returns the tags that may be endorsed by the Node.
+    pub fn can_endorse_integrity_tags(&self) -> &HashSet<Tag> {
+        &self.can_endorse_integrity_tags
+    }
+
+    /// Returns the privilege that combines all the declassification and endorsement capabilities
+    /// of `self` and `other`.
+    pub fn union(&self, other: &NodePrivilege) -> NodePrivilege {
+        NodePrivilege {
+            can_declassify_confidentiality_tags: self
+                .can_declassify_confidentiality_tags
+                .union(&other.can_declassify_confidentiality_tags)
+                .cloned()
+                .collect(),
+            can_endorse_integrity_tags: self
+                .can_endorse_integrity_tags
+                .union(&other.can_endorse_integrity_tags)
+                .cloned()
+                .collect(),
+        }
+    }
+
     /// Return the infinite privilege.
     ///
     /// A Node with this privilege can downgrade any data regardless of its label. It should only
     /// be used by the trusted pseudo-nodes.
     pub(crate) fn top_privilege() -> Self {
+        Self::namespace_top_privilege(TagNamespace::All)
+    }
+
+    /// Returns whether the privilege includes the infinite privilege returned by
+    /// [`NodePrivilege::top_privilege`].
+    pub(crate) fn is_top_privilege(&self) -> bool {
+        let top = Self::top_privilege();
+        top.can_declassify_confidentiality_tags
+            .is_subset(&self.can_declassify_confidentiality_tags)
+            && top
+                .can_endorse_integrity_tags
+                .is_subset(&self.can_endorse_integrity_tags)
+    }
+
+    /// Returns the infinite privilege over the sub-lattice of tags in the provided `namespace`.
+    ///
+    /// A Node with this privilege can downgrade any data with respect to the tags in `namespace`,
+    /// but not with respect to tags in other sub-lattices.
+    pub fn namespace_top_privilege(namespace: TagNamespace) -> Self {
         let mut top_tag = HashSet::new();
-        top_tag.insert(top());
+        top_tag.insert(namespace_top(namespace));
         NodePrivilege {
             can_declassify_confidentiality_tags: top_tag.clone(),
             can_endorse_integrity_tags: top_tag,
@@ -275,23 +734,20 @@ impl NodePrivilege {
     /// Generates a new [`Label`] from `label` that is downgraded as much as possible using the
     /// current privilege.
     fn downgrade_label(&self, label: &Label) -> Label {
-        let has_top_privilege = self.can_declassify_confidentiality_tags.contains(&top());
-
-        let confidentiality_tags = if has_top_privilege {
-            // Remove all the confidentiality tags if the node has the `top` privilege.
-            // TODO(#1631): When we have a separate top for each sub-lattice, this check should be
-            // done separately for each sub-lattice, removing only the tags belonging to that
-            // sub-lattice.
-            vec![]
-        } else {
-            // Remove all the confidentiality tags that the Node may declassify.
-            label
-                .confidentiality_tags
-                .iter()
-                .filter(|t| !self.can_declassify_confidentiality_tags.contains(t))
-                .cloned()
-                .collect()
-        };
+        // Remove all the confidentiality tags that the Node may declassify, either explicitly or
+        // by having the `top` privilege of their sub-lattice.
+        let confidentiality_tags = label
+            .confidentiality_tags
+            .iter()
+            .filter(|t| {
+                !self.can_declassify_confidentiality_tags.contains(t)
+                    && !self
+                        .can_declassify_confidentiality_tags
+                        .iter()
+                        .any(|privilege| privilege.is_top_of(t))
+            })
+            .cloned()
+            .collect();
         Label {
             confidentiality_tags,
             // Add all the integrity tags that the Node may endorse.
@@ -331,8 +787,13 @@ impl std::fmt::Debug for NodeInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "NodeInfo {{'{}', label={:?}, node_stopper={:?}, handles=[",
-            self.name, self.label, self.node_stopper,
+            "NodeInfo {{'{}', label={:?}, node_stopper={:?}, initial_handle={:?}, \
+             creatable_node_types={:?}, handles=[",
+            self.name,
+            self.label,
+            self.node_stopper,
+            self.initial_handle,
+            self.creatable_node_types,
         )?;
         write!(
             f,
@@ -346,6 +807,51 @@ impl std::fmt::Debug for NodeInfo {
     }
 }
 
+/// Outcome of stopping the [`Runtime`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShutdownStatus {
+    /// All the Node threads terminated.
+    Completed,
+    /// Some Node threads did not terminate within the
+    /// [shutdown timeout](RuntimeConfiguration::shutdown_timeout), and were abandoned.
+    Forced,
+}
+
+/// Detailed outcome of stopping the [`Runtime`], see [`Runtime::stop_with_report`].
+#[derive(Debug)]
+pub struct ShutdownReport {
+    /// Overall outcome of stopping the Runtime.
+    pub status: ShutdownStatus,
+    /// Result of joining the main thread of each Node, holding the panic payload if the Node
+    /// panicked.
+    ///
+    /// This does not include the Nodes whose threads were abandoned. Of the Nodes whose threads
+    /// were already joined before stopping the Runtime, only those that panicked are included, and
+    /// only the most recent [`MAX_EARLY_JOINED_NODE_FAILURES`] of them, so that a long-running
+    /// Runtime does not keep the results of all the Nodes that ever exited.
+    pub node_results: Vec<(NodeId, thread::Result<()>)>,
+}
+
+impl ShutdownReport {
+    /// Returns the [`NodeId`]s of the Nodes whose main thread panicked.
+    pub fn panicked_nodes(&self) -> Vec<NodeId> {
+        self.node_results
+            .iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(node_id, _)| *node_id)
+            .collect()
+    }
+}
+
+/// Outcome of ensuring that a Node with a given name exists, identifying the Node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnsuredNode {
+    /// The Node was newly created.
+    Created(NodeId),
+    /// A Node with the same name already existed, so no Node was created.
+    Existing(NodeId),
+}
+
 /// A unique internal identifier for a Node or pseudo-Node instance.
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, PartialOrd, Ord)]
 pub struct NodeId(pub u64);
@@ -369,6 +875,27 @@ pub enum LabelReadStatus {
     NeedsCapacity(usize),
 }
 
+/// Result of reading from a channel with [`Runtime::channel_read_with_close_reason`].
+#[derive(Debug, PartialEq)]
+pub enum ChannelReadOutcome {
+    /// A message was read from the channel.
+    Message(NodeMessage),
+    /// The channel is empty, but still has writers.
+    Empty,
+    /// The channel is empty and orphaned, closed by its last writer for the provided reason.
+    Closed(CloseReason),
+}
+
+/// A copy of the front message of a channel, obtained without dequeuing it.
+///
+/// Only the number of handles included in the message is provided, as peeking does not allocate
+/// ABI handles for them.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PeekedMessage {
+    pub bytes: Vec<u8>,
+    pub handle_count: usize,
+}
+
 /// Indicator whether an operation is executed using the Node's label-downgrading privilege or
 /// without it.
 #[derive(Clone, Copy, Debug)]
@@ -377,18 +904,67 @@ enum Downgrading {
     Yes,
 }
 
+/// Direction in which a message moves through a channel, as reported by the
+/// `runtime_channel_messages_total` and `runtime_channel_bytes_total` metrics.
+#[derive(Clone, Copy, Debug)]
+enum ChannelTraffic {
+    Enqueue,
+    Dequeue,
+}
+
 /// Information for managing an associated server.
 pub struct AuxServer {
     pub name: String,
     pub join_handle: Option<JoinHandle<()>>,
     pub termination_notification_sender: Option<tokio::sync::oneshot::Sender<()>>,
+    /// Receiver notified once the server has bound to its address, if the server listens on one.
+    bound_notification_receiver: Option<mpsc::Receiver<()>>,
 }
 
 impl AuxServer {
-    /// Start a new auxiliary server, running on its own thread.
-    fn new<F: FnOnce(u16, Arc<Runtime>, tokio::sync::oneshot::Receiver<()>) + 'static + Send>(
+    /// Start a new auxiliary server listening on `address`, running on its own thread.
+    ///
+    /// The server is expected to serve HTTPS using the provided TLS identity, if any, and to send a
+    /// notification on the provided [`mpsc::Sender`] once it has bound to `address`, or to stop
+    /// without sending it if it cannot bind to it.
+    fn new<
+        F: FnOnce(
+                SocketAddr,
+                Option<tls::TlsConfig>,
+                Arc<Runtime>,
+                tokio::sync::oneshot::Receiver<()>,
+                mpsc::Sender<()>,
+            )
+            + 'static
+            + Send,
+    >(
+        name: &str,
+        address: SocketAddr,
+        tls_config: Option<tls::TlsConfig>,
+        runtime: Arc<Runtime>,
+        f: F,
+    ) -> Self {
+        let (bound_notification_sender, bound_notification_receiver) = mpsc::channel::<()>();
+        let mut aux_server = Self::spawn(
+            name,
+            runtime,
+            move |runtime, termination_notification_receiver| {
+                f(
+                    address,
+                    tls_config,
+                    runtime,
+                    termination_notification_receiver,
+                    bound_notification_sender,
+                )
+            },
+        );
+        aux_server.bound_notification_receiver = Some(bound_notification_receiver);
+        aux_server
+    }
+
+    /// Start a new auxiliary server that does not listen on an address, running on its own thread.
+    fn spawn<F: FnOnce(Arc<Runtime>, tokio::sync::oneshot::Receiver<()>) + 'static + Send>(
         name: &str,
-        port: u16,
         runtime: Arc<Runtime>,
         f: F,
     ) -> Self {
@@ -397,13 +973,29 @@ impl AuxServer {
         info!("spawning {} server on new thread", name);
         let join_handle = thread::Builder::new()
             .name(format!("{}-server", name))
-            .spawn(move || f(port, runtime, termination_notification_receiver))
+            .spawn(move || f(runtime, termination_notification_receiver))
             .expect("failed to spawn introspection thread");
         AuxServer {
             name: name.to_string(),
             join_handle: Some(join_handle),
             termination_notification_sender: Some(termination_notification_sender),
+            bound_notification_receiver: None,
+        }
+    }
+
+    /// Blocks until the server has bound to its address, if it listens on one.
+    ///
+    /// Fails with [`OakStatus::ErrInternal`] if the server stopped before binding, e.g. because
+    /// the address is already in use.
+    fn wait_until_bound(&mut self) -> Result<(), OakStatus> {
+        if let Some(bound_notification_receiver) = self.bound_notification_receiver.take() {
+            info!("waiting for {} server to bind", self.name);
+            bound_notification_receiver.recv().map_err(|_| {
+                error!("{} server stopped before binding", self.name);
+                OakStatus::ErrInternal
+            })?;
         }
+        Ok(())
     }
 }
 
@@ -429,6 +1021,8 @@ impl Drop for AuxServer {
 /// Runtime structure for configuring and running a set of Oak Nodes.
 pub struct Runtime {
     terminating: AtomicBool,
+    /// Whether the [`Runtime`] is draining before stopping: see [`Runtime::stop_drain`].
+    draining: AtomicBool,
 
     next_channel_id: AtomicU64,
 
@@ -443,9 +1037,130 @@ pub struct Runtime {
     #[allow(dead_code)]
     introspection_event_queue: Mutex<VecDeque<Event>>,
 
+    /// Maximum length of [`Runtime::introspection_event_queue`].
+    #[allow(dead_code)]
+    introspection_event_queue_capacity: AtomicUsize,
+
+    /// Number of introspection events dropped from [`Runtime::introspection_event_queue`] because
+    /// it was full.
+    #[allow(dead_code)]
+    introspection_events_dropped: AtomicU64,
+
+    /// Bitmask of the categories of introspection events that are not generated.
+    #[allow(dead_code)]
+    disabled_introspection_event_categories: AtomicU64,
+
+    /// Background writer of introspection events to a file, if enabled.
+    #[cfg(feature = "oak-unsafe")]
+    introspection_event_log: Mutex<Option<introspection_event_log::IntrospectionEventLog>>,
+
+    /// Senders to the receivers returned by [`Runtime::subscribe_events`] that are still alive.
+    #[allow(dead_code)]
+    introspection_event_subscribers: Mutex<Vec<mpsc::Sender<Event>>>,
+
     node_factory: node::ServerNodeFactory,
 
     pub metrics_data: Metrics,
+
+    /// Serializes calls to [`Runtime::ensure_node`], so that checking for an existing Node and
+    /// creating a new one happen atomically.
+    ensure_node_lock: Mutex<()>,
+
+    /// Maximum length of the downgrade history carried by messages, or zero if downgrade histories
+    /// are not tracked.
+    downgrade_history_limit: AtomicUsize,
+
+    /// Whether messages are tagged with the [`NodeId`] of their writer.
+    tag_message_writers: AtomicBool,
+
+    /// Maximum number of handles that a single message written to a channel may contain.
+    max_handles_per_message: AtomicUsize,
+
+    /// Default [`NodeConfiguration`]s for each type of Node, see
+    /// [`RuntimeConfiguration::node_config_defaults`].
+    node_config_defaults: RwLock<HashMap<String, NodeConfiguration>>,
+
+    /// Minimum interval in milliseconds between two rate-limited log messages of the same kind
+    /// for the same Node.
+    log_rate_limit_interval_millis: AtomicU64,
+
+    /// Tracks when rate-limited log messages were last logged.
+    log_rate_limiter: log_limiter::LogRateLimiter,
+
+    /// Queue lengths reported by the `runtime_channel_queue_length` metric.
+    channel_queue_lengths: metrics::ChannelQueueLengths,
+
+    /// Message rates reported by the `runtime_channel_message_rate` metric.
+    channel_message_rates: metrics::ChannelMessageRates,
+
+    /// Label buckets of the channels reported by the metrics aggregated by label bucket.
+    channel_label_buckets: metrics::ChannelLabelBuckets,
+
+    /// Whether channel names must be unique, see
+    /// [`RuntimeConfiguration::require_unique_channel_names`].
+    require_unique_channel_names: AtomicBool,
+
+    /// Names of the live channels, mapped to their channel IDs, if channel names must be unique.
+    channel_names: Mutex<HashMap<String, u64>>,
+
+    /// Nodes removed within the grace period, see
+    /// [`RuntimeConfiguration::node_tombstone_grace_period`].
+    node_tombstones: node_tombstones::NodeTombstones,
+
+    /// Stoppers of Nodes that have already been removed, whose threads are yet to be joined.
+    ///
+    /// A Node thread removes its own Node on exit, so it cannot join itself; its stopper is kept
+    /// here instead until the thread has finished, see [`Runtime::join_exited_nodes`].
+    exited_node_stoppers: Mutex<HashMap<NodeId, NodeStopper>>,
+
+    /// Failures of the threads of Nodes that were joined before stopping the Runtime, to be
+    /// included in its [`ShutdownReport`]; bounded by [`MAX_EARLY_JOINED_NODE_FAILURES`].
+    joined_node_failures: Mutex<VecDeque<(NodeId, thread::Result<()>)>>,
+
+    /// [`NodeId`]s derived from Node names, see [`RuntimeConfiguration::stable_node_ids`].
+    stable_node_ids: node_ids::StableNodeIds,
+
+    /// Seed provided as [`RuntimeConfiguration::rng_seed`], from which the random number
+    /// generators used to allocate handles (see [`Runtime::handle_rng`]) and those of random
+    /// pseudo-Nodes (see [`Runtime::node_rng`]) are derived.
+    rng_seed: Mutex<Option<u64>>,
+
+    /// Maximum time in milliseconds that stopping waits for Node threads to terminate, or 0 to
+    /// wait indefinitely.
+    shutdown_timeout_millis: AtomicU64,
+
+    /// Source of the current time, used to expire messages written with a TTL.
+    clock: RwLock<Arc<dyn time::Clock>>,
+
+    /// Write half of the channel to which undeliverable messages are forwarded, if any.
+    dead_letter_channel: RwLock<Option<ChannelHalf>>,
+
+    /// Whether the introspection server shows labels as JSON, see
+    /// [`RuntimeConfiguration::introspect_json_labels`].
+    introspect_json_labels: AtomicBool,
+
+    /// Token required by the introspection server, see
+    /// [`RuntimeConfiguration::introspect_auth_token`].
+    introspect_auth_token: RwLock<Option<String>>,
+
+    /// Maximum number of live Nodes, or `usize::MAX` if unlimited, see
+    /// [`RuntimeConfiguration::max_nodes`].
+    max_nodes: AtomicUsize,
+
+    /// Number of Nodes that capacity has been reserved for with
+    /// [`Runtime::reserve_node_capacity`], but that have not been added yet. Only updated
+    /// while holding the [`Runtime::node_infos`] write lock, so that it is consistent with the
+    /// number of live Nodes.
+    reserved_nodes: AtomicUsize,
+
+    /// Token buckets limiting the rate at which each Node creates other Nodes, see
+    /// [`RuntimeConfiguration::node_creation_rate_limit`].
+    node_creation_rate_limiter: node_creation_limiter::NodeCreationRateLimiter,
+
+    /// Whether the time since which each Node has been waiting on channels is tracked, which is
+    /// only needed to detect stuck Nodes, see [`RuntimeConfiguration::heartbeat_config`] and
+    /// [`RuntimeConfiguration::watchdog_config`].
+    track_waiting_nodes: AtomicBool,
 }
 
 /// Manual implementation of the [`Drop`] trait to ensure that all components of
@@ -464,9 +1179,153 @@ impl Runtime {
     fn new_abi_handle(&self, node_id: NodeId, half: ChannelHalf) -> oak_abi::Handle {
         let mut node_infos = self.node_infos.write().unwrap();
         let node_info = node_infos.get_mut(&node_id).expect("Invalid node_id");
+        self.insert_abi_handle(node_id, node_info, half)
+    }
+
+    /// Registers [`ChannelHalf`]s with a Node, returning the new handle values for them in the same
+    /// order.
+    ///
+    /// Fails with [`OakStatus::ErrInvalidArgs`], without registering any of them, if the handle
+    /// table of the Node cannot hold them all according to the `max_handles_per_node` permission.
+    /// The check and the registration happen under a single write lock on [`Runtime::node_infos`],
+    /// so that concurrent operations of the Node cannot exceed the limit.
+    fn new_abi_handles(
+        &self,
+        node_id: NodeId,
+        halves: Vec<ChannelHalf>,
+    ) -> Result<Vec<oak_abi::Handle>, OakStatus> {
+        let count = halves.len();
+        {
+            let mut node_infos = self.node_infos.write().unwrap();
+            let node_info = node_infos.get_mut(&node_id).expect("Invalid node_id");
+            if count <= self.handle_table_space_for(node_info.abi_handles.len()) {
+                return Ok(halves
+                    .into_iter()
+                    .map(|half| self.insert_abi_handle(node_id, node_info, half))
+                    .collect());
+            }
+        }
+        self.log_rate_limited(Level::Warn, node_id, "handle_table_full", || {
+            format!(
+                "{:?}: cannot add {} handles to full handle table",
+                self.get_node_debug_id(node_id),
+                count
+            )
+        });
+        Err(OakStatus::ErrInvalidArgs)
+    }
+
+    /// Returns the number of handles that can still be added to the handle table of the Node
+    /// identified by `node_id` according to the `max_handles_per_node` permission, or `usize::MAX`
+    /// if it is unlimited.
+    ///
+    /// If the number is limited and `lock` is set, the write lock on [`Runtime::node_infos`] is
+    /// also returned, and must be held until the handles are added to the table (see
+    /// [`Runtime::node_message_from`]), so that concurrent operations of the Node cannot take up
+    /// the space in the meantime. Since the lock is taken first, channel locks may be taken while
+    /// holding it.
+    ///
+    /// If the number is limited and `lock` is not set, no lock is taken and 0 is returned: a
+    /// reader may then only receive messages without handles, and must read again with `lock` set
+    /// to receive a message carrying handles. This keeps the global write lock off the read path
+    /// of the (common) messages without handles.
+    fn lock_handle_table_space(
+        &self,
+        node_id: NodeId,
+        lock: bool,
+    ) -> (usize, Option<RwLockWriteGuard<'_, HashMap<NodeId, NodeInfo>>>) {
+        if self
+            .node_factory
+            .permissions_configuration
+            .max_handles_per_node
+            .is_none()
+        {
+            return (usize::MAX, None);
+        }
+        if !lock {
+            return (0, None);
+        }
+        let node_infos = self.node_infos.write().unwrap();
+        let handles = node_infos
+            .get(&node_id)
+            .map_or(0, |node_info| node_info.abi_handles.len());
+        (self.handle_table_space_for(handles), Some(node_infos))
+    }
+
+    /// Returns the number of handles that can still be added to a handle table that already holds
+    /// `handles` handles, or `usize::MAX` if it is unlimited.
+    fn handle_table_space_for(&self, handles: usize) -> usize {
+        match self
+            .node_factory
+            .permissions_configuration
+            .max_handles_per_node
+        {
+            Some(max_handles) => max_handles.saturating_sub(handles),
+            None => usize::MAX,
+        }
+    }
+
+    /// Seeds the random number generators used to allocate handles, including those of existing
+    /// Nodes, or reverts to using fresh entropy if `seed` is `None`.
+    fn set_rng_seed(&self, seed: Option<u64>) {
+        *self.rng_seed.lock().unwrap() = seed;
+        let mut node_infos = self.node_infos.write().unwrap();
+        for (node_id, node_info) in node_infos.iter_mut() {
+            node_info.handle_rng = self.handle_rng(*node_id);
+        }
+    }
+
+    /// Returns the random number generator used to allocate the handles of the Node identified by
+    /// `node_id`, derived from both [`RuntimeConfiguration::rng_seed`] and the [`NodeId`], or
+    /// `None` if no seed was provided and fresh entropy should be used instead.
+    ///
+    /// Each Node has its own generator, so that allocating handles does not contend on a lock
+    /// shared by all Nodes.
+    fn handle_rng(&self, node_id: NodeId) -> Option<StdRng> {
+        self.rng_seed
+            .lock()
+            .unwrap()
+            .map(|seed| StdRng::seed_from_u64(seed ^ node_id.0))
+    }
+
+    /// Returns a random number generator for the Node named `node_name`, derived from both
+    /// [`RuntimeConfiguration::rng_seed`] and the name so that it produces the same values across
+    /// runs, or `None` if no seed was provided and fresh entropy should be used instead.
+    ///
+    /// Unlike [`Runtime::handle_rng`], the values produced do not depend on the order in which
+    /// Nodes are created.
+    pub(crate) fn node_rng(&self, node_name: &str) -> Option<StdRng> {
+        self.rng_seed.lock().unwrap().map(|seed| {
+            let mut input = seed.to_le_bytes().to_vec();
+            input.extend_from_slice(node_name.as_bytes());
+            let mut node_seed = <StdRng as SeedableRng>::Seed::default();
+            node_seed.copy_from_slice(&oak_sign::get_sha256(&input));
+            StdRng::from_seed(node_seed)
+        })
+    }
+
+    /// Returns a random candidate value for a new handle of the Node with the provided
+    /// [`NodeInfo`].
+    fn random_handle(node_info: &mut NodeInfo) -> oak_abi::Handle {
+        match node_info.handle_rng.as_mut() {
+            Some(rng) => rng.next_u64(),
+            None => rand::thread_rng().next_u64(),
+        }
+    }
+
+    /// Register a [`ChannelHalf`] in the provided [`NodeInfo`] of the Node identified by
+    /// `node_id`, returning the new handle value for it.
+    ///
+    /// The caller is expected to hold the write lock on [`Runtime::node_infos`].
+    fn insert_abi_handle(
+        &self,
+        node_id: NodeId,
+        node_info: &mut NodeInfo,
+        half: ChannelHalf,
+    ) -> oak_abi::Handle {
         loop {
-            let candidate = rand::thread_rng().next_u64();
-            if node_info.abi_handles.get(&candidate).is_none() {
+            let candidate = Self::random_handle(node_info);
+            if !node_info.abi_handles.contains_key(&candidate) {
                 debug!(
                     "{:?}: new ABI handle {} maps to {:?}",
                     node_info.get_debug_id(node_id),
@@ -492,13 +1351,26 @@ impl Runtime {
             }
         }
     }
-    /// Remove the handle from the Node's handle table.
-    fn drop_abi_handle(&self, node_id: NodeId, handle: oak_abi::Handle) -> Result<(), OakStatus> {
+    /// Remove the handle from the Node's handle table, returning the [`ChannelHalf`] it referred
+    /// to.
+    fn drop_abi_handle(
+        &self,
+        node_id: NodeId,
+        handle: oak_abi::Handle,
+    ) -> Result<ChannelHalf, OakStatus> {
         let mut node_infos = self.node_infos.write().unwrap();
         let node_info = node_infos.get_mut(&node_id).expect("Invalid node_id");
 
         match node_info.abi_handles.remove(&handle) {
             Some(half) => {
+                if half.direction == ChannelHalfDirection::Read
+                    && !node_info.abi_handles.values().any(|other| {
+                        other.direction == ChannelHalfDirection::Read
+                            && other.get_channel_id() == half.get_channel_id()
+                    })
+                {
+                    half.remove_consumer(node_id);
+                }
                 self.introspection_event(EventDetails::HandleDestroyed(HandleDestroyed {
                     node_id: node_id.0,
                     handle,
@@ -509,7 +1381,7 @@ impl Runtime {
                     },
                 }));
 
-                Ok(())
+                Ok(half)
             }
             None => Err(OakStatus::ErrBadHandle),
         }
@@ -528,7 +1400,7 @@ impl Runtime {
             .ok_or(OakStatus::ErrBadHandle)?;
         trace!(
             "{:?}: map ABI handle {} to {:?}",
-            self.get_node_debug_id(node_id),
+            node_info.get_debug_id(node_id),
             handle,
             half
         );
@@ -571,8 +1443,33 @@ impl Runtime {
         Ok(half.direction)
     }
 
+    /// Returns the handle table of the Node identified by `node_id`, as the direction and channel
+    /// ID of the channel half that each handle refers to, ordered by handle. Returns an empty list
+    /// if there is no such Node.
+    ///
+    /// The whole table is read under a single lock, so it is a consistent snapshot. It only
+    /// contains metadata, and neither labels nor message contents.
+    pub fn list_handles(
+        &self,
+        node_id: NodeId,
+    ) -> Vec<(oak_abi::Handle, ChannelHalfDirection, u64)> {
+        let node_infos = self.node_infos.read().unwrap();
+        node_infos
+            .get(&node_id)
+            .map(|node_info| {
+                node_info
+                    .abi_handles
+                    .iter()
+                    .map(|(handle, half)| (*handle, half.direction, half.get_channel_id()))
+                    .sorted_by_key(|(handle, _, _)| *handle)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Return the accumulated metrics for the `Runtime`.
     pub fn gather_metrics(&self) -> Vec<MetricFamily> {
+        self.refresh_channel_message_rates();
         self.metrics_data.gather()
     }
 }
@@ -584,36 +1481,197 @@ impl Runtime {
         self.terminating.load(SeqCst)
     }
 
-    /// Signal termination to a [`Runtime`] and wait for its Node threads to terminate.
-    pub fn stop(&self) {
-        info!("stopping runtime instance");
-
-        // Terminate any running servers.
-        self.aux_servers.lock().unwrap().drain(..);
+    /// Return whether the [`Runtime`] is draining the messages queued on its channels before
+    /// stopping.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(SeqCst)
+    }
 
-        // Set the terminating flag; this will prevent additional Nodes from starting to wait again,
-        // because `wait_on_channels` will return immediately with `OakStatus::ErrTerminated`.
-        self.terminating.store(true, SeqCst);
+    /// Stop accepting new messages on channels, wait until Nodes have read all the messages
+    /// already queued or until `timeout` has elapsed, and then proceed as in [`Runtime::stop`].
+    ///
+    /// Nodes keep running normally while draining, except that writing to a channel fails with
+    /// [`OakStatus::ErrTerminated`].
+    pub fn stop_drain(&self, timeout: Duration) -> ShutdownStatus {
+        info!("draining runtime instance");
+        self.draining.store(true, SeqCst);
+
+        // A timeout too large to be represented is equivalent to no timeout at all.
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let pending_messages = self.pending_message_count();
+            if pending_messages == 0 {
+                info!("drained all channels");
+                break;
+            }
+            if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                warn!(
+                    "timed out draining channels, discarding {} pending messages",
+                    pending_messages
+                );
+                break;
+            }
+            thread::sleep(STOP_POLL_INTERVAL);
+        }
 
-        // Unpark any threads that are blocked waiting on any channels.
-        self.notify_all_waiters();
+        self.stop()
+    }
 
-        // Wait for the main thread of each Node to finish. Any thread that was blocked on
-        // `wait_on_channels` is now unblocked and received `OakStatus::ErrTerminated`, so we wait
-        // for any additional work to be finished here. This may take an arbitrary amount of time,
-        // depending on the work that the Node thread has to perform, but at least we know that the
-        // it will not be able to enter again in a blocking state.
-        let node_stoppers = self.take_node_stoppers();
+    /// Returns the number of messages queued on the channels that Nodes can still read from,
+    /// including channels whose read halves are themselves in messages yet to be read.
+    fn pending_message_count(&self) -> usize {
+        let mut pending_messages = HashMap::new();
+        let mut visitor = |half: &ChannelHalf| {
+            half.direction == ChannelHalfDirection::Read
+                && pending_messages
+                    .insert(half.get_channel_id(), half.get_messages().len())
+                    .is_none()
+        };
+        let node_infos = self
+            .node_infos
+            .read()
+            .expect("could not acquire lock on node_infos");
+        for half in node_infos
+            .values()
+            .flat_map(|node_info| node_info.abi_handles.values())
+        {
+            half.visit_halves(&mut visitor);
+        }
+        pending_messages.values().sum()
+    }
+
+    /// Signal termination to a [`Runtime`] and wait for its Node threads to terminate, or until
+    /// the [shutdown timeout](RuntimeConfiguration::shutdown_timeout) has elapsed.
+    ///
+    /// See [`Runtime::stop_with_report`] for the outcome of joining each Node thread.
+    pub fn stop(&self) -> ShutdownStatus {
+        self.stop_with_report().status
+    }
+
+    /// Same as [`Runtime::stop`], but also returns the result of joining each Node thread, so that
+    /// callers can detect Nodes that panicked.
+    pub fn stop_with_report(&self) -> ShutdownReport {
+        info!("stopping runtime instance");
+
+        // Start the shutdown timeout before anything else, so that it also covers the time spent
+        // terminating the auxiliary servers.
+        let deadline = self
+            .shutdown_timeout()
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+
+        // Terminate any running servers.
+        self.aux_servers.lock().unwrap().drain(..);
+
+        // Set the terminating flag; this will prevent additional Nodes from starting to wait again,
+        // because `wait_on_channels` will return immediately with `OakStatus::ErrTerminated`.
+        self.terminating.store(true, SeqCst);
+
+        // Unpark any threads that are blocked waiting on any channels.
+        self.notify_all_waiters();
+
+        // Wait for the main thread of each Node to finish. Any thread that was blocked on
+        // `wait_on_channels` is now unblocked and received `OakStatus::ErrTerminated`, so we wait
+        // for any additional work to be finished here. This may take an arbitrary amount of time,
+        // depending on the work that the Node thread has to perform, but at least we know that the
+        // it will not be able to enter again in a blocking state.
+        //
+        // If a shutdown timeout is configured, the remaining threads are abandoned once it has
+        // elapsed, so that a misbehaving Node cannot block the Runtime from stopping forever.
+        let mut abandoned_nodes = Vec::new();
+        let mut node_results: Vec<_> =
+            std::mem::take(&mut *self.joined_node_failures.lock().unwrap()).into();
+        let mut node_stoppers = self.take_node_stoppers();
+        // Also join the threads of Nodes that have exited but have not been joined yet.
+        node_stoppers.extend(
+            self.exited_node_stoppers
+                .lock()
+                .unwrap()
+                .drain()
+                .map(|(node_id, node_stopper)| (node_id, Some(node_stopper))),
+        );
         for (node_id, node_stopper_opt) in node_stoppers {
             if let Some(node_stopper) = node_stopper_opt {
                 let node_debug_id = node_stopper.get_debug_id(node_id);
                 info!("stopping node {:?} ...", node_debug_id);
-                if let Err(err) = node_stopper.stop_node(node_id) {
-                    error!("could not stop node {:?}: {:?}", node_debug_id, err);
+                match node_stopper.stop_node(node_id, deadline) {
+                    Some(result) => {
+                        match &result {
+                            Ok(()) => info!("stopping node {:?}...done", node_debug_id),
+                            Err(err) => {
+                                error!("could not stop node {:?}: {:?}", node_debug_id, err)
+                            }
+                        }
+                        node_results.push((node_id, result));
+                    }
+                    None => abandoned_nodes.push(node_debug_id),
                 }
-                info!("stopping node {:?}...done", node_debug_id);
             }
         }
+        let status = if abandoned_nodes.is_empty() {
+            ShutdownStatus::Completed
+        } else {
+            error!(
+                "nodes failed to terminate within the shutdown timeout: {:?}",
+                abandoned_nodes
+            );
+            self.metrics_data
+                .runtime_metrics
+                .runtime_forced_shutdowns_total
+                .inc();
+            ShutdownStatus::Forced
+        };
+
+        // Only close the introspection event log once all Nodes are stopped, so that it includes
+        // the events generated by stopping them.
+        #[cfg(feature = "oak-unsafe")]
+        self.close_introspection_event_log();
+
+        ShutdownReport {
+            status,
+            node_results,
+        }
+    }
+
+    /// Sets the maximum time that stopping waits for Node threads to terminate, or waits
+    /// indefinitely if `timeout` is `None`.
+    pub(crate) fn set_shutdown_timeout(&self, timeout: Option<Duration>) {
+        self.shutdown_timeout_millis.store(
+            // A zero timeout would mean waiting indefinitely, so round it up.
+            timeout.map_or(0, |timeout| (timeout.as_millis() as u64).max(1)),
+            SeqCst,
+        );
+    }
+
+    /// Sets the maximum number of live Nodes, see [`RuntimeConfiguration::max_nodes`].
+    pub(crate) fn set_max_nodes(&self, max_nodes: Option<usize>) {
+        self.max_nodes.store(max_nodes.unwrap_or(usize::MAX), SeqCst);
+    }
+
+    /// Returns the maximum time that stopping waits for Node threads to terminate, if any.
+    fn shutdown_timeout(&self) -> Option<Duration> {
+        match self.shutdown_timeout_millis.load(SeqCst) {
+            0 => None,
+            millis => Some(Duration::from_millis(millis)),
+        }
+    }
+
+    /// Returns the number of Nodes currently registered in the [`Runtime`], only counting Nodes of
+    /// type `node_type` if provided.
+    ///
+    /// This is intentionally label-agnostic, as it only exposes a count and not the identities of
+    /// the Nodes.
+    pub fn node_count(&self, node_type: Option<&str>) -> u64 {
+        let node_infos = self
+            .node_infos
+            .read()
+            .expect("could not acquire lock on node_infos");
+        node_infos
+            .values()
+            .filter(|node_info| match node_type {
+                Some(node_type) => node_info.node_type == node_type,
+                None => true,
+            })
+            .count() as u64
     }
 
     /// Move all of the [`NodeStopper`] objects out of the `node_infos` tracker and return them.
@@ -678,11 +1736,43 @@ impl Runtime {
         downgrade: Downgrading,
     ) -> Label {
         match downgrade {
-            Downgrading::Yes => self.get_node_downgraded_label(node_id, initial_label),
+            Downgrading::Yes => {
+                let downgraded_label = self.get_node_downgraded_label(node_id, initial_label);
+                // Downgrading never makes a label more restrictive, so it only changes the label
+                // if the result is strictly less restrictive.
+                if !initial_label.flows_to(&downgraded_label) {
+                    self.record_label_downgrade(node_id, initial_label, &downgraded_label);
+                }
+                downgraded_label
+            }
             Downgrading::No => initial_label.to_owned(),
         }
     }
 
+    /// Records that the Node identified by `node_id` has used its privilege to downgrade
+    /// `original_label` to `downgraded_label`, for auditing.
+    ///
+    /// The `runtime_downgrades_total` metric is always incremented, but the labels are only
+    /// reported in a `LabelDowngraded` introspection event with the `oak-unsafe` feature.
+    fn record_label_downgrade(
+        &self,
+        node_id: NodeId,
+        original_label: &Label,
+        downgraded_label: &Label,
+    ) {
+        self.metrics_data
+            .runtime_metrics
+            .runtime_downgrades_total
+            .inc();
+        if cfg!(feature = "oak-unsafe") {
+            self.introspection_event(EventDetails::LabelDowngraded(LabelDowngraded {
+                node_id: node_id.0,
+                original_label: Some(original_label.clone()),
+                downgraded_label: Some(downgraded_label.clone()),
+            }));
+        }
+    }
+
     /// Returns a clone of the [`NodePrivilege`] of the provided Node.
     fn get_node_privilege(&self, node_id: NodeId) -> NodePrivilege {
         let node_infos = self
@@ -693,6 +1783,52 @@ impl Runtime {
         node_info.privilege.clone()
     }
 
+    /// Returns the combined [`NodePrivilege`] of a chain of Nodes, i.e. the union of their
+    /// declassification and endorsement capabilities.
+    ///
+    /// Data flowing through all the Nodes in the chain may be downgraded by any of them, so this
+    /// describes the aggregate downgrading reach of a pipeline, e.g. to confirm during security
+    /// review that no path can declassify a sensitive tag.
+    ///
+    /// Fails with [`OakStatus::ErrInvalidArgs`] if any of the Nodes does not exist.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn chain_privilege(&self, node_ids: &[NodeId]) -> Result<NodePrivilege, OakStatus> {
+        let node_infos = self
+            .node_infos
+            .read()
+            .expect("could not acquire lock on node_infos");
+        node_ids
+            .iter()
+            .try_fold(NodePrivilege::default(), |privilege, node_id| {
+                node_infos
+                    .get(node_id)
+                    .map(|node_info| privilege.union(&node_info.privilege))
+                    .ok_or(OakStatus::ErrInvalidArgs)
+            })
+    }
+
+    /// Returns the least restrictive label that the Node identified by `node_id` may downgrade
+    /// `label` to using its own privilege, i.e. the effective label of `label` for the Node when
+    /// it performs an operation with [`Downgrading::Yes`].
+    ///
+    /// The Node's privilege only removes the confidentiality tags of `label` that the Node may
+    /// declassify, and adds all the integrity tags that the Node may endorse, so the result
+    /// reveals the endorsement privilege of the Node. As for [`Runtime::chain_privilege`], this is
+    /// therefore only available for introspection.
+    ///
+    /// Fails with [`OakStatus::ErrInvalidArgs`] if the Node does not exist.
+    #[cfg(feature = "oak-unsafe")]
+    pub fn downgraded_label(&self, node_id: NodeId, label: &Label) -> Result<Label, OakStatus> {
+        let node_infos = self
+            .node_infos
+            .read()
+            .expect("could not acquire lock on node_infos");
+        node_infos
+            .get(&node_id)
+            .map(|node_info| node_info.privilege.downgrade_label(label))
+            .ok_or(OakStatus::ErrInvalidArgs)
+    }
+
     /// Returns a unique debug_id used to identify the Node in the debug output,
     /// consisting out of the provided [`NodeId`], and the Node name.
     fn get_node_debug_id(&self, node_id: NodeId) -> String {
@@ -727,8 +1863,8 @@ impl Runtime {
         handle: oak_abi::Handle,
         capacity: usize,
     ) -> Result<LabelReadStatus, OakStatus> {
-        let label = self.get_channel_label(node_id, handle)?;
-        serialize_label(label, capacity)
+        let half = self.abi_to_half(node_id, handle)?;
+        Ok(label_read_status(half.get_serialized_label(), capacity))
     }
 
     /// Returns the [`Label`] associated with the node serialized as a byte array.
@@ -740,7 +1876,9 @@ impl Runtime {
         node_id: NodeId,
         capacity: usize,
     ) -> Result<LabelReadStatus, OakStatus> {
-        serialize_label(self.get_node_label(node_id), capacity)
+        let node_infos = self.node_infos.read().unwrap();
+        let node_info = node_infos.get(&node_id).expect("Invalid node_id");
+        Ok(label_read_status(&node_info.serialized_label, capacity))
     }
 
     /// Returns the [`NodePrivilege`] associated with the node converted to a [`Label`] and
@@ -753,7 +1891,9 @@ impl Runtime {
         node_id: NodeId,
         capacity: usize,
     ) -> Result<LabelReadStatus, OakStatus> {
-        serialize_label(self.get_node_privilege(node_id).into(), capacity)
+        let node_infos = self.node_infos.read().unwrap();
+        let node_info = node_infos.get(&node_id).expect("Invalid node_id");
+        Ok(label_read_status(&node_info.serialized_privilege, capacity))
     }
 
     /// Returns the [`Label`] associated with the channel handle.
@@ -791,25 +1931,38 @@ impl Runtime {
         downgrade: Downgrading,
     ) -> Result<(), OakStatus> {
         let target_label = self.get_node_label(node_id);
-        let node_debug_id = self.get_node_debug_id(node_id);
-        trace!(
-            "{:?}: original source label: {:?}?",
-            node_debug_id,
-            source_label
-        );
-
         let effective_label = self.get_effective_label(node_id, source_label, downgrade);
-        trace!(
-            "{:?}: effective label: {:?}?",
-            node_debug_id,
-            effective_label
-        );
-        trace!("{:?}: target label: {:?}?", node_debug_id, target_label);
-        if effective_label.flows_to(&target_label) {
-            trace!("{:?}: can read from {:?}", node_debug_id, source_label);
+        let can_read = effective_label.flows_to(&target_label);
+        // This is on the path of every channel read, so avoid computing the debug ID (which
+        // requires locking the Node table) unless it is going to be logged.
+        if log_enabled!(Level::Trace) {
+            let node_debug_id = self.get_node_debug_id(node_id);
+            trace!(
+                "{:?}: original source label: {:?}?",
+                node_debug_id,
+                source_label
+            );
+            trace!(
+                "{:?}: effective label: {:?}?",
+                node_debug_id,
+                effective_label
+            );
+            trace!("{:?}: target label: {:?}?", node_debug_id, target_label);
+            if can_read {
+                trace!("{:?}: can read from {:?}", node_debug_id, source_label);
+            }
+        }
+        if can_read {
             Ok(())
         } else {
-            debug!("{:?}: cannot read from {:?}", node_debug_id, source_label);
+            self.record_permission_denied(node_id, Direction::Read, source_label);
+            self.log_rate_limited(Level::Debug, node_id, "cannot_read", || {
+                format!(
+                    "{:?}: cannot read from {:?}",
+                    self.get_node_debug_id(node_id),
+                    source_label
+                )
+            });
             Err(OakStatus::ErrPermissionDenied)
         }
     }
@@ -835,34 +1988,116 @@ impl Runtime {
         downgrade: Downgrading,
     ) -> Result<(), OakStatus> {
         let original_label = self.get_node_label(node_id);
-        let node_debug_id = self.get_node_debug_id(node_id);
-        trace!(
-            "{:?}: original source label: {:?}?",
-            node_debug_id,
-            &original_label
-        );
         let effective_label = self.get_effective_label(node_id, &original_label, downgrade);
-        trace!(
-            "{:?}: effective label: {:?}?",
-            node_debug_id,
-            effective_label
-        );
-        trace!("{:?}: target label: {:?}?", node_debug_id, target_label);
-        if effective_label.flows_to(target_label) {
-            trace!("{:?}: can write to {:?}", node_debug_id, target_label);
+        let can_write = effective_label.flows_to(target_label);
+        // This is on the path of every channel write, so avoid computing the debug ID (which
+        // requires locking the Node table) unless it is going to be logged.
+        if log_enabled!(Level::Trace) {
+            let node_debug_id = self.get_node_debug_id(node_id);
+            trace!(
+                "{:?}: original source label: {:?}?",
+                node_debug_id,
+                &original_label
+            );
+            trace!(
+                "{:?}: effective label: {:?}?",
+                node_debug_id,
+                effective_label
+            );
+            trace!("{:?}: target label: {:?}?", node_debug_id, target_label);
+            if can_write {
+                trace!("{:?}: can write to {:?}", node_debug_id, target_label);
+            }
+        }
+        if can_write {
             Ok(())
         } else {
-            warn!("{:?}: cannot write to {:?}", node_debug_id, target_label);
+            self.record_permission_denied(node_id, Direction::Write, target_label);
+            self.log_rate_limited(Level::Warn, node_id, "cannot_write", || {
+                format!(
+                    "{:?}: cannot write to {:?}",
+                    self.get_node_debug_id(node_id),
+                    target_label
+                )
+            });
             Err(OakStatus::ErrPermissionDenied)
         }
     }
 
-    /// Creates a new [`Channel`] and returns a `(writer, reader)` pair of [`oak_abi::Handle`]s.
+    /// Records that the Node identified by `node_id` was not allowed to read from or write to, as
+    /// indicated by `direction`, an entity with the provided `label`.
+    ///
+    /// The `runtime_permission_denied_total` metric is always incremented, but the label is only
+    /// reported in a `PermissionDenied` introspection event with the `oak-unsafe` feature.
+    fn record_permission_denied(&self, node_id: NodeId, direction: Direction, label: &Label) {
+        let direction_label = match direction {
+            Direction::Read => "read",
+            Direction::Write => "write",
+        };
+        self.metrics_data
+            .runtime_metrics
+            .runtime_permission_denied_total
+            .with_label_values(&[direction_label])
+            .inc();
+        if cfg!(feature = "oak-unsafe") {
+            self.introspection_event(EventDetails::PermissionDenied(PermissionDenied {
+                node_id: node_id.0,
+                direction: direction as i32,
+                label: Some(label.clone()),
+            }));
+        }
+    }
+
+    /// Allocates the ID of a new channel named `name`, registering the name if channel names must
+    /// be unique. Fails with [`OakStatus::ErrInvalidArgs`] if the name is already registered.
+    fn allocate_channel_id(&self, node_id: NodeId, name: &str) -> Result<u64, OakStatus> {
+        if name.is_empty()
+            || !self.require_unique_channel_names.load(SeqCst)
+            || !self.is_sandboxed(node_id)
+        {
+            return Ok(self.next_channel_id.fetch_add(1, SeqCst));
+        }
+        let mut channel_names = self.channel_names.lock().unwrap();
+        if let Some(existing_id) = channel_names.get(name) {
+            warn!(
+                "{:?}: cannot create channel with name {:?} already used by channel {}",
+                self.get_node_debug_id(node_id),
+                name,
+                existing_id
+            );
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+        let channel_id = self.next_channel_id.fetch_add(1, SeqCst);
+        channel_names.insert(name.to_owned(), channel_id);
+        Ok(channel_id)
+    }
+
+    /// Returns whether the Node identified by `node_id` is sandboxed, as opposed to a pseudo-Node.
+    fn is_sandboxed(&self, node_id: NodeId) -> bool {
+        self.node_infos
+            .read()
+            .unwrap()
+            .get(&node_id)
+            .map_or(true, |node_info| node_info.sandboxed)
+    }
+
+    /// Unregisters the name of the destroyed channel identified by `channel_id`, if it was
+    /// registered, so that it can be used by a new channel.
+    fn release_channel_name(&self, name: &str, channel_id: u64) {
+        let mut channel_names = self.channel_names.lock().unwrap();
+        if channel_names.get(name) == Some(&channel_id) {
+            channel_names.remove(name);
+        }
+    }
+
+    /// Creates a new [`Channel`] with the provided [`ChannelOptions`] and returns a
+    /// `(writer, reader)` pair of [`oak_abi::Handle`]s.
     fn channel_create(
         self: &Arc<Self>,
         node_id: NodeId,
         name: &str,
         label: &Label,
+        options: &ChannelOptions,
         downgrade: Downgrading,
     ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
         if self.is_terminating() {
@@ -877,9 +2112,17 @@ impl Runtime {
         // to by the current Node, since in general this may be lower than "public untrusted".
         self.validate_can_write_to_label(node_id, label, downgrade)?;
 
+        if options.capacity == Some(0) {
+            warn!(
+                "{:?}: cannot create channel with zero capacity",
+                self.get_node_debug_id(node_id)
+            );
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+
         // First get a pair of `ChannelHalf` objects.
-        let channel_id = self.next_channel_id.fetch_add(1, SeqCst);
-        let channel = Channel::new(channel_id, name, label, Arc::downgrade(self));
+        let channel_id = self.allocate_channel_id(node_id, name)?;
+        let channel = Channel::new(channel_id, name, label, options, Arc::downgrade(self));
         let write_half = ChannelHalf::new(channel.clone(), ChannelHalfDirection::Write);
         let read_half = ChannelHalf::new(channel, ChannelHalfDirection::Read);
         let node_debug_id = self.get_node_debug_id(node_id);
@@ -897,11 +2140,13 @@ impl Runtime {
             channel_id,
             name: name.to_owned(),
             label: Some(label.clone()),
+            capacity: options.capacity.unwrap_or(0) as u64,
         }));
 
-        // Insert them into the handle table and return the ABI handles to the caller.
-        let write_handle = self.new_abi_handle(node_id, write_half);
-        let read_handle = self.new_abi_handle(node_id, read_half);
+        // Insert them into the handle table and return the ABI handles to the caller. If the
+        // handle table is full, the halves are dropped, which destroys the channel again.
+        let handles = self.new_abi_handles(node_id, vec![write_half, read_half])?;
+        let (write_handle, read_handle) = (handles[0], handles[1]);
         trace!(
             "{:?}: allocated handles w={}, r={} for channel",
             node_debug_id,
@@ -912,6 +2157,43 @@ impl Runtime {
         Ok((write_handle, read_handle))
     }
 
+    /// Creates a new channel as in [`Runtime::channel_create`], already holding the `initial`
+    /// message, and returns a `(writer, reader)` pair of [`oak_abi::Handle`]s.
+    ///
+    /// The message is enqueued before the handles are returned, so a reader of the channel never
+    /// observes it empty. The message is subject to the same checks as in
+    /// [`Runtime::channel_write`]; if it is rejected, the channel is closed again.
+    fn channel_create_with_message(
+        self: &Arc<Self>,
+        node_id: NodeId,
+        name: &str,
+        label: &Label,
+        initial: NodeMessage,
+        downgrade: Downgrading,
+    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
+        let (write_handle, read_handle) =
+            self.channel_create(node_id, name, label, &ChannelOptions::default(), downgrade)?;
+        let result = self
+            .prepare_channel_write(node_id, write_handle, initial, downgrade)
+            .and_then(|(half, msg, event_details)| {
+                self.push_message(node_id, &half, msg, event_details)
+            });
+        if let Err(status) = result {
+            for handle in [write_handle, read_handle] {
+                if let Err(close_status) = self.channel_close(node_id, handle) {
+                    warn!(
+                        "{:?}: could not close handle {} of rejected channel: {:?}",
+                        self.get_node_debug_id(node_id),
+                        handle,
+                        close_status
+                    );
+                }
+            }
+            return Err(status);
+        }
+        Ok((write_handle, read_handle))
+    }
+
     /// Creates a new distinct handle to the same channel as `handle`.
     fn handle_clone(
         self: &Arc<Self>,
@@ -923,7 +2205,7 @@ impl Runtime {
         }
 
         let cloned_half = self.abi_to_half(node_id, handle)?;
-        Ok(self.new_abi_handle(node_id, cloned_half))
+        Ok(self.new_abi_handles(node_id, vec![cloned_half])?[0])
     }
 
     /// Reads the readable statuses for a slice of `ChannelHalf`s.
@@ -942,6 +2224,30 @@ impl Runtime {
             .collect()
     }
 
+    /// Returns the current statuses of the channels identified by `read_handles`, without
+    /// blocking.
+    ///
+    /// The statuses are computed in the same way as by [`Runtime::wait_on_channels`], so handles
+    /// that do not identify the read half of a channel are reported as
+    /// [`ChannelReadStatus::InvalidChannel`], and channels the Node may not read from as
+    /// [`ChannelReadStatus::PermissionDenied`]. The returned vector is in 1-1 correspondence with
+    /// `read_handles`.
+    fn poll_channels(
+        &self,
+        node_id: NodeId,
+        read_handles: &[oak_abi::Handle],
+        downgrade: Downgrading,
+    ) -> Vec<ChannelReadStatus> {
+        read_handles
+            .iter()
+            .map(|handle| {
+                self.abi_to_read_half(node_id, *handle)
+                    .and_then(|half| self.channel_status(node_id, &half, downgrade))
+                    .unwrap_or(ChannelReadStatus::InvalidChannel)
+            })
+            .collect()
+    }
+
     /// Given a slice of `ChannelHalf`s representing channel read handles:
     /// - If the [`Runtime`] is terminating this will return immediately with an `ErrTerminated`
     ///   status.
@@ -964,6 +2270,83 @@ impl Runtime {
         node_id: NodeId,
         read_handles: &[oak_abi::Handle],
         downgrade: Downgrading,
+    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        // Record that the Node is waiting, so that it is not considered stuck in the meantime.
+        self.set_node_waiting(node_id, true);
+        let result = self.park_until_ready(node_id, read_handles, downgrade, None);
+        self.set_node_waiting(node_id, false);
+        result
+    }
+
+    /// Same as [`Runtime::wait_on_channels`], but stops blocking once `timeout` has elapsed, in
+    /// which case all the statuses are [`ChannelReadStatus::NotReady`].
+    ///
+    /// A zero `timeout` does not block at all, and just polls the current statuses.
+    fn wait_on_channels_timeout(
+        &self,
+        node_id: NodeId,
+        read_handles: &[oak_abi::Handle],
+        timeout: Duration,
+        downgrade: Downgrading,
+    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        // A timeout too large to be represented is equivalent to no timeout at all.
+        let deadline = Instant::now().checked_add(timeout);
+        self.set_node_waiting(node_id, true);
+        let result = self.park_until_ready(node_id, read_handles, downgrade, deadline);
+        self.set_node_waiting(node_id, false);
+        result
+    }
+
+    /// Same as [`Runtime::wait_on_channels`], but returns as soon as one of the channels is ready
+    /// or in an erroneous status, with the index of the first such channel in `read_handles` and
+    /// its status, rather than the statuses of all the channels.
+    ///
+    /// Statuses are only computed up to the first channel found ready on each wakeup, which is
+    /// cheaper than [`Runtime::wait_on_channels`] when waiting on many channels. Fails with
+    /// [`OakStatus::ErrInvalidArgs`] if `read_handles` is empty.
+    fn wait_on_channels_first(
+        &self,
+        node_id: NodeId,
+        read_handles: &[oak_abi::Handle],
+        downgrade: Downgrading,
+    ) -> Result<(usize, ChannelReadStatus), OakStatus> {
+        if read_handles.is_empty() {
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+        let mut readers = Vec::new();
+        for (i, handle) in read_handles.iter().enumerate() {
+            match self.abi_to_read_half(node_id, *handle) {
+                Ok(half) => readers.push(half),
+                Err(_) => return Ok((i, ChannelReadStatus::InvalidChannel)),
+            }
+        }
+
+        self.set_node_waiting(node_id, true);
+        let result = self.park_until(node_id, &readers, None, || {
+            readers.iter().enumerate().find_map(|(i, reader)| {
+                match self
+                    .channel_status(node_id, reader, downgrade)
+                    .unwrap_or(ChannelReadStatus::InvalidChannel)
+                {
+                    ChannelReadStatus::NotReady => None,
+                    status => Some((i, status)),
+                }
+            })
+        });
+        self.set_node_waiting(node_id, false);
+        // Without a deadline, waiting only stops once a channel is found.
+        result.map(|found| found.expect("stopped waiting without a deadline"))
+    }
+
+    /// Implementation of [`Runtime::wait_on_channels`] and [`Runtime::wait_on_channels_timeout`],
+    /// to be called while the Node is marked as waiting. Stops blocking at `deadline`, if
+    /// provided.
+    fn park_until_ready(
+        &self,
+        node_id: NodeId,
+        read_handles: &[oak_abi::Handle],
+        downgrade: Downgrading,
+        deadline: Option<Instant>,
     ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
         // Accumulate both the valid channels and their original position.
         let mut all_statuses = vec![ChannelReadStatus::InvalidChannel; read_handles.len()];
@@ -976,10 +2359,66 @@ impl Runtime {
             }
         }
 
+        self.park_until(node_id, &readers, deadline, || {
+            let statuses = self.readers_statuses(node_id, &readers, downgrade);
+            // Transcribe the status for valid channels back to the original position
+            // in the list of all statuses.
+            for i in 0..readers.len() {
+                all_statuses[reader_pos[i]] = statuses[i];
+            }
+
+            let all_not_ready = statuses.iter().all(|&s| s == ChannelReadStatus::NotReady);
+
+            if !all_not_ready || read_handles.is_empty() || readers.len() != read_handles.len() {
+                Some(())
+            } else {
+                None
+            }
+        })?;
+        Ok(all_statuses)
+    }
+
+    /// Waiter logic shared by the ways of waiting on channels: registers the current thread as a
+    /// waiter on `readers` and calls `poll`, parking the thread until woken up by one of the
+    /// channels for as long as `poll` returns `None`.
+    ///
+    /// Returns the result of `poll` once it returns `Some`, or `None` once `deadline` is reached,
+    /// if provided. Fails with [`OakStatus::ErrTerminated`] if the [`Runtime`] is terminating.
+    fn park_until<T, F: FnMut() -> Option<T>>(
+        &self,
+        node_id: NodeId,
+        readers: &[ChannelHalf],
+        deadline: Option<Instant>,
+        poll: F,
+    ) -> Result<Option<T>, OakStatus> {
+        let result = self.park_until_polled(node_id, readers, deadline, poll);
+        // The wait is over, so the thread no longer keeps its place among the waiters.
+        let thread_id = thread::current().id();
+        for reader in readers {
+            reader.remove_waiter(thread_id);
+        }
+        result
+    }
+
+    /// Implementation of [`Runtime::park_until`], leaving the current thread registered as a
+    /// waiter on `readers`.
+    fn park_until_polled<T, F: FnMut() -> Option<T>>(
+        &self,
+        node_id: NodeId,
+        readers: &[ChannelHalf],
+        deadline: Option<Instant>,
+        mut poll: F,
+    ) -> Result<Option<T>, OakStatus> {
         let thread = thread::current();
 
-        let node_debug_id = self.get_node_debug_id(node_id);
+        // Only compute the debug ID if it is going to be logged while parking the thread.
+        let node_debug_id = if log_enabled!(Level::Debug) {
+            self.get_node_debug_id(node_id)
+        } else {
+            String::new()
+        };
 
+        let mut parked = false;
         while !self.is_terminating() {
             // Create a new Arc each iteration to be dropped after `thread::park` e.g. when the
             // thread is resumed. When the Arc is deallocated, any remaining `Weak`
@@ -993,23 +2432,30 @@ impl Runtime {
 
             let thread_ref = Arc::new(thread.clone());
 
-            for reader in &readers {
+            for reader in readers {
                 with_reader_channel(reader, |channel| {
-                    channel.add_waiter(&thread_ref);
+                    channel.add_waiter(node_id, &thread_ref);
                     Ok(())
                 })?;
             }
-            let statuses = self.readers_statuses(node_id, &readers, downgrade);
-            // Transcribe the status for valid channels back to the original position
-            // in the list of all statuses.
-            for i in 0..readers.len() {
-                all_statuses[reader_pos[i]] = statuses[i];
+            if let Some(result) = poll() {
+                return Ok(Some(result));
             }
-
-            let all_not_ready = statuses.iter().all(|&s| s == ChannelReadStatus::NotReady);
-
-            if !all_not_ready || read_handles.is_empty() || readers.len() != read_handles.len() {
-                return Ok(all_statuses);
+            // The remaining time is recomputed on every iteration, so that spurious wakeups do not
+            // extend the overall wait.
+            let remaining = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if remaining > Duration::from_secs(0) => Some(remaining),
+                    _ => return Ok(None),
+                },
+                None => None,
+            };
+            // The thread was woken up before its deadline, but none of the channels is ready.
+            if parked {
+                self.metrics_data
+                    .runtime_metrics
+                    .runtime_channel_spurious_wakeups_total
+                    .inc();
             }
 
             debug!(
@@ -1018,7 +2464,15 @@ impl Runtime {
                 thread::current()
             );
 
-            thread::park();
+            self.metrics_data
+                .runtime_metrics
+                .runtime_channel_park_total
+                .inc();
+            match remaining {
+                Some(remaining) => thread::park_timeout(remaining),
+                None => thread::park(),
+            }
+            parked = true;
 
             debug!(
                 "{:?}: wait_on_channels: thread {:?} re-woken",
@@ -1030,7 +2484,12 @@ impl Runtime {
     }
 
     /// Write a message to a channel. Fails with [`OakStatus::ErrChannelClosed`] if the underlying
-    /// channel has been orphaned.
+    /// channel has been orphaned, with [`OakStatus::ErrChannelFull`] if the channel already holds
+    /// as many messages as its capacity allows, and with [`OakStatus::ErrInvalidArgs`] if the
+    /// message contains more handles than allowed by
+    /// [`RuntimeConfiguration::max_handles_per_message`] or is rejected by the
+    /// [`MessageValidator`] of the channel. Fails with [`OakStatus::ErrTerminated`] if the Runtime
+    /// is terminating or draining, since the message may never be consumed.
     fn channel_write(
         &self,
         node_id: NodeId,
@@ -1038,35 +2497,189 @@ impl Runtime {
         node_msg: NodeMessage,
         downgrade: Downgrading,
     ) -> Result<(), OakStatus> {
-        let half = self.abi_to_write_half(node_id, write_handle)?;
-        self.validate_can_write_to_channel(node_id, &half, downgrade)?;
+        let (half, msg, event_details) =
+            self.prepare_channel_write(node_id, write_handle, node_msg, downgrade)?;
+        self.push_message(node_id, &half, msg, event_details)
+    }
 
-        let event_details = MessageEnqueued {
-            node_id: node_id.0,
-            channel_id: half.get_channel_id(),
-            included_handles: node_msg.handles.clone(),
-        };
+    /// Same as [`Runtime::channel_write`], but the message expires once `ttl` has elapsed, after
+    /// which readers skip it.
+    fn channel_write_with_ttl(
+        &self,
+        node_id: NodeId,
+        write_handle: oak_abi::Handle,
+        node_msg: NodeMessage,
+        ttl: Duration,
+        downgrade: Downgrading,
+    ) -> Result<(), OakStatus> {
+        let (half, mut msg, event_details) =
+            self.prepare_channel_write(node_id, write_handle, node_msg, downgrade)?;
+        msg.expiry = Some((self.now(), ttl));
+        self.push_message(node_id, &half, msg, event_details)
+    }
 
-        // Translate the Node-relative handles in the `NodeMessage` to channel halves.
-        let msg = self.message_from(node_msg, node_id)?;
-        let result = with_writer_channel(&half, |channel| {
-            if !channel.has_readers() {
-                return Err(OakStatus::ErrChannelClosed);
+    /// Implementation of [`Runtime::channel_write`] and [`Runtime::channel_write_with_ttl`], to be
+    /// called with the results of [`Runtime::prepare_channel_write`].
+    ///
+    /// If the channel has no readers left, the message is forwarded to the dead-letter channel,
+    /// if any.
+    fn push_message(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        msg: Message,
+        event_details: MessageEnqueued,
+    ) -> Result<(), OakStatus> {
+        let bytes = msg.data.len();
+        let result = match with_writer_channel(half, |channel| Ok(channel.try_push_message(msg))) {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(RejectedMessage::ChannelFull(_msg))) => Err(OakStatus::ErrChannelFull),
+            Ok(Err(RejectedMessage::ChannelClosed(msg))) => {
+                self.forward_dead_letter(node_id, half.get_channel_id(), msg.data);
+                Err(OakStatus::ErrChannelClosed)
             }
-            channel.messages.write().unwrap().push_back(msg);
-            channel.wake_waiters();
-
-            Ok(())
-        });
+            Err(status) => Err(status),
+        };
 
+        self.update_channel_queue_length(half);
+        if result.is_ok() {
+            self.record_channel_message(half);
+            self.record_channel_traffic(ChannelTraffic::Enqueue, bytes);
+        }
         self.introspection_event(EventDetails::MessageEnqueued(event_details));
 
         result
     }
 
+    /// Write a message to a channel, blocking while the channel is at capacity rather than failing
+    /// with [`OakStatus::ErrChannelFull`].
+    ///
+    /// Fails with [`OakStatus::ErrChannelClosed`] if the underlying channel is (or becomes, while
+    /// blocked) orphaned, in which case the message is forwarded to the dead-letter channel, if
+    /// any, and with [`OakStatus::ErrTerminated`] if the Runtime is terminating.
+    fn channel_write_blocking(
+        &self,
+        node_id: NodeId,
+        write_handle: oak_abi::Handle,
+        node_msg: NodeMessage,
+        downgrade: Downgrading,
+    ) -> Result<(), OakStatus> {
+        let (half, msg, event_details) =
+            self.prepare_channel_write(node_id, write_handle, node_msg, downgrade)?;
+        let bytes = msg.data.len();
+        // Record that the Node is waiting, so that it is not considered stuck in the meantime.
+        self.set_node_waiting(node_id, true);
+        let result = self.park_until_enqueued(node_id, &half, msg);
+        self.set_node_waiting(node_id, false);
+
+        if result.is_ok() {
+            self.update_channel_queue_length(&half);
+            self.record_channel_message(&half);
+            self.record_channel_traffic(ChannelTraffic::Enqueue, bytes);
+            self.introspection_event(EventDetails::MessageEnqueued(event_details));
+        }
+
+        result
+    }
+
+    /// Implementation of [`Runtime::channel_write_blocking`], to be called while the Node is marked
+    /// as waiting.
+    fn park_until_enqueued(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        mut msg: Message,
+    ) -> Result<(), OakStatus> {
+        let thread = thread::current();
+        while !self.is_terminating() {
+            // As in `park_until_ready`, register as a waiter before checking the capacity of the
+            // channel, so that a message read in between still wakes this thread.
+            let thread_ref = Arc::new(thread.clone());
+            with_writer_channel(half, |channel| {
+                channel.add_writer_waiter(&thread_ref);
+                Ok(())
+            })?;
+            msg = match with_writer_channel(half, |channel| Ok(channel.try_push_message(msg)))? {
+                Ok(()) => return Ok(()),
+                Err(RejectedMessage::ChannelFull(msg)) => msg,
+                Err(RejectedMessage::ChannelClosed(msg)) => {
+                    self.forward_dead_letter(node_id, half.get_channel_id(), msg.data);
+                    return Err(OakStatus::ErrChannelClosed);
+                }
+            };
+
+            debug!(
+                "{:?}: channel_write_blocking: channel full, parking thread {:?}",
+                self.get_node_debug_id(node_id),
+                thread::current()
+            );
+            thread::park();
+        }
+        Err(OakStatus::ErrTerminated)
+    }
+
+    /// Performs the checks common to all ways of writing a message to a channel, and returns the
+    /// write half of the channel, the [`Message`] to write to it and the details of the
+    /// introspection event to emit once it has been written.
+    fn prepare_channel_write(
+        &self,
+        node_id: NodeId,
+        write_handle: oak_abi::Handle,
+        node_msg: NodeMessage,
+        downgrade: Downgrading,
+    ) -> Result<(ChannelHalf, Message, MessageEnqueued), OakStatus> {
+        // As for other operations, bail out before any validation during termination or draining.
+        // Reads are still allowed, so that Nodes can drain the messages already queued.
+        if self.is_terminating() || self.is_draining() {
+            return Err(OakStatus::ErrTerminated);
+        }
+
+        let half = self.abi_to_write_half(node_id, write_handle)?;
+        self.validate_can_write_to_channel(node_id, &half, downgrade)?;
+        if node_msg.handles.len() > self.max_handles_per_message.load(SeqCst) {
+            self.log_rate_limited(Level::Warn, node_id, "too_many_handles", || {
+                format!(
+                    "{:?}: message with {} handles exceeds the maximum allowed",
+                    self.get_node_debug_id(node_id),
+                    node_msg.handles.len()
+                )
+            });
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+        if let Err(reason) = half.validate_message(&node_msg.bytes) {
+            self.log_rate_limited(Level::Debug, node_id, "message_rejected", || {
+                format!(
+                    "{:?}: message rejected by the validator of channel {}: {}",
+                    self.get_node_debug_id(node_id),
+                    half.get_channel_debug_id(),
+                    reason
+                )
+            });
+            self.introspection_event(EventDetails::MessageRejected(MessageRejected {
+                node_id: node_id.0,
+                channel_id: half.get_channel_id(),
+                reason,
+            }));
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+
+        let event_details = MessageEnqueued {
+            node_id: node_id.0,
+            channel_id: half.get_channel_id(),
+            included_handles: node_msg.handles.clone(),
+        };
+
+        // Translate the Node-relative handles in the `NodeMessage` to channel halves.
+        let msg = self.message_from(node_msg, node_id)?;
+        Ok((half, msg, event_details))
+    }
+
     /// Translate the Node-relative handles in the `NodeMessage` to channel halves.
     fn message_from(&self, node_msg: NodeMessage, node_id: NodeId) -> Result<Message, OakStatus> {
         Ok(Message {
+            downgrade_history: self.downgrade_history(node_id),
+            writer: self.message_writer(node_id),
+            expiry: None,
             data: node_msg.bytes,
             channels: node_msg
                 .handles
@@ -1076,8 +2689,55 @@ impl Runtime {
         })
     }
 
-    /// Read a message from a channel. Fails with [`OakStatus::ErrChannelClosed`] if
-    /// the underlying channel is empty and has been orphaned.
+    /// Sets the priority of the Node identified by `node_id` as a consumer of the channel that
+    /// `read_handle` refers to, used if the channel was created with [`ConsumerPolicy::Priority`].
+    /// Consumers with a higher priority are woken first.
+    fn channel_set_consumer_priority(
+        &self,
+        node_id: NodeId,
+        read_handle: oak_abi::Handle,
+        priority: i32,
+    ) -> Result<(), OakStatus> {
+        let half = self.abi_to_read_half(node_id, read_handle)?;
+        with_reader_channel(&half, |channel| {
+            channel.set_consumer_priority(node_id, priority);
+            Ok(())
+        })
+    }
+
+    /// Returns a copy of the front message of a channel without dequeuing it, or `None` if the
+    /// channel is empty. Fails with [`OakStatus::ErrChannelClosed`] if the underlying channel is
+    /// empty and has been orphaned.
+    fn channel_peek(
+        &self,
+        node_id: NodeId,
+        read_handle: oak_abi::Handle,
+        downgrade: Downgrading,
+    ) -> Result<Option<PeekedMessage>, OakStatus> {
+        let half = self.abi_to_read_half(node_id, read_handle)?;
+        self.validate_can_read_from_channel(node_id, &half, downgrade)?;
+        with_reader_channel(&half, |channel| {
+            channel.record_activity();
+            match channel.messages.read().unwrap().front() {
+                Some(msg) => Ok(Some(PeekedMessage {
+                    bytes: msg.data.clone(),
+                    handle_count: msg.channels.len(),
+                })),
+                None => {
+                    if !channel.has_writers() {
+                        Err(OakStatus::ErrChannelClosed)
+                    } else {
+                        Ok(None)
+                    }
+                }
+            }
+        })
+    }
+
+    /// Read a message from a channel, skipping expired messages. Fails with
+    /// [`OakStatus::ErrChannelClosed`] if the underlying channel is empty and has been orphaned, or
+    /// with [`OakStatus::ErrInvalidArgs`], leaving the message in the channel, if the handle table
+    /// of the Node cannot hold the handles carried by the message.
     fn channel_read(
         &self,
         node_id: NodeId,
@@ -1085,10 +2745,93 @@ impl Runtime {
         downgrade: Downgrading,
     ) -> Result<Option<NodeMessage>, OakStatus> {
         let half = self.abi_to_read_half(node_id, read_handle)?;
-        self.validate_can_read_from_channel(node_id, &half, downgrade)?;
-        match with_reader_channel(&half, |channel| {
-            match channel.messages.write().unwrap().pop_front() {
-                Some(m) => Ok(Some(m)),
+        self.channel_read_half(node_id, &half, downgrade)
+    }
+
+    /// Same as [`Runtime::channel_read`], but returns the [`CloseReason`] of the channel together
+    /// with the indication that it is orphaned, instead of failing with
+    /// [`OakStatus::ErrChannelClosed`].
+    fn channel_read_with_close_reason(
+        &self,
+        node_id: NodeId,
+        read_handle: oak_abi::Handle,
+        downgrade: Downgrading,
+    ) -> Result<ChannelReadOutcome, OakStatus> {
+        let half = self.abi_to_read_half(node_id, read_handle)?;
+        match self.channel_read_half(node_id, &half, downgrade) {
+            Ok(Some(message)) => Ok(ChannelReadOutcome::Message(message)),
+            Ok(None) => Ok(ChannelReadOutcome::Empty),
+            // An orphaned channel cannot gain writers again, so its close reason is settled.
+            Err(OakStatus::ErrChannelClosed) => with_reader_channel(&half, |channel| {
+                Ok(ChannelReadOutcome::Closed(
+                    channel.close_reason().unwrap_or_default(),
+                ))
+            }),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Reads a message from the channel of the provided read `half`, as in
+    /// [`Runtime::channel_read`].
+    fn channel_read_half(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        downgrade: Downgrading,
+    ) -> Result<Option<NodeMessage>, OakStatus> {
+        self.validate_can_read_from_channel(node_id, half, downgrade)?;
+        // The handle table of the Node is only locked if the message turns out to carry handles.
+        match self.channel_read_half_locking(node_id, half, downgrade, false) {
+            Some(result) => result,
+            None => self
+                .channel_read_half_locking(node_id, half, downgrade, true)
+                .expect("handle table space checked under lock"),
+        }
+    }
+
+    /// Implementation of [`Runtime::channel_read_half`], locking the handle table of the Node
+    /// according to `lock_handle_table` (see [`Runtime::lock_handle_table_space`]).
+    ///
+    /// Returns `None`, leaving the message in the channel, if the handle table is not locked but
+    /// the message carries handles.
+    fn channel_read_half_locking(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        downgrade: Downgrading,
+        lock_handle_table: bool,
+    ) -> Option<Result<Option<NodeMessage>, OakStatus>> {
+        let now = self.now();
+        let (handle_table_space, mut node_infos) =
+            self.lock_handle_table_space(node_id, lock_handle_table);
+        let mut expired = Vec::new();
+        let mut needs_lock = false;
+        let result = with_reader_channel(half, |channel| {
+            channel.record_activity();
+            let popped = {
+                let mut messages = channel.messages.write().unwrap();
+                expired = message_expiry::remove_expired_front(&mut messages, now);
+                match messages.front() {
+                    Some(front) if front.channels.len() > handle_table_space => {
+                        if !lock_handle_table {
+                            needs_lock = true;
+                        }
+                        Err(OakStatus::ErrInvalidArgs)
+                    }
+                    _ => Ok(messages.pop_front()),
+                }
+            };
+            if !expired.is_empty() {
+                // Skipping expired messages also makes room for blocked writers.
+                channel.wake_writer_waiters();
+            }
+            match popped? {
+                Some(m) => {
+                    channel.record_message_read();
+                    // Reading a message makes room for writers blocked on the channel capacity.
+                    channel.wake_writer_waiters();
+                    Ok(Some(m))
+                }
                 None => {
                     if !channel.has_writers() {
                         Err(OakStatus::ErrChannelClosed)
@@ -1097,31 +2840,184 @@ impl Runtime {
                     }
                 }
             }
-        }) {
+        });
+        let result = result.map(|popped| {
+            popped.map(|mut runtime_msg| {
+                let downgrade_history = std::mem::take(&mut runtime_msg.downgrade_history);
+                let writer = runtime_msg.writer;
+                let node_msg =
+                    self.node_message_from(runtime_msg, node_id, node_infos.as_deref_mut());
+                (node_msg, downgrade_history, writer)
+            })
+        });
+        drop(node_infos);
+        self.record_expired_messages(node_id, half, expired);
+        if needs_lock {
+            return None;
+        }
+        Some(match result {
             Err(status) => Err(status),
             Ok(None) => Ok(None),
-            Ok(Some(runtime_msg)) => {
-                let node_msg = self.node_message_from(runtime_msg, node_id);
+            Ok(Some((node_msg, downgrade_history, writer))) => {
+                self.record_downgrade_history(node_id, half, downgrade, downgrade_history);
+                self.record_message_writers(node_id, vec![writer]);
 
+                self.update_channel_queue_length(half);
+                self.record_channel_traffic(ChannelTraffic::Dequeue, node_msg.bytes.len());
                 self.introspection_event(EventDetails::MessageDequeued(MessageDequeued {
                     node_id: node_id.0,
                     channel_id: half.get_channel_id(),
                     acquired_handles: node_msg.handles.clone(),
+                    expired: false,
                 }));
 
                 Ok(Some(node_msg))
             }
+        })
+    }
+
+    /// Read up to `max_messages` messages from a channel, dequeuing them under a single lock and
+    /// skipping expired messages.
+    ///
+    /// Returns the messages available on the channel, which may be fewer than `max_messages` (or
+    /// none) if the channel empties or if the handle table of the Node cannot hold the handles
+    /// carried by the next message. A message carrying handles may also end a batch early, as
+    /// messages without handles are read without locking the handle table. Fails with [`OakStatus::ErrChannelClosed`] only if the
+    /// underlying channel is empty and has been orphaned, and with [`OakStatus::ErrInvalidArgs`]
+    /// if not even the first message can be read because of its handles.
+    fn channel_read_batch(
+        &self,
+        node_id: NodeId,
+        read_handle: oak_abi::Handle,
+        max_messages: usize,
+        downgrade: Downgrading,
+    ) -> Result<Vec<NodeMessage>, OakStatus> {
+        let half = self.abi_to_read_half(node_id, read_handle)?;
+        self.validate_can_read_from_channel(node_id, &half, downgrade)?;
+        // The handle table of the Node is only locked if the first message turns out to carry
+        // handles.
+        match self.channel_read_batch_locking(node_id, &half, max_messages, downgrade, false) {
+            Some(result) => result,
+            None => self
+                .channel_read_batch_locking(node_id, &half, max_messages, downgrade, true)
+                .expect("handle table space checked under lock"),
+        }
+    }
+
+    /// Implementation of [`Runtime::channel_read_batch`], locking the handle table of the Node
+    /// according to `lock_handle_table` (see [`Runtime::lock_handle_table_space`]).
+    ///
+    /// If the handle table is not locked, the batch stops before the first message carrying
+    /// handles, and `None` is returned if that is the first message of the batch.
+    fn channel_read_batch_locking(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        max_messages: usize,
+        downgrade: Downgrading,
+        lock_handle_table: bool,
+    ) -> Option<Result<Vec<NodeMessage>, OakStatus>> {
+        let now = self.now();
+        let (mut handle_table_space, mut node_infos) =
+            self.lock_handle_table_space(node_id, lock_handle_table);
+        let mut expired = Vec::new();
+        let mut needs_lock = false;
+        let result = with_reader_channel(half, |channel| {
+            channel.record_activity();
+            let mut drained = Vec::new();
+            let mut handle_table_full = false;
+            {
+                let mut messages = channel.messages.write().unwrap();
+                while drained.len() < max_messages {
+                    expired.extend(message_expiry::remove_expired_front(&mut messages, now));
+                    match messages.front() {
+                        Some(front) if front.channels.len() > handle_table_space => {
+                            handle_table_full = true;
+                            break;
+                        }
+                        Some(front) => handle_table_space -= front.channels.len(),
+                        None => break,
+                    }
+                    drained.extend(messages.pop_front());
+                }
+            }
+            if !drained.is_empty() {
+                channel.record_message_read();
+            }
+            if !drained.is_empty() || !expired.is_empty() {
+                // Reading messages makes room for writers blocked on the channel capacity.
+                channel.wake_writer_waiters();
+            }
+            if drained.is_empty() && handle_table_full {
+                needs_lock = !lock_handle_table;
+                return Err(OakStatus::ErrInvalidArgs);
+            }
+            if drained.is_empty() && max_messages > 0 && !channel.has_writers() {
+                return Err(OakStatus::ErrChannelClosed);
+            }
+            Ok(drained)
+        });
+        let result = result.map(|runtime_msgs| {
+            runtime_msgs
+                .into_iter()
+                .map(|mut runtime_msg| {
+                    let downgrade_history = std::mem::take(&mut runtime_msg.downgrade_history);
+                    let writer = runtime_msg.writer;
+                    let node_msg =
+                        self.node_message_from(runtime_msg, node_id, node_infos.as_deref_mut());
+                    (node_msg, downgrade_history, writer)
+                })
+                .collect::<Vec<_>>()
+        });
+        drop(node_infos);
+        self.record_expired_messages(node_id, half, expired);
+        if needs_lock {
+            return None;
+        }
+        let msgs = match result {
+            Ok(msgs) => msgs,
+            Err(status) => return Some(Err(status)),
+        };
+        if !msgs.is_empty() {
+            self.update_channel_queue_length(half);
+            self.record_message_writers(
+                node_id,
+                msgs.iter().map(|(_, _, writer)| *writer).collect(),
+            );
         }
+
+        Some(Ok(msgs
+            .into_iter()
+            .map(|(node_msg, downgrade_history, _)| {
+                self.record_downgrade_history(node_id, half, downgrade, downgrade_history);
+
+                self.record_channel_traffic(ChannelTraffic::Dequeue, node_msg.bytes.len());
+                self.introspection_event(EventDetails::MessageDequeued(MessageDequeued {
+                    node_id: node_id.0,
+                    channel_id: half.get_channel_id(),
+                    acquired_handles: node_msg.handles.clone(),
+                    expired: false,
+                }));
+
+                node_msg
+            })
+            .collect()))
     }
 
     /// Determine the readable status of a channel, returning:
-    /// - `Ok`([`ChannelReadStatus::ReadReady`]) if there is at least one message in the channel.
-    /// - `Ok`([`ChannelReadStatus::Orphaned`]) if there are no messages and there are no writers.
-    /// - `Ok`([`ChannelReadStatus::NotReady`]) if there are no messages but there are some writers.
+    /// - `Ok`([`ChannelReadStatus::ReadReady`]) if there is at least one live message in the
+    ///   channel.
+    /// - `Ok`([`ChannelReadStatus::Orphaned`]) if there are no live messages and there are no
+    ///   writers.
+    /// - `Ok`([`ChannelReadStatus::NotReady`]) if there are no live messages but there are some
+    ///   writers.
     /// - `Ok`([`ChannelReadStatus::PermissionDenied`]) if the node does not have permission to read
     ///   from the channel.
     /// - `Err`([`OakStatus::ErrBadHandle`]) if the input handle does not indicate the read half of
     ///   a channel.
+    ///
+    /// Expired messages at the front of the channel are discarded, as they would be by a read, so
+    /// that a channel holding only expired messages is not reported as ready.
     fn channel_status(
         &self,
         node_id: NodeId,
@@ -1133,24 +3029,50 @@ impl Runtime {
         {
             return Ok(ChannelReadStatus::PermissionDenied);
         };
-        with_reader_channel(half, |channel| {
-            Ok(if channel.messages.read().unwrap().front().is_some() {
+        let now = self.now();
+        let mut expired = Vec::new();
+        let status = with_reader_channel(half, |channel| {
+            let (has_front, front_expired) = channel
+                .messages
+                .read()
+                .unwrap()
+                .front()
+                .map_or((false, false), |front| (true, front.is_expired(now)));
+            // Only take the write lock if there are expired messages to discard.
+            let ready = if front_expired {
+                let mut messages = channel.messages.write().unwrap();
+                expired = message_expiry::remove_expired_front(&mut messages, now);
+                messages.front().is_some()
+            } else {
+                has_front
+            };
+            if !expired.is_empty() {
+                // Discarding expired messages makes room for blocked writers.
+                channel.wake_writer_waiters();
+            }
+            Ok(if ready {
                 ChannelReadStatus::ReadReady
             } else if !channel.has_writers() {
                 ChannelReadStatus::Orphaned
             } else {
                 ChannelReadStatus::NotReady
             })
-        })
+        });
+        self.record_expired_messages(node_id, half, expired);
+        status
     }
 
     /// Reads a message from the channel if `bytes_capacity` and `handles_capacity` are large
-    /// enough to accept the message. Fails with `OakStatus::ErrChannelClosed` if the underlying
-    /// channel has been orphaned _and_ is empty. If there was not enough `bytes_capacity` or
-    /// `handles_capacity`, `try_read_message` returns the required capacity values in
+    /// enough to accept the message, skipping expired messages. Fails with
+    /// `OakStatus::ErrChannelClosed` if the underlying channel has been orphaned _and_ is
+    /// empty. If there was not enough `bytes_capacity` or `handles_capacity`,
+    /// `try_read_message` returns the required capacity values in
     /// `Some(NodeReadStatus::NeedsCapacity(needed_bytes_capacity,needed_handles_capacity))`. Does
     /// not guarantee that the next call will succeed after capacity adjustments as another Node
     /// may have read the original message.
+    ///
+    /// Fails with [`OakStatus::ErrInvalidArgs`], leaving the message in the channel, if the
+    /// handle table of the Node cannot hold the handles carried by the message.
     fn channel_try_read_message(
         &self,
         node_id: NodeId,
@@ -1161,8 +3083,50 @@ impl Runtime {
     ) -> Result<Option<NodeReadStatus>, OakStatus> {
         let half = self.abi_to_read_half(node_id, handle)?;
         self.validate_can_read_from_channel(node_id, &half, downgrade)?;
-        let result = with_reader_channel(&half, |channel| {
+        // The handle table of the Node is only locked if the message turns out to carry handles.
+        let read = |lock_handle_table| {
+            self.channel_try_read_message_locking(
+                node_id,
+                &half,
+                bytes_capacity,
+                handles_capacity,
+                downgrade,
+                lock_handle_table,
+            )
+        };
+        match read(false) {
+            Some(result) => result,
+            None => read(true).expect("handle table space checked under lock"),
+        }
+    }
+
+    /// Implementation of [`Runtime::channel_try_read_message`], locking the handle table of the
+    /// Node according to `lock_handle_table` (see [`Runtime::lock_handle_table_space`]).
+    ///
+    /// Returns `None`, leaving the message in the channel, if the handle table is not locked but
+    /// the message carries handles.
+    fn channel_try_read_message_locking(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        bytes_capacity: usize,
+        handles_capacity: usize,
+        downgrade: Downgrading,
+        lock_handle_table: bool,
+    ) -> Option<Result<Option<NodeReadStatus>, OakStatus>> {
+        let now = self.now();
+        let (handle_table_space, mut node_infos) =
+            self.lock_handle_table_space(node_id, lock_handle_table);
+        let mut expired = Vec::new();
+        let mut needs_lock = false;
+        let result = with_reader_channel(half, |channel| {
+            channel.record_activity();
             let mut messages = channel.messages.write().unwrap();
+            expired = message_expiry::remove_expired_front(&mut messages, now);
+            if !expired.is_empty() {
+                // Skipping expired messages also makes room for blocked writers.
+                channel.wake_writer_waiters();
+            }
             match messages.front() {
                 Some(front) => {
                     let req_bytes_capacity = front.data.len();
@@ -1175,10 +3139,18 @@ impl Runtime {
                             req_bytes_capacity,
                             req_handles_capacity,
                         )))
+                    } else if req_handles_capacity > handle_table_space {
+                        needs_lock = !lock_handle_table;
+                        Err(OakStatus::ErrInvalidArgs)
                     } else {
-                        Ok(Some(ReadStatus::Success(messages.pop_front().expect(
+                        let msg = messages.pop_front().expect(
                             "Front element disappeared while we were holding the write lock!",
-                        ))))
+                        );
+                        channel.record_message_read();
+                        // Reading a message makes room for writers blocked on the channel
+                        // capacity.
+                        channel.wake_writer_waiters();
+                        Ok(Some(ReadStatus::Success(msg)))
                     }
                 }
                 None => {
@@ -1189,62 +3161,256 @@ impl Runtime {
                     }
                 }
             }
-        })?;
+        });
         // Translate the result into the handle numbering space of this Node.
-        Ok(match result {
+        let result = result.map(|status| match status {
+            None => None,
+            Some(ReadStatus::NeedsCapacity(z, c)) => Some(Err((z, c))),
+            Some(ReadStatus::Success(mut msg)) => {
+                let downgrade_history = std::mem::take(&mut msg.downgrade_history);
+                let writer = msg.writer;
+                let message = self.node_message_from(msg, node_id, node_infos.as_deref_mut());
+                Some(Ok((message, downgrade_history, writer)))
+            }
+        });
+        drop(node_infos);
+        self.record_expired_messages(node_id, half, expired);
+        if needs_lock {
+            return None;
+        }
+        let result = match result {
+            Ok(result) => result,
+            Err(status) => return Some(Err(status)),
+        };
+        Some(Ok(match result {
             None => None,
-            Some(ReadStatus::NeedsCapacity(z, c)) => Some(NodeReadStatus::NeedsCapacity(z, c)),
-            Some(ReadStatus::Success(msg)) => {
-                let message = self.node_message_from(msg, node_id);
+            Some(Err((z, c))) => Some(NodeReadStatus::NeedsCapacity(z, c)),
+            Some(Ok((message, downgrade_history, writer))) => {
+                self.record_downgrade_history(node_id, half, downgrade, downgrade_history);
+                self.record_message_writers(node_id, vec![writer]);
 
+                self.update_channel_queue_length(half);
+                self.record_channel_traffic(ChannelTraffic::Dequeue, message.bytes.len());
                 self.introspection_event(EventDetails::MessageDequeued(MessageDequeued {
                     node_id: node_id.0,
                     channel_id: half.get_channel_id(),
                     acquired_handles: message.handles.clone(),
+                    expired: false,
                 }));
 
                 Some(NodeReadStatus::Success(message))
             }
-        })
+        }))
     }
 
     /// Translate a Message to include ABI handles (which are relative to this Node) rather than
     /// internal channel references.
-    fn node_message_from(&self, msg: Message, node_id: NodeId) -> NodeMessage {
-        NodeMessage {
-            bytes: msg.data,
-            handles: msg
+    ///
+    /// If provided, `node_infos` is the write lock on [`Runtime::node_infos`] held by the caller
+    /// (see [`Runtime::lock_handle_table_space`]).
+    fn node_message_from(
+        &self,
+        msg: Message,
+        node_id: NodeId,
+        node_infos: Option<&mut HashMap<NodeId, NodeInfo>>,
+    ) -> NodeMessage {
+        let handles = match node_infos {
+            Some(node_infos) => {
+                let node_info = node_infos.get_mut(&node_id).expect("Invalid node_id");
+                msg.channels
+                    .iter()
+                    .map(|half| self.insert_abi_handle(node_id, node_info, half.clone()))
+                    .collect()
+            }
+            None => msg
                 .channels
                 .iter()
                 .map(|half| self.new_abi_handle(node_id, half.clone()))
                 .collect(),
+        };
+        NodeMessage {
+            bytes: msg.data,
+            handles,
         }
     }
 
     /// Close an [`oak_abi::Handle`], potentially orphaning the underlying [`channel::Channel`].
     fn channel_close(&self, node_id: NodeId, handle: oak_abi::Handle) -> Result<(), OakStatus> {
-        // Remove the ABI handle -> half mapping; half will be dropped at end of scope.
-        self.drop_abi_handle(node_id, handle)?;
+        self.channel_close_with_reason(node_id, handle, CloseReason::Completed)
+    }
+
+    /// Same as [`Runtime::channel_close`], but closing a handle that is already closed is a no-op
+    /// rather than failing with [`OakStatus::ErrBadHandle`], for Nodes that prefer idempotent
+    /// cleanup.
+    ///
+    /// Handles are not tracked after being closed, so closing a handle that never existed is also
+    /// a no-op.
+    fn channel_close_idempotent(
+        &self,
+        node_id: NodeId,
+        handle: oak_abi::Handle,
+    ) -> Result<(), OakStatus> {
+        match self.channel_close(node_id, handle) {
+            Err(OakStatus::ErrBadHandle) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Close the channel half identified by `handle`, giving a [`CloseReason`] that is recorded on
+    /// the channel if this closes its last write half.
+    ///
+    /// The reason is then available to readers through [`Runtime::channel_read_with_close_reason`]
+    /// and [`Runtime::channel_close_reason`].
+    fn channel_close_with_reason(
+        &self,
+        node_id: NodeId,
+        handle: oak_abi::Handle,
+        reason: CloseReason,
+    ) -> Result<(), OakStatus> {
+        // Remove the ABI handle -> half mapping, and then close the half.
+        let half = self.drop_abi_handle(node_id, handle)?;
+        half.close(reason);
         Ok(())
     }
 
-    /// Create a fresh [`NodeId`].
-    fn new_node_id(&self) -> NodeId {
-        NodeId(self.next_node_id.fetch_add(1, SeqCst))
+    /// Returns the [`CloseReason`] of the channel read half identified by `read_handle` once the
+    /// channel is orphaned, or `None` if it still has writers.
+    ///
+    /// This complements the [`OakStatus::ErrChannelClosed`] status returned when reading from an
+    /// orphaned channel, by indicating whether its writers completed normally.
+    fn channel_close_reason(
+        &self,
+        node_id: NodeId,
+        read_handle: oak_abi::Handle,
+    ) -> Result<Option<CloseReason>, OakStatus> {
+        let half = self.abi_to_read_half(node_id, read_handle)?;
+        self.validate_can_read_from_channel(node_id, &half, Downgrading::No)?;
+        with_reader_channel(&half, |channel| Ok(channel.close_reason()))
+    }
+
+    /// Moves all the handles of the Node identified by `from_node` into the handle table of the
+    /// Node identified by `to_node`, and removes the source Node, as a single atomic operation.
+    ///
+    /// The transfer is requested by the Node identified by `caller`, which must be either the
+    /// source Node itself or a Node with the top privilege; otherwise the operation fails with
+    /// [`OakStatus::ErrPermissionDenied`].
+    ///
+    /// Every channel is validated against the [`Label`] of the target Node before anything is
+    /// moved: if any of them may not be used by the target Node, the operation is aborted and both
+    /// handle tables are left unchanged. The same applies if the handles do not fit in the handle
+    /// table of the target Node, in which case the operation fails with
+    /// [`OakStatus::ErrInvalidArgs`].
+    ///
+    /// If the source Node has been started, it is notified to terminate instead, and its entry is
+    /// removed once its thread exits; in the meantime, it has no handles left to act on.
+    ///
+    /// Returns a map from each handle of the source Node to the corresponding new handle of the
+    /// target Node.
+    fn transfer_handles(
+        &self,
+        caller: NodeId,
+        from_node: NodeId,
+        to_node: NodeId,
+        downgrade: Downgrading,
+    ) -> Result<HashMap<oak_abi::Handle, oak_abi::Handle>, OakStatus> {
+        if from_node == to_node {
+            return Err(OakStatus::ErrInvalidArgs);
+        }
+        let (handle_map, source_started) = {
+            let mut node_infos = self.node_infos.write().unwrap();
+            let caller_info = node_infos.get(&caller).expect("Invalid node_id");
+            if caller != from_node && !caller_info.privilege.is_top_privilege() {
+                warn!(
+                    "{:?}: cannot transfer handles of node {:?}",
+                    caller_info.get_debug_id(caller),
+                    from_node
+                );
+                return Err(OakStatus::ErrPermissionDenied);
+            }
+            let source_info = node_infos
+                .get(&from_node)
+                .filter(|source_info| !source_info.transferred)
+                .ok_or(OakStatus::ErrInvalidArgs)?;
+            let target_info = node_infos.get(&to_node).ok_or(OakStatus::ErrInvalidArgs)?;
+            let count = source_info.abi_handles.len();
+            if count > self.handle_table_space_for(target_info.abi_handles.len()) {
+                warn!(
+                    "{:?}: cannot take over {} handles from {:?}: handle table full",
+                    target_info.get_debug_id(to_node),
+                    count,
+                    source_info.get_debug_id(from_node)
+                );
+                return Err(OakStatus::ErrInvalidArgs);
+            }
+            for (handle, half) in &source_info.abi_handles {
+                if let Err(status) = target_info.validate_can_use_half(half, downgrade) {
+                    warn!(
+                        "{:?}: cannot take over handle {} => {:?} from {:?}: {:?}",
+                        target_info.get_debug_id(to_node),
+                        handle,
+                        half,
+                        source_info.get_debug_id(from_node),
+                        status
+                    );
+                    return Err(status);
+                }
+            }
+
+            let source_info = node_infos.get_mut(&from_node).unwrap();
+            let halves = std::mem::take(&mut source_info.abi_handles);
+            // Marking the Node as transferred while holding the lock ensures that it is removed
+            // exactly once: here if it has not been started, or otherwise by its own thread, once
+            // notified to terminate either here or as soon as its `NodeStopper` is added.
+            source_info.transferred = true;
+            if let Some(node_stopper) = source_info.node_stopper.as_mut() {
+                node_stopper.notify(from_node);
+            }
+            let source_started =
+                source_info.node_stopper.is_some() || source_info.initial_handle.is_some();
+            for (handle, half) in &halves {
+                if half.direction == ChannelHalfDirection::Read {
+                    half.remove_consumer(from_node);
+                }
+                self.introspection_event(EventDetails::HandleDestroyed(HandleDestroyed {
+                    node_id: from_node.0,
+                    handle: *handle,
+                    channel_id: half.get_channel_id(),
+                    direction: match half.direction {
+                        ChannelHalfDirection::Read => Direction::Read as i32,
+                        ChannelHalfDirection::Write => Direction::Write as i32,
+                    },
+                }));
+            }
+
+            let target_info = node_infos.get_mut(&to_node).unwrap();
+            let handle_map = halves
+                .into_iter()
+                .sorted_by_key(|(handle, _)| *handle)
+                .map(|(handle, half)| (handle, self.insert_abi_handle(to_node, target_info, half)))
+                .collect();
+            (handle_map, source_started)
+        };
+        if !source_started {
+            self.remove_node_id(from_node);
+        }
+        Ok(handle_map)
+    }
+
+    /// Create a fresh [`NodeId`] for a Node with the provided name.
+    fn new_node_id(&self, node_name: &str) -> NodeId {
+        self.stable_node_id(node_name)
+            .unwrap_or_else(|| NodeId(self.next_node_id.fetch_add(1, SeqCst)))
     }
 
     /// Remove a Node by [`NodeId`] from the [`Runtime`].
     fn remove_node_id(&self, node_id: NodeId) {
         // Close any remaining handles
-        let (remaining_handles, node_type): (Vec<_>, &'static str) = {
+        let remaining_handles: Vec<_> = {
             let node_infos = self.node_infos.read().unwrap();
             let node_info = node_infos
                 .get(&node_id)
                 .unwrap_or_else(|| panic!("remove_node_id: No such node_id {:?}", node_id));
-            (
-                node_info.abi_handles.keys().copied().collect(),
-                node_info.node_type,
-            )
+            node_info.abi_handles.keys().copied().collect()
         };
 
         debug!(
@@ -1258,47 +3424,195 @@ impl Runtime {
                 .expect("remove_node_id: Unable to close hanging channel!");
         }
 
-        self.node_infos
+        let mut node_info = self
+            .node_infos
             .write()
             .unwrap()
             .remove(&node_id)
             .expect("remove_node_id: Node didn't exist!");
-        self.update_nodes_count_metric(node_type, -1);
+        if let Some(node_stopper) = node_info.node_stopper.take() {
+            // This is usually invoked from the Node thread itself, which therefore cannot be
+            // joined yet.
+            self.exited_node_stoppers
+                .lock()
+                .unwrap()
+                .insert(node_id, node_stopper);
+        }
+        self.log_rate_limiter.remove_node(node_id);
+        self.node_creation_rate_limiter.remove_node(node_id);
+        self.release_stable_node_id(node_id);
+        self.node_tombstones.add(node_id);
+        self.update_nodes_count_metric(node_info.node_type, -1);
+        self.report_node_cpu_time(node_info.node_type, &node_info.name, node_info.cpu_time);
 
         self.introspection_event(EventDetails::NodeDestroyed(NodeDestroyed {
             node_id: node_id.0,
+            crashed: node_info.crashed,
         }))
     }
 
-    /// Add an [`NodeId`] [`NodeInfo`] pair to the [`Runtime`]. This method temporarily holds the
-    /// [`Runtime::node_infos`] write lock.
-    fn add_node_info(&self, node_id: NodeId, node_info: NodeInfo) {
+    /// Records that the main thread of the Node identified by `node_id` panicked with the provided
+    /// payload, so that the Node is reported as crashed when it is removed.
+    fn record_node_panic(&self, node_id: NodeId, panic: &(dyn Any + Send)) {
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic payload");
+        error!(
+            "{:?}: node panicked: {}",
+            self.get_node_debug_id(node_id),
+            message
+        );
+        let node_type = {
+            let mut node_infos = self.node_infos.write().unwrap();
+            let node_info = node_infos
+                .get_mut(&node_id)
+                .expect("record_node_panic: Node didn't exist!");
+            node_info.crashed = true;
+            node_info.node_type
+        };
+        self.metrics_data
+            .runtime_metrics
+            .runtime_node_panics_total
+            .with_label_values(&[node_type])
+            .inc();
+    }
+
+    /// Add an [`NodeId`] [`NodeInfo`] pair to the [`Runtime`], unless there is no `capacity` left
+    /// for it, in which case this fails with [`OakStatus::ErrResourceExhausted`]. This method
+    /// temporarily holds the [`Runtime::node_infos`] write lock, so that the capacity is checked
+    /// atomically with the insertion.
+    fn add_node_info(
+        &self,
+        node_id: NodeId,
+        node_info: NodeInfo,
+        capacity: NodeCapacity,
+    ) -> Result<(), OakStatus> {
         let node_type = node_info.node_type;
-        self.node_infos
-            .write()
-            .expect("could not acquire lock on node_infos")
-            .insert(node_id, node_info);
+        {
+            let mut node_infos = self
+                .node_infos
+                .write()
+                .expect("could not acquire lock on node_infos");
+            match capacity {
+                NodeCapacity::Limit(max_nodes) => {
+                    if node_infos.len() + self.reserved_nodes.load(SeqCst) >= max_nodes {
+                        return Err(OakStatus::ErrResourceExhausted);
+                    }
+                }
+                NodeCapacity::Reserved(reservation) => {
+                    if reservation.remaining.load(SeqCst) == 0 {
+                        return Err(OakStatus::ErrResourceExhausted);
+                    }
+                    reservation.remaining.fetch_sub(1, SeqCst);
+                    self.reserved_nodes.fetch_sub(1, SeqCst);
+                }
+            }
+            node_infos.insert(node_id, node_info);
+        }
         self.update_nodes_count_metric(node_type, 1);
+        Ok(())
     }
 
-    /// Add the [`NodeStopper`] for a running Node to `NodeInfo`.
-    /// The provided [`NodeId`] value must already be present in [`Runtime::node_infos`].
-    fn add_node_stopper(&self, node_id: NodeId, node_stopper: NodeStopper) {
-        let mut node_infos = self
+    /// Reserves capacity for `count` Nodes, so that they can then be added with
+    /// [`NodeCapacity::Reserved`] even if other Nodes are created in the meantime.
+    ///
+    /// Fails with [`OakStatus::ErrResourceExhausted`] if the [`Runtime`] would then run, or have
+    /// reserved capacity for, more than [`RuntimeConfiguration::max_nodes`] Nodes.
+    fn reserve_node_capacity(&self, count: usize) -> Result<NodeReservation<'_>, OakStatus> {
+        let node_infos = self
             .node_infos
             .write()
             .expect("could not acquire lock on node_infos");
-        match node_infos.get_mut(&node_id) {
-            Some(node_info) => {
-                assert!(node_info.node_stopper.is_none());
-                node_info.node_stopper = Some(node_stopper);
+        let reserved_nodes = self.reserved_nodes.load(SeqCst);
+        if node_infos.len() + reserved_nodes + count > self.max_nodes.load(SeqCst) {
+            return Err(OakStatus::ErrResourceExhausted);
+        }
+        self.reserved_nodes.store(reserved_nodes + count, SeqCst);
+        Ok(NodeReservation {
+            runtime: self,
+            remaining: AtomicUsize::new(count),
+        })
+    }
+
+    /// Add the [`NodeStopper`] for a running Node to `NodeInfo`.
+    /// The provided [`NodeId`] value must already be present in [`Runtime::node_infos`].
+    fn add_node_stopper(&self, node_id: NodeId, node_stopper: NodeStopper) {
+        // Opportunistically join the threads of Nodes that have exited since the last call.
+        self.join_exited_nodes();
+        let node_stopper = {
+            let mut node_infos = self
+                .node_infos
+                .write()
+                .expect("could not acquire lock on node_infos");
+            match node_infos.get_mut(&node_id) {
+                Some(node_info) => {
+                    assert!(node_info.node_stopper.is_none());
+                    let mut node_stopper = node_stopper;
+                    // The handles of the Node were transferred before it could be notified.
+                    if node_info.transferred {
+                        node_stopper.notify(node_id);
+                    }
+                    node_info.node_stopper = Some(node_stopper);
+                    return;
+                }
+                None => node_stopper,
             }
-            None => {
-                // If the node thread terminated before this method is invoked, its NodeInfo entry
-                // may have already been deleted, in which case we just log a warning and continue.
-                // See https://github.com/project-oak/oak/issues/1762.
-                warn!("No NodeInfo found for node {:?}", node_id);
+        };
+        // If the node thread terminated before this method is invoked, its NodeInfo entry may
+        // have already been deleted. See https://github.com/project-oak/oak/issues/1762.
+        //
+        // Removing the Node is the last step of its thread, so the thread has finished or is about
+        // to, and can be joined here.
+        if self.node_tombstones.take(node_id) {
+            debug!(
+                "Node {:?} terminated before being fully started, joining its thread",
+                node_id
+            );
+        } else {
+            warn!(
+                "No NodeInfo found for node {:?}, joining its thread",
+                node_id
+            );
+        }
+        self.join_exited_node(node_id, node_stopper);
+    }
+
+    /// Joins the threads of removed Nodes that have finished, so that their resources are
+    /// released without waiting for the Runtime to stop.
+    fn join_exited_nodes(&self) {
+        let finished_node_stoppers: Vec<_> = {
+            let mut exited_node_stoppers = self.exited_node_stoppers.lock().unwrap();
+            let finished_node_ids: Vec<_> = exited_node_stoppers
+                .iter()
+                .filter(|(_, node_stopper)| node_stopper.is_finished())
+                .map(|(node_id, _)| *node_id)
+                .collect();
+            finished_node_ids
+                .into_iter()
+                .filter_map(|node_id| {
+                    exited_node_stoppers
+                        .remove(&node_id)
+                        .map(|node_stopper| (node_id, node_stopper))
+                })
+                .collect()
+        };
+        for (node_id, node_stopper) in finished_node_stoppers {
+            self.join_exited_node(node_id, node_stopper);
+        }
+    }
+
+    /// Joins the thread of the removed Node identified by `node_id`, and keeps the result for the
+    /// [`ShutdownReport`] if the thread panicked.
+    fn join_exited_node(&self, node_id: NodeId, node_stopper: NodeStopper) {
+        if let Some(Err(err)) = node_stopper.stop_node(node_id, None) {
+            warn!("Node {:?} thread panicked: {:?}", node_id, err);
+            let mut joined_node_failures = self.joined_node_failures.lock().unwrap();
+            if joined_node_failures.len() == MAX_EARLY_JOINED_NODE_FAILURES {
+                joined_node_failures.pop_front();
             }
+            joined_node_failures.push_back((node_id, Err(err)));
         }
     }
 
@@ -1310,9 +3624,17 @@ impl Runtime {
     /// <https://github.com/project-oak/oak/blob/main/docs/concepts.md#labels> for more
     /// information on labels.
     ///
+    /// The calling Node may only create Nodes of the types in its allowlist of creatable Node
+    /// types, if it has one. The new Node may in turn only create Nodes of the types in
+    /// `creatable_node_types`, if provided, that are also allowed for the calling Node, so that
+    /// restrictions cannot be bypassed by creating intermediate Nodes.
+    ///
     /// This method is defined on [`Arc`] and not [`Runtime`] itself, so that
     /// the [`Arc`] can clone itself and be included in a [`RuntimeProxy`] object
     /// to be given to a new Node instance.
+    ///
+    /// Returns the [`NodeId`] of the newly created Node.
+    #[allow(clippy::too_many_arguments)]
     fn node_create_and_register(
         self: Arc<Self>,
         node_id: NodeId,
@@ -1320,16 +3642,135 @@ impl Runtime {
         config: &NodeConfiguration,
         label: &Label,
         initial_handle: oak_abi::Handle,
+        creatable_node_types: Option<&[&str]>,
         downgrade: Downgrading,
-    ) -> Result<(), OakStatus> {
+    ) -> Result<NodeId, OakStatus> {
+        // Settings explicitly specified in `config` take precedence over the defaults for its type.
+        let config =
+            node::apply_node_config_defaults(&self.node_config_defaults.read().unwrap(), config);
         // This only creates a Node instance, but does not start it.
-        let instance = self.node_factory.create_node(name, config).map_err(|err| {
-            warn!("could not create node: {:?}", err);
-            OakStatus::ErrInvalidArgs
-        })?;
+        let instance = self
+            .node_factory
+            .create_node(name, &config)
+            .map_err(|err| {
+                warn!("could not create node: {:?}", err);
+                OakStatus::ErrInvalidArgs
+            })?;
+        self.validate_can_create_node_type(node_id, instance.instance.node_type())?;
+        let creatable_node_types = self.child_creatable_node_types(node_id, creatable_node_types);
 
         // Register the instance within the `Runtime`.
-        self.node_register(node_id, instance, name, label, initial_handle, downgrade)
+        self.node_register(
+            node_id,
+            instance,
+            name,
+            label,
+            initial_handle,
+            creatable_node_types,
+            Some(config),
+            downgrade,
+        )
+    }
+
+    /// Checks that the Node identified by `node_id` is allowed to create a Node of type
+    /// `node_type` according to its allowlist of creatable Node types, if it has one.
+    fn validate_can_create_node_type(
+        &self,
+        node_id: NodeId,
+        node_type: &str,
+    ) -> Result<(), OakStatus> {
+        let node_infos = self.node_infos.read().unwrap();
+        let node_info = match node_infos.get(&node_id) {
+            Some(node_info) => node_info,
+            None => return Ok(()),
+        };
+        match &node_info.creatable_node_types {
+            Some(creatable_node_types) if !creatable_node_types.contains(node_type) => {
+                warn!(
+                    "{:?}: not allowed to create Node of type {}",
+                    node_info.get_debug_id(node_id),
+                    node_type
+                );
+                Err(OakStatus::ErrPermissionDenied)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the allowlist of creatable Node types of a Node created by the Node identified by
+    /// `node_id`, which is the intersection of the `requested` allowlist, if provided, and of the
+    /// allowlist of the creating Node, if it has one.
+    fn child_creatable_node_types(
+        &self,
+        node_id: NodeId,
+        requested: Option<&[&str]>,
+    ) -> Option<HashSet<String>> {
+        let inherited = self
+            .node_infos
+            .read()
+            .unwrap()
+            .get(&node_id)
+            .and_then(|node_info| node_info.creatable_node_types.clone());
+        match (requested, inherited) {
+            (None, inherited) => inherited,
+            (Some(requested), None) => Some(requested.iter().map(|t| t.to_string()).collect()),
+            (Some(requested), Some(inherited)) => Some(
+                requested
+                    .iter()
+                    .filter(|t| inherited.contains(**t))
+                    .map(|t| t.to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Ensures that a Node with the specified name exists within the [`Runtime`], creating it as
+    /// in [`Runtime::node_create_and_register`] if there is none.
+    ///
+    /// Checking for an existing Node and creating the new one happen atomically with respect to
+    /// other calls to this method, so that concurrent calls with the same name create at most one
+    /// Node. Node names are not otherwise unique, so if several Nodes with the specified name were
+    /// created by other means, the one with the lowest [`NodeId`] is returned.
+    ///
+    /// If a Node already exists, the channel identified by `initial_handle` is left untouched in
+    /// the handle table of the calling Node.
+    fn ensure_node(
+        self: Arc<Self>,
+        node_id: NodeId,
+        name: &str,
+        config: &NodeConfiguration,
+        label: &Label,
+        initial_handle: oak_abi::Handle,
+        downgrade: Downgrading,
+    ) -> Result<EnsuredNode, OakStatus> {
+        // Whether a Node with the specified name exists is effectively public, so only disclose it
+        // to callers that would be allowed to create a Node in the first place.
+        self.validate_can_write_to_label(node_id, &Label::public_untrusted(), downgrade)?;
+
+        let _guard = self.ensure_node_lock.lock().unwrap();
+        let existing_node_id = self
+            .node_infos
+            .read()
+            .expect("could not acquire lock on node_infos")
+            .iter()
+            .filter(|(_, node_info)| node_info.name == name)
+            .map(|(node_id, _)| *node_id)
+            .min();
+        match existing_node_id {
+            Some(existing_node_id) => Ok(EnsuredNode::Existing(existing_node_id)),
+            None => self
+                .clone()
+                .node_create_and_register(
+                    node_id,
+                    name,
+                    config,
+                    label,
+                    initial_handle,
+                    None,
+                    downgrade,
+                )
+                .map(EnsuredNode::Created),
+        }
     }
 
     /// Registers the given [`CreatedNode`] instance within the [`Runtime`]. The registration fails
@@ -1337,6 +3778,21 @@ impl Runtime {
     ///
     /// If `downgrade` is set to [`Downgrading::Yes`], the calling Node's downgrading privilege is
     /// taken into account when checking IFC restrictions.
+    ///
+    /// The new Node may only create Nodes of the types in `creatable_node_types`, if provided. The
+    /// `configuration` the instance was created from, if any, is recorded so that the Node can be
+    /// respawned.
+    ///
+    /// The registration fails with [`OakStatus::ErrResourceExhausted`] if the [`Runtime`] already
+    /// runs [`RuntimeConfiguration::max_nodes`] Nodes, or if the calling Node exceeds the
+    /// [`RuntimeConfiguration::node_creation_rate_limit`]. Since the rate limit is checked before
+    /// the Node is started, the creation is only counted against it if the registration succeeds:
+    /// the registration may still fail afterwards, e.g. if `initial_handle` is invalid, if the
+    /// limit of live Nodes is reached in the meantime, or with [`OakStatus::ErrTerminated`] if the
+    /// new Node is removed by [`Runtime::transfer_handles`] before it is started.
+    ///
+    /// Returns the [`NodeId`] of the newly registered Node.
+    #[allow(clippy::too_many_arguments)]
     fn node_register(
         self: Arc<Self>,
         node_id: NodeId,
@@ -1344,8 +3800,10 @@ impl Runtime {
         node_name: &str,
         label: &Label,
         initial_handle: oak_abi::Handle,
+        creatable_node_types: Option<HashSet<String>>,
+        configuration: Option<NodeConfiguration>,
         downgrade: Downgrading,
-    ) -> Result<(), OakStatus> {
+    ) -> Result<NodeId, OakStatus> {
         if self.is_terminating() {
             return Err(OakStatus::ErrTerminated);
         }
@@ -1358,47 +3816,150 @@ impl Runtime {
         // by the current Node, since in general this may be lower than "public untrusted".
         self.validate_can_write_to_label(node_id, label, downgrade)?;
 
-        let instance = created_node.instance;
-
-        let node_type = instance.node_type();
-        let node_privilege = created_node.privilege;
+        // Make sure that a Node that is not sandboxed is allowed to communicate externally before
+        // registering and starting it.
+        self.validate_node_isolation(&created_node, node_name, label)?;
+
+        self.acquire_node_creations(node_id, 1, || format!("node {}", node_name))?;
+        self.clone()
+            .node_start_with_initial_handle(
+                node_id,
+                created_node,
+                node_name,
+                label,
+                initial_handle,
+                creatable_node_types,
+                configuration,
+                NodeCapacity::Limit(self.max_nodes.load(SeqCst)),
+            )
+            .map_err(|status| {
+                // No Node was created, so the creation does not count towards the rate limit.
+                self.node_creation_rate_limiter.release(node_id, 1);
+                status
+            })
+    }
 
-        // If the new node is not sandboxed it can communicate externally without restriction, so we
-        // should make sure that it has the privilege to downgrade its label to "public untrusted"
-        // before registering and starting it.
-        match instance.isolation() {
-            NodeIsolation::Uncontrolled => {
-                let downgraded_label = node_privilege.downgrade_label(label);
-                debug!(
-                    "Maximum downgraded label for node {}: {:?}",
-                    node_name, &downgraded_label
-                );
-                if !downgraded_label.flows_to(&Label::public_untrusted()) {
-                    error!(
-                        "Node {} of type {} has insufficent privilege.",
-                        node_name, node_type
-                    );
-                    return Err(OakStatus::ErrPermissionDenied);
-                };
-            }
-            NodeIsolation::Sandboxed => {
-                trace!(
-                    "Node {} of type {} is sandboxed, so not checking privilege.",
-                    node_name,
-                    node_type
-                );
-            }
+    /// Takes `count` tokens from the [`RuntimeConfiguration::node_creation_rate_limit`] bucket of
+    /// the Node identified by `node_id`, to create the Nodes described by `describe_nodes`. Either
+    /// all the tokens are taken, or none.
+    ///
+    /// Fails with [`OakStatus::ErrResourceExhausted`] if the calling Node exceeds the rate limit.
+    fn acquire_node_creations<F: FnOnce() -> String>(
+        &self,
+        node_id: NodeId,
+        count: usize,
+        describe_nodes: F,
+    ) -> Result<(), OakStatus> {
+        if self
+            .node_creation_rate_limiter
+            .try_acquire(node_id, count, self.now())
+        {
+            return Ok(());
         }
+        self.log_rate_limited(Level::Warn, node_id, "node_create_throttled", || {
+            format!(
+                "{:?}: cannot create {}: node creation rate limit exceeded",
+                self.get_node_debug_id(node_id),
+                describe_nodes()
+            )
+        });
+        self.metrics_data
+            .runtime_metrics
+            .runtime_node_creations_throttled_total
+            .inc();
+        Err(OakStatus::ErrResourceExhausted)
+    }
 
+    /// Starts the given [`CreatedNode`] instance with the channel identified by `initial_handle`
+    /// in the handle table of the calling Node, once all the checks of [`Runtime::node_register`],
+    /// including the rate limit, have passed, if there is `capacity` left for it.
+    ///
+    /// Returns the [`NodeId`] of the newly started Node.
+    #[allow(clippy::too_many_arguments)]
+    fn node_start_with_initial_handle(
+        self: Arc<Self>,
+        node_id: NodeId,
+        created_node: CreatedNode,
+        node_name: &str,
+        label: &Label,
+        initial_handle: oak_abi::Handle,
+        creatable_node_types: Option<HashSet<String>>,
+        configuration: Option<NodeConfiguration>,
+        capacity: NodeCapacity,
+    ) -> Result<NodeId, OakStatus> {
         let reader = self.abi_to_read_half(node_id, initial_handle)?;
+        self.node_start(
+            node_id,
+            created_node,
+            node_name,
+            label,
+            reader,
+            creatable_node_types,
+            configuration,
+            capacity,
+        )
+    }
+
+    /// Starts the given [`CreatedNode`] instance, created on behalf of the Node identified by
+    /// `node_id`, once all the checks of [`Runtime::node_register`] have passed, with the `reader`
+    /// half of its initial channel.
+    ///
+    /// Fails with [`OakStatus::ErrResourceExhausted`] if there is no `capacity` left for the Node.
+    ///
+    /// Returns the [`NodeId`] of the newly started Node.
+    #[allow(clippy::too_many_arguments)]
+    fn node_start(
+        self: Arc<Self>,
+        node_id: NodeId,
+        created_node: CreatedNode,
+        node_name: &str,
+        label: &Label,
+        reader: ChannelHalf,
+        creatable_node_types: Option<HashSet<String>>,
+        configuration: Option<NodeConfiguration>,
+        capacity: NodeCapacity,
+    ) -> Result<NodeId, OakStatus> {
+        let instance = created_node.instance;
+        let node_type = instance.node_type();
+        let sandboxed = matches!(instance.isolation(), NodeIsolation::Sandboxed);
+        let node_privilege = created_node.privilege;
+        let initial_channel_id = reader.get_channel_id();
 
         let new_node_proxy = self.clone().proxy_for_new_node(node_name);
         let new_node_id = new_node_proxy.node_id;
 
-        self.node_configure_instance(new_node_id, node_type, node_name, label, &node_privilege);
-        let initial_handle = new_node_proxy
-            .runtime
-            .new_abi_handle(new_node_proxy.node_id, reader);
+        self.node_configure_instance(
+            new_node_id,
+            node_type,
+            node_name,
+            label,
+            &node_privilege,
+            capacity,
+        )
+        .map_err(|status| {
+            warn!(
+                "{:?}: cannot create node {}: limit of live nodes reached",
+                self.get_node_debug_id(node_id),
+                node_name
+            );
+            self.release_stable_node_id(new_node_id);
+            status
+        })?;
+        let initial_handle = {
+            // Setting the initial handle marks the Node as started, so this must happen atomically
+            // with checking that the Node has not been removed by a transfer of its handles in the
+            // meantime, see `Runtime::transfer_handles`.
+            let mut node_infos = self.node_infos.write().unwrap();
+            let node_info = node_infos
+                .get_mut(&new_node_id)
+                .ok_or(OakStatus::ErrTerminated)?;
+            let initial_handle = self.insert_abi_handle(new_node_id, node_info, reader);
+            node_info.initial_handle = Some((initial_handle, initial_channel_id));
+            node_info.creatable_node_types = creatable_node_types;
+            node_info.configuration = configuration;
+            node_info.sandboxed = sandboxed;
+            initial_handle
+        };
 
         info!(
             "{:?}: start node instance {:?} of type {} with privilege {:?}",
@@ -1418,6 +3979,48 @@ impl Runtime {
         // `Node::stop` will be called on it eventually.
         self.add_node_stopper(new_node_id, node_stopper);
 
+        Ok(new_node_id)
+    }
+
+    /// Checks that a newly created Node that is not sandboxed, and so can communicate externally
+    /// without restriction, has the privilege to downgrade its label to the configured egress label
+    /// ("public untrusted" by default).
+    fn validate_node_isolation(
+        &self,
+        created_node: &CreatedNode,
+        node_name: &str,
+        label: &Label,
+    ) -> Result<(), OakStatus> {
+        let node_type = created_node.instance.node_type();
+        match created_node.instance.isolation() {
+            NodeIsolation::Uncontrolled => {
+                let downgraded_label = created_node.privilege.downgrade_label(label);
+                debug!(
+                    "Maximum downgraded label for node {}: {:?}",
+                    node_name, &downgraded_label
+                );
+                let egress_label = self
+                    .node_factory
+                    .permissions_configuration
+                    .uncontrolled_node_egress_label
+                    .clone()
+                    .unwrap_or_else(Label::public_untrusted);
+                if !downgraded_label.flows_to(&egress_label) {
+                    error!(
+                        "Node {} of type {} has insufficent privilege.",
+                        node_name, node_type
+                    );
+                    return Err(OakStatus::ErrPermissionDenied);
+                };
+            }
+            NodeIsolation::Sandboxed => {
+                trace!(
+                    "Node {} of type {} is sandboxed, so not checking privilege.",
+                    node_name,
+                    node_type
+                );
+            }
+        }
         Ok(())
     }
 
@@ -1442,14 +4045,29 @@ impl Runtime {
         // We also want no locks to be held while the instance is starting.
         let node_id = node_proxy.node_id;
         let (node_notify_sender, node_notify_receiver) = tokio::sync::oneshot::channel::<()>();
+        let finished = Arc::new(AtomicBool::new(false));
+        let node_finished = finished.clone();
         let node_join_handle = thread::Builder::new()
-            .name(node_name.to_string())
+            .name(node_thread_name(node_name, node_id))
             .spawn(move || {
                 node_proxy.set_as_current();
-                node_instance.run(node_proxy, initial_handle, node_notify_receiver);
+                let cpu_timer = ThreadCpuTimer::start();
+                // Catch panics, so that they are reported rather than just terminating the thread.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    node_instance.run(node_proxy, initial_handle, node_notify_receiver)
+                }));
+                self.record_node_cpu_time(node_id, cpu_timer.elapsed());
+                if let Err(panic) = &result {
+                    self.record_node_panic(node_id, panic.as_ref());
+                }
                 // It's now safe to remove the state for this Node, as there's nothing left
                 // that can invoke `Runtime` functionality for it.
-                self.remove_node_id(node_id)
+                self.remove_node_id(node_id);
+                node_finished.store(true, SeqCst);
+                // Propagate the panic, so that it is reported when joining the thread.
+                if let Err(panic) = result {
+                    panic::resume_unwind(panic);
+                }
             })
             .expect("failed to spawn thread");
         // Note: self has been moved into the thread running the closure.
@@ -1457,11 +4075,15 @@ impl Runtime {
         Ok(NodeStopper {
             node_name: node_name.to_string(),
             join_handle: node_join_handle,
-            notify_sender: node_notify_sender,
+            finished,
+            notify_sender: Some(node_notify_sender),
         })
     }
 
     /// Configure data structures for a Node instance.
+    ///
+    /// Fails with [`OakStatus::ErrResourceExhausted`], leaving the [`Runtime`] unchanged, if there
+    /// is no `capacity` left for the Node.
     fn node_configure_instance(
         &self,
         node_id: NodeId,
@@ -1469,16 +4091,8 @@ impl Runtime {
         node_name: &str,
         label: &Label,
         privilege: &NodePrivilege,
-    ) {
-        // TODO(#913): Add automated tests that verify that NodeCreated is
-        // always fired prior to any other introspection events related to the
-        // node.
-        self.introspection_event(EventDetails::NodeCreated(NodeCreated {
-            node_id: node_id.0,
-            name: node_name.to_string(),
-            label: Some(label.clone()),
-        }));
-
+        capacity: NodeCapacity,
+    ) -> Result<(), OakStatus> {
         self.add_node_info(
             node_id,
             NodeInfo {
@@ -1486,16 +4100,44 @@ impl Runtime {
                 node_type,
                 label: label.clone(),
                 privilege: privilege.clone(),
+                serialized_label: serialize_label(label),
+                serialized_privilege: serialize_label(&privilege.clone().into()),
                 abi_handles: HashMap::new(),
+                initial_handle: None,
+                creatable_node_types: None,
+                configuration: None,
                 node_stopper: None,
+                liveness: NodeLiveness::default(),
+                downgrade_history: Vec::new(),
+                last_read_message_writers: Vec::new(),
+                cpu_time: Duration::from_secs(0),
+                crashed: false,
+                handle_rng: self.handle_rng(node_id),
+                sandboxed: true,
+                transferred: false,
             },
-        );
+            capacity,
+        )?;
+
+        // TODO(#913): Add automated tests that verify that NodeCreated is
+        // always fired prior to any other introspection events related to the
+        // node.
+        self.introspection_event(EventDetails::NodeCreated(NodeCreated {
+            node_id: node_id.0,
+            name: node_name.to_string(),
+            label: Some(label.clone()),
+        }));
+        self.introspection_event(EventDetails::NodePrivilegeAssigned(NodePrivilegeAssigned {
+            node_id: node_id.0,
+            privilege: Some(privilege.clone().into()),
+        }));
+        Ok(())
     }
 
     /// Create a [`RuntimeProxy`] instance for a new Node, creating the new [`NodeId`]
     /// value along the way.
     fn proxy_for_new_node(self: Arc<Self>, node_name: &str) -> RuntimeProxy {
-        let node_id = self.new_node_id();
+        let node_id = self.new_node_id(node_name);
         RuntimeProxy {
             runtime: self,
             node_id,
@@ -1503,6 +4145,24 @@ impl Runtime {
         }
     }
 
+    /// Counts a message of `bytes` bytes moving through a channel in the provided direction, in
+    /// the metrics aggregated across all channels.
+    fn record_channel_traffic(&self, direction: ChannelTraffic, bytes: usize) {
+        let direction = match direction {
+            ChannelTraffic::Enqueue => "enqueue",
+            ChannelTraffic::Dequeue => "dequeue",
+        };
+        let runtime_metrics = &self.metrics_data.runtime_metrics;
+        runtime_metrics
+            .runtime_channel_messages_total
+            .with_label_values(&[direction])
+            .inc();
+        runtime_metrics
+            .runtime_channel_bytes_total
+            .with_label_values(&[direction])
+            .inc_by(bytes as u64);
+    }
+
     /// Update the node count metric with the current value.
     fn update_nodes_count_metric(&self, node_type: &'static str, delta: i64) {
         self.metrics_data
@@ -1513,22 +4173,37 @@ impl Runtime {
     }
 }
 
-/// Searializes a [`Label`] as a byte array.
+/// Serializes a [`Label`] as a byte array.
+fn serialize_label(label: &Label) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(label.encoded_len());
+    label
+        .encode(&mut encoded)
+        .expect("could not encode label into a vector with enough capacity");
+    encoded
+}
+
+/// Serializes a [`Label`] as JSON, in the format accepted for labels provided as JSON over HTTP.
+///
+/// The label is converted to its [canonical form](Label::canonical) first, so that labels with
+/// the same tags produce identical output regardless of the order in which tags were added.
 ///
 /// If the serialized size is larger than the specified capacity, it will return a status
 /// indicating the required capacity.
-fn serialize_label(label: Label, capacity: usize) -> Result<LabelReadStatus, OakStatus> {
-    let size = label.encoded_len();
-    if size > capacity {
-        Ok(LabelReadStatus::NeedsCapacity(size))
+pub fn serialize_label_json(label: &Label, capacity: usize) -> LabelReadStatus {
+    label_read_status(label_json(label).as_bytes(), capacity)
+}
+
+/// Returns the JSON representation of a [`Label`] produced by [`serialize_label_json`].
+fn label_json(label: &Label) -> String {
+    serde_json::to_string(&label.canonical()).expect("could not serialize label to JSON")
+}
+
+/// Returns a copy of the provided `serialized_label`, or a status indicating the required capacity
+/// if its size is larger than the specified capacity.
+fn label_read_status(serialized_label: &[u8], capacity: usize) -> LabelReadStatus {
+    if serialized_label.len() > capacity {
+        LabelReadStatus::NeedsCapacity(serialized_label.len())
     } else {
-        let mut encoded = Vec::with_capacity(size);
-        match label.encode(&mut encoded) {
-            Err(error) => {
-                error!("Could not encode label: {}", error);
-                Err(OakStatus::ErrInternal)
-            }
-            Ok(()) => Ok(LabelReadStatus::Success(encoded)),
-        }
+        LabelReadStatus::Success(serialized_label.to_vec())
     }
 }
@@ -0,0 +1,144 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Rate limiting of log messages emitted on hot paths.
+//!
+//! Some log messages (e.g. about a Node repeatedly being denied access to a channel) may be
+//! emitted on every operation of a misbehaving Node, drowning out any other log output. Such
+//! messages are logged the first time they occur for a given Node, and then at most once per
+//! interval, together with the number of occurrences suppressed in the meantime.
+
+use crate::{NodeId, Runtime};
+use log::{log, log_enabled, Level};
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering::SeqCst, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Default minimum interval between two log messages of the same kind for the same Node.
+pub const DEFAULT_LOG_RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Logging state of a single kind of message for a single Node.
+struct RateLimitEntry {
+    /// Time at which the message was last logged.
+    last_logged: Instant,
+    /// Number of occurrences of the message suppressed since it was last logged.
+    suppressed: usize,
+}
+
+/// Tracks when each kind of message was last logged for each Node.
+#[derive(Default)]
+pub(crate) struct LogRateLimiter {
+    entries: Mutex<HashMap<(NodeId, &'static str), RateLimitEntry>>,
+}
+
+impl LogRateLimiter {
+    /// Records an occurrence of the message identified by `kind` for the Node identified by
+    /// `node_id`.
+    ///
+    /// Returns the number of occurrences suppressed since the message was last logged if it should
+    /// be logged now, or `None` if it should be suppressed.
+    pub(crate) fn check(
+        &self,
+        node_id: NodeId,
+        kind: &'static str,
+        interval: Duration,
+    ) -> Option<usize> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        match entries.get_mut(&(node_id, kind)) {
+            Some(entry) if now.duration_since(entry.last_logged) < interval => {
+                entry.suppressed += 1;
+                None
+            }
+            Some(entry) => {
+                entry.last_logged = now;
+                Some(std::mem::take(&mut entry.suppressed))
+            }
+            None => {
+                entries.insert(
+                    (node_id, kind),
+                    RateLimitEntry {
+                        last_logged: now,
+                        suppressed: 0,
+                    },
+                );
+                Some(0)
+            }
+        }
+    }
+
+    /// Returns the number of occurrences of the message identified by `kind` for the Node
+    /// identified by `node_id` suppressed since it was last logged.
+    #[cfg(test)]
+    pub(crate) fn suppressed(&self, node_id: NodeId, kind: &'static str) -> usize {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&(node_id, kind))
+            .map(|entry| entry.suppressed)
+            .unwrap_or_default()
+    }
+
+    /// Forgets the state of all the messages of the Node identified by `node_id`.
+    pub(crate) fn remove_node(&self, node_id: NodeId) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(entry_node_id, _), _| *entry_node_id != node_id);
+    }
+}
+
+// Rate-limited logging related methods for the Runtime.
+impl Runtime {
+    /// Sets the minimum interval between two log messages of the same kind for the same Node.
+    /// Rate limiting is disabled if `interval` is zero.
+    pub(crate) fn set_log_rate_limit_interval(&self, interval: Duration) {
+        self.log_rate_limit_interval_millis
+            .store(interval.as_millis() as u64, SeqCst);
+    }
+
+    /// Logs the message built by `message` at the provided `level`, unless a message of the same
+    /// `kind` was already logged for the Node identified by `node_id` within the configured
+    /// interval.
+    ///
+    /// The message is only built if it is actually logged, and mentions how many occurrences of
+    /// it were suppressed since it was last logged. Occurrences are not tracked at all if `level`
+    /// is disabled, so that hot paths do not contend on the rate limiter in that case.
+    pub(crate) fn log_rate_limited<F: FnOnce() -> String>(
+        &self,
+        level: Level,
+        node_id: NodeId,
+        kind: &'static str,
+        message: F,
+    ) {
+        if !log_enabled!(level) {
+            return;
+        }
+        let interval = Duration::from_millis(self.log_rate_limit_interval_millis.load(SeqCst));
+        match self.log_rate_limiter.check(node_id, kind, interval) {
+            Some(0) => log!(level, "{}", message()),
+            Some(suppressed) => log!(
+                level,
+                "{} ({} similar messages suppressed)",
+                message(),
+                suppressed
+            ),
+            None => {}
+        }
+    }
+}
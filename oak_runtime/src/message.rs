@@ -25,4 +25,21 @@
 pub struct Message {
     pub data: Vec<u8>,
     pub channels: Vec<crate::ChannelHalf>,
+    /// Downgrades applied to the data this message was derived from, if tracked.
+    pub downgrade_history: Vec<crate::DowngradeRecord>,
+    /// The Node that wrote this message, if tagging messages with their writer is enabled.
+    pub writer: Option<crate::NodeId>,
+    /// When the message was enqueued, and for how long it stays relevant, if it was written with
+    /// a TTL. Expired messages are skipped by readers.
+    pub expiry: Option<(std::time::Instant, std::time::Duration)>,
+}
+
+impl Message {
+    /// Returns whether the TTL of the message, if any, has elapsed at time `now`.
+    pub fn is_expired(&self, now: std::time::Instant) -> bool {
+        match self.expiry {
+            Some((enqueued_at, ttl)) => now.saturating_duration_since(enqueued_at) >= ttl,
+            None => false,
+        }
+    }
 }
@@ -0,0 +1,85 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Expiry of messages written with a TTL.
+//!
+//! A message written with [`crate::RuntimeProxy::channel_write_with_ttl`] records when it was
+//! enqueued, according to the [clock](crate::time::Clock) of the Runtime. Once its TTL has
+//! elapsed, readers skip the message, and checking the status of the channel (e.g. while waiting
+//! on it) does not report it as ready: it is dequeued and discarded, a `MessageDequeued`
+//! introspection event marked as expired is emitted, and the `runtime_messages_expired_total`
+//! metric is incremented.
+
+#[cfg(test)]
+use crate::time::Clock;
+use crate::{
+    message::Message,
+    proto::oak::introspection_events::{event::EventDetails, MessageDequeued},
+    ChannelHalf, NodeId, Runtime,
+};
+#[cfg(test)]
+use std::sync::Arc;
+use std::{collections::VecDeque, time::Instant};
+
+/// Removes the expired messages at the front of `messages`, so that the front message, if any, is
+/// live, and returns them.
+pub(crate) fn remove_expired_front(messages: &mut VecDeque<Message>, now: Instant) -> Vec<Message> {
+    let mut expired = Vec::new();
+    while matches!(messages.front(), Some(msg) if msg.is_expired(now)) {
+        expired.extend(messages.pop_front());
+    }
+    expired
+}
+
+// Message expiry related methods for the Runtime.
+impl Runtime {
+    /// Returns the current time according to the [clock](crate::time::Clock) of the Runtime.
+    pub(crate) fn now(&self) -> Instant {
+        self.clock.read().unwrap().now()
+    }
+
+    /// Replaces the [`Clock`] of the Runtime.
+    #[cfg(test)]
+    pub(crate) fn set_clock(&self, clock: Arc<dyn Clock>) {
+        *self.clock.write().unwrap() = clock;
+    }
+
+    /// Records that the Node identified by `node_id` has skipped the `expired` messages of the
+    /// channel that `half` refers to.
+    pub(crate) fn record_expired_messages(
+        &self,
+        node_id: NodeId,
+        half: &ChannelHalf,
+        expired: Vec<Message>,
+    ) {
+        if expired.is_empty() {
+            return;
+        }
+        self.metrics_data
+            .runtime_metrics
+            .runtime_messages_expired_total
+            .inc_by(expired.len() as u64);
+        for _ in expired {
+            self.introspection_event(EventDetails::MessageDequeued(MessageDequeued {
+                node_id: node_id.0,
+                channel_id: half.get_channel_id(),
+                acquired_handles: vec![],
+                expired: true,
+            }));
+        }
+        self.update_channel_queue_length(half);
+    }
+}
@@ -0,0 +1,77 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Tagging of messages with the [`NodeId`] of the Node that wrote them, for auditing.
+//!
+//! When enabled, each message written to a channel records the Node that wrote it. As for
+//! downgrade histories (see [`crate::downgrade_history`]), the writers of the messages most
+//! recently read by a Node are recorded for that Node, and can be queried by the Node after the
+//! read. A batch read records the writer of each message it returns.
+
+use crate::{NodeId, Runtime};
+use std::sync::atomic::Ordering::SeqCst;
+
+// Message writer related methods for the Runtime.
+impl Runtime {
+    /// Enables or disables tagging messages with the [`NodeId`] of their writer.
+    pub(crate) fn set_tag_message_writers(&self, enabled: bool) {
+        self.tag_message_writers.store(enabled, SeqCst);
+    }
+
+    /// Returns the writer to tag a message written by the Node identified by `node_id` with, or
+    /// `None` if tagging is disabled.
+    pub(crate) fn message_writer(&self, node_id: NodeId) -> Option<NodeId> {
+        if self.tag_message_writers.load(SeqCst) {
+            Some(node_id)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the [`NodeId`] of the Node that wrote the message most recently read by the Node
+    /// identified by `node_id`, or `None` if no message has been read, or if the message was not
+    /// tagged with its writer.
+    pub(crate) fn last_read_message_writer(&self, node_id: NodeId) -> Option<NodeId> {
+        self.last_read_message_writers(node_id)
+            .last()
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the [`NodeId`]s of the Nodes that wrote the messages returned by the most recent
+    /// read of the Node identified by `node_id`, in the order the messages were returned. Each
+    /// entry is `None` if the corresponding message was not tagged with its writer.
+    pub(crate) fn last_read_message_writers(&self, node_id: NodeId) -> Vec<Option<NodeId>> {
+        let node_infos = self.node_infos.read().unwrap();
+        node_infos
+            .get(&node_id)
+            .expect("Invalid node_id")
+            .last_read_message_writers
+            .clone()
+    }
+
+    /// Records that the most recent read of the Node identified by `node_id` returned messages
+    /// written by `writers`, in that order.
+    pub(crate) fn record_message_writers(&self, node_id: NodeId, writers: Vec<Option<NodeId>>) {
+        if !self.tag_message_writers.load(SeqCst) {
+            return;
+        }
+        let mut node_infos = self.node_infos.write().unwrap();
+        if let Some(node_info) = node_infos.get_mut(&node_id) {
+            node_info.last_read_message_writers = writers;
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Channel metrics aggregated by label bucket, for analysing the flow of data by sensitivity.
+//!
+//! Labels are high-cardinality and their tags may be sensitive, so they are not exported as is.
+//! Instead, each channel is assigned to one of a configured list of [`LabelBucket`]s, and the
+//! `runtime_channel_queue_length_by_label` and `runtime_channel_messages_by_label_total` metrics
+//! are labeled with the name of the bucket.
+
+use crate::{ChannelHalf, Runtime};
+use oak_abi::label::Label;
+use prometheus::IntGaugeVec;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+};
+
+/// Label value of the metrics aggregating the channels that do not belong to any bucket.
+pub const OTHER_LABEL_BUCKET: &str = "other";
+
+/// A named group of channel labels, used as a label value of the metrics aggregated by label
+/// bucket.
+#[derive(Clone, Debug)]
+pub struct LabelBucket {
+    /// Name of the bucket, used as the label value.
+    pub name: String,
+    /// Upper bound of the labels of the channels in the bucket: a channel belongs to the bucket if
+    /// its label flows to this label.
+    pub label: Label,
+}
+
+/// State of a channel whose metrics are aggregated by label bucket.
+struct ChannelBucket {
+    /// Name of the bucket the channel belongs to.
+    name: String,
+    /// Last queue length reported for the channel.
+    length: i64,
+}
+
+/// Tracks the label bucket of each channel, and the queue lengths reported for them.
+#[derive(Default)]
+pub(crate) struct ChannelLabelBuckets {
+    /// Configured buckets, in order of precedence. The metrics are disabled if empty.
+    buckets: RwLock<Vec<LabelBucket>>,
+    channels: Mutex<HashMap<u64, ChannelBucket>>,
+}
+
+impl ChannelLabelBuckets {
+    /// Returns the name of the first bucket that `label` belongs to.
+    fn bucket_name(buckets: &[LabelBucket], label: &Label) -> String {
+        buckets
+            .iter()
+            .find(|bucket| label.flows_to(&bucket.label))
+            .map(|bucket| bucket.name.clone())
+            .unwrap_or_else(|| OTHER_LABEL_BUCKET.to_string())
+    }
+
+    /// Runs `f` on the state of the channel that `half` refers to, if the metrics are enabled.
+    fn with_channel<F: FnOnce(&mut ChannelBucket)>(&self, half: &ChannelHalf, f: F) {
+        let buckets = self.buckets.read().unwrap();
+        if buckets.is_empty() {
+            return;
+        }
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels
+            .entry(half.get_channel_id())
+            .or_insert_with(|| ChannelBucket {
+                name: Self::bucket_name(&buckets, half.get_label()),
+                length: 0,
+            });
+        f(channel);
+    }
+
+    /// Stops accounting for the queue length of the destroyed channel identified by `channel_id`.
+    fn remove(&self, gauge: &IntGaugeVec, channel_id: u64) {
+        if let Some(channel) = self.channels.lock().unwrap().remove(&channel_id) {
+            gauge
+                .with_label_values(&[&channel.name])
+                .sub(channel.length);
+        }
+    }
+}
+
+// Label bucket metric related methods for the Runtime.
+impl Runtime {
+    /// Enables the metrics aggregated by label bucket, using the provided buckets, or disables
+    /// them if `buckets` is empty.
+    pub(crate) fn set_channel_label_buckets(&self, buckets: Vec<LabelBucket>) {
+        *self.channel_label_buckets.buckets.write().unwrap() = buckets;
+    }
+
+    /// Reports the current queue length of the channel that `half` refers to, if the metrics
+    /// aggregated by label bucket are enabled.
+    pub(crate) fn update_channel_label_queue_length(&self, half: &ChannelHalf) {
+        let gauge = &self
+            .metrics_data
+            .runtime_metrics
+            .runtime_channel_queue_length_by_label;
+        self.channel_label_buckets.with_channel(half, |channel| {
+            // Read the length under the lock, so that concurrent updates are reported in order.
+            let length = half.get_messages().len() as i64;
+            gauge
+                .with_label_values(&[&channel.name])
+                .add(length - channel.length);
+            channel.length = length;
+        });
+    }
+
+    /// Records that a message was written to the channel that `half` refers to, if the metrics
+    /// aggregated by label bucket are enabled.
+    pub(crate) fn record_channel_label_message(&self, half: &ChannelHalf) {
+        let counter = &self
+            .metrics_data
+            .runtime_metrics
+            .runtime_channel_messages_by_label_total;
+        self.channel_label_buckets.with_channel(half, |channel| {
+            counter.with_label_values(&[&channel.name]).inc();
+        });
+    }
+
+    /// Stops accounting for the queue length of the destroyed channel identified by `channel_id`.
+    pub(crate) fn remove_channel_label_queue_length(&self, channel_id: u64) {
+        self.channel_label_buckets.remove(
+            &self
+                .metrics_data
+                .runtime_metrics
+                .runtime_channel_queue_length_by_label,
+            channel_id,
+        );
+    }
+}
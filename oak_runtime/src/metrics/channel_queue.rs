@@ -0,0 +1,147 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Per-channel queue length metrics.
+//!
+//! Channel IDs are not reused, so a gauge labeled by channel ID could end up with an unbounded
+//! number of label values. Only the first channels written to or read from (up to a configurable
+//! limit) get their own label value; the queue lengths of any further channels are added up under
+//! the [`OTHER_CHANNELS_LABEL`] label value.
+
+use crate::{ChannelHalf, Runtime};
+use prometheus::IntGaugeVec;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Mutex,
+    },
+};
+
+/// Label value of the gauge aggregating the queue lengths of the channels beyond the limit.
+pub const OTHER_CHANNELS_LABEL: &str = "other";
+
+/// Last queue length reported for a channel.
+struct QueueLength {
+    /// Whether the channel has its own label value, rather than being aggregated.
+    labeled: bool,
+    length: i64,
+}
+
+#[derive(Default)]
+struct QueueLengthsState {
+    lengths: HashMap<u64, QueueLength>,
+    /// Number of entries of `lengths` that have their own label value.
+    labeled_count: usize,
+}
+
+/// Tracks the queue lengths reported for each channel.
+#[derive(Default)]
+pub(crate) struct ChannelQueueLengths {
+    /// Maximum number of channels with their own label value, or zero if the metric is disabled.
+    limit: AtomicUsize,
+    state: Mutex<QueueLengthsState>,
+}
+
+impl ChannelQueueLengths {
+    /// Reports the current queue length of the channel identified by `channel_id`, as returned
+    /// by `length`, to `gauge`.
+    fn update<F: FnOnce() -> usize>(&self, gauge: &IntGaugeVec, channel_id: u64, length: F) {
+        let limit = self.limit.load(SeqCst);
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let labeled_count = &mut state.labeled_count;
+        let entry = state.lengths.entry(channel_id).or_insert_with(|| {
+            let labeled = *labeled_count < limit;
+            if labeled {
+                *labeled_count += 1;
+            }
+            QueueLength { labeled, length: 0 }
+        });
+        // Read the length under the lock, so that concurrent updates are reported in order.
+        let length = length() as i64;
+        let delta = length - entry.length;
+        entry.length = length;
+        if entry.labeled {
+            gauge
+                .with_label_values(&[&channel_id.to_string()])
+                .set(length);
+        } else {
+            gauge.with_label_values(&[OTHER_CHANNELS_LABEL]).add(delta);
+        }
+    }
+
+    /// Stops reporting the queue length of the channel identified by `channel_id` to `gauge`.
+    fn remove(&self, gauge: &IntGaugeVec, channel_id: u64) {
+        let mut state = self.state.lock().unwrap();
+        match state.lengths.remove(&channel_id) {
+            Some(QueueLength {
+                labeled: true,
+                length: _,
+            }) => {
+                state.labeled_count -= 1;
+                // The label value may not exist if the metric was disabled in the meantime.
+                let _ = gauge.remove_label_values(&[&channel_id.to_string()]);
+            }
+            Some(QueueLength {
+                labeled: false,
+                length,
+            }) => gauge.with_label_values(&[OTHER_CHANNELS_LABEL]).sub(length),
+            None => {}
+        }
+    }
+}
+
+// Channel queue length metric related methods for the Runtime.
+impl Runtime {
+    /// Enables the `runtime_channel_queue_length` metric, giving at most `limit` channels their own
+    /// label value, or disables it if `limit` is `None`.
+    pub(crate) fn set_channel_queue_length_limit(&self, limit: Option<usize>) {
+        self.channel_queue_lengths
+            .limit
+            .store(limit.unwrap_or(0), SeqCst);
+    }
+
+    /// Reports the current queue length of the channel that `half` refers to, if the
+    /// `runtime_channel_queue_length` metric is enabled, and to the metrics aggregated by label
+    /// bucket.
+    pub(crate) fn update_channel_queue_length(&self, half: &ChannelHalf) {
+        self.update_channel_label_queue_length(half);
+        if self.channel_queue_lengths.limit.load(SeqCst) == 0 {
+            return;
+        }
+        self.channel_queue_lengths.update(
+            &self
+                .metrics_data
+                .runtime_metrics
+                .runtime_channel_queue_length,
+            half.get_channel_id(),
+            || half.get_messages().len(),
+        );
+    }
+
+    /// Stops reporting the queue length of the destroyed channel identified by `channel_id`.
+    pub(crate) fn remove_channel_queue_length(&self, channel_id: u64) {
+        self.remove_channel_label_queue_length(channel_id);
+        self.channel_queue_lengths.remove(
+            &self
+                .metrics_data
+                .runtime_metrics
+                .runtime_channel_queue_length,
+            channel_id,
+        );
+    }
+}
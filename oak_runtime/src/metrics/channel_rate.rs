@@ -0,0 +1,179 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Per-channel message rate metrics.
+//!
+//! The rate at which messages are written to each channel is estimated as an exponentially
+//! weighted moving average over [`RATE_TIME_CONSTANT`], so that it can be read directly rather
+//! than computed from counters by the scraper. Rates are updated whenever a message is written, and
+//! decayed whenever the metrics are gathered, so that the rates of idle channels drop towards zero.
+//!
+//! As for the queue length metric (see [`super::channel_queue`]), only the first channels written
+//! to (up to a configurable limit) get their own label value; any further channels are aggregated
+//! under the [`OTHER_CHANNELS_LABEL`] label value.
+
+use super::channel_queue::OTHER_CHANNELS_LABEL;
+use crate::{ChannelHalf, Runtime};
+use prometheus::GaugeVec;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Time constant of the moving average of the message rates: the contribution of each message to
+/// the rate decays by a factor of e over this duration.
+pub const RATE_TIME_CONSTANT: Duration = Duration::from_secs(10);
+
+/// Moving average of the message rate for a label value.
+struct Rate {
+    /// Messages per second, as of `updated`.
+    value: f64,
+    updated: Instant,
+}
+
+impl Rate {
+    /// Decays the rate to account for the time elapsed until `now` without any new messages.
+    fn decay(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.value *= (-elapsed / RATE_TIME_CONSTANT.as_secs_f64()).exp();
+        self.updated = now;
+    }
+}
+
+#[derive(Default)]
+struct MessageRatesState {
+    /// Label value of each channel written to.
+    labels: HashMap<u64, String>,
+    /// Number of entries of `labels` that have their own label value.
+    labeled_count: usize,
+    /// Rate for each label value in use.
+    rates: HashMap<String, Rate>,
+}
+
+/// Tracks the message rates reported for each channel.
+#[derive(Default)]
+pub(crate) struct ChannelMessageRates {
+    /// Maximum number of channels with their own label value, or zero if the metric is disabled.
+    limit: AtomicUsize,
+    state: Mutex<MessageRatesState>,
+}
+
+impl ChannelMessageRates {
+    /// Records that a message was written to the channel identified by `channel_id` at `now`, and
+    /// reports the updated rate to `gauge`.
+    pub(crate) fn record(&self, gauge: &GaugeVec, channel_id: u64, now: Instant) {
+        let limit = self.limit.load(SeqCst);
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let labeled_count = &mut state.labeled_count;
+        let label = state.labels.entry(channel_id).or_insert_with(|| {
+            if *labeled_count < limit {
+                *labeled_count += 1;
+                channel_id.to_string()
+            } else {
+                OTHER_CHANNELS_LABEL.to_string()
+            }
+        });
+        let rate = state.rates.entry(label.clone()).or_insert(Rate {
+            value: 0.0,
+            updated: now,
+        });
+        rate.decay(now);
+        // Each message contributes an impulse that integrates to one message over time.
+        rate.value += 1.0 / RATE_TIME_CONSTANT.as_secs_f64();
+        gauge.with_label_values(&[label.as_str()]).set(rate.value);
+    }
+
+    /// Decays all the rates to account for the time elapsed until `now`, and reports them to
+    /// `gauge`.
+    pub(crate) fn refresh(&self, gauge: &GaugeVec, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        for (label, rate) in state.rates.iter_mut() {
+            rate.decay(now);
+            gauge.with_label_values(&[label.as_str()]).set(rate.value);
+        }
+    }
+
+    /// Stops reporting the message rate of the destroyed channel identified by `channel_id` to
+    /// `gauge`. The rate of aggregated channels is left to decay.
+    fn remove(&self, gauge: &GaugeVec, channel_id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(label) = state.labels.remove(&channel_id) {
+            if label != OTHER_CHANNELS_LABEL {
+                state.labeled_count -= 1;
+                state.rates.remove(&label);
+                // The label value may not exist if the metric was disabled in the meantime.
+                let _ = gauge.remove_label_values(&[&label]);
+            }
+        }
+    }
+}
+
+// Channel message rate metric related methods for the Runtime.
+impl Runtime {
+    /// Enables the `runtime_channel_message_rate` metric, giving at most `limit` channels their own
+    /// label value, or disables it if `limit` is `None`.
+    pub(crate) fn set_channel_message_rate_limit(&self, limit: Option<usize>) {
+        self.channel_message_rates
+            .limit
+            .store(limit.unwrap_or(0), SeqCst);
+    }
+
+    /// Records that a message was written to the channel that `half` refers to, if the
+    /// `runtime_channel_message_rate` metric is enabled, and in the metrics aggregated by label
+    /// bucket.
+    pub(crate) fn record_channel_message(&self, half: &ChannelHalf) {
+        self.record_channel_label_message(half);
+        if self.channel_message_rates.limit.load(SeqCst) == 0 {
+            return;
+        }
+        self.channel_message_rates.record(
+            &self
+                .metrics_data
+                .runtime_metrics
+                .runtime_channel_message_rate,
+            half.get_channel_id(),
+            Instant::now(),
+        );
+    }
+
+    /// Reports the message rates of all channels as of now, accounting for the time elapsed since
+    /// their last messages.
+    pub(crate) fn refresh_channel_message_rates(&self) {
+        self.channel_message_rates.refresh(
+            &self
+                .metrics_data
+                .runtime_metrics
+                .runtime_channel_message_rate,
+            Instant::now(),
+        );
+    }
+
+    /// Stops reporting the message rate of the destroyed channel identified by `channel_id`.
+    pub(crate) fn remove_channel_message_rate(&self, channel_id: u64) {
+        self.channel_message_rates.remove(
+            &self
+                .metrics_data
+                .runtime_metrics
+                .runtime_channel_message_rate,
+            channel_id,
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Encoding of metrics as JSON, for tools that do not support the Prometheus text format.
+//!
+//! Metric families are encoded as an array of objects, each with the `name`, `type` and `help` of
+//! the family, and its `metrics`. Each metric has its `labels` as an object, and a `value`, which
+//! is a number for counters and gauges, and an object with the `count`, `sum` and either the
+//! cumulative `buckets` or the `quantiles` for histograms and summaries respectively.
+
+use super::Metrics;
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use serde_json::{json, Map, Value};
+
+/// Content type of metrics encoded as JSON.
+pub const JSON_FORMAT: &str = "application/json";
+
+/// Returns the value of the provided metric, which belongs to a family of type `metric_type`.
+///
+/// Non-finite values (e.g. the `+Inf` bucket bound) are encoded as `null`, as JSON numbers cannot
+/// represent them.
+fn metric_value(metric_type: MetricType, metric: &Metric) -> Value {
+    match metric_type {
+        MetricType::COUNTER => json!(metric.get_counter().get_value()),
+        MetricType::GAUGE => json!(metric.get_gauge().get_value()),
+        MetricType::HISTOGRAM => {
+            let histogram = metric.get_histogram();
+            let buckets: Vec<Value> = histogram
+                .get_bucket()
+                .iter()
+                .map(|bucket| {
+                    json!({
+                        "le": bucket.get_upper_bound(),
+                        "count": bucket.get_cumulative_count(),
+                    })
+                })
+                .collect();
+            json!({
+                "count": histogram.get_sample_count(),
+                "sum": histogram.get_sample_sum(),
+                "buckets": buckets,
+            })
+        }
+        MetricType::SUMMARY => {
+            let summary = metric.get_summary();
+            let quantiles: Vec<Value> = summary
+                .get_quantile()
+                .iter()
+                .map(|quantile| {
+                    json!({
+                        "quantile": quantile.get_quantile(),
+                        "value": quantile.get_value(),
+                    })
+                })
+                .collect();
+            json!({
+                "count": summary.get_sample_count(),
+                "sum": summary.get_sample_sum(),
+                "quantiles": quantiles,
+            })
+        }
+        // Untyped metrics are not produced by the Prometheus client library.
+        MetricType::UNTYPED => Value::Null,
+    }
+}
+
+impl Metrics {
+    /// Encodes the provided metric families as a JSON array.
+    pub fn encode_json(metric_families: &[MetricFamily]) -> String {
+        let families: Vec<Value> = metric_families
+            .iter()
+            .map(|family| {
+                let type_name = match family.get_field_type() {
+                    MetricType::COUNTER => "counter",
+                    MetricType::GAUGE => "gauge",
+                    MetricType::HISTOGRAM => "histogram",
+                    MetricType::SUMMARY => "summary",
+                    MetricType::UNTYPED => "untyped",
+                };
+                let metrics: Vec<Value> = family
+                    .get_metric()
+                    .iter()
+                    .map(|metric| {
+                        let labels: Map<String, Value> = metric
+                            .get_label()
+                            .iter()
+                            .map(|label| (label.get_name().to_string(), json!(label.get_value())))
+                            .collect();
+                        json!({
+                            "labels": labels,
+                            "value": metric_value(family.get_field_type(), metric),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": family.get_name(),
+                    "type": type_name,
+                    "help": family.get_help(),
+                    "metrics": metrics,
+                })
+            })
+            .collect();
+        Value::Array(families).to_string()
+    }
+}
@@ -17,12 +17,23 @@
 //! Functionality to expose metrics from a running Runtime.
 
 use prometheus::{
-    proto::MetricFamily, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
-    Registry,
+    proto::MetricFamily, CounterVec, GaugeVec, HistogramOpts, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
 };
 
+pub(crate) mod channel_label;
+pub(crate) mod channel_queue;
+pub(crate) mod channel_rate;
+mod json;
+pub(crate) mod node_cpu_time;
+mod openmetrics;
+pub(crate) mod push;
 pub mod server;
 
+pub(crate) use channel_label::ChannelLabelBuckets;
+pub(crate) use channel_queue::ChannelQueueLengths;
+pub(crate) use channel_rate::ChannelMessageRates;
+
 /// Helper struct with functions for creating and registering metrics.
 struct MetricsBuilder {
     pub registry: Registry,
@@ -54,6 +65,24 @@ pub struct GrpcClientMetrics {
 pub struct RuntimeMetrics {
     pub runtime_nodes_by_type: IntGaugeVec,
     pub runtime_health_check: IntGauge,
+    pub runtime_stuck_nodes_total: IntCounterVec,
+    pub runtime_stuck_waiting_nodes: IntGauge,
+    pub runtime_channel_queue_length: IntGaugeVec,
+    pub runtime_channel_message_rate: GaugeVec,
+    pub runtime_node_cpu_seconds_total: CounterVec,
+    pub runtime_node_panics_total: IntCounterVec,
+    pub runtime_channel_queue_length_by_label: IntGaugeVec,
+    pub runtime_channel_messages_by_label_total: IntCounterVec,
+    pub runtime_forced_shutdowns_total: IntCounter,
+    pub runtime_messages_expired_total: IntCounter,
+    pub runtime_downgrades_total: IntCounter,
+    pub runtime_permission_denied_total: IntCounterVec,
+    pub runtime_wasm_nodes_out_of_fuel_total: IntCounter,
+    pub runtime_node_creations_throttled_total: IntCounter,
+    pub runtime_channel_park_total: IntCounter,
+    pub runtime_channel_spurious_wakeups_total: IntCounter,
+    pub runtime_channel_messages_total: IntCounterVec,
+    pub runtime_channel_bytes_total: IntCounterVec,
 }
 
 /// Struct that collects all the metrics in one place
@@ -78,11 +107,21 @@ impl MetricsBuilder {
     }
 }
 
+fn int_counter(metric_name: &str, help: &str) -> IntCounter {
+    let opts = Opts::new(metric_name, help);
+    IntCounter::with_opts(opts).unwrap()
+}
+
 fn counter_vec(metric_name: &str, labels: &[&str], help: &str) -> IntCounterVec {
     let opts = Opts::new(metric_name, help);
     IntCounterVec::new(opts, labels).unwrap()
 }
 
+fn float_counter_vec(metric_name: &str, labels: &[&str], help: &str) -> CounterVec {
+    let opts = Opts::new(metric_name, help);
+    CounterVec::new(opts, labels).unwrap()
+}
+
 fn histogram_vec(metric_name: &str, labels: &[&str], help: &str) -> HistogramVec {
     let opts = HistogramOpts::new(metric_name, help);
     HistogramVec::new(opts, labels).unwrap()
@@ -98,6 +137,11 @@ fn int_gauge_vec(metric_name: &str, labels: &[&str], help: &str) -> IntGaugeVec
     IntGaugeVec::new(opts, labels).unwrap()
 }
 
+fn gauge_vec(metric_name: &str, labels: &[&str], help: &str) -> GaugeVec {
+    let opts = Opts::new(metric_name, help);
+    GaugeVec::new(opts, labels).unwrap()
+}
+
 impl GrpcServerMetrics {
     fn new(builder: &MetricsBuilder) -> Self {
         GrpcServerMetrics {
@@ -215,6 +259,88 @@ impl RuntimeMetrics {
                 "runtime_health_check",
                 "Health indicator for the runtime.",
             )),
+            runtime_stuck_nodes_total: builder.register(counter_vec(
+                "runtime_stuck_nodes_total",
+                &["node_type"],
+                "Number of times nodes were considered stuck for not sending heartbeats, by node type.",
+            )),
+            runtime_stuck_waiting_nodes: builder.register(int_gauge(
+                "runtime_stuck_waiting_nodes",
+                "Number of nodes waiting on channels for longer than the watchdog threshold, if enabled.",
+            )),
+            runtime_channel_queue_length: builder.register(int_gauge_vec(
+                "runtime_channel_queue_length",
+                &["channel"],
+                "Number of messages waiting to be read, by channel ID, if enabled.",
+            )),
+            runtime_channel_message_rate: builder.register(gauge_vec(
+                "runtime_channel_message_rate",
+                &["channel"],
+                "Moving average of the number of messages written per second, by channel ID, if enabled.",
+            )),
+            runtime_node_cpu_seconds_total: builder.register(float_counter_vec(
+                "runtime_node_cpu_seconds_total",
+                &["node_type", "node_name"],
+                "CPU time spent by the main thread of terminated nodes, by node type and name.",
+            )),
+            runtime_node_panics_total: builder.register(counter_vec(
+                "runtime_node_panics_total",
+                &["node_type"],
+                "Number of nodes whose main thread panicked, by node type.",
+            )),
+            runtime_channel_queue_length_by_label: builder.register(int_gauge_vec(
+                "runtime_channel_queue_length_by_label",
+                &["label_bucket"],
+                "Number of messages waiting to be read, by configured channel label bucket.",
+            )),
+            runtime_channel_messages_by_label_total: builder.register(counter_vec(
+                "runtime_channel_messages_by_label_total",
+                &["label_bucket"],
+                "Number of messages written to channels, by configured channel label bucket.",
+            )),
+            runtime_forced_shutdowns_total: builder.register(int_counter(
+                "runtime_forced_shutdowns_total",
+                "Number of times the runtime stopped without all node threads terminating.",
+            )),
+            runtime_messages_expired_total: builder.register(int_counter(
+                "runtime_messages_expired_total",
+                "Number of messages skipped by readers because their TTL had elapsed.",
+            )),
+            runtime_downgrades_total: builder.register(int_counter(
+                "runtime_downgrades_total",
+                "Number of times a node used its privilege to downgrade a label.",
+            )),
+            runtime_permission_denied_total: builder.register(counter_vec(
+                "runtime_permission_denied_total",
+                &["direction"],
+                "Number of times a node was not allowed to read or write because of labels.",
+            )),
+            runtime_wasm_nodes_out_of_fuel_total: builder.register(int_counter(
+                "runtime_wasm_nodes_out_of_fuel_total",
+                "Number of WebAssembly nodes trapped for exhausting their fuel.",
+            )),
+            runtime_node_creations_throttled_total: builder.register(int_counter(
+                "runtime_node_creations_throttled_total",
+                "Number of node creations rejected because the creating node exceeded its rate limit.",
+            )),
+            runtime_channel_park_total: builder.register(int_counter(
+                "runtime_channel_park_total",
+                "Number of times a node thread blocked waiting on channels.",
+            )),
+            runtime_channel_spurious_wakeups_total: builder.register(int_counter(
+                "runtime_channel_spurious_wakeups_total",
+                "Number of times a node thread waiting on channels woke up with none of them ready.",
+            )),
+            runtime_channel_messages_total: builder.register(counter_vec(
+                "runtime_channel_messages_total",
+                &["direction"],
+                "Number of messages enqueued to or dequeued from channels, by direction.",
+            )),
+            runtime_channel_bytes_total: builder.register(counter_vec(
+                "runtime_channel_bytes_total",
+                &["direction"],
+                "Number of bytes of messages enqueued to or dequeued from channels, by direction.",
+            )),
         }
     }
 }
@@ -0,0 +1,103 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Per-Node CPU time metric.
+//!
+//! The CPU time spent by the main thread of each Node is measured from within the thread, around
+//! the execution of the Node, and added to the `runtime_node_cpu_seconds_total` metric once the
+//! Node is removed. On platforms where the CPU time of a thread is not available, the wall-clock
+//! time is used instead.
+
+use crate::{NodeId, Runtime};
+use std::time::{Duration, Instant};
+
+/// Returns the CPU time consumed so far by the calling thread.
+#[cfg(target_os = "linux")]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // Safety: `clock_gettime` only writes to the provided `timespec`, which is valid for writes.
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+    if result == 0 {
+        Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    } else {
+        None
+    }
+}
+
+/// The CPU time of a thread is not available on this platform.
+#[cfg(not(target_os = "linux"))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Measures the CPU time spent by the thread it was started on, falling back to the wall-clock
+/// time if the CPU time of the thread is not available.
+pub(crate) struct ThreadCpuTimer {
+    cpu_start: Option<Duration>,
+    wall_start: Instant,
+}
+
+impl ThreadCpuTimer {
+    /// Starts measuring the CPU time of the calling thread.
+    pub(crate) fn start() -> Self {
+        Self {
+            cpu_start: thread_cpu_time(),
+            wall_start: Instant::now(),
+        }
+    }
+
+    /// Returns the time elapsed since the timer was started. Must be called on the same thread as
+    /// [`ThreadCpuTimer::start`].
+    pub(crate) fn elapsed(&self) -> Duration {
+        match (self.cpu_start, thread_cpu_time()) {
+            (Some(start), Some(end)) => end.saturating_sub(start),
+            _ => self.wall_start.elapsed(),
+        }
+    }
+}
+
+// Node CPU time metric related methods for the Runtime.
+impl Runtime {
+    /// Records that the main thread of the Node identified by `node_id` has spent `cpu_time`,
+    /// to be reported when the Node is removed.
+    pub(crate) fn record_node_cpu_time(&self, node_id: NodeId, cpu_time: Duration) {
+        let mut node_infos = self.node_infos.write().unwrap();
+        if let Some(node_info) = node_infos.get_mut(&node_id) {
+            node_info.cpu_time += cpu_time;
+        }
+    }
+
+    /// Adds the CPU time spent by a removed Node to the `runtime_node_cpu_seconds_total` metric.
+    pub(crate) fn report_node_cpu_time(
+        &self,
+        node_type: &str,
+        node_name: &str,
+        cpu_time: Duration,
+    ) {
+        // Nodes without a thread (e.g. registered directly in tests) do not get a label value.
+        if cpu_time == Duration::from_secs(0) {
+            return;
+        }
+        self.metrics_data
+            .runtime_metrics
+            .runtime_node_cpu_seconds_total
+            .with_label_values(&[node_type, node_name])
+            .inc_by(cpu_time.as_secs_f64());
+    }
+}
@@ -0,0 +1,209 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Encoding of metrics in the [OpenMetrics] text exposition format (version 1.0).
+//!
+//! [OpenMetrics]: https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+
+use super::Metrics;
+use prometheus::proto::{LabelPair, Metric, MetricFamily, MetricType};
+use std::fmt::Write;
+
+/// Content type of metrics encoded in the OpenMetrics text format.
+pub const OPENMETRICS_FORMAT: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Metric name suffixes that denote a unit, and the corresponding units.
+const UNIT_SUFFIXES: &[(&str, &str)] = &[("_seconds", "seconds"), ("_bytes", "bytes")];
+
+/// Escapes a label value or help text, as required by the OpenMetrics text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Formats a sample value or bucket bound.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else if value.fract() == 0.0 {
+        // Use the canonical representation of integral floats (e.g. "1.0"), which is required
+        // for bucket bounds and quantiles.
+        format!("{:.1}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes a single sample of the provided metric, with an additional label if provided.
+fn write_sample(
+    s: &mut String,
+    name: &str,
+    labels: &[LabelPair],
+    extra_label: Option<(&str, f64)>,
+    value: f64,
+) {
+    let mut label_strings: Vec<String> = labels
+        .iter()
+        .map(|label| format!("{}=\"{}\"", label.get_name(), escape(label.get_value())))
+        .collect();
+    if let Some((label_name, label_value)) = extra_label {
+        label_strings.push(format!("{}=\"{}\"", label_name, format_value(label_value)));
+    }
+    if label_strings.is_empty() {
+        writeln!(s, "{} {}", name, format_value(value)).unwrap();
+    } else {
+        writeln!(
+            s,
+            "{}{{{}}} {}",
+            name,
+            label_strings.join(","),
+            format_value(value)
+        )
+        .unwrap();
+    }
+}
+
+/// Writes the samples of a histogram metric, including the mandatory `+Inf` bucket.
+fn write_histogram(s: &mut String, name: &str, metric: &Metric) {
+    let histogram = metric.get_histogram();
+    let labels = metric.get_label();
+    let mut has_inf_bucket = false;
+    for bucket in histogram.get_bucket() {
+        has_inf_bucket = bucket.get_upper_bound().is_infinite();
+        write_sample(
+            s,
+            &format!("{}_bucket", name),
+            labels,
+            Some(("le", bucket.get_upper_bound())),
+            bucket.get_cumulative_count() as f64,
+        );
+    }
+    if !has_inf_bucket {
+        write_sample(
+            s,
+            &format!("{}_bucket", name),
+            labels,
+            Some(("le", f64::INFINITY)),
+            histogram.get_sample_count() as f64,
+        );
+    }
+    write_sample(
+        s,
+        &format!("{}_count", name),
+        labels,
+        None,
+        histogram.get_sample_count() as f64,
+    );
+    write_sample(
+        s,
+        &format!("{}_sum", name),
+        labels,
+        None,
+        histogram.get_sample_sum(),
+    );
+}
+
+/// Writes the samples of a summary metric.
+fn write_summary(s: &mut String, name: &str, metric: &Metric) {
+    let summary = metric.get_summary();
+    let labels = metric.get_label();
+    for quantile in summary.get_quantile() {
+        write_sample(
+            s,
+            name,
+            labels,
+            Some(("quantile", quantile.get_quantile())),
+            quantile.get_value(),
+        );
+    }
+    write_sample(
+        s,
+        &format!("{}_count", name),
+        labels,
+        None,
+        summary.get_sample_count() as f64,
+    );
+    write_sample(
+        s,
+        &format!("{}_sum", name),
+        labels,
+        None,
+        summary.get_sample_sum(),
+    );
+}
+
+impl Metrics {
+    /// Encodes the provided metric families in the OpenMetrics text format.
+    ///
+    /// Existing metric names are preserved as sample names, except that counter samples gain the
+    /// `_total` suffix if they do not already have it, as required by OpenMetrics. The name of a
+    /// counter family is the name of its samples without that suffix. Units are derived from
+    /// the `_seconds` and `_bytes` name suffixes.
+    pub fn encode_openmetrics(metric_families: &[MetricFamily]) -> String {
+        let mut s = String::new();
+        for family in metric_families {
+            let sample_name = family.get_name();
+            let (family_name, type_name) = match family.get_field_type() {
+                MetricType::COUNTER => (
+                    sample_name.strip_suffix("_total").unwrap_or(sample_name),
+                    "counter",
+                ),
+                MetricType::GAUGE => (sample_name, "gauge"),
+                MetricType::HISTOGRAM => (sample_name, "histogram"),
+                MetricType::SUMMARY => (sample_name, "summary"),
+                MetricType::UNTYPED => (sample_name, "unknown"),
+            };
+            writeln!(s, "# TYPE {} {}", family_name, type_name).unwrap();
+            if let Some((_, unit)) = UNIT_SUFFIXES
+                .iter()
+                .find(|(suffix, _)| family_name.ends_with(suffix))
+            {
+                writeln!(s, "# UNIT {} {}", family_name, unit).unwrap();
+            }
+            if !family.get_help().is_empty() {
+                writeln!(s, "# HELP {} {}", family_name, escape(family.get_help())).unwrap();
+            }
+            for metric in family.get_metric() {
+                match family.get_field_type() {
+                    MetricType::COUNTER => write_sample(
+                        &mut s,
+                        &format!("{}_total", family_name),
+                        metric.get_label(),
+                        None,
+                        metric.get_counter().get_value(),
+                    ),
+                    MetricType::GAUGE => write_sample(
+                        &mut s,
+                        sample_name,
+                        metric.get_label(),
+                        None,
+                        metric.get_gauge().get_value(),
+                    ),
+                    MetricType::HISTOGRAM => write_histogram(&mut s, sample_name, metric),
+                    MetricType::SUMMARY => write_summary(&mut s, sample_name, metric),
+                    // Untyped metrics are not produced by the Prometheus client library.
+                    MetricType::UNTYPED => {}
+                }
+            }
+        }
+        s.push_str("# EOF\n");
+        s
+    }
+}
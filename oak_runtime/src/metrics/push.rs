@@ -0,0 +1,217 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Periodic push of metrics to a StatsD server, for deployments in which the metrics server cannot
+//! be scraped.
+//!
+//! Counters and gauges are sent using the [DogStatsD] line protocol, with Prometheus labels
+//! translated into tags. Since StatsD counters are increments, each counter is sent as the
+//! difference between its current value and its value at the previous flush. Histograms and
+//! summaries are not sent.
+//!
+//! [DogStatsD]: https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/
+
+use crate::Runtime;
+use log::{info, warn};
+use prometheus::proto::{LabelPair, MetricFamily, MetricType};
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// Maximum size of the UDP datagrams sent to the StatsD server, chosen to avoid fragmentation on
+/// common networks.
+const MAX_DATAGRAM_SIZE: usize = 1432;
+
+/// Maximum time between two checks for termination of the push thread.
+const TERMINATION_CHECK_PERIOD: Duration = Duration::from_millis(100);
+
+/// Configuration options for pushing metrics to a StatsD server.
+#[derive(Clone, Debug)]
+pub struct MetricsPushConfiguration {
+    /// UDP address of the StatsD server.
+    pub address: SocketAddr,
+    /// Interval between two pushes of the metrics.
+    pub flush_interval: Duration,
+    /// Prefix prepended to the name of each metric, separated by a dot, if not empty.
+    pub prefix: String,
+}
+
+/// Formats a metric value, omitting the fractional part of integral values.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Translates Prometheus labels into DogStatsD tags, replacing the characters that are reserved in
+/// the line protocol.
+fn format_tags(labels: &[LabelPair]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let sanitize = |s: &str| s.replace([',', '|', '#', ':', '\n'], "_");
+    let tags: Vec<String> = labels
+        .iter()
+        .map(|label| {
+            format!(
+                "{}:{}",
+                sanitize(label.get_name()),
+                sanitize(label.get_value())
+            )
+        })
+        .collect();
+    format!("|#{}", tags.join(","))
+}
+
+/// Encodes metrics in the StatsD line protocol, keeping track of the counter values sent so far.
+pub(crate) struct StatsdEncoder {
+    prefix: String,
+    /// Value of each counter at the previous flush, keyed by its name and tags.
+    counter_values: HashMap<String, f64>,
+}
+
+impl StatsdEncoder {
+    pub(crate) fn new(prefix: &str) -> Self {
+        StatsdEncoder {
+            prefix: prefix.to_string(),
+            counter_values: HashMap::new(),
+        }
+    }
+
+    /// Returns the lines describing the provided metric families. Counters that have not changed
+    /// since the previous call are omitted.
+    pub(crate) fn encode(&mut self, metric_families: &[MetricFamily]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for family in metric_families {
+            let name = if self.prefix.is_empty() {
+                family.get_name().to_string()
+            } else {
+                format!("{}.{}", self.prefix, family.get_name())
+            };
+            for metric in family.get_metric() {
+                let tags = format_tags(metric.get_label());
+                match family.get_field_type() {
+                    MetricType::COUNTER => {
+                        let value = metric.get_counter().get_value();
+                        let previous = self
+                            .counter_values
+                            .insert(format!("{}{}", name, tags), value)
+                            .unwrap_or_default();
+                        // A counter lower than previously sent has been reset, so all of its
+                        // current value is new.
+                        let delta = if value >= previous {
+                            value - previous
+                        } else {
+                            value
+                        };
+                        if delta > 0.0 {
+                            lines.push(format!("{}:{}|c{}", name, format_value(delta), tags));
+                        }
+                    }
+                    MetricType::GAUGE => {
+                        let value = metric.get_gauge().get_value();
+                        // A signed gauge value is interpreted as a change to the current value,
+                        // so a negative value must be preceded by a reset to zero.
+                        if value < 0.0 {
+                            lines.push(format!("{}:0|g{}", name, tags));
+                        }
+                        lines.push(format!("{}:{}|g{}", name, format_value(value), tags));
+                    }
+                    MetricType::HISTOGRAM | MetricType::SUMMARY | MetricType::UNTYPED => {}
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Groups lines into newline-separated datagram payloads no larger than [`MAX_DATAGRAM_SIZE`],
+/// unless a single line is larger.
+fn datagrams(lines: &[String]) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > MAX_DATAGRAM_SIZE {
+            datagrams.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        datagrams.push(current);
+    }
+    datagrams
+}
+
+/// Sends the current metrics of the Runtime to the StatsD server.
+fn flush(runtime: &Runtime, encoder: &mut StatsdEncoder, socket: &UdpSocket, address: &SocketAddr) {
+    let lines = encoder.encode(&runtime.gather_metrics());
+    for datagram in datagrams(&lines) {
+        if let Err(err) = socket.send_to(datagram.as_bytes(), address) {
+            warn!("failed to push metrics to {}: {}", address, err);
+        }
+    }
+}
+
+/// Periodically pushes the metrics of the `runtime` to a StatsD server, until notified on
+/// `termination_notification_receiver`. The metrics are pushed a final time before returning.
+pub(crate) fn push_metrics(
+    config: MetricsPushConfiguration,
+    runtime: Arc<Runtime>,
+    mut termination_notification_receiver: oneshot::Receiver<()>,
+) {
+    let local_address: SocketAddr = if config.address.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = match UdpSocket::bind(local_address) {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("failed to bind socket to push metrics: {}", err);
+            return;
+        }
+    };
+    info!(
+        "pushing metrics to {} every {:?}",
+        config.address, config.flush_interval
+    );
+    let mut encoder = StatsdEncoder::new(&config.prefix);
+    let mut next_flush = Instant::now() + config.flush_interval;
+    while let Err(oneshot::error::TryRecvError::Empty) =
+        termination_notification_receiver.try_recv()
+    {
+        let now = Instant::now();
+        if now >= next_flush {
+            flush(&runtime, &mut encoder, &socket, &config.address);
+            next_flush = now + config.flush_interval;
+        }
+        thread::sleep(std::cmp::min(
+            next_flush.saturating_duration_since(Instant::now()),
+            TERMINATION_CHECK_PERIOD,
+        ));
+    }
+    flush(&runtime, &mut encoder, &socket, &config.address);
+}
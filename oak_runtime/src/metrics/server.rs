@@ -14,14 +14,15 @@
 // limitations under the License.
 //
 
-use crate::Runtime;
-use http::{method::Method, StatusCode};
+use super::{json::JSON_FORMAT, openmetrics::OPENMETRICS_FORMAT, Metrics};
+use crate::{tls::TlsConfig, Runtime};
+use http::{method::Method, HeaderMap, StatusCode};
 use hyper::{
-    header::CONTENT_TYPE,
+    header::{ACCEPT, CONTENT_TYPE},
     service::{make_service_fn, service_fn},
     Body, Request, Response, Server,
 };
-use log::info;
+use log::{error, info};
 use prometheus::{Encoder, TextEncoder};
 use std::{net::SocketAddr, sync::Arc};
 
@@ -42,31 +43,94 @@ impl std::fmt::Display for MetricsServerError {
 
 impl std::error::Error for MetricsServerError {}
 
-async fn handle_metrics_request(runtime: &Runtime) -> Result<Response<Body>, MetricsServerError> {
+/// Returns whether the `Accept` header of a request asks for the OpenMetrics text format, rather
+/// than the Prometheus text format.
+fn accepts_openmetrics(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_range| {
+            let mut params = media_range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default();
+            // A zero quality value means that the client does not accept the media type.
+            media_type.eq_ignore_ascii_case("application/openmetrics-text")
+                && !params.any(|param| matches!(param, "q=0" | "q=0.0" | "q=0.00" | "q=0.000"))
+        })
+}
+
+async fn handle_metrics_request(
+    runtime: &Runtime,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, MetricsServerError> {
     let encoder = TextEncoder::new();
     let metric_families = runtime.gather_metrics();
-    let mut buffer = vec![];
-    encoder.encode(&metric_families, &mut buffer).map_err(|e| {
-        MetricsServerError::EncodingError(format!("Could not encode metrics data: {}", e))
-    })?;
+    let (buffer, format_type) = if accepts_openmetrics(headers) {
+        (
+            Metrics::encode_openmetrics(&metric_families).into_bytes(),
+            OPENMETRICS_FORMAT,
+        )
+    } else {
+        let mut buffer = vec![];
+        encoder.encode(&metric_families, &mut buffer).map_err(|e| {
+            MetricsServerError::EncodingError(format!("Could not encode metrics data: {}", e))
+        })?;
+        (buffer, encoder.format_type())
+    };
 
     info!("Metrics size: {}", buffer.len());
 
     Response::builder()
         .status(StatusCode::OK)
-        .header(CONTENT_TYPE, encoder.format_type())
+        .header(CONTENT_TYPE, format_type)
         .body(Body::from(buffer))
         .map_err(|e| {
             MetricsServerError::ResponseError(format!("Could not build the response: {}", e))
         })
 }
 
+/// Responds with the metrics encoded as JSON, rather than in a Prometheus text format.
+fn handle_metrics_json_request(runtime: &Runtime) -> Result<Response<Body>, MetricsServerError> {
+    let buffer = Metrics::encode_json(&runtime.gather_metrics());
+
+    info!("Metrics size: {}", buffer.len());
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, JSON_FORMAT)
+        .body(Body::from(buffer))
+        .map_err(|e| {
+            MetricsServerError::ResponseError(format!("Could not build the response: {}", e))
+        })
+}
+
+/// Responds with whether the Runtime is healthy, for use by liveness and readiness probes.
+///
+/// The Runtime is healthy while it is not terminating and its implicit initial Node exists.
+fn handle_healthz_request(runtime: &Runtime) -> Result<Response<Body>, MetricsServerError> {
+    let healthy = !runtime.is_terminating() && runtime.node_count(Some("implicit")) > 0;
+    let (status, body) = if healthy {
+        (StatusCode::OK, "OK\n")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "Unavailable\n")
+    };
+    Response::builder()
+        .status(status)
+        .body(Body::from(body))
+        .map_err(|e| {
+            MetricsServerError::ResponseError(format!("Could not build the response: {}", e))
+        })
+}
+
 async fn serve_metrics(
     runtime: Arc<Runtime>,
     req: Request<Body>,
 ) -> Result<Response<Body>, MetricsServerError> {
     match (req.method(), req.uri().path()) {
-        (&Method::GET, "/metrics") => handle_metrics_request(&runtime).await,
+        (&Method::GET, "/metrics") => handle_metrics_request(&runtime, req.headers()).await,
+        (&Method::GET, "/metrics.json") => handle_metrics_json_request(&runtime),
+        (&Method::GET, "/healthz") => handle_healthz_request(&runtime),
         _ => Ok(Response::builder()
             .status(StatusCode::NOT_FOUND)
             .body(Body::from("Not Found!\n"))
@@ -75,12 +139,12 @@ async fn serve_metrics(
 }
 
 async fn make_server(
-    port: u16,
+    addr: SocketAddr,
+    tls_config: Option<TlsConfig>,
     runtime: Arc<Runtime>,
     termination_notificiation_receiver: tokio::sync::oneshot::Receiver<()>,
+    bound_notification_sender: std::sync::mpsc::Sender<()>,
 ) {
-    let addr = SocketAddr::from((std::net::Ipv6Addr::UNSPECIFIED, port));
-
     // A `Service` is needed for every connection, so this
     // creates one from the `serve_metrics` function.
     let make_service = make_service_fn(move |_conn| {
@@ -93,15 +157,23 @@ async fn make_server(
         }
     });
 
-    let server = Server::bind(&addr).serve(make_service);
+    let server = match crate::aux_server::bind(addr, tls_config).await {
+        Ok(incoming) => Server::builder(incoming).serve(make_service),
+        Err(err) => {
+            error!("could not bind metrics server to {}: {}", addr, err);
+            return;
+        }
+    };
+    // The Runtime may not be waiting for the notification, so ignore errors when sending it.
+    let _ = bound_notification_sender.send(());
     let graceful = server.with_graceful_shutdown(async {
         // Treat notification failure the same as a notification.
         let _ = termination_notificiation_receiver.await;
     });
     info!(
-        "{:?}: Started metrics server on port {:?}",
+        "{:?}: Started metrics server on {:?}",
         std::thread::current().id(),
-        port
+        addr
     );
 
     // Run until asked to terminate...
@@ -109,17 +181,22 @@ async fn make_server(
     info!("metrics server terminated with {:?}", result);
 }
 
-// Start running a metrics server on the given port, running until the
-// `termination_notificiation_receiver` is triggered.
+// Start running a metrics server on the given address, over TLS if `tls_config` is
+// provided, running until the `termination_notificiation_receiver` is triggered. A notification
+// is sent on `bound_notification_sender` once the server has bound to the address.
 pub fn start_metrics_server(
-    port: u16,
+    addr: SocketAddr,
+    tls_config: Option<TlsConfig>,
     runtime: Arc<Runtime>,
     termination_notificiation_receiver: tokio::sync::oneshot::Receiver<()>,
+    bound_notification_sender: std::sync::mpsc::Sender<()>,
 ) {
     let tokio_runtime = tokio::runtime::Runtime::new().expect("Couldn't create Tokio runtime");
     tokio_runtime.block_on(make_server(
-        port,
+        addr,
+        tls_config,
         runtime,
         termination_notificiation_receiver,
+        bound_notification_sender,
     ));
 }
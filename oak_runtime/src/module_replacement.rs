@@ -0,0 +1,190 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Replacement of WebAssembly modules while the Runtime is running.
+//!
+//! Replacing a module only affects the Wasm Nodes created afterwards: Nodes already running the
+//! previous version of the module keep running it until they exit, unless they are respawned with
+//! [`Runtime::respawn_wasm_module_nodes`].
+
+use crate::{
+    node::NodeFactory,
+    proto::oak::introspection_events::{event::EventDetails, Direction, HandleDestroyed},
+    ChannelHalf, ChannelHalfDirection, NodeCapacity, NodeId, Runtime,
+};
+use log::{info, warn};
+use oak_abi::{
+    proto::oak::application::{node_configuration::ConfigType, NodeConfiguration},
+    OakStatus,
+};
+use oak_sign::SignatureBundle;
+use std::sync::Arc;
+
+/// Returns whether `configuration` is that of a Wasm Node running the module named `module_name`.
+fn runs_wasm_module(configuration: &Option<NodeConfiguration>, module_name: &str) -> bool {
+    matches!(
+        configuration.as_ref().and_then(|configuration| configuration.config_type.as_ref()),
+        Some(ConfigType::WasmConfig(config)) if config.wasm_module_name == module_name
+    )
+}
+
+// WebAssembly module replacement related methods for the Runtime.
+impl Runtime {
+    /// Replaces the bytes of the WebAssembly module named `module_name` of the application with
+    /// `wasm_module_bytes`, so that Wasm Nodes created from now on run the new module.
+    ///
+    /// The new module is subject to the same checks as the modules provided at startup: the
+    /// provided `signatures` must be valid signatures of the new module, and they are added to the
+    /// signature table, which must allow the new module to run. The new module must also export
+    /// valid entrypoints for the initial Node and for the live Nodes running the previous version
+    /// of the module, so that they can be respawned. Fails with [`OakStatus::ErrInvalidArgs`],
+    /// leaving the module unchanged, if any check fails or if there is no module with this name.
+    pub fn replace_wasm_module(
+        &self,
+        module_name: &str,
+        wasm_module_bytes: Vec<u8>,
+        signatures: &[SignatureBundle],
+    ) -> Result<(), OakStatus> {
+        let entrypoint_names = self
+            .node_infos
+            .read()
+            .unwrap()
+            .values()
+            .filter_map(|node_info| {
+                match node_info.configuration.as_ref()?.config_type.as_ref()? {
+                    ConfigType::WasmConfig(config) if config.wasm_module_name == module_name => {
+                        Some(config.wasm_entrypoint_name.clone())
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        self.node_factory
+            .replace_wasm_module(module_name, wasm_module_bytes, signatures, entrypoint_names)
+            .map_err(|err| {
+                warn!("could not replace Wasm module {:?}: {}", module_name, err);
+                OakStatus::ErrInvalidArgs
+            })?;
+        info!("replaced Wasm module {:?}", module_name);
+        Ok(())
+    }
+
+    /// Respawns the live Wasm Nodes running the module named `module_name`, so that they run the
+    /// current version of the module, e.g. after it has been replaced with
+    /// [`Runtime::replace_wasm_module`].
+    ///
+    /// Each Node is replaced by a new Node created from the same configuration, with the same name
+    /// and label, which takes over the initial channel of the previous Node: the read half of the
+    /// channel is moved from the previous Node to the new Node, so that messages queued on it are
+    /// read by the new Node, and the previous Node is notified to terminate. Nodes that have closed
+    /// their initial handle, or that were not created from a configuration, are left unchanged.
+    ///
+    /// Fails with [`OakStatus::ErrInvalidArgs`] if a new Node cannot be created from its
+    /// configuration, in which case the Nodes already respawned are kept.
+    ///
+    /// Returns the [`NodeId`]s of the new Nodes.
+    pub fn respawn_wasm_module_nodes(
+        self: &Arc<Self>,
+        module_name: &str,
+    ) -> Result<Vec<NodeId>, OakStatus> {
+        if self.is_terminating() {
+            return Err(OakStatus::ErrTerminated);
+        }
+        let nodes: Vec<_> = self
+            .node_infos
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, node_info)| {
+                !node_info.transferred && runs_wasm_module(&node_info.configuration, module_name)
+            })
+            .filter_map(|(node_id, node_info)| {
+                Some((
+                    *node_id,
+                    node_info.name.clone(),
+                    node_info.label.clone(),
+                    node_info.configuration.clone()?,
+                    node_info.creatable_node_types.clone(),
+                ))
+            })
+            .collect();
+
+        let mut new_node_ids = Vec::new();
+        for (node_id, name, label, configuration, creatable_node_types) in nodes {
+            let instance = self
+                .node_factory
+                .create_node(&name, &configuration)
+                .map_err(|err| {
+                    warn!("could not respawn node {:?}: {:?}", name, err);
+                    OakStatus::ErrInvalidArgs
+                })?;
+            let reader = match self.take_initial_half(node_id) {
+                Some(reader) => reader,
+                None => continue,
+            };
+            let new_node_id = self.clone().node_start(
+                node_id,
+                instance,
+                &name,
+                &label,
+                reader,
+                creatable_node_types,
+                Some(configuration),
+                // The new Node replaces the previous Node, which is notified to terminate, so it
+                // is not subject to the limit of live Nodes.
+                NodeCapacity::Limit(usize::MAX),
+            )?;
+            info!(
+                "{:?}: respawned as {:?}",
+                self.get_node_debug_id(node_id),
+                self.get_node_debug_id(new_node_id)
+            );
+            new_node_ids.push(new_node_id);
+        }
+        Ok(new_node_ids)
+    }
+
+    /// Removes the handle to the initial channel of the Node identified by `node_id` from its
+    /// handle table, notifies the Node to terminate, and returns the read half of the channel, or
+    /// `None` if the Node no longer holds its initial handle.
+    fn take_initial_half(&self, node_id: NodeId) -> Option<ChannelHalf> {
+        let mut node_infos = self.node_infos.write().unwrap();
+        let node_info = node_infos.get_mut(&node_id)?;
+        let (handle, channel_id) = node_info.initial_handle?;
+        match node_info.abi_handles.get(&handle) {
+            Some(half)
+                if half.direction == ChannelHalfDirection::Read
+                    && half.get_channel_id() == channel_id => {}
+            _ => return None,
+        }
+        let half = node_info.abi_handles.remove(&handle)?;
+        if !node_info.abi_handles.values().any(|other| {
+            other.direction == ChannelHalfDirection::Read && other.get_channel_id() == channel_id
+        }) {
+            half.remove_consumer(node_id);
+        }
+        if let Some(node_stopper) = node_info.node_stopper.as_mut() {
+            node_stopper.notify(node_id);
+        }
+        self.introspection_event(EventDetails::HandleDestroyed(HandleDestroyed {
+            node_id: node_id.0,
+            handle,
+            channel_id,
+            direction: Direction::Read as i32,
+        }));
+        Some(half)
+    }
+}
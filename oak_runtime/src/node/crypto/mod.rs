@@ -18,6 +18,7 @@
 
 use crate::{
     io::{Receiver, ReceiverExt},
+    kms_credentials::KmsCredentials,
     node::invocation::InvocationExt,
     proto::oak::invocation::GrpcInvocation as Invocation,
     RuntimeProxy,
@@ -29,7 +30,7 @@ use oak_services::proto::{
     google::{rpc, rpc::Code},
     oak::encap::GrpcResponse,
 };
-use std::string::String;
+use std::{string::String, sync::Arc};
 use tokio::sync::oneshot;
 
 mod tinkwrap;
@@ -78,7 +79,7 @@ pub struct CryptoNodeServer {
 
 impl CryptoNodeServer {
     /// Creates a new [`CryptoNode`] instance, but does not start it.
-    pub fn new(node_name: &str, kms_credentials: Option<std::path::PathBuf>) -> Self {
+    pub fn new(node_name: &str, kms_credentials: Arc<KmsCredentials>) -> Self {
         Self {
             node_name: node_name.to_string(),
             tink: tinkwrap::TinkWrapper::new(kms_credentials),
@@ -158,12 +159,12 @@ impl CryptoNodeServer {
 /// Cryptographic pseudo-Node.
 pub struct CryptoNode {
     node_name: String,
-    kms_credentials: Option<std::path::PathBuf>,
+    kms_credentials: Arc<KmsCredentials>,
 }
 
 impl CryptoNode {
     /// Creates a new [`CryptoNode`] instance, but does not start it.
-    pub fn new(node_name: &str, kms_credentials: Option<std::path::PathBuf>) -> Self {
+    pub fn new(node_name: &str, kms_credentials: Arc<KmsCredentials>) -> Self {
         Self {
             node_name: node_name.to_string(),
             kms_credentials,
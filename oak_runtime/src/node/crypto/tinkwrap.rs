@@ -15,14 +15,17 @@
 //
 
 use super::rpc_status;
-use log::debug;
+use crate::kms_credentials::KmsCredentials;
 use oak_services::proto::{
     google::{rpc, rpc::Code},
     oak::{crypto, crypto::keyset_generate_request::TemplateId},
 };
 use prost::Message;
 use rand::RngCore;
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
 
 /// Helper to convert a [`tink_core::TinkError`] to an [`rpc::Status`].
 fn tinkerr(e: tink_core::TinkError) -> rpc::Status {
@@ -43,11 +46,11 @@ enum Keyset {
 /// objects that can perform cryptographic operations.
 pub struct TinkWrapper {
     handles: HashMap<u64, Keyset>,
-    kms_credentials: Option<std::path::PathBuf>,
+    kms_credentials: Arc<KmsCredentials>,
 }
 
 impl TinkWrapper {
-    pub fn new(kms_credentials: Option<std::path::PathBuf>) -> TinkWrapper {
+    pub fn new(kms_credentials: Arc<KmsCredentials>) -> TinkWrapper {
         tink_aead::init();
         tink_daead::init();
         tink_mac::init();
@@ -64,8 +67,8 @@ impl TinkWrapper {
     fn register_keyset(&mut self, k: Keyset) -> u64 {
         loop {
             let candidate = rand::thread_rng().next_u64();
-            if self.handles.get(&candidate).is_none() {
-                self.handles.insert(candidate, k);
+            if let Entry::Vacant(entry) = self.handles.entry(candidate) {
+                entry.insert(k);
                 return candidate;
             }
         }
@@ -219,7 +222,10 @@ impl TinkWrapper {
         &mut self,
         req: crypto::KmsProxyRequest,
     ) -> Result<crypto::KeysetResponse, rpc::Status> {
-        let kms_client = self.get_kms_client(&req.kms_identifier).map_err(tinkerr)?;
+        let kms_client = self
+            .kms_credentials
+            .get_kms_client(&req.kms_identifier)
+            .map_err(tinkerr)?;
         let aead = kms_client.get_aead(&req.kms_identifier).map_err(tinkerr)?;
 
         let api_handle = self.register_kms_aead(aead);
@@ -326,38 +332,6 @@ impl TinkWrapper {
         d.verify(&req.signature, &req.data).map_err(tinkerr)?;
         Ok(crypto::SignatureVerifyResponse {})
     }
-
-    fn get_kms_client(
-        &self,
-        key_uri: &str,
-    ) -> Result<std::sync::Arc<dyn tink_core::registry::KmsClient>, tink_core::TinkError> {
-        debug!(
-            "retrieve KMS client for {} using credentials in {:?}",
-            key_uri, self.kms_credentials
-        );
-        #[cfg(feature = "awskms")]
-        if key_uri.starts_with(tink_awskms::AWS_PREFIX) {
-            let g = if let Some(kms_creds) = &self.kms_credentials {
-                tink_awskms::AwsClient::new_with_credentials(key_uri, &kms_creds)?
-            } else {
-                tink_awskms::AwsClient::new(key_uri)?
-            };
-            tink_core::registry::register_kms_client(g);
-            return tink_core::registry::get_kms_client(key_uri);
-        }
-        // TODO(#745): sort out clashing dependencies
-        #[cfg(feature = "gcpkms")]
-        if key_uri.starts_with(tink_gcpkms::GCP_PREFIX) {
-            let g = if let Some(kms_creds) = &self.kms_credentials {
-                tink_gcpkms::GcpClient::new_with_credentials(key_uri, &kms_creds)?
-            } else {
-                tink_gcpkms::GcpClient::new(key_uri)?
-            };
-            tink_core::registry::register_kms_client(g);
-            return tink_core::registry::get_kms_client(key_uri);
-        }
-        Err("Unrecognized key URI".into())
-    }
 }
 
 fn write_keyset_with<T: tink_core::keyset::Writer>(
@@ -17,7 +17,10 @@
 //! gRPC service implementation for handling OpenID Connect authentication requests from clients.
 
 use crate::{
-    auth::oidc_utils::exchange_code_for_token,
+    auth::oidc_utils::{
+        exchange_code_for_token, TokenError, TokenErrorKind, TokenValidationConfiguration,
+        TokenValidator,
+    },
     proto::oak::authentication::{
         authentication_server::{Authentication, AuthenticationServer},
         AuthParameters, IdentityTokenRequest, IdentityTokenResponse,
@@ -28,24 +31,35 @@ use tonic::{Request, Response, Status};
 pub fn build_service(
     client_id: &str,
     client_secret: &str,
+    token_validation_config: TokenValidationConfiguration,
 ) -> AuthenticationServer<AuthenticationHandler> {
-    AuthenticationServer::new(AuthenticationHandler::new(client_id, client_secret))
+    AuthenticationServer::new(AuthenticationHandler::new(
+        client_id,
+        client_secret,
+        token_validation_config,
+    ))
 }
 
 /// Service implementation to handle authentication requests.
-#[derive(Default)]
 pub struct AuthenticationHandler {
     client_id: String,
     client_secret: String,
+    /// Shared by all requests, so that the keys used for validating identity tokens are cached.
+    token_validator: TokenValidator,
     /* TODO(#922): Add a state storage mechanism to be able to match a client request to the
      * nonce and code challenge. */
 }
 
 impl AuthenticationHandler {
-    pub fn new(client_id: &str, client_secret: &str) -> AuthenticationHandler {
+    pub fn new(
+        client_id: &str,
+        client_secret: &str,
+        token_validation_config: TokenValidationConfiguration,
+    ) -> AuthenticationHandler {
         AuthenticationHandler {
             client_id: client_id.to_owned(),
             client_secret: client_secret.to_owned(),
+            token_validator: TokenValidator::new(token_validation_config),
         }
     }
 }
@@ -76,11 +90,17 @@ impl Authentication for AuthenticationHandler {
             &request.into_inner().auth_code,
             &self.client_id,
             &self.client_secret,
+            &self.token_validator,
         )
         .await
         {
             Ok(token) => Ok(Response::new(IdentityTokenResponse { token })),
-            Err(error) => Err(Status::internal(format!("Unexpected error: {}", error))),
+            Err(error) => match error.downcast_ref::<TokenError>() {
+                Some(token_error) if token_error.kind() == TokenErrorKind::Expired => {
+                    Err(Status::unauthenticated(token_error.to_string()))
+                }
+                _ => Err(Status::internal(format!("Unexpected error: {}", error))),
+            },
         }
     }
 }
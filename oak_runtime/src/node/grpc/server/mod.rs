@@ -17,7 +17,7 @@
 //! gRPC server pseudo-Node functionality.
 
 use crate::{
-    auth::oidc_utils::ClientInfo,
+    auth::oidc_utils::{ClientInfo, TokenValidationConfiguration},
     io::{channel_create_with_downgrade, ReceiverExt, SenderExt},
     metrics::Metrics,
     node::{
@@ -25,12 +25,15 @@ use crate::{
         ConfigurationError, Node,
     },
     proto::oak::invocation::{GrpcInvocation, GrpcInvocationSender},
+    tls::{to_rustls_server_config, Certificate, TlsConfig},
     RuntimeProxy,
 };
 use hyper::service::Service;
 use log::{debug, error, info, trace, warn};
 use oak_abi::{
-    label::{confidentiality_label, public_key_identity_tag, Label},
+    label::{
+        confidentiality_label, public_key_identity_tag, tls_client_certificate_tag, Label, Tag,
+    },
     proto::oak::{application::GrpcServerConfiguration, identity::SignedChallenge},
     OakStatus,
 };
@@ -55,9 +58,14 @@ use tonic::{
     server::{Grpc, ServerStreamingService},
     transport::{Identity, NamedService},
 };
+use x509_parser::extensions::{GeneralName, ParsedExtension};
 
 mod auth;
 
+#[cfg(test)]
+#[cfg(not(feature = "linear-handles"))]
+mod tests;
+
 /// Struct that represents a gRPC server pseudo-Node.
 pub struct GrpcServerNode {
     /// Pseudo-Node name.
@@ -66,9 +74,17 @@ pub struct GrpcServerNode {
     address: SocketAddr,
     /// Loaded files containing a server TLS key and certificates.
     tls_identity: Identity,
+    /// TLS configuration used instead of `tls_identity` if provided, in order to restrict the TLS
+    /// parameters accepted by the server and to verify client certificates.
+    tls_config: Option<TlsConfig>,
     /// OpenID Connect Authentication client information. A value of `None` will mean that the
     /// server will not support OpenID Connect authentication.
     oidc_client_info: Option<ClientInfo>,
+    /// Options for validating the identity tokens obtained with OpenID Connect authentication.
+    oidc_token_validation_config: TokenValidationConfiguration,
+    /// Encoded `FileDescriptorSet`s to serve with the gRPC server reflection service. A value of
+    /// `None` will mean that the server will not support reflection.
+    reflection_file_descriptor_sets: Option<Vec<Vec<u8>>>,
 }
 
 /// Checks if port is greater than 1023.
@@ -80,21 +96,76 @@ fn check_port(address: &SocketAddr) -> Result<(), ConfigurationError> {
     }
 }
 
+/// Configures a builder for a gRPC server reflection service describing the services in the
+/// provided encoded `FileDescriptorSet`s, as well as the reflection service itself.
+fn reflection_service_builder(
+    file_descriptor_sets: &[Vec<u8>],
+) -> tonic_reflection::server::Builder<'_> {
+    file_descriptor_sets.iter().fold(
+        tonic_reflection::server::Builder::configure(),
+        |builder, file_descriptor_set| {
+            builder.register_encoded_file_descriptor_set(file_descriptor_set)
+        },
+    )
+}
+
 impl GrpcServerNode {
     /// Creates a new [`GrpcServerNode`] instance, but does not start it.
+    ///
+    /// If `client_ca_certificate` is provided, client certificates are verified against it, which
+    /// requires `tls_config`. Clients without a verified certificate are rejected during the TLS
+    /// handshake, before any request reaches the Node, if `require_client_certificate` is set.
+    /// The identity in the verified certificate of a client is added as a confidentiality tag to
+    /// the label of the response channel of each of its invocations.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         node_name: &str,
         config: GrpcServerConfiguration,
         tls_identity: Identity,
+        tls_config: Option<TlsConfig>,
+        client_ca_certificate: Option<Certificate>,
+        require_client_certificate: bool,
         oidc_client_info: Option<ClientInfo>,
+        oidc_token_validation_config: TokenValidationConfiguration,
+        reflection_file_descriptor_sets: Option<Vec<Vec<u8>>>,
     ) -> Result<Self, ConfigurationError> {
         let address = config.address.parse()?;
         check_port(&address)?;
+        let tls_config = match (tls_config, client_ca_certificate) {
+            (Some(tls_config), Some(client_ca_certificate)) => Some(
+                tls_config
+                    .with_client_verification(&client_ca_certificate, require_client_certificate)
+                    .map_err(|err| {
+                        warn!("invalid gRPC client CA certificate: {}", err);
+                        ConfigurationError::InvalidNodeConfiguration
+                    })?,
+            ),
+            (None, Some(_)) => {
+                warn!("verifying gRPC client certificates requires a TLS configuration");
+                return Err(ConfigurationError::InvalidNodeConfiguration);
+            }
+            (_, None) if require_client_certificate => {
+                warn!("requiring gRPC client certificates requires a client CA certificate");
+                return Err(ConfigurationError::InvalidNodeConfiguration);
+            }
+            (tls_config, None) => tls_config,
+        };
+        if let Some(file_descriptor_sets) = &reflection_file_descriptor_sets {
+            reflection_service_builder(file_descriptor_sets)
+                .build()
+                .map_err(|err| {
+                    warn!("invalid gRPC reflection FileDescriptorSet: {}", err);
+                    ConfigurationError::InvalidNodeConfiguration
+                })?;
+        }
         Ok(Self {
             node_name: node_name.to_string(),
             address,
             tls_identity,
+            tls_config,
             oidc_client_info,
+            oidc_token_validation_config,
+            reflection_file_descriptor_sets,
         })
     }
 
@@ -163,19 +234,39 @@ impl Node for GrpcServerNode {
             Some(auth_config) => auth::oidc_service::build_service(
                 &auth_config.client_id,
                 &auth_config.client_secret,
+                self.oidc_token_validation_config,
             ),
             // TODO(#1021): Add better handling to cases where the client info is not supplied.
-            _ => auth::oidc_service::build_service("", ""),
+            _ => auth::oidc_service::build_service("", "", self.oidc_token_validation_config),
         };
 
+        // Build a service to describe the available services, if enabled. It only reveals the
+        // provided service descriptions and does not exchange any data with Nodes, so it does not
+        // need to be subject to label checks.
+        let reflection_handler = self
+            .reflection_file_descriptor_sets
+            .map(|file_descriptor_sets| {
+                reflection_service_builder(&file_descriptor_sets)
+                    .build()
+                    .expect("FileDescriptorSets were validated when creating the Node")
+            });
+
         // Build a service to process all other incoming HTTP/2 requests.
         let generic_handler = HttpRequestHandler {
             runtime: runtime.clone(),
             invocation_channel,
         };
 
+        let mut server_tls_config =
+            tonic::transport::ServerTlsConfig::new().identity(self.tls_identity);
+        if let Some(tls_config) = self.tls_config {
+            // This restricts the TLS parameters negotiated with clients and sets up the
+            // verification of client certificates, while tonic still applies the identity.
+            server_tls_config.rustls_server_config(to_rustls_server_config(tls_config));
+        }
+
         let server = tonic::transport::Server::builder()
-            .tls_config(tonic::transport::ServerTlsConfig::new().identity(self.tls_identity))
+            .tls_config(server_tls_config)
             .expect("Couldn't create TLS configuration")
             // The order for adding services are important. The namespaces of the services are
             // checked in the reverse order to which it was added. The `generic_handler` should
@@ -183,6 +274,7 @@ impl Node for GrpcServerNode {
             // intended for other services.
             .add_service(generic_handler)
             .add_service(auth_handler)
+            .add_optional_service(reflection_handler)
             .serve_with_shutdown(self.address, async {
                 // Treat notification failure the same as a notification.
                 let _ = notify_receiver.await;
@@ -392,6 +484,8 @@ fn verify_signed_challenge(signature: SignedChallenge) -> Result<Vec<u8>, OakIde
         public_key_der: signature.public_key.clone(),
         signed_hash: signature.signed_hash,
         hash,
+        // Public key identities are Ed25519 keys.
+        algorithm: oak_sign::SignatureAlgorithm::Ed25519,
     };
 
     match sig_bundle.verify() {
@@ -403,6 +497,48 @@ fn verify_signed_challenge(signature: SignedChallenge) -> Result<Vec<u8>, OakIde
     }
 }
 
+/// Returns the [`Tag`] identifying the client of an incoming gRPC request by the subject and
+/// subject alternative names of the certificate it presented during the TLS handshake, or `None` if
+/// it did not present one.
+///
+/// Clients are only asked for certificates if the server verifies them against its client CA
+/// certificate, so a certificate is only available here once it has been verified.
+fn get_client_certificate_tag<T>(
+    request: &tonic::Request<T>,
+) -> Result<Option<Tag>, OakIdentityError> {
+    let certificates = match request.peer_certs() {
+        Some(certificates) => certificates,
+        None => return Ok(None),
+    };
+    // The first certificate is that of the client, followed by the rest of its chain.
+    let certificate = match certificates.first() {
+        Some(certificate) => certificate,
+        None => return Ok(None),
+    };
+    let (_, certificate) =
+        x509_parser::parse_x509_certificate(certificate.get_ref()).map_err(|err| {
+            warn!("could not parse client certificate: {}", err);
+            OakIdentityError::InvalidIdentity
+        })?;
+    let subject_alternative_names = certificate
+        .extensions()
+        .iter()
+        .filter_map(|extension| match extension.parsed_extension() {
+            ParsedExtension::SubjectAlternativeName(names) => Some(&names.general_names),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|name| match name {
+            GeneralName::DNSName(name) | GeneralName::RFC822Name(name) => Some(name.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    Ok(Some(tls_client_certificate_tag(
+        &certificate.subject().to_string(),
+        &subject_alternative_names,
+    )))
+}
+
 /// Handler for an individual gRPC method invocation.
 #[derive(Clone)]
 struct GrpcInvocationHandler {
@@ -441,7 +577,12 @@ impl ServerStreamingService<Vec<u8>> for GrpcInvocationHandler {
                 request.get_ref().len(),
                 oak_label
             );
-            let identity_label = get_user_identity_label(request.metadata())?;
+            let mut identity_label = get_user_identity_label(request.metadata())?;
+            if let Some(client_certificate_tag) = get_client_certificate_tag(&request)? {
+                identity_label
+                    .confidentiality_tags
+                    .push(client_certificate_tag);
+            }
 
             // Create an encapsulated gRPC request.
             // TODO(#97): Add client-streaming support.
@@ -0,0 +1,486 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::{
+    io::{channel_create, ReceiverExt, SenderExt},
+    node::{grpc::codec::VecCodec, http::tests::tls_handshake},
+    permissions::PermissionsConfiguration,
+    proto::oak::invocation::{GrpcInvocation, GrpcInvocationSender},
+    tls::{self, TlsConfig, TlsPolicy, TlsVersion},
+    GrpcConfiguration, RuntimeProxy, SecureServerConfiguration, SignatureTable,
+};
+use log::info;
+use maplit::hashmap;
+use oak_abi::{
+    label::{confidentiality_label, tls_client_certificate_tag, top, Label},
+    proto::oak::application::{
+        node_configuration::ConfigType, ApplicationConfiguration, GrpcServerConfiguration,
+        NodeConfiguration,
+    },
+    OakStatus,
+};
+use oak_io::Receiver;
+use prost::Message;
+use prost_types::{
+    DescriptorProto, FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto,
+    ServiceDescriptorProto,
+};
+use rustls::ProtocolVersion;
+use std::time::Duration;
+use tonic::{
+    metadata::MetadataValue,
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity},
+};
+
+const TEST_SERVICE_NAME: &str = "oak.test.TestService";
+const REFLECTION_SERVICE_NAME: &str = "grpc.reflection.v1alpha.ServerReflection";
+
+fn init_logger() {
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+/// Returns an encoded `FileDescriptorSet` describing a single [`TEST_SERVICE_NAME`] service.
+fn test_file_descriptor_set() -> Vec<u8> {
+    let file_descriptor_set = FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("test.proto".to_string()),
+            package: Some("oak.test".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("TestMessage".to_string()),
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("TestService".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("TestMethod".to_string()),
+                    input_type: Some(".oak.test.TestMessage".to_string()),
+                    output_type: Some(".oak.test.TestMessage".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        }],
+    };
+    let mut bytes = Vec::new();
+    file_descriptor_set
+        .encode(&mut bytes)
+        .expect("Couldn't encode FileDescriptorSet");
+    bytes
+}
+
+/// Returns a [`GrpcConfiguration`] with the local test TLS identity.
+fn grpc_configuration() -> GrpcConfiguration {
+    GrpcConfiguration {
+        grpc_server_tls_identity: Some(Identity::from_pem(
+            include_str!("../../../../../examples/certs/local/local.pem"),
+            include_str!("../../../../../examples/certs/local/local.key"),
+        )),
+        ..Default::default()
+    }
+}
+
+fn create_runtime(grpc_configuration: GrpcConfiguration) -> RuntimeProxy {
+    let configuration = ApplicationConfiguration {
+        wasm_modules: hashmap! {},
+        initial_node_configuration: None,
+        module_signatures: vec![],
+    };
+    let permissions = PermissionsConfiguration {
+        allow_grpc_server_nodes: true,
+        ..Default::default()
+    };
+    let secure_server_config = SecureServerConfiguration {
+        grpc_config: Some(grpc_configuration),
+        http_config: None,
+    };
+    info!("Create runtime for test");
+    RuntimeProxy::create_runtime(
+        &configuration,
+        &permissions,
+        &secure_server_config,
+        &SignatureTable::default(),
+        None,
+    )
+}
+
+/// Creates a gRPC server Node listening on `port`, whose invocations are never handled.
+fn create_server_node(runtime: &RuntimeProxy, port: u16) -> Result<(), OakStatus> {
+    create_server_node_with_invocations(runtime, port).map(|_invocation_receiver| ())
+}
+
+/// Creates a gRPC server Node listening on `port`, and returns the receiver for its invocations.
+fn create_server_node_with_invocations(
+    runtime: &RuntimeProxy,
+    port: u16,
+) -> Result<Receiver<GrpcInvocation>, OakStatus> {
+    let (init_sender, init_receiver) = channel_create::<GrpcInvocationSender>(
+        runtime,
+        "gRPC server init",
+        &Label::public_untrusted(),
+    )?;
+    let (invocation_sender, invocation_receiver) = channel_create::<GrpcInvocation>(
+        runtime,
+        "gRPC server invocation",
+        &Label::public_untrusted(),
+    )?;
+    init_sender
+        .send(
+            GrpcInvocationSender {
+                sender: Some(invocation_sender),
+            },
+            runtime,
+        )
+        .expect("Couldn't write to the `init_sender` channel");
+    init_sender
+        .close(runtime)
+        .expect("Couldn't close the `init_sender` channel");
+
+    let server_config = NodeConfiguration {
+        config_type: Some(ConfigType::GrpcServerConfig(GrpcServerConfiguration {
+            address: format!("[::]:{}", port),
+        })),
+    };
+    runtime.node_create(
+        "test_server",
+        &server_config,
+        &confidentiality_label(top()),
+        init_receiver.handle.handle,
+    )?;
+    Ok(invocation_receiver)
+}
+
+/// Returns a [`TlsConfig`] with the local test TLS identity.
+fn tls_config() -> TlsConfig {
+    TlsConfig::new(
+        "../examples/certs/local/local.pem",
+        "../examples/certs/local/local.key",
+    )
+    .expect("Couldn't create TLS config from local certs.")
+}
+
+fn client_ca_certificate() -> tls::Certificate {
+    tls::Certificate::parse(
+        include_bytes!("../../../../../examples/certs/local_clients/ca.pem").to_vec(),
+    )
+    .expect("Couldn't parse client CA certificate")
+}
+
+fn trusted_client_identity() -> Identity {
+    Identity::from_pem(
+        include_str!("../../../../../examples/certs/local_clients/client.pem"),
+        include_str!("../../../../../examples/certs/local_clients/client.key"),
+    )
+}
+
+fn untrusted_client_identity() -> Identity {
+    Identity::from_pem(
+        include_str!("../../../../../examples/certs/local_clients/untrusted_client.pem"),
+        include_str!("../../../../../examples/certs/local_clients/untrusted_client.key"),
+    )
+}
+
+/// Returns an [`Endpoint`] for the gRPC server on `port`, which presents `client_identity` as the
+/// client certificate if provided.
+fn endpoint(port: u16, client_identity: Option<Identity>) -> Endpoint {
+    let mut tls_config = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(include_bytes!(
+            "../../../../../examples/certs/local/ca.pem"
+        )))
+        .domain_name("localhost");
+    if let Some(client_identity) = client_identity {
+        tls_config = tls_config.identity(client_identity);
+    }
+    Channel::from_shared(format!("https://localhost:{}", port))
+        .expect("Couldn't parse URI")
+        .tls_config(tls_config)
+        .expect("Couldn't create TLS configuration")
+}
+
+/// Connects to the gRPC server on `port`, waiting for it to start listening if needed.
+async fn connect(port: u16, client_identity: Option<Identity>) -> Channel {
+    let endpoint = endpoint(port, client_identity);
+    let mut attempts = 0;
+    loop {
+        match endpoint.connect().await {
+            Ok(channel) => return channel,
+            Err(err) if attempts < 50 => {
+                info!("Couldn't connect to gRPC server yet: {}", err);
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(err) => panic!("Couldn't connect to gRPC server: {}", err),
+        }
+    }
+}
+
+/// Stops `runtime` without blocking the test executor, which must keep driving the client
+/// connections so that the gRPC server can shut down gracefully.
+async fn stop_runtime(runtime: RuntimeProxy) {
+    tokio::task::spawn_blocking(move || runtime.runtime.stop())
+        .await
+        .expect("Couldn't stop the runtime");
+}
+
+/// Subset of the `grpc.reflection.v1alpha.ServerReflectionRequest` message, which is not exposed
+/// by `tonic_reflection`, that only lists services.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ListServicesRequest {
+    #[prost(string, optional, tag = "7")]
+    list_services: Option<String>,
+}
+
+/// Subset of the `grpc.reflection.v1alpha.ServerReflectionResponse` message holding the
+/// response to a [`ListServicesRequest`].
+#[derive(Clone, PartialEq, prost::Message)]
+struct ListServicesResponse {
+    #[prost(message, optional, tag = "6")]
+    list_services_response: Option<ServiceList>,
+}
+
+/// `grpc.reflection.v1alpha.ListServiceResponse` message.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServiceList {
+    #[prost(message, repeated, tag = "1")]
+    service: Vec<ServiceName>,
+}
+
+/// `grpc.reflection.v1alpha.ServiceResponse` message.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServiceName {
+    #[prost(string, tag = "1")]
+    name: String,
+}
+
+/// Lists the services of the gRPC server connected to `channel` using the reflection service.
+async fn list_services(channel: Channel) -> Result<Vec<String>, tonic::Status> {
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|err| tonic::Status::unavailable(err.to_string()))?;
+    let request = ListServicesRequest {
+        list_services: Some(String::new()),
+    };
+    let path = format!("/{}/ServerReflectionInfo", REFLECTION_SERVICE_NAME)
+        .parse()
+        .expect("Couldn't parse method path");
+    let mut responses = client
+        .streaming(
+            tonic::Request::new(tokio_stream::iter(vec![request])),
+            path,
+            tonic::codec::ProstCodec::<ListServicesRequest, ListServicesResponse>::default(),
+        )
+        .await?
+        .into_inner();
+    let response = responses
+        .message()
+        .await?
+        .expect("Empty reflection response stream");
+    match response.list_services_response {
+        Some(list) => Ok(list
+            .service
+            .into_iter()
+            .map(|service| service.name)
+            .collect()),
+        None => panic!("Unexpected reflection response"),
+    }
+}
+
+/// Sends a request to [`TEST_SERVICE_NAME`] on the gRPC server connected to `channel`, with a
+/// public untrusted label, and returns the stream of its responses.
+async fn send_test_request(
+    channel: Channel,
+) -> Result<tonic::codec::Streaming<Vec<u8>>, tonic::Status> {
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|err| tonic::Status::unavailable(err.to_string()))?;
+    let mut label_bytes = Vec::new();
+    Label::public_untrusted()
+        .encode(&mut label_bytes)
+        .expect("Couldn't encode label");
+    let mut request = tonic::Request::new(vec![]);
+    request.metadata_mut().insert_bin(
+        oak_abi::OAK_LABEL_GRPC_METADATA_KEY,
+        MetadataValue::from_bytes(&label_bytes),
+    );
+    let path = format!("/{}/TestMethod", TEST_SERVICE_NAME)
+        .parse()
+        .expect("Couldn't parse method path");
+    client
+        .server_streaming(request, path, VecCodec)
+        .await
+        .map(tonic::Response::into_inner)
+}
+
+/// Checks that a client presenting `client_identity` (if any) cannot use the gRPC server on
+/// `port`, which is expected to be listening already.
+async fn assert_client_rejected(port: u16, client_identity: Option<Identity>) {
+    // Depending on the TLS version, the server may only reject the client certificate after the
+    // client considers the handshake complete, so the rejection may only surface on the first
+    // request.
+    if let Ok(channel) = endpoint(port, client_identity).connect().await {
+        assert!(list_services(channel).await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_server_serves_reflection() {
+    init_logger();
+    let port = 2540;
+    let runtime = create_runtime(GrpcConfiguration {
+        grpc_reflection_file_descriptor_sets: Some(vec![test_file_descriptor_set()]),
+        ..grpc_configuration()
+    });
+    create_server_node(&runtime, port).expect("Couldn't create gRPC server node");
+
+    let service_names = list_services(connect(port, None).await)
+        .await
+        .expect("Couldn't list services");
+    assert!(
+        service_names.contains(&TEST_SERVICE_NAME.to_string()),
+        "{:?}",
+        service_names
+    );
+    assert!(
+        service_names.contains(&REFLECTION_SERVICE_NAME.to_string()),
+        "{:?}",
+        service_names
+    );
+
+    stop_runtime(runtime).await;
+}
+
+#[test]
+fn test_grpc_server_rejects_invalid_file_descriptor_sets() {
+    init_logger();
+    let runtime = create_runtime(GrpcConfiguration {
+        grpc_reflection_file_descriptor_sets: Some(vec![vec![0xff]]),
+        ..grpc_configuration()
+    });
+    assert_eq!(
+        Err(OakStatus::ErrInvalidArgs),
+        create_server_node(&runtime, 2541)
+    );
+    runtime.runtime.stop();
+}
+
+#[tokio::test]
+async fn test_grpc_server_refuses_tls_1_2_when_tls_1_3_is_required() {
+    init_logger();
+    let port = 2542;
+    let tls_policy = TlsPolicy::new(TlsVersion::Tls13, vec![]).expect("Couldn't create TLS policy");
+    let runtime = create_runtime(GrpcConfiguration {
+        grpc_server_tls_config: Some(tls_config().with_policy(tls_policy)),
+        ..grpc_configuration()
+    });
+    create_server_node(&runtime, port).expect("Couldn't create gRPC server node");
+
+    assert!(tls_handshake(port.into(), vec![ProtocolVersion::TLSv1_3])
+        .await
+        .is_ok());
+    assert!(tls_handshake(port.into(), vec![ProtocolVersion::TLSv1_2])
+        .await
+        .is_err());
+
+    runtime.runtime.stop();
+}
+
+#[tokio::test]
+async fn test_grpc_server_requires_trusted_client_certificate() {
+    init_logger();
+    let port = 2543;
+    let runtime = create_runtime(GrpcConfiguration {
+        grpc_server_tls_config: Some(tls_config()),
+        grpc_server_client_ca_certificate: Some(client_ca_certificate()),
+        grpc_server_require_client_certificate: true,
+        grpc_reflection_file_descriptor_sets: Some(vec![test_file_descriptor_set()]),
+        ..grpc_configuration()
+    });
+    create_server_node(&runtime, port).expect("Couldn't create gRPC server node");
+
+    let service_names = list_services(connect(port, Some(trusted_client_identity())).await)
+        .await
+        .expect("Couldn't list services with a trusted client certificate");
+    assert!(
+        service_names.contains(&TEST_SERVICE_NAME.to_string()),
+        "{:?}",
+        service_names
+    );
+    assert_client_rejected(port, Some(untrusted_client_identity())).await;
+    assert_client_rejected(port, None).await;
+
+    runtime.runtime.stop();
+}
+
+#[tokio::test]
+async fn test_grpc_server_tags_invocations_with_client_certificate() {
+    init_logger();
+    let port = 2545;
+    let runtime = create_runtime(GrpcConfiguration {
+        grpc_server_tls_config: Some(tls_config()),
+        grpc_server_client_ca_certificate: Some(client_ca_certificate()),
+        grpc_server_require_client_certificate: true,
+        ..grpc_configuration()
+    });
+    let invocation_receiver = create_server_node_with_invocations(&runtime, port)
+        .expect("Couldn't create gRPC server node");
+
+    // The Node never responds, so the request is left pending while the invocation is checked.
+    let channel = connect(port, Some(trusted_client_identity())).await;
+    let request = tokio::spawn(send_test_request(channel));
+
+    let runtime_copy = runtime.clone();
+    let invocation =
+        tokio::task::spawn_blocking(move || invocation_receiver.receive(&runtime_copy))
+            .await
+            .expect("Couldn't join the invocation reader")
+            .expect("Couldn't receive gRPC invocation");
+    let response_sender = invocation
+        .sender
+        .expect("Missing gRPC response sender in invocation");
+    let response_label = runtime
+        .get_channel_label(response_sender.handle.handle)
+        .expect("Couldn't get gRPC response channel label");
+    assert!(
+        response_label
+            .confidentiality_tags
+            .contains(&tls_client_certificate_tag("CN=Oak Test Client", &[])),
+        "{:?}",
+        response_label
+    );
+
+    request.abort();
+    stop_runtime(runtime).await;
+}
+
+#[test]
+fn test_grpc_server_rejects_client_ca_certificate_without_tls_config() {
+    init_logger();
+    let runtime = create_runtime(GrpcConfiguration {
+        grpc_server_client_ca_certificate: Some(client_ca_certificate()),
+        grpc_server_require_client_certificate: true,
+        ..grpc_configuration()
+    });
+    assert_eq!(
+        Err(OakStatus::ErrInvalidArgs),
+        create_server_node(&runtime, 2544)
+    );
+    runtime.runtime.stop();
+}
@@ -17,6 +17,7 @@
 pub mod client;
 pub mod server;
 pub(crate) mod util;
+pub mod websocket;
 
 #[cfg(test)]
 #[cfg(not(feature = "linear-handles"))]
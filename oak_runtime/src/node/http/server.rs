@@ -61,7 +61,7 @@ use prost::Message;
 use tokio_rustls::TlsAcceptor;
 
 /// Checks that port is not reserved (i.e., is greater than 1023).
-fn check_port(address: &SocketAddr) -> Result<(), ConfigurationError> {
+pub(crate) fn check_port(address: &SocketAddr) -> Result<(), ConfigurationError> {
     if address.port() > 1023 {
         Ok(())
     } else {
@@ -158,7 +158,7 @@ impl HttpServerNode {
                 return;
             }
         };
-        let tls_server = self.build_tls_server(tcp);
+        let tls_server = build_tls_server(tcp, self.tls_config.clone());
         let server = Server::builder(tls_server).serve(service);
 
         let graceful_server = server.with_graceful_shutdown(async {
@@ -178,25 +178,28 @@ impl HttpServerNode {
             self.address, result
         );
     }
+}
 
-    /// Build a server that checks incoming TCP connections for TLS handshake.
-    fn build_tls_server(&self, tcp: TcpListener) -> TlsServer {
-        let tls_cfg = crate::tls::to_server_config(self.tls_config.clone());
-        let tls_acceptor = TlsAcceptor::from(tls_cfg);
-
-        let incoming_tls_stream = TcpListenerStream::new(tcp)
-            .and_then(move |stream| {
-                debug!("Received incoming TLS stream: {:?}", stream);
-                tls_acceptor.accept(stream).map_err(|err| {
-                    error!("Client-connection error: {:?}", err);
-                    io::Error::new(io::ErrorKind::Other, format!("TLS Error: {:?}", err))
-                })
+/// Build a server that checks incoming TCP connections for TLS handshake.
+pub(crate) fn build_tls_server(
+    tcp: TcpListener,
+    tls_config: crate::tls::TlsConfig,
+) -> TlsServer<'static> {
+    let tls_cfg = crate::tls::to_server_config(tls_config);
+    let tls_acceptor = TlsAcceptor::from(tls_cfg);
+
+    let incoming_tls_stream = TcpListenerStream::new(tcp)
+        .and_then(move |stream| {
+            debug!("Received incoming TLS stream: {:?}", stream);
+            tls_acceptor.accept(stream).map_err(|err| {
+                error!("Client-connection error: {:?}", err);
+                io::Error::new(io::ErrorKind::Other, format!("TLS Error: {:?}", err))
             })
-            .boxed();
+        })
+        .boxed();
 
-        TlsServer {
-            acceptor: incoming_tls_stream,
-        }
+    TlsServer {
+        acceptor: incoming_tls_stream,
     }
 }
 
@@ -280,7 +283,7 @@ fn get_invocation_channel(
     }
 }
 
-fn create_async_runtime(runtime: RuntimeProxy) -> tokio::runtime::Runtime {
+pub(crate) fn create_async_runtime(runtime: RuntimeProxy) -> tokio::runtime::Runtime {
     // Use simple scheduler that runs all tasks on the current-thread.
     // https://docs.rs/tokio/1.5.0/tokio/runtime/index.html#current-thread-scheduler
     tokio::runtime::Builder::new_current_thread()
@@ -338,13 +341,7 @@ impl HttpRequestHandler {
         request: HttpRequest,
         request_label: Label,
     ) -> anyhow::Result<HttpResponseReceiver> {
-        let user_identity = get_user_identity(&request)?;
-        let user_identity_label = if user_identity.is_empty() {
-            // If no identity is provided, return public-untrusted
-            Label::public_untrusted()
-        } else {
-            confidentiality_label(public_key_identity_tag(&user_identity))
-        };
+        let user_identity_label = get_user_identity_label(&request)?;
 
         // Create a pair of temporary channels to pass the HTTP request to the Oak Node, and
         // receive the response.
@@ -373,7 +370,7 @@ impl HttpRequestHandler {
 /// HTTP requests can either provide JSON formatted labels or protobuf encoded labels. But exactly
 /// one of these should be provided. This method checks that exactly one label is provided in a
 /// header in the request and extracts it for use for further handling of the request.
-fn get_oak_label(req: &HttpRequest) -> anyhow::Result<Label> {
+pub(crate) fn get_oak_label(req: &HttpRequest) -> anyhow::Result<Label> {
     let headers = (
         req.headers.as_ref().and_then(|map| {
             map.headers
@@ -434,6 +431,21 @@ fn get_user_identity(req: &HttpRequest) -> anyhow::Result<Vec<u8>> {
     }
 }
 
+/// Returns the label of the data sent back to the user who sent the request: a confidentiality
+/// label for the user identity provided in the request, or public-untrusted if the request does
+/// not provide any.
+pub(crate) fn get_user_identity_label(req: &HttpRequest) -> anyhow::Result<Label> {
+    let user_identity = get_user_identity(req)?;
+    if user_identity.is_empty() {
+        // If no identity is provided, return public-untrusted
+        Ok(Label::public_untrusted())
+    } else {
+        Ok(confidentiality_label(public_key_identity_tag(
+            &user_identity,
+        )))
+    }
+}
+
 fn parse_json_label(label_str: &[u8]) -> anyhow::Result<Label> {
     let label_str = String::from_utf8(label_str.to_vec())
         .context("The label must be a valid UTF-8 JSON-formatted string")?;
@@ -484,6 +496,8 @@ fn verify_signed_challenge(
         public_key_der: signature.public_key.clone(),
         signed_hash: signature.signed_hash,
         hash,
+        // Public key identities are Ed25519 keys.
+        algorithm: oak_sign::SignatureAlgorithm::Ed25519,
     };
 
     match sig_bundle.verify() {
@@ -535,7 +549,7 @@ impl Drop for HttpResponseReceiver {
 }
 
 /// Create an instance of Oak HttpRequest from the given hyper Request.
-async fn to_oak_http_request(req: Request<Body>) -> anyhow::Result<HttpRequest> {
+pub(crate) async fn to_oak_http_request(req: Request<Body>) -> anyhow::Result<HttpRequest> {
     let uri = req.uri().to_string();
     let method = req.method().as_str().to_string();
     let headers = Some(HeaderMap::from(req.headers().to_owned()));
@@ -16,26 +16,38 @@
 
 use crate::{
     io::{channel_create, ReceiverExt, SenderExt},
-    node::{http::util::Pipe, CreatedNode, Node, NodeIsolation},
+    node::{
+        http::{util::Pipe, websocket::websocket_accept_key},
+        CreatedNode, Node, NodeIsolation,
+    },
     permissions::PermissionsConfiguration,
-    proto::oak::invocation::{HttpInvocation, HttpInvocationSender},
+    proto::oak::invocation::{
+        HttpInvocation, HttpInvocationSender, WebSocketInvocation, WebSocketInvocationSender,
+    },
+    tls::{TlsPolicy, TlsVersion},
     NodePrivilege, RuntimeProxy,
 };
+use futures_util::{SinkExt, StreamExt};
 use log::{error, info};
 use maplit::hashmap;
 use oak_abi::{
     label::{confidentiality_label, public_key_identity_tag, tls_endpoint_tag, Label},
     proto::oak::application::{
         node_configuration::ConfigType, ApplicationConfiguration, HttpClientConfiguration,
-        HttpServerConfiguration, NodeConfiguration,
+        HttpServerConfiguration, NodeConfiguration, WebSocketServerConfiguration,
     },
     OakStatus,
 };
 use oak_io::{handle::ReadHandle, OakError, Receiver};
 use oak_services::proto::oak::encap::{HttpRequest, HttpResponse};
-use prost::Message;
-use std::{fs, io, sync::mpsc};
+use prost::Message as _;
+use rustls::ProtocolVersion;
+use std::{
+    fs, io,
+    sync::{mpsc, Arc},
+};
 use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::{protocol::Role, Message};
 
 const LOCAL_CA: &str = "../examples/certs/local/ca.pem";
 const GCP_CA: &str = "../examples/certs/gcp/ca.pem";
@@ -375,6 +387,25 @@ async fn test_https_server_can_serve_https_requests_with_user_identity_as_reques
     http_server_tester.cleanup();
 }
 
+#[tokio::test]
+async fn test_https_server_refuses_tls_1_2_when_tls_1_3_is_required() {
+    init_logger();
+
+    let tls_policy = TlsPolicy::new(TlsVersion::Tls13, vec![]).expect("Couldn't create TLS policy");
+    let runtime = create_runtime_with_tls_policy(get_permissions(), tls_policy);
+    let _invocation_receiver =
+        create_server_node(&runtime, 2531).expect("Couldn't create HTTP server node!");
+
+    assert!(tls_handshake(2531, vec![ProtocolVersion::TLSv1_3])
+        .await
+        .is_ok());
+    assert!(tls_handshake(2531, vec![ProtocolVersion::TLSv1_2])
+        .await
+        .is_err());
+
+    runtime.runtime.stop();
+}
+
 #[test]
 fn test_https_client_can_handle_https_requests_to_an_external_service() {
     init_logger();
@@ -476,6 +507,13 @@ fn test_https_client_can_handle_http_requests_to_an_external_service() {
 }
 
 fn create_runtime(permissions: PermissionsConfiguration) -> RuntimeProxy {
+    create_runtime_with_tls_policy(permissions, TlsPolicy::default())
+}
+
+fn create_runtime_with_tls_policy(
+    permissions: PermissionsConfiguration,
+    tls_policy: TlsPolicy,
+) -> RuntimeProxy {
     let configuration = ApplicationConfiguration {
         wasm_modules: hashmap! {},
         initial_node_configuration: None,
@@ -485,7 +523,8 @@ fn create_runtime(permissions: PermissionsConfiguration) -> RuntimeProxy {
         "../examples/certs/local/local.pem",
         "../examples/certs/local/local.key",
     )
-    .expect("Couldn't create TLS config from local certs.");
+    .expect("Couldn't create TLS config from local certs.")
+    .with_policy(tls_policy);
     let secure_server_config = crate::SecureServerConfiguration {
         grpc_config: None,
         http_config: Some(crate::HttpConfiguration {
@@ -599,6 +638,34 @@ fn create_client(
     hyper::client::Client::builder().build(https)
 }
 
+/// Performs a TLS handshake with the server listening on `port` on localhost, offering only the
+/// given TLS `versions`, and waiting for the server to start listening if needed.
+pub(crate) async fn tls_handshake(port: u32, versions: Vec<ProtocolVersion>) -> io::Result<()> {
+    let mut config = rustls::ClientConfig::new();
+    config
+        .root_store
+        .add_pem_file(&mut io::BufReader::new(fs::File::open(LOCAL_CA)?))
+        .map_err(|()| io::Error::new(io::ErrorKind::InvalidData, "invalid CA certificate"))?;
+    config.versions = versions;
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+
+    let mut attempts = 0;
+    let tcp = loop {
+        match tokio::net::TcpStream::connect(format!("localhost:{}", port)).await {
+            Ok(tcp) => break tcp,
+            Err(err) if attempts < 50 => {
+                info!("Couldn't connect to server yet: {}", err);
+                attempts += 1;
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    let domain = tokio_rustls::webpki::DNSNameRef::try_from_ascii_str("localhost")
+        .expect("Couldn't parse domain name");
+    connector.connect(domain, tcp).await.map(|_| ())
+}
+
 fn create_signature() -> oak_abi::proto::oak::identity::SignedChallenge {
     let key_pair = oak_sign::KeyPair::generate().unwrap();
     let signature =
@@ -811,3 +878,185 @@ impl Node for ClientTesterNode {
             .expect("could not send the result");
     }
 }
+
+#[test]
+fn test_websocket_accept_key() {
+    // Example handshake from RFC 6455, section 1.3.
+    let mut headers = http::HeaderMap::new();
+    headers.insert(http::header::UPGRADE, "websocket".parse().unwrap());
+    headers.insert(http::header::CONNECTION, "Upgrade".parse().unwrap());
+    headers.insert(http::header::SEC_WEBSOCKET_VERSION, "13".parse().unwrap());
+    assert_eq!(None, websocket_accept_key(&headers));
+
+    headers.insert(
+        http::header::SEC_WEBSOCKET_KEY,
+        "dGhlIHNhbXBsZSBub25jZQ==".parse().unwrap(),
+    );
+    assert_eq!(
+        Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_string()),
+        websocket_accept_key(&headers)
+    );
+
+    headers.remove(http::header::UPGRADE);
+    assert_eq!(None, websocket_accept_key(&headers));
+}
+
+#[cfg(not(feature = "oak-unsafe"))]
+#[test]
+fn test_cannot_create_websocket_server_node_if_not_permitted() {
+    init_logger();
+    let runtime = create_runtime(PermissionsConfiguration::default());
+    let result = create_websocket_server_node(&runtime, 8080);
+    assert!(result.is_err())
+}
+
+#[tokio::test]
+async fn test_websocket_server_forwards_messages() {
+    init_logger();
+    let runtime = create_runtime(PermissionsConfiguration {
+        allow_web_socket_server_nodes: true,
+        ..Default::default()
+    });
+    let invocation_receiver =
+        create_websocket_server_node(&runtime, 2530).expect("Couldn't create WebSocket server!");
+    runtime
+        .node_register(
+            CreatedNode {
+                instance: Box::new(WebSocketEchoNode),
+                privilege: NodePrivilege::default(),
+            },
+            "websocket_echo_node",
+            &Label::public_untrusted(),
+            invocation_receiver.handle.handle,
+        )
+        .expect("Couldn't create Oak node!");
+    let client = create_client(LOCAL_CA);
+
+    // Requests that do not ask for a WebSocket upgrade are rejected.
+    let resp = send_request(
+        client.clone(),
+        "https://localhost:2530",
+        create_signature(),
+        Label::public_untrusted(),
+    )
+    .await
+    .expect("Couldn't send the request");
+    assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+
+    let mut label_bytes = vec![];
+    Label::public_untrusted().encode(&mut label_bytes).unwrap();
+    let request = hyper::Request::builder()
+        .method(http::Method::GET)
+        .uri("https://localhost:2530")
+        .header(
+            oak_abi::OAK_LABEL_HTTP_PROTOBUF_KEY,
+            base64::encode(label_bytes),
+        )
+        .header(http::header::UPGRADE, "websocket")
+        .header(http::header::CONNECTION, "Upgrade")
+        .header(http::header::SEC_WEBSOCKET_VERSION, "13")
+        .header(http::header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+        .body(hyper::Body::empty())
+        .unwrap();
+    let resp = client.request(request).await.expect("Couldn't upgrade");
+    assert_eq!(http::StatusCode::SWITCHING_PROTOCOLS, resp.status());
+    let upgraded = hyper::upgrade::on(resp).await.expect("Couldn't upgrade");
+    let mut stream =
+        tokio_tungstenite::WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await;
+
+    // Messages are sent to the Oak node, and the messages it writes are sent back.
+    stream
+        .send(Message::Binary(b"hello".to_vec()))
+        .await
+        .expect("Couldn't send the message");
+    match stream.next().await {
+        Some(Ok(Message::Binary(data))) => {
+            assert_eq!(b"hello".to_vec(), data.to_vec())
+        }
+        message => panic!("unexpected message {:?}", message),
+    }
+    stream.close(None).await.expect("Couldn't close");
+
+    runtime.runtime.stop();
+}
+
+/// Creates a WebSocket server pseudo-node in the given Runtime, and returns the receiver of the
+/// invocations for its connections.
+fn create_websocket_server_node(
+    runtime: &RuntimeProxy,
+    port: u32,
+) -> Result<Receiver<WebSocketInvocation>, OakStatus> {
+    let (init_sender, init_receiver) = channel_create::<WebSocketInvocationSender>(
+        runtime,
+        "WebSocket server init",
+        &Label::public_untrusted(),
+    )
+    .expect("Couldn't create channel");
+    let (invocation_sender, invocation_receiver) = channel_create::<WebSocketInvocation>(
+        runtime,
+        "WebSocket server invocation",
+        &Label::public_untrusted(),
+    )
+    .expect("Couldn't create channel");
+    init_sender
+        .send(
+            WebSocketInvocationSender {
+                sender: Some(invocation_sender),
+            },
+            runtime,
+        )
+        .expect("Couldn't write to the `init_sender` channel");
+    init_sender
+        .close(runtime)
+        .expect("Couldn't close the `init_sender` channel");
+
+    let server_config = NodeConfiguration {
+        config_type: Some(ConfigType::WebSocketServerConfig(
+            WebSocketServerConfiguration {
+                address: format!("[::]:{}", port),
+            },
+        )),
+    };
+    // TODO(#1631): When we have a separate top for each sub-lattice, this should be changed to
+    // the top of the identity sub-lattice.
+    let top_label = oak_abi::label::confidentiality_label(oak_abi::label::top());
+    runtime.node_create(
+        "test_websocket_server",
+        &server_config,
+        &top_label,
+        init_receiver.handle.handle,
+    )?;
+
+    Ok(invocation_receiver)
+}
+
+/// A simple Oak node that echoes back the messages received over each WebSocket connection.
+struct WebSocketEchoNode;
+
+impl Node for WebSocketEchoNode {
+    fn node_type(&self) -> &'static str {
+        "test-websocket-echo"
+    }
+    fn isolation(&self) -> NodeIsolation {
+        // Even though this node is not actually sandboxed, we are simulating a Wasm node during
+        // testing.
+        NodeIsolation::Sandboxed
+    }
+    fn run(
+        self: Box<Self>,
+        runtime: RuntimeProxy,
+        handle: oak_abi::Handle,
+        _notify_receiver: oneshot::Receiver<()>,
+    ) {
+        let invocation_receiver = Receiver::<WebSocketInvocation>::new(ReadHandle { handle });
+        while let Ok(invocation) = invocation_receiver.receive(&runtime) {
+            let receiver = invocation.receiver.expect("Empty receiver on invocation.");
+            let sender = invocation.sender.expect("Empty sender on invocation.");
+            while let Ok(message) = receiver.receive(&runtime) {
+                sender.send(message, &runtime).unwrap();
+            }
+            let _ = receiver.close(&runtime);
+            let _ = sender.close(&runtime);
+        }
+    }
+}
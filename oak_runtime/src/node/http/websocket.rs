@@ -0,0 +1,445 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! WebSocket server pseudo-Node that allows external clients, such as browsers, to maintain
+//! bidirectional connections with an Oak node.
+//!
+//! The server accepts WebSocket upgrade requests, carrying the same label and identity headers as
+//! the requests handled by the HTTP server pseudo-Node. Each connection is mapped to a pair of
+//! channels, which are sent to the designated Oak node in a `WebSocketInvocation`: messages
+//! received from the client are written to one of them, and messages read from the other one are
+//! sent to the client.
+
+use crate::{
+    io::{channel_create_with_downgrade, Receiver, ReceiverExt, Sender, SenderExt},
+    node::{
+        copy_or_clone,
+        http::server::{
+            build_tls_server, check_port, create_async_runtime, get_oak_label,
+            get_user_identity_label, to_oak_http_request,
+        },
+        ConfigurationError, Node,
+    },
+    proto::oak::invocation::{WebSocketInvocation, WebSocketInvocationSender},
+    RuntimeProxy,
+};
+use anyhow::Context;
+use futures_util::{SinkExt, StreamExt};
+use http::{request::Request, response::Response, HeaderMap};
+use hyper::{
+    header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, UPGRADE},
+    service::{make_service_fn, service_fn},
+    Body, Server, StatusCode,
+};
+use log::{debug, error, info, warn};
+use oak_abi::{label::Label, proto::oak::application::WebSocketServerConfiguration, OakStatus};
+use oak_io::{
+    handle::{ReadHandle, WriteHandle},
+    OakError,
+};
+use oak_services::proto::oak::encap::WebSocketMessage;
+use std::net::SocketAddr;
+use tokio::{net::TcpListener, sync::oneshot};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+
+/// Struct that represents a WebSocket server pseudo-Node.
+pub struct WebSocketServerNode {
+    /// Pseudo-Node name.
+    node_name: String,
+    /// Server address to listen client connections on.
+    address: SocketAddr,
+    /// TLS certificate and private key for establishing secure connections.
+    tls_config: crate::tls::TlsConfig,
+}
+
+impl WebSocketServerNode {
+    /// Creates a new [`WebSocketServerNode`] instance, but does not start it.
+    pub fn new(
+        node_name: &str,
+        config: WebSocketServerConfiguration,
+        tls_config: crate::tls::TlsConfig,
+    ) -> Result<Self, ConfigurationError> {
+        let address = config.address.parse()?;
+        check_port(&address)?;
+        Ok(Self {
+            node_name: node_name.to_string(),
+            address,
+            tls_config,
+        })
+    }
+
+    /// Make a server, with graceful shutdown, from the given [`WebSocketRequestHandler`].
+    async fn make_server(
+        &self,
+        request_handler: WebSocketRequestHandler,
+        notify_receiver: oneshot::Receiver<()>,
+    ) {
+        let service = make_service_fn(move |_conn| {
+            let handler = request_handler.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req| {
+                    let handler = handler.clone();
+                    async move { handler.handle(req).await }
+                }))
+            }
+        });
+
+        let tcp = match TcpListener::bind(&self.address).await {
+            Ok(tcp) => tcp,
+            Err(e) => {
+                error!(
+                    "{:?}: Couldn't create TCP listener: {:?}",
+                    std::thread::current().id(),
+                    e
+                );
+                return;
+            }
+        };
+        let tls_server = build_tls_server(tcp, self.tls_config.clone());
+        let server = Server::builder(tls_server).serve(service);
+
+        let graceful_server = server.with_graceful_shutdown(async {
+            // Treat notification failure the same as a notification.
+            let _ = notify_receiver.await;
+        });
+        info!(
+            "{:?}: Started WebSocket server pseudo-node on port {:?}",
+            std::thread::current().id(),
+            &self.address.port()
+        );
+
+        // Run until asked to terminate...
+        let result = graceful_server.await;
+        info!(
+            "WebSocket server pseudo-node on addr {:?} terminated with {:?}",
+            self.address, result
+        );
+    }
+}
+
+/// Oak Node implementation for the WebSocket server.
+impl Node for WebSocketServerNode {
+    fn node_type(&self) -> &'static str {
+        "websocket-server"
+    }
+
+    fn run(
+        self: Box<Self>,
+        runtime: RuntimeProxy,
+        startup_handle: oak_abi::Handle,
+        notify_receiver: oneshot::Receiver<()>,
+    ) {
+        // At start-of-day we need/expect to receive a write handle for an invocation channel
+        // to use for all subsequent activity.
+        info!("{}: Waiting for invocation channel", self.node_name);
+        let read_handle = ReadHandle {
+            handle: startup_handle,
+        };
+        let invocation_channel = match get_invocation_channel(&runtime, read_handle) {
+            Ok(writer) => writer,
+            Err(status) => {
+                error!(
+                    "Failed to retrieve invocation channel write handle: {:?}",
+                    status
+                );
+                return;
+            }
+        };
+        if let Err(err) = runtime.channel_close(startup_handle) {
+            error!(
+                "Failed to close initial inbound channel {}: {:?}",
+                startup_handle, err
+            );
+        }
+
+        let request_handler = WebSocketRequestHandler {
+            runtime: runtime.clone(),
+            invocation_channel,
+        };
+        let server = self.make_server(request_handler, notify_receiver);
+
+        // TODO(#1280): Use a single shared tokio runtime, instead of creating a new one here.
+        let async_runtime = create_async_runtime(runtime);
+
+        info!(
+            "{}: Starting WebSocket server pseudo-Node on: {}",
+            self.node_name, self.address
+        );
+        async_runtime.block_on(server);
+    }
+}
+
+/// Reads the [`WriteHandle`] (to be used for sending new invocations) from a startup channel.
+/// Returns an error if the startup channel couldn't be read, or if the initial message is
+/// invalid (it must be an encoded [`WebSocketInvocationSender`]).
+fn get_invocation_channel(
+    runtime: &RuntimeProxy,
+    startup_handle: ReadHandle,
+) -> Result<WriteHandle, OakError> {
+    let startup_receiver = Receiver::<WebSocketInvocationSender>::new(startup_handle);
+    let invocation_channel = startup_receiver.receive(runtime)?;
+    match invocation_channel.sender {
+        Some(invocation_sender) => {
+            info!(
+                "Invocation channel write handle received: {}",
+                invocation_sender.handle.handle
+            );
+            Ok(invocation_sender.handle)
+        }
+        None => {
+            error!("Couldn't receive the invocation sender.");
+            Err(OakError::OakStatus(OakStatus::ErrBadHandle))
+        }
+    }
+}
+
+/// Returns the value of the `Sec-WebSocket-Accept` header of the response accepting the provided
+/// request, or `None` if it is not a valid WebSocket upgrade request.
+pub(crate) fn websocket_accept_key(headers: &HeaderMap) -> Option<String> {
+    let header_contains = |name, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(token))
+    };
+    if !header_contains(UPGRADE, "websocket")
+        || !header_contains(CONNECTION, "upgrade")
+        || !header_contains(SEC_WEBSOCKET_VERSION, "13")
+    {
+        return None;
+    }
+    headers
+        .get(SEC_WEBSOCKET_KEY)
+        .map(|key| derive_accept_key(key.as_bytes()))
+}
+
+/// [`WebSocketRequestHandler`] handles WebSocket upgrade requests from a client, and serves the
+/// resulting connections.
+#[derive(Clone)]
+struct WebSocketRequestHandler {
+    /// Reference to the Runtime in the context of this WebSocket server pseudo-Node.
+    runtime: RuntimeProxy,
+    /// Channel handle used for writing WebSocket invocations.
+    invocation_channel: WriteHandle,
+}
+
+impl WebSocketRequestHandler {
+    async fn handle(&self, mut req: Request<Body>) -> anyhow::Result<Response<Body>> {
+        let accept_key = match websocket_accept_key(req.headers()) {
+            Some(accept_key) => accept_key,
+            None => {
+                warn!("Received a request that is not a WebSocket upgrade request");
+                return bad_request("Expected a WebSocket upgrade request.");
+            }
+        };
+        let on_upgrade = hyper::upgrade::on(&mut req);
+        let request = to_oak_http_request(req).await?;
+        let labels = get_oak_label(&request)
+            .and_then(|request_label| Ok((request_label, get_user_identity_label(&request)?)));
+        let (request_label, user_identity_label) = match labels {
+            Ok(labels) => labels,
+            Err(err) => {
+                warn!("Invalid or missing Oak label: {}", err);
+                return bad_request("Invalid or missing Oak label.");
+            }
+        };
+        info!(
+            "Handling WebSocket upgrade request; label: {:?}",
+            request_label
+        );
+
+        let connection = self.inject_connection(&request_label, &user_identity_label)?;
+        tokio::spawn(async move {
+            match on_upgrade.await {
+                Ok(upgraded) => {
+                    let stream =
+                        WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                    connection.serve(stream).await;
+                }
+                Err(err) => {
+                    warn!("Couldn't upgrade the connection: {}", err);
+                    connection.close();
+                }
+            }
+        });
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(CONNECTION, "Upgrade")
+            .header(UPGRADE, "websocket")
+            .header(SEC_WEBSOCKET_ACCEPT, accept_key)
+            .body(Body::empty())
+            .context("Couldn't create response")
+    }
+
+    /// Creates the pair of channels of a new connection, and sends them to the Oak node.
+    ///
+    /// Messages from the client are labelled with the label of the upgrade request, and messages
+    /// to the client must be allowed to flow to the identity of the user, as for HTTP requests
+    /// and responses.
+    fn inject_connection(
+        &self,
+        request_label: &Label,
+        user_identity_label: &Label,
+    ) -> anyhow::Result<WebSocketConnection> {
+        let (inbound_sender, inbound_receiver) =
+            channel_create_with_downgrade(&self.runtime, "WebSocket inbound", request_label)?;
+        let (outbound_sender, outbound_receiver) = channel_create_with_downgrade(
+            &self.runtime,
+            "WebSocket outbound",
+            user_identity_label,
+        )?;
+
+        let invocation = WebSocketInvocation {
+            receiver: Some(Receiver::new(copy_or_clone(&inbound_receiver.handle))),
+            sender: Some(Sender::new(copy_or_clone(&outbound_sender.handle))),
+        };
+        let invocation_sender = Sender::new(copy_or_clone(&self.invocation_channel));
+        let sent = invocation_sender
+            .send_with_downgrade(invocation, &self.runtime)
+            .context("Couldn't write the invocation message");
+
+        // The Oak node holds its own handles to the channels now.
+        if let Err(err) = inbound_receiver.close(&self.runtime) {
+            error!("Failed to close inbound receiver channel: {:?}", err);
+        }
+        if let Err(err) = outbound_sender.close(&self.runtime) {
+            error!("Failed to close outbound sender channel: {:?}", err);
+        }
+
+        let connection = WebSocketConnection {
+            runtime: self.runtime.clone(),
+            inbound_sender,
+            outbound_receiver,
+        };
+        match sent {
+            Ok(()) => Ok(connection),
+            Err(err) => {
+                connection.close();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Returns a response rejecting a request, with the provided explanation.
+fn bad_request(message: &'static str) -> anyhow::Result<Response<Body>> {
+    http::response::Builder::new()
+        .status(StatusCode::BAD_REQUEST)
+        .body(Body::from(message))
+        .context("Couldn't create response")
+}
+
+/// The handles of the WebSocket server pseudo-Node to the channels of a connection.
+struct WebSocketConnection {
+    runtime: RuntimeProxy,
+    /// Channel handle used for writing the messages received from the client.
+    inbound_sender: Sender<WebSocketMessage>,
+    /// Channel handle used for reading the messages to send to the client.
+    outbound_receiver: Receiver<WebSocketMessage>,
+}
+
+impl WebSocketConnection {
+    /// Forwards messages between the client and the channels of the connection, until either the
+    /// client or the Oak node closes the connection.
+    async fn serve<S>(self, stream: WebSocketStream<S>)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let WebSocketConnection {
+            runtime,
+            inbound_sender,
+            outbound_receiver: channel_receiver,
+        } = self;
+        let (mut client_sink, mut client_stream) = stream.split();
+
+        // Reading from a channel blocks, so do it outside of the async runtime.
+        let (outbound_sender, mut outbound_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let reader_runtime = runtime.clone();
+        tokio::task::spawn_blocking(move || {
+            while let Ok(message) = channel_receiver.receive(&reader_runtime) {
+                if outbound_sender.send(message).is_err() {
+                    break;
+                }
+            }
+            if let Err(err) = channel_receiver.close(&reader_runtime) {
+                error!("Failed to close outbound receiver channel: {:?}", err);
+            }
+        });
+        // Writes a message received from the client to the inbound channel.
+        let forward_to_channel = |data: Vec<u8>| {
+            if let Err(err) =
+                inbound_sender.send_with_downgrade(WebSocketMessage { data }, &runtime)
+            {
+                warn!("Couldn't write to the inbound channel: {:?}", err);
+            }
+        };
+
+        loop {
+            tokio::select! {
+                incoming = client_stream.next() => match incoming {
+                    Some(Ok(Message::Binary(data))) => {
+                        forward_to_channel(data.to_vec());
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        forward_to_channel(text.as_bytes().to_vec());
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    // Control frames are handled by the WebSocket implementation itself.
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => {
+                        warn!("Couldn't read from the WebSocket connection: {}", err);
+                        break;
+                    }
+                },
+                outgoing = outbound_receiver.recv() => match outgoing {
+                    Some(message) => {
+                        if let Err(err) = client_sink.send(Message::Binary(message.data)).await {
+                            warn!("Couldn't write to the WebSocket connection: {}", err);
+                            break;
+                        }
+                    }
+                    None => {
+                        // The Oak node closed the outbound channel, so close the connection.
+                        debug!("Outbound channel closed, closing the WebSocket connection");
+                        let _ = client_sink.send(Message::Close(None)).await;
+                        break;
+                    }
+                },
+            }
+        }
+
+        // Closing the inbound channel lets the Oak node know that the connection is over.
+        if let Err(err) = inbound_sender.close(&runtime) {
+            error!("Failed to close inbound sender channel: {:?}", err);
+        }
+    }
+
+    /// Closes the handles of the connection without serving it.
+    fn close(self) {
+        if let Err(err) = self.inbound_sender.close(&self.runtime) {
+            error!("Failed to close inbound sender channel: {:?}", err);
+        }
+        if let Err(err) = self.outbound_receiver.close(&self.runtime) {
+            error!("Failed to close outbound receiver channel: {:?}", err);
+        }
+    }
+}
@@ -17,15 +17,22 @@
 //! Functionality for different Node types.
 
 use crate::{
-    permissions::PermissionsConfiguration, NodePrivilege, RuntimeProxy, SecureServerConfiguration,
-    SignatureTable,
+    kms_credentials::KmsCredentials, permissions::PermissionsConfiguration, NodePrivilege,
+    RuntimeProxy, SecureServerConfiguration, SignatureTable,
 };
+use itertools::Itertools;
 use log::warn;
 use oak_abi::proto::oak::application::{
     node_configuration::ConfigType, ApplicationConfiguration, CryptoConfiguration,
-    LogConfiguration, NodeConfiguration,
+    LogConfiguration, NodeConfiguration, WebAssemblyConfiguration,
+};
+use oak_sign::{get_sha256_hex, SignatureBundle};
+use prost::Message;
+use std::{
+    collections::{HashMap, HashSet},
+    net::AddrParseError,
+    sync::{Arc, RwLock},
 };
-use std::net::AddrParseError;
 use tokio::sync::oneshot;
 
 mod crypto;
@@ -33,8 +40,10 @@ pub mod grpc;
 pub mod http;
 mod invocation;
 mod logger;
+mod random;
 mod roughtime;
 mod storage;
+mod timer;
 mod wasm;
 
 /// Trait encapsulating execution of a Node or pseudo-Node.
@@ -81,9 +90,17 @@ pub enum ConfigurationError {
     IncorrectURI,
     NoHostElement,
     IncorrectWebAssemblyModuleName,
+    WebAssemblyModuleNotFound(String),
     InvalidNodeConfiguration,
     WasmiModuleInializationError(wasmi::Error),
     NodeCreationNotPermitted,
+    WebAssemblyModuleRevoked(String),
+    InsufficientWebAssemblyModuleSignatures(String),
+    InvalidWebAssemblyModuleSignature(String),
+    WebAssemblyMemoryLimitExceeded {
+        initial_pages: u32,
+        max_memory_pages: u32,
+    },
 }
 
 impl From<AddrParseError> for ConfigurationError {
@@ -104,6 +121,9 @@ impl std::fmt::Display for ConfigurationError {
             ConfigurationError::IncorrectWebAssemblyModuleName => {
                 write!(f, "Incorrect WebAssembly module name")
             }
+            ConfigurationError::WebAssemblyModuleNotFound(name) => {
+                write!(f, "WebAssembly module {:?} not found", name)
+            }
             ConfigurationError::InvalidNodeConfiguration => write!(f, "Invalid NodeConfiguration"),
             ConfigurationError::WasmiModuleInializationError(e) => {
                 write!(f, "Failed to initialize wasmi::Module: {}", e)
@@ -111,18 +131,118 @@ impl std::fmt::Display for ConfigurationError {
             ConfigurationError::NodeCreationNotPermitted => {
                 write!(f, "Node creation not permitted")
             }
+            ConfigurationError::WebAssemblyModuleRevoked(hash) => {
+                write!(f, "WebAssembly module with hash {} is revoked", hash)
+            }
+            ConfigurationError::InsufficientWebAssemblyModuleSignatures(hash) => write!(
+                f,
+                "WebAssembly module with hash {} does not have enough trusted signatures",
+                hash
+            ),
+            ConfigurationError::InvalidWebAssemblyModuleSignature(hash) => {
+                write!(
+                    f,
+                    "Invalid signature for WebAssembly module with hash {}",
+                    hash
+                )
+            }
+            ConfigurationError::WebAssemblyMemoryLimitExceeded {
+                initial_pages,
+                max_memory_pages,
+            } => write!(
+                f,
+                "WebAssembly module requires {} memory pages, more than the limit of {}",
+                initial_pages, max_memory_pages
+            ),
         }
     }
 }
 
 /// Implementation of [`NodeFactory`] for server-like Oak applications running on cloud
 /// environments with WebAssembly support.
+///
+/// The application configuration and the signature table may be updated while the Runtime is
+/// running, when replacing WebAssembly modules.
 pub struct ServerNodeFactory {
-    pub application_configuration: ApplicationConfiguration,
+    pub application_configuration: RwLock<ApplicationConfiguration>,
     pub permissions_configuration: PermissionsConfiguration,
     pub secure_server_configuration: SecureServerConfiguration,
-    pub signature_table: SignatureTable,
-    pub kms_credentials: Option<std::path::PathBuf>,
+    pub signature_table: RwLock<SignatureTable>,
+    /// Shared by all crypto pseudo-Nodes, so that reloading it affects all of them.
+    pub kms_credentials: Arc<KmsCredentials>,
+}
+
+impl ServerNodeFactory {
+    /// Replaces the bytes of the WebAssembly module named `module_name` with `wasm_module_bytes`,
+    /// and adds the provided `signatures` of the new module to the signature table.
+    ///
+    /// The new module must export each of the `entrypoint_names`, as well as the entrypoint of the
+    /// initial Node if it runs this module, which are validated as when creating a Wasm Node.
+    ///
+    /// Fails, leaving the module unchanged, if there is no module with this name, if the new
+    /// module is not valid, if any of the signatures is invalid or does not match the new module,
+    /// or if the new module could not be run because of the signature table (e.g. because it is
+    /// revoked).
+    pub(crate) fn replace_wasm_module(
+        &self,
+        module_name: &str,
+        wasm_module_bytes: Vec<u8>,
+        signatures: &[SignatureBundle],
+        mut entrypoint_names: HashSet<String>,
+    ) -> Result<(), ConfigurationError> {
+        let mut application_configuration = self.application_configuration.write().unwrap();
+        find_wasm_module(&application_configuration, module_name)?;
+        wasmi::Module::from_buffer(&wasm_module_bytes)
+            .map_err(ConfigurationError::WasmiModuleInializationError)?;
+        if let Some(ConfigType::WasmConfig(config)) = application_configuration
+            .initial_node_configuration
+            .as_ref()
+            .and_then(|config| config.config_type.as_ref())
+        {
+            if config.wasm_module_name == module_name {
+                entrypoint_names.insert(config.wasm_entrypoint_name.clone());
+            }
+        }
+        for wasm_entrypoint_name in entrypoint_names {
+            wasm::WasmNode::new(
+                module_name,
+                &wasm_module_bytes,
+                WebAssemblyConfiguration {
+                    wasm_module_name: module_name.to_string(),
+                    wasm_entrypoint_name,
+                },
+                self.permissions_configuration.max_wasm_memory_pages,
+                self.permissions_configuration.wasm_fuel,
+            )?;
+        }
+
+        let module_hash = get_sha256_hex(&wasm_module_bytes);
+        for signature in signatures {
+            if signature.verify().is_err() || hex::encode(&signature.hash) != module_hash {
+                warn!(
+                    "invalid signature for Wasm module with SHA-256 hash {}",
+                    module_hash
+                );
+                return Err(ConfigurationError::InvalidWebAssemblyModuleSignature(
+                    module_hash,
+                ));
+            }
+        }
+        let mut signature_table = self.signature_table.write().unwrap();
+        let mut new_signature_table = signature_table.clone();
+        new_signature_table
+            .values
+            .entry(module_hash)
+            .or_default()
+            .extend(signatures.iter().cloned());
+        wasm::get_privilege(&wasm_module_bytes, &new_signature_table)?;
+
+        *signature_table = new_signature_table;
+        application_configuration
+            .wasm_modules
+            .insert(module_name.to_string(), wasm_module_bytes);
+        Ok(())
+    }
 }
 
 impl NodeFactory<NodeConfiguration> for ServerNodeFactory {
@@ -177,7 +297,12 @@ impl NodeFactory<NodeConfiguration> for ServerNodeFactory {
                             .as_ref()
                             .expect("no gRPC server TLS identity provided to Oak Runtime")
                             .clone(),
+                        grpc_configuration.grpc_server_tls_config.clone(),
+                        grpc_configuration.grpc_server_client_ca_certificate.clone(),
+                        grpc_configuration.grpc_server_require_client_certificate,
                         grpc_configuration.oidc_client_info.clone(),
+                        grpc_configuration.oidc_token_validation_config.clone(),
+                        grpc_configuration.grpc_reflection_file_descriptor_sets,
                     )?),
                     // This node needs to have `top` privilege to be able to declassify data tagged
                     // with any arbitrary user identities.
@@ -187,18 +312,21 @@ impl NodeFactory<NodeConfiguration> for ServerNodeFactory {
                 })
             }
             Some(ConfigType::WasmConfig(config)) => {
-                let wasm_module_bytes = self
-                    .application_configuration
-                    .wasm_modules
-                    .get(&config.wasm_module_name)
-                    .ok_or(ConfigurationError::IncorrectWebAssemblyModuleName)?;
+                let application_configuration = self.application_configuration.read().unwrap();
+                let wasm_module_bytes =
+                    find_wasm_module(&application_configuration, &config.wasm_module_name)?;
                 Ok(CreatedNode {
                     instance: Box::new(wasm::WasmNode::new(
                         node_name,
                         wasm_module_bytes,
                         config.clone(),
+                        self.permissions_configuration.max_wasm_memory_pages,
+                        self.permissions_configuration.wasm_fuel,
                     )?),
-                    privilege: wasm::get_privilege(wasm_module_bytes, &self.signature_table),
+                    privilege: wasm::get_privilege(
+                        wasm_module_bytes,
+                        &self.signature_table.read().unwrap(),
+                    )?,
                 })
             }
             Some(ConfigType::GrpcClientConfig(config)) => {
@@ -230,6 +358,14 @@ impl NodeFactory<NodeConfiguration> for ServerNodeFactory {
                 instance: Box::new(storage::StorageNode::new(node_name)),
                 privilege: NodePrivilege::default(),
             }),
+            Some(ConfigType::TimerConfig(config)) => Ok(CreatedNode {
+                instance: Box::new(timer::TimerNode::new(node_name, config)?),
+                privilege: NodePrivilege::default(),
+            }),
+            Some(ConfigType::RandomConfig(_config)) => Ok(CreatedNode {
+                instance: Box::new(random::RandomNode::new(node_name)),
+                privilege: NodePrivilege::default(),
+            }),
             Some(ConfigType::HttpServerConfig(config)) => {
                 let tls_config = self
                     .secure_server_configuration
@@ -250,6 +386,26 @@ impl NodeFactory<NodeConfiguration> for ServerNodeFactory {
                     privilege: NodePrivilege::top_privilege(),
                 })
             }
+            Some(ConfigType::WebSocketServerConfig(config)) => {
+                let tls_config = self
+                    .secure_server_configuration
+                    .http_config
+                    .clone()
+                    .expect("no TLS configuration for WebSocket servers provided to Oak Runtime")
+                    .tls_config;
+                Ok(CreatedNode {
+                    instance: Box::new(http::websocket::WebSocketServerNode::new(
+                        node_name,
+                        config.clone(),
+                        tls_config,
+                    )?),
+                    // As for the HTTP server pseudo-Node, this node needs to have `top` privilege
+                    // to be able to declassify data tagged with any arbitrary user identities.
+                    // TODO(#1631): When we have a separate top for each sub-lattice, this should be
+                    // changed to the top of the `identity` sub-lattice.
+                    privilege: NodePrivilege::top_privilege(),
+                })
+            }
             Some(ConfigType::HttpClientConfig(config)) => {
                 let http_client_root_tls_certificate = self
                     .secure_server_configuration
@@ -272,6 +428,92 @@ impl NodeFactory<NodeConfiguration> for ServerNodeFactory {
     }
 }
 
+/// Looks up the bytes of the WebAssembly module named `module_name` in the application
+/// configuration.
+///
+/// If the module is not present, the available module names are logged so that the
+/// misconfiguration can be diagnosed without inspecting the application configuration by hand.
+pub(crate) fn find_wasm_module<'a>(
+    application_configuration: &'a ApplicationConfiguration,
+    module_name: &str,
+) -> Result<&'a Vec<u8>, ConfigurationError> {
+    application_configuration
+        .wasm_modules
+        .get(module_name)
+        .ok_or_else(|| {
+            warn!(
+                "WebAssembly module {:?} not found; available modules: {:?}",
+                module_name,
+                application_configuration
+                    .wasm_modules
+                    .keys()
+                    .sorted()
+                    .collect::<Vec<_>>()
+            );
+            ConfigurationError::WebAssemblyModuleNotFound(module_name.to_string())
+        })
+}
+
+/// Returns the name of the `config_type` field set in the provided [`NodeConfiguration`] (e.g.
+/// `"wasm_config"`), which identifies the type of Node it configures.
+pub(crate) fn config_type_name(node_configuration: &NodeConfiguration) -> Option<&'static str> {
+    match node_configuration.config_type.as_ref()? {
+        ConfigType::WasmConfig(_) => Some("wasm_config"),
+        ConfigType::LogConfig(_) => Some("log_config"),
+        ConfigType::StorageConfig(_) => Some("storage_config"),
+        ConfigType::GrpcServerConfig(_) => Some("grpc_server_config"),
+        ConfigType::GrpcClientConfig(_) => Some("grpc_client_config"),
+        ConfigType::RoughtimeClientConfig(_) => Some("roughtime_client_config"),
+        ConfigType::HttpServerConfig(_) => Some("http_server_config"),
+        ConfigType::HttpClientConfig(_) => Some("http_client_config"),
+        ConfigType::CryptoConfig(_) => Some("crypto_config"),
+        ConfigType::WebSocketServerConfig(_) => Some("web_socket_server_config"),
+        ConfigType::TimerConfig(_) => Some("timer_config"),
+        ConfigType::RandomConfig(_) => Some("random_config"),
+    }
+}
+
+/// Merges the defaults registered in `defaults` for the type of Node configured by
+/// `node_configuration` into it, and returns the result.
+///
+/// Fields explicitly set in `node_configuration` take precedence over the defaults, mostly
+/// following the protobuf merge semantics: fields with non-default values override the
+/// corresponding default fields, and fields left at their default value (e.g. empty strings) are
+/// taken from the defaults. Unlike in protobuf merges, non-empty repeated fields (i.e. the servers
+/// of a Roughtime client) replace the default ones as a whole instead of being appended to them.
+/// Defaults registered for a different type of Node than the one they are keyed by are ignored.
+pub(crate) fn apply_node_config_defaults(
+    defaults: &HashMap<String, NodeConfiguration>,
+    node_configuration: &NodeConfiguration,
+) -> NodeConfiguration {
+    let type_name = config_type_name(node_configuration);
+    match defaults.get(type_name.unwrap_or_default()) {
+        Some(default_configuration) if config_type_name(default_configuration) == type_name => {
+            let mut merged = default_configuration.clone();
+            let mut encoded = Vec::with_capacity(node_configuration.encoded_len());
+            node_configuration
+                .encode(&mut encoded)
+                .expect("could not encode Node configuration");
+            merged
+                .merge(encoded.as_slice())
+                .expect("could not merge Node configuration");
+            if let (
+                Some(ConfigType::RoughtimeClientConfig(merged_config)),
+                Some(ConfigType::RoughtimeClientConfig(config)),
+            ) = (
+                merged.config_type.as_mut(),
+                node_configuration.config_type.as_ref(),
+            ) {
+                if !config.servers.is_empty() {
+                    merged_config.servers = config.servers.clone();
+                }
+            }
+            merged
+        }
+        _ => node_configuration.clone(),
+    }
+}
+
 /// A holder struct containing a [`Node`] instance, together with the [`NodePrivilege`] that is
 /// assigned to it.
 ///
@@ -0,0 +1,122 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Random pseudo-Node functionality.
+
+use crate::{
+    io::{Receiver, ReceiverExt, SenderExt},
+    RuntimeProxy,
+};
+use log::{error, info, warn};
+use oak_abi::OakStatus;
+use oak_io::{handle::ReadHandle, OakError};
+use oak_services::proto::oak::random::{RandomBytes, RandomRequest};
+use rand::{rngs::OsRng, RngCore};
+use tokio::sync::oneshot;
+
+/// Maximum number of bytes that may be requested in a single [`RandomRequest`].
+const MAX_RANDOM_BYTES_LENGTH: u32 = 64 * 1024;
+
+/// Random pseudo-Node, which writes a [`RandomBytes`] message in response to each
+/// [`RandomRequest`] read from the channel it is created with.
+///
+/// The bytes are reproducible across runs if the Runtime was configured with a seed, see
+/// [`crate::RuntimeConfiguration::rng_seed`], and come from the operating system otherwise.
+pub struct RandomNode {
+    node_name: String,
+}
+
+impl RandomNode {
+    /// Creates a new [`RandomNode`] instance, but does not start it.
+    pub fn new(node_name: &str) -> Self {
+        Self {
+            node_name: node_name.to_string(),
+        }
+    }
+
+    /// Writes `request.length` bytes from `rng` to the sender of `request`, and closes it.
+    fn handle_request(
+        &self,
+        runtime: &RuntimeProxy,
+        rng: &mut dyn RngCore,
+        request: RandomRequest,
+    ) {
+        let sender = match request.sender {
+            Some(sender) => sender,
+            None => {
+                warn!("{} received request without sender", self.node_name);
+                return;
+            }
+        };
+        if request.length > MAX_RANDOM_BYTES_LENGTH {
+            warn!(
+                "{} received request for {} bytes, more than the maximum of {}",
+                self.node_name, request.length, MAX_RANDOM_BYTES_LENGTH
+            );
+        } else {
+            let mut data = vec![0; request.length as usize];
+            rng.fill_bytes(&mut data);
+            if let Err(err) = sender.send(RandomBytes { data }, runtime) {
+                warn!("{} failed to write random bytes: {:?}", self.node_name, err);
+            }
+        }
+        if let Err(err) = sender.close(runtime) {
+            warn!("{} failed to close sender: {:?}", self.node_name, err);
+        }
+    }
+}
+
+impl super::Node for RandomNode {
+    fn node_type(&self) -> &'static str {
+        "random"
+    }
+
+    /// Main execution loop for the random pseudo-Node, which expects `handle` to be the read half
+    /// of the channel to read [`RandomRequest`]s from.
+    fn run(
+        self: Box<Self>,
+        runtime: RuntimeProxy,
+        handle: oak_abi::Handle,
+        _notify_receiver: oneshot::Receiver<()>,
+    ) {
+        let mut rng: Box<dyn RngCore> = match runtime.node_rng(&self.node_name) {
+            Some(rng) => Box::new(rng),
+            None => Box::new(OsRng),
+        };
+        let receiver = Receiver::<RandomRequest>::new(ReadHandle { handle });
+        loop {
+            match receiver.receive(&runtime) {
+                Ok(request) => self.handle_request(&runtime, rng.as_mut(), request),
+                // Recoverable errors:
+                Err(OakError::ProtobufDecodeError(err)) => {
+                    warn!("{} failed to decode request: {:?}", self.node_name, err);
+                }
+                // Errors that lead to Node termination:
+                Err(OakError::OakStatus(OakStatus::ErrTerminated)) => break,
+                Err(OakError::OakStatus(OakStatus::ErrChannelClosed)) => {
+                    info!("{} channel closed", self.node_name);
+                    break;
+                }
+                Err(err) => {
+                    error!("{} failed channel receive: {:?}", self.node_name, err);
+                    break;
+                }
+            }
+        }
+        info!("{} random execution complete", self.node_name);
+        let _ = runtime.channel_close(handle);
+    }
+}
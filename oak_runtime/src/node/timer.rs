@@ -0,0 +1,147 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Timer pseudo-Node functionality.
+
+use crate::{
+    io::{Receiver, ReceiverExt, Sender, SenderExt},
+    node::ConfigurationError,
+    RuntimeProxy,
+};
+use log::{error, info, warn};
+use oak_abi::{proto::oak::application::TimerConfiguration, OakStatus};
+use oak_io::{handle::ReadHandle, OakError};
+use oak_services::proto::oak::timer::{Tick, TickSender};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Timer pseudo-Node, which writes a [`Tick`] at a regular interval to the channel provided by the
+/// [`TickSender`] it reads at startup, so that Nodes can be woken up periodically without
+/// busy-waiting.
+pub struct TimerNode {
+    node_name: String,
+    interval: Duration,
+}
+
+impl TimerNode {
+    /// Creates a new [`TimerNode`] instance, but does not start it.
+    pub fn new(node_name: &str, config: &TimerConfiguration) -> Result<Self, ConfigurationError> {
+        if config.interval_millis == 0 {
+            warn!("{}: timer interval must be positive", node_name);
+            return Err(ConfigurationError::InvalidNodeConfiguration);
+        }
+        Ok(Self {
+            node_name: node_name.to_string(),
+            interval: Duration::from_millis(config.interval_millis),
+        })
+    }
+
+    /// Writes ticks to `sender` every interval, measured with the clock of the Runtime, until
+    /// notified to terminate or the channel cannot be written to anymore.
+    async fn tick(
+        &self,
+        runtime: &RuntimeProxy,
+        sender: &Sender<Tick>,
+        mut notify_receiver: oneshot::Receiver<()>,
+    ) {
+        let mut next_tick = runtime.now() + self.interval;
+        let mut count = 0;
+        loop {
+            let delay = next_tick.saturating_duration_since(runtime.now());
+            tokio::select! {
+                // Treat notification failure the same as a notification.
+                _ = &mut notify_receiver => break,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            let now = runtime.now();
+            if now < next_tick {
+                continue;
+            }
+            count += 1;
+            match sender.send(Tick { count }, runtime) {
+                Ok(()) => {}
+                Err(OakError::OakStatus(OakStatus::ErrTerminated)) => break,
+                Err(OakError::OakStatus(OakStatus::ErrChannelClosed)) => {
+                    info!("{} channel closed", self.node_name);
+                    break;
+                }
+                Err(err) => {
+                    error!("{} failed channel write: {:?}", self.node_name, err);
+                    break;
+                }
+            }
+            // Ticks are scheduled relative to the previous one so that they do not drift, but
+            // ticks that are overdue by more than an interval are skipped rather than bunched up.
+            next_tick += self.interval;
+            if next_tick <= now {
+                next_tick = now + self.interval;
+            }
+        }
+    }
+}
+
+impl super::Node for TimerNode {
+    fn node_type(&self) -> &'static str {
+        "timer"
+    }
+
+    /// Main execution loop for the timer pseudo-Node, which expects `handle` to be the read half
+    /// of a channel providing a [`TickSender`] with the channel to write ticks to.
+    fn run(
+        self: Box<Self>,
+        runtime: RuntimeProxy,
+        handle: oak_abi::Handle,
+        notify_receiver: oneshot::Receiver<()>,
+    ) {
+        let sender = match get_tick_sender(&runtime, ReadHandle { handle }) {
+            Ok(sender) => sender,
+            Err(err) => {
+                error!(
+                    "{} failed to receive tick sender: {:?}",
+                    self.node_name, err
+                );
+                return;
+            }
+        };
+        if let Err(err) = runtime.channel_close(handle) {
+            error!(
+                "{} failed to close initial inbound channel {}: {:?}",
+                self.node_name, handle, err
+            );
+        }
+        let async_runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("Couldn't create Async runtime");
+        async_runtime.block_on(self.tick(&runtime, &sender, notify_receiver));
+        info!("{} timer execution complete", self.node_name);
+        let _ = runtime.channel_close(sender.handle.handle);
+    }
+}
+
+/// Reads the [`Sender`] to write ticks to from the initial channel of the timer pseudo-Node.
+/// Returns an error if the channel couldn't be read, or if the [`TickSender`] read from it does
+/// not contain a sender.
+fn get_tick_sender(
+    runtime: &RuntimeProxy,
+    startup_handle: ReadHandle,
+) -> Result<Sender<Tick>, OakError> {
+    let startup_receiver = Receiver::<TickSender>::new(startup_handle);
+    startup_receiver
+        .receive(runtime)?
+        .sender
+        .ok_or(OakError::OakStatus(OakStatus::ErrBadHandle))
+}
@@ -19,7 +19,7 @@
 use crate::{
     node::{ConfigurationError, NodeIsolation},
     Downgrading, LabelReadStatus, NodeMessage, NodePrivilege, NodeReadStatus, RuntimeProxy,
-    SignatureTable,
+    SignaturePolicy, SignatureTable,
 };
 use byteorder::{ByteOrder, LittleEndian};
 use log::{debug, error, info, trace, warn};
@@ -31,7 +31,7 @@ use oak_abi::{
 };
 use oak_sign::get_sha256_hex;
 use rand::RngCore;
-use std::{string::String, sync::Arc};
+use std::{collections::HashSet, string::String, sync::Arc};
 use tokio::sync::oneshot;
 use wasmi::ValueType;
 
@@ -56,8 +56,15 @@ const WAIT_ON_CHANNELS_WITH_DOWNGRADE: usize = 12;
 const CHANNEL_LABEL_READ: usize = 13;
 const NODE_LABEL_READ: usize = 14;
 const NODE_PRIVILEGE_READ: usize = 15;
+const NODE_COUNT: usize = 16;
 // TODO(#817): remove this; we shouldn't need to have WASI stubs.
-const WASI_STUB: usize = 16;
+const WASI_STUB: usize = 17;
+const CONSUME_FUEL: usize = 18;
+
+/// Name of the module from which Wasm modules instrumented for fuel metering import the function
+/// that consumes fuel, which is named [`CONSUME_FUEL_FUNCTION`].
+const METERING_MODULE: &str = "oak_metering";
+const CONSUME_FUEL_FUNCTION: &str = "consume_fuel";
 
 // Type aliases for positions and offsets in Wasm linear memory. Any future 64-bit version
 // of Wasm would use different types.
@@ -85,9 +92,24 @@ struct WasmInterface {
     /// relative addresses will perform reads/writes against this reference.
     memory: Option<wasmi::MemoryRef>,
 
+    /// The remaining fuel of the Node, if it is metered.
+    fuel: Option<u64>,
+
     runtime: RuntimeProxy,
 }
 
+/// Host error with which a metered Node is trapped once it has exhausted its fuel.
+#[derive(Debug)]
+struct FuelExhausted;
+
+impl std::fmt::Display for FuelExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "fuel exhausted")
+    }
+}
+
+impl wasmi::HostError for FuelExhausted {}
+
 impl WasmInterface {
     /// Helper function to get memory.
     fn get_memory(&self) -> &wasmi::MemoryRef {
@@ -109,14 +131,32 @@ impl WasmInterface {
     }
 
     /// Creates a new `WasmInterface` structure.
-    pub fn new(pretty_name: &str, runtime: RuntimeProxy) -> WasmInterface {
+    pub fn new(pretty_name: &str, fuel: Option<u64>, runtime: RuntimeProxy) -> WasmInterface {
         WasmInterface {
             pretty_name: pretty_name.to_string(),
             memory: None,
+            fuel,
             runtime,
         }
     }
 
+    /// Consumes `amount` units of fuel, which is called by the metering code injected in the Wasm
+    /// module at the start of each block of instructions.
+    ///
+    /// Traps the Node with [`FuelExhausted`] if not enough fuel is left.
+    fn consume_fuel(&mut self, amount: u64) -> Result<(), wasmi::Trap> {
+        if let Some(fuel) = self.fuel.as_mut() {
+            if *fuel < amount {
+                *fuel = 0;
+                return Err(wasmi::Trap::new(wasmi::TrapKind::Host(Box::new(
+                    FuelExhausted,
+                ))));
+            }
+            *fuel -= amount;
+        }
+        Ok(())
+    }
+
     /// Corresponds to the host ABI function [`node_create`](https://github.com/project-oak/oak/blob/main/docs/abi.md#node_create).
     #[allow(clippy::too_many_arguments)]
     fn node_create(
@@ -765,6 +805,40 @@ impl WasmInterface {
         self.write_label_to_memory(label, dest, actual_length_addr)
     }
 
+    /// Corresponds to the host ABI function [`node_count`](https://github.com/project-oak/oak/blob/main/docs/abi.md#node_count).
+    fn node_count(
+        &mut self,
+        type_buf: AbiPointer,
+        type_len: AbiPointerOffset,
+        count_out: AbiPointer,
+    ) -> Result<(), OakStatus> {
+        trace!(
+            "{}: node_count({}, {}, {})",
+            self.pretty_name,
+            type_buf,
+            type_len,
+            count_out
+        );
+
+        self.validate_ptr(count_out, 8)?;
+
+        let node_type = if type_len == 0 {
+            None
+        } else {
+            Some(self.fetch_string(type_buf, type_len)?)
+        };
+        let count = self.runtime.node_count(node_type.as_deref());
+        self.get_memory()
+            .set_value(count_out, count as i64)
+            .map_err(|err| {
+                error!(
+                    "{}: node_count(): Unable to write count into guest memory: {:?}",
+                    self.pretty_name, err
+                );
+                OakStatus::ErrInvalidArgs
+            })
+    }
+
     /// Helper function to write a serialized label and the actual/required length to Wasm linear
     /// memory at `dest` and `actual_length_addr`.
     fn write_label_to_memory(
@@ -1000,6 +1074,11 @@ impl wasmi::Externals for WasmInterface {
                 args.nth_checked(1)?,
                 args.nth_checked(2)?,
             )),
+            NODE_COUNT => map_host_errors(self.node_count(
+                args.nth_checked(0)?,
+                args.nth_checked(1)?,
+                args.nth_checked(2)?,
+            )),
             WAIT_ON_CHANNELS => {
                 map_host_errors(self.wait_on_channels(args.nth_checked(0)?, args.nth_checked(1)?))
             }
@@ -1007,6 +1086,10 @@ impl wasmi::Externals for WasmInterface {
                 self.wait_on_channels_with_downgrade(args.nth_checked(0)?, args.nth_checked(1)?),
             ),
             WASI_STUB => panic!("Attempt to invoke unimplemented WASI function"),
+            CONSUME_FUEL => {
+                let amount: i64 = args.nth_checked(0)?;
+                self.consume_fuel(amount as u64).map(|()| None)
+            }
             _ => panic!("Unimplemented function at {}", index),
         }
     }
@@ -1227,6 +1310,17 @@ fn oak_resolve_func(
                 Some(ValueType::I32),
             ),
         ),
+        "node_count" => (
+            NODE_COUNT,
+            wasmi::Signature::new(
+                &[
+                    ABI_USIZE, // type_buf
+                    ABI_USIZE, // type_len
+                    ABI_USIZE, // count (out)
+                ][..],
+                Some(ValueType::I32),
+            ),
+        ),
         _ => {
             return Err(wasmi::Error::Instantiation(format!(
                 "Export {} not found",
@@ -1263,6 +1357,33 @@ impl wasmi::ModuleImportResolver for WasmInterfaceStub {
     }
 }
 
+/// Resolver of the function that Wasm modules instrumented for fuel metering import from
+/// [`METERING_MODULE`].
+struct MeteringResolver;
+
+impl wasmi::ModuleImportResolver for MeteringResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        signature: &wasmi::Signature,
+    ) -> Result<wasmi::FuncRef, wasmi::Error> {
+        let sig = wasmi::Signature::new(&[ValueType::I64][..], None);
+        if field_name != CONSUME_FUEL_FUNCTION {
+            return Err(wasmi::Error::Instantiation(format!(
+                "Export {} not found",
+                field_name
+            )));
+        }
+        if &sig != signature {
+            return Err(wasmi::Error::Instantiation(format!(
+                "Export `{}` doesnt match expected type {:?}",
+                field_name, signature
+            )));
+        }
+        Ok(wasmi::FuncInstance::alloc_host(sig, CONSUME_FUEL))
+    }
+}
+
 /// Stub implementation of WASI exported functions, to allow partially-ported
 /// applications that have references to WASI functions to be loaded. Note
 /// that if the the application actually tries to *use* the WASI functions
@@ -1343,7 +1464,8 @@ fn validate_entrypoint(module: &wasmi::Module, entrypoint: &str) -> Result<(), O
         module,
         &wasmi::ImportsBuilder::new()
             .with_resolver("oak", &abi_stub)
-            .with_resolver("wasi_snapshot_preview1", &wasi_stub),
+            .with_resolver("wasi_snapshot_preview1", &wasi_stub)
+            .with_resolver(METERING_MODULE, &MeteringResolver),
     )
     .expect("failed to instantiate wasm module")
     .assert_no_start();
@@ -1377,17 +1499,41 @@ pub struct WasmNode {
     node_name: String,
     module: Arc<wasmi::Module>,
     entrypoint_name: String,
+    /// The fuel budget of the Node, if it is metered.
+    fuel: Option<u64>,
 }
 
 impl WasmNode {
     /// Creates a new [`WasmNode`] instance, but does not start it.
-    /// May fail if the provided Wasm module is not valid.
+    /// May fail if the provided Wasm module is not valid, or if it requires more than
+    /// `max_memory_pages` pages of linear memory.
+    ///
+    /// If a `fuel` budget is provided, the module is instrumented so that the Node is trapped once
+    /// it has executed that many instructions.
     pub fn new(
         node_name: &str,
         wasm_module_bytes: &[u8],
         node_configuration: WebAssemblyConfiguration,
+        max_memory_pages: Option<u32>,
+        fuel: Option<u64>,
     ) -> Result<Self, ConfigurationError> {
-        let module = wasmi::Module::from_buffer(&wasm_module_bytes)
+        let metered_module_bytes;
+        let wasm_module_bytes = match fuel {
+            Some(_) => {
+                metered_module_bytes = inject_metering(wasm_module_bytes)?;
+                &metered_module_bytes
+            }
+            None => wasm_module_bytes,
+        };
+        let limited_module_bytes;
+        let wasm_module_bytes = match max_memory_pages {
+            Some(max_memory_pages) => {
+                limited_module_bytes = limit_memory(wasm_module_bytes, max_memory_pages)?;
+                &limited_module_bytes
+            }
+            None => wasm_module_bytes,
+        };
+        let module = wasmi::Module::from_buffer(wasm_module_bytes)
             .map_err(ConfigurationError::WasmiModuleInializationError)?;
         let entrypoint_name = node_configuration.wasm_entrypoint_name;
         validate_entrypoint(&module, &entrypoint_name).map_err(|err| {
@@ -1399,19 +1545,115 @@ impl WasmNode {
             node_name: node_name.to_string(),
             module: Arc::new(module),
             entrypoint_name,
+            fuel,
         })
     }
 }
 
+/// Instruments the Wasm module in `wasm_module_bytes` to consume one unit of fuel per executed
+/// instruction, by calling the function imported from [`METERING_MODULE`] at the start of each
+/// block of instructions, and returns the instrumented module.
+fn inject_metering(wasm_module_bytes: &[u8]) -> Result<Vec<u8>, ConfigurationError> {
+    use wasm_instrument::{gas_metering, parity_wasm};
+    let invalid_module = |message: String| {
+        ConfigurationError::WasmiModuleInializationError(wasmi::Error::Validation(message))
+    };
+    let module = parity_wasm::deserialize_buffer(wasm_module_bytes)
+        .map_err(|err: parity_wasm::elements::Error| invalid_module(err.to_string()))?;
+    let module = gas_metering::inject(
+        module,
+        gas_metering::host_function::Injector::new(METERING_MODULE, CONSUME_FUEL_FUNCTION),
+        &gas_metering::ConstantCostRules::default(),
+    )
+    .map_err(|_| invalid_module("could not instrument module for fuel metering".to_string()))?;
+    parity_wasm::serialize(module).map_err(|err| invalid_module(err.to_string()))
+}
+
+/// Limits the linear memory of the Wasm module in `wasm_module_bytes` to `max_memory_pages` pages,
+/// by lowering the maximum size of the memories it declares or imports, so that they cannot grow
+/// beyond the limit, and returns the modified module.
+///
+/// Fails if the initial size of a memory is already over the limit.
+fn limit_memory(
+    wasm_module_bytes: &[u8],
+    max_memory_pages: u32,
+) -> Result<Vec<u8>, ConfigurationError> {
+    use wasm_instrument::parity_wasm::{self, elements::External};
+    let invalid_module = |message: String| {
+        ConfigurationError::WasmiModuleInializationError(wasmi::Error::Validation(message))
+    };
+    let mut module: parity_wasm::elements::Module =
+        parity_wasm::deserialize_buffer(wasm_module_bytes)
+            .map_err(|err: parity_wasm::elements::Error| invalid_module(err.to_string()))?;
+    if let Some(import_section) = module.import_section_mut() {
+        for entry in import_section.entries_mut() {
+            if let External::Memory(memory) = entry.external_mut() {
+                limit_memory_type(memory, max_memory_pages)?;
+            }
+        }
+    }
+    if let Some(memory_section) = module.memory_section_mut() {
+        for memory in memory_section.entries_mut() {
+            limit_memory_type(memory, max_memory_pages)?;
+        }
+    }
+    parity_wasm::serialize(module).map_err(|err| invalid_module(err.to_string()))
+}
+
+/// Lowers the maximum size of `memory` to `max_memory_pages` pages, failing if its initial size is
+/// already over the limit.
+fn limit_memory_type(
+    memory: &mut wasm_instrument::parity_wasm::elements::MemoryType,
+    max_memory_pages: u32,
+) -> Result<(), ConfigurationError> {
+    let limits = memory.limits();
+    if limits.initial() > max_memory_pages {
+        warn!(
+            "Wasm module requires {} memory pages, more than the limit of {}",
+            limits.initial(),
+            max_memory_pages
+        );
+        return Err(ConfigurationError::WebAssemblyMemoryLimitExceeded {
+            initial_pages: limits.initial(),
+            max_memory_pages,
+        });
+    }
+    let maximum = limits
+        .maximum()
+        .map_or(max_memory_pages, |maximum| maximum.min(max_memory_pages));
+    *memory =
+        wasm_instrument::parity_wasm::elements::MemoryType::new(limits.initial(), Some(maximum));
+    Ok(())
+}
+
 /// Computes the [`NodePrivilege`] granted to a WebAssembly Node running the specified WebAssembly
 /// module.
 /// Created [`NodePrivilege`] consists of Wasm module hash and any matching signatures.
+///
+/// Fails if the module is revoked in the [`SignatureTable`], regardless of its signatures, or if
+/// its signatures do not satisfy the [`SignaturePolicy`] of the table.
 pub(crate) fn get_privilege(
     wasm_module_bytes: &[u8],
     signature_table: &SignatureTable,
-) -> NodePrivilege {
+) -> Result<NodePrivilege, ConfigurationError> {
     let module_hash = get_sha256_hex(wasm_module_bytes);
     debug!("Wasm module SHA-256 hash: {:?}", module_hash);
+    if signature_table.revoked_hashes.contains(&module_hash) {
+        warn!("Wasm module with SHA-256 hash {} is revoked", module_hash);
+        return Err(ConfigurationError::WebAssemblyModuleRevoked(module_hash));
+    }
+    if let Some(policy) = &signature_table.signature_policy {
+        let signers = trusted_signers(&module_hash, signature_table, policy);
+        if signers < policy.threshold {
+            warn!(
+                "Wasm module with SHA-256 hash {} is signed by {} trusted signers, {} required",
+                module_hash, signers, policy.threshold
+            );
+            return Err(ConfigurationError::InsufficientWebAssemblyModuleSignatures(
+                module_hash,
+            ));
+        }
+    }
 
     // Create hash tags.
     let module_hash_bytes = hex::decode(&module_hash).expect("Couldn't decode SHA-256 hex value");
@@ -1430,7 +1672,31 @@ pub(crate) fn get_privilege(
         }
     }
 
-    NodePrivilege::new(confidentiality_tags, integrity_tags)
+    Ok(NodePrivilege::new(confidentiality_tags, integrity_tags))
+}
+
+/// Returns the number of distinct trusted signers of the [`SignaturePolicy`] that have validly
+/// signed the module identified by `module_hash`.
+fn trusted_signers(
+    module_hash: &str,
+    signature_table: &SignatureTable,
+    policy: &SignaturePolicy,
+) -> usize {
+    signature_table
+        .values
+        .get(module_hash)
+        .into_iter()
+        .flatten()
+        .filter(|signature| {
+            policy
+                .trusted_public_keys
+                .contains(&signature.public_key_der)
+                && hex::encode(&signature.hash) == module_hash
+                && signature.verify().is_ok()
+        })
+        .map(|signature| &signature.public_key_der)
+        .collect::<HashSet<_>>()
+        .len()
 }
 
 impl super::Node for WasmNode {
@@ -1454,13 +1720,14 @@ impl super::Node for WasmNode {
             self.node_name, self.entrypoint_name
         );
         let wasi_stub = WasiStub;
-        let mut abi = WasmInterface::new(&self.node_name, runtime);
+        let mut abi = WasmInterface::new(&self.node_name, self.fuel, runtime.clone());
 
         let instance = wasmi::ModuleInstance::new(
             &self.module,
             &wasmi::ImportsBuilder::new()
                 .with_resolver("oak", &abi)
-                .with_resolver("wasi_snapshot_preview1", &wasi_stub),
+                .with_resolver("wasi_snapshot_preview1", &wasi_stub)
+                .with_resolver(METERING_MODULE, &MeteringResolver),
         )
         .expect("failed to instantiate wasm module")
         .assert_no_start();
@@ -1476,8 +1743,22 @@ impl super::Node for WasmNode {
             &[wasmi::RuntimeValue::I64(handle as i64)],
             &mut abi,
         );
-        if let Err(err) = result {
-            error!("Invocation of Wasm entrypoint failed: {:?}", err);
+        match result {
+            Err(err)
+                if err
+                    .as_host_error()
+                    .and_then(|err| err.downcast_ref::<FuelExhausted>())
+                    .is_some() =>
+            {
+                warn!("{}: Wasm node exhausted its fuel", self.node_name);
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_wasm_nodes_out_of_fuel_total
+                    .inc();
+            }
+            Err(err) => error!("Invocation of Wasm entrypoint failed: {:?}", err),
+            Ok(_) => {}
         }
         debug!(
             "{}: entrypoint '{}' completed",
@@ -23,7 +23,7 @@ use oak_abi::{
         node_configuration::ConfigType, ApplicationConfiguration, WebAssemblyConfiguration,
     },
 };
-use oak_sign::{get_sha256_hex, SignatureBundle};
+use oak_sign::{get_sha256_hex, KeyPair, SignatureAlgorithm, SignatureBundle};
 use std::fs::read;
 use wat::parse_str;
 
@@ -31,6 +31,39 @@ fn start_node(
     wasm_module: Vec<u8>,
     entrypoint_name: &str,
     signatures: &[SignatureBundle],
+) -> Result<(), OakStatus> {
+    start_node_with_signature_table(
+        wasm_module,
+        entrypoint_name,
+        signatures,
+        SignatureTable::default(),
+    )
+}
+
+/// Same as [`start_node`], but adds the signatures to the provided [`SignatureTable`].
+fn start_node_with_signature_table(
+    wasm_module: Vec<u8>,
+    entrypoint_name: &str,
+    signatures: &[SignatureBundle],
+    signature_table: SignatureTable,
+) -> Result<(), OakStatus> {
+    start_node_with_configuration(
+        wasm_module,
+        entrypoint_name,
+        signatures,
+        signature_table,
+        PermissionsConfiguration::default(),
+    )
+}
+
+/// Same as [`start_node_with_signature_table`], but also applies the WebAssembly related settings
+/// of the provided [`PermissionsConfiguration`].
+fn start_node_with_configuration(
+    wasm_module: Vec<u8>,
+    entrypoint_name: &str,
+    signatures: &[SignatureBundle],
+    signature_table: SignatureTable,
+    permissions: PermissionsConfiguration,
 ) -> Result<(), OakStatus> {
     crate::tests::init_logging();
     let module_name = "oak_module";
@@ -52,10 +85,11 @@ fn start_node(
     }
     let permissions = PermissionsConfiguration {
         allow_grpc_server_nodes: true,
-        ..Default::default()
+        ..permissions
     };
     let signature_table = SignatureTable {
         values: hashmap! { module_hash => signatures.to_vec() },
+        ..signature_table
     };
     let proxy = RuntimeProxy::create_runtime(
         &application_configuration,
@@ -179,6 +213,214 @@ fn wasm_verify_module_signature_succeeds() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn wasm_verify_module_ecdsa_signature_succeeds() {
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let signature = load_signature("testdata/minimal_ecdsa.sign");
+    assert_eq!(SignatureAlgorithm::EcdsaP256Sha256, signature.algorithm);
+    let result = start_node(binary, "oak_main", vec![signature].as_ref());
+    assert!(result.is_ok());
+}
+
+#[test]
+fn wasm_verify_module_signature_with_mismatched_algorithm_fails() {
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let signature = SignatureBundle {
+        algorithm: SignatureAlgorithm::Ed25519,
+        ..load_signature("testdata/minimal_ecdsa.sign")
+    };
+    let result = start_node(binary, "oak_main", vec![signature].as_ref());
+    assert_eq!(Some(OakStatus::ErrInvalidArgs), result.err());
+}
+
+#[test]
+fn wasm_starting_revoked_module_fails() {
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let signature = load_signature("testdata/minimal.sign");
+    let result = start_node(binary.clone(), "oak_main", vec![signature.clone()].as_ref());
+    assert!(result.is_ok());
+
+    // The same module is rejected once revoked, even though its signature is still valid.
+    let signature_table = SignatureTable {
+        revoked_hashes: hashset! { get_sha256_hex(&binary) },
+        ..Default::default()
+    };
+    let result = start_node_with_signature_table(
+        binary,
+        "oak_main",
+        vec![signature].as_ref(),
+        signature_table,
+    );
+    assert_eq!(Some(OakStatus::ErrInvalidArgs), result.err());
+}
+
+#[test]
+fn wasm_starting_module_within_memory_limit_succeeds() {
+    // The minimal module declares 18 pages of memory.
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let result = start_node_with_configuration(
+        binary,
+        "oak_main",
+        vec![].as_ref(),
+        SignatureTable::default(),
+        PermissionsConfiguration {
+            max_wasm_memory_pages: Some(18),
+            ..Default::default()
+        },
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn wasm_starting_module_exceeding_memory_limit_fails() {
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let result = start_node_with_configuration(
+        binary,
+        "oak_main",
+        vec![].as_ref(),
+        SignatureTable::default(),
+        PermissionsConfiguration {
+            max_wasm_memory_pages: Some(17),
+            ..Default::default()
+        },
+    );
+    assert_eq!(Some(OakStatus::ErrInvalidArgs), result.err());
+}
+
+#[test]
+fn wasm_starting_module_importing_memory_exceeding_limit_fails() {
+    let wat = r#"
+    (module
+        (import "oak" "memory" (memory 20))
+        (func $oak_main (param i64))
+        (export "oak_main" (func $oak_main)))
+    "#;
+    let result = start_node_with_configuration(
+        parse_str(wat).unwrap(),
+        "oak_main",
+        vec![].as_ref(),
+        SignatureTable::default(),
+        PermissionsConfiguration {
+            max_wasm_memory_pages: Some(18),
+            ..Default::default()
+        },
+    );
+    assert_eq!(Some(OakStatus::ErrInvalidArgs), result.err());
+}
+
+#[test]
+fn wasm_starting_metered_module_succeeds() {
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let result = start_node_with_configuration(
+        binary,
+        "oak_main",
+        vec![].as_ref(),
+        SignatureTable::default(),
+        PermissionsConfiguration {
+            wasm_fuel: Some(1_000),
+            ..Default::default()
+        },
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn wasm_metered_node_exhausting_its_fuel_terminates() {
+    crate::tests::init_logging();
+    // The entrypoint loops forever, so the Node only terminates once out of fuel.
+    let wat = r#"
+    (module
+        (type (;0;) (func (param i64)))
+        (func $oak_main (type 0)
+          (loop
+            br 0))
+        (memory (;0;) 18)
+        (export "memory" (memory 0))
+        (export "oak_main" (func $oak_main)))
+    "#;
+    let application_configuration = ApplicationConfiguration {
+        wasm_modules: hashmap! { "oak_module".to_string() => parse_str(wat).unwrap() },
+        initial_node_configuration: None,
+        module_signatures: vec![],
+    };
+    let permissions = PermissionsConfiguration {
+        wasm_fuel: Some(1_000_000),
+        ..Default::default()
+    };
+    let proxy = RuntimeProxy::create_runtime(
+        &application_configuration,
+        &permissions,
+        &SecureServerConfiguration::default(),
+        &SignatureTable::default(),
+        None,
+    );
+    let (_write_handle, read_handle) = proxy
+        .channel_create("", &Label::public_untrusted())
+        .expect("could not create channel");
+    let result = proxy.node_create(
+        "test",
+        &NodeConfiguration {
+            config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+                wasm_module_name: "oak_module".to_string(),
+                wasm_entrypoint_name: "oak_main".to_string(),
+            })),
+        },
+        &Label::public_untrusted(),
+        read_handle,
+    );
+    assert_eq!(Ok(()), result);
+
+    // Stopping the Runtime waits for the Node to terminate.
+    assert_eq!(crate::ShutdownStatus::Completed, proxy.runtime.stop());
+    assert_eq!(
+        1,
+        proxy
+            .metrics_data()
+            .runtime_metrics
+            .runtime_wasm_nodes_out_of_fuel_total
+            .get()
+    );
+}
+
+/// Starts the minimal module signed by the provided number of the 3 signers trusted by a 2-of-3
+/// signature policy.
+fn start_node_with_trusted_signatures(signers: usize) -> Result<(), OakStatus> {
+    let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let key_pairs: Vec<KeyPair> = (0..3)
+        .map(|_| KeyPair::generate().expect("Couldn't generate key pair"))
+        .collect();
+    let signature_table = SignatureTable {
+        signature_policy: Some(SignaturePolicy {
+            threshold: 2,
+            trusted_public_keys: key_pairs
+                .iter()
+                .map(|key_pair| key_pair.public_key_der().unwrap())
+                .collect(),
+        }),
+        ..Default::default()
+    };
+    let sign = |key_pair| SignatureBundle::create(&binary, key_pair).unwrap();
+    let mut signatures: Vec<SignatureBundle> = key_pairs[..signers].iter().map(sign).collect();
+    // Neither a second signature by the same signer, nor a signature by an untrusted signer, count
+    // towards the threshold.
+    signatures.push(sign(&key_pairs[0]));
+    signatures.push(sign(&KeyPair::generate().unwrap()));
+    start_node_with_signature_table(binary, "oak_main", &signatures, signature_table)
+}
+
+#[test]
+fn wasm_starting_module_with_2_of_3_signatures_succeeds() {
+    assert!(start_node_with_trusted_signatures(2).is_ok());
+}
+
+#[test]
+fn wasm_starting_module_with_1_of_3_signatures_fails() {
+    assert_eq!(
+        Some(OakStatus::ErrInvalidArgs),
+        start_node_with_trusted_signatures(1).err()
+    );
+}
+
 #[test]
 fn wasm_verify_module_signature_fails() {
     let binary = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
@@ -186,3 +428,246 @@ fn wasm_verify_module_signature_fails() {
     let result = start_node(binary, "oak_main", vec![signature].as_ref());
     assert_eq!(Some(OakStatus::ErrInvalidArgs), result.err());
 }
+
+/// Module whose entrypoint reads a message with a single write handle from its initial channel,
+/// and writes the message `"new"` to that handle.
+const REPLACEMENT_MODULE: &str = r#"
+    (module
+        (import "oak" "channel_read"
+          (func $channel_read (param i64 i32 i32 i32 i32 i32 i32) (result i32)))
+        (import "oak" "channel_write"
+          (func $channel_write (param i64 i32 i32 i32 i32) (result i32)))
+        (func $oak_main (param $handle i64)
+          (drop (call $channel_read
+            (local.get $handle) (i32.const 0) (i32.const 0) (i32.const 8)
+            (i32.const 16) (i32.const 1) (i32.const 12)))
+          (drop (call $channel_write
+            (i64.load (i32.const 16)) (i32.const 64) (i32.const 3) (i32.const 0) (i32.const 0))))
+        (memory (;0;) 1)
+        (data (i32.const 64) "new")
+        (export "memory" (memory 0))
+        (export "oak_main" (func $oak_main)))
+    "#;
+
+#[test]
+fn wasm_replacing_module_runs_new_module_in_new_nodes() {
+    crate::tests::init_logging();
+    let application_configuration = ApplicationConfiguration {
+        wasm_modules: hashmap! {
+            "oak_module".to_string() => read("testdata/minimal.wasm").expect("Couldn't read Wasm file"),
+        },
+        initial_node_configuration: None,
+        module_signatures: vec![],
+    };
+    let proxy = RuntimeProxy::create_runtime(
+        &application_configuration,
+        &PermissionsConfiguration::default(),
+        &SecureServerConfiguration::default(),
+        &SignatureTable::default(),
+        None,
+    );
+    let new_module = parse_str(REPLACEMENT_MODULE).unwrap();
+    assert_eq!(
+        Ok(()),
+        proxy
+            .runtime
+            .replace_wasm_module("oak_module", new_module.clone(), &[])
+    );
+    assert_eq!(
+        Some(&new_module),
+        proxy
+            .runtime
+            .node_factory
+            .application_configuration
+            .read()
+            .unwrap()
+            .wasm_modules
+            .get("oak_module")
+    );
+
+    let label = Label::public_untrusted();
+    let (in_write_handle, in_read_handle) = proxy.channel_create("in", &label).unwrap();
+    let (out_write_handle, out_read_handle) = proxy.channel_create("out", &label).unwrap();
+    proxy
+        .channel_write(
+            in_write_handle,
+            NodeMessage {
+                bytes: vec![],
+                handles: vec![out_write_handle],
+            },
+        )
+        .unwrap();
+    let result = proxy.node_create(
+        "test",
+        &NodeConfiguration {
+            config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+                wasm_module_name: "oak_module".to_string(),
+                wasm_entrypoint_name: "oak_main".to_string(),
+            })),
+        },
+        &label,
+        in_read_handle,
+    );
+    assert_eq!(Ok(()), result);
+
+    proxy.wait_on_channels(&[out_read_handle]).unwrap();
+    let message = proxy.channel_read(out_read_handle).unwrap().unwrap();
+    assert_eq!(b"new".to_vec(), message.bytes);
+
+    proxy.runtime.stop();
+}
+
+#[test]
+fn wasm_replacing_module_with_mismatched_signature_fails() {
+    crate::tests::init_logging();
+    let minimal_module = read("testdata/minimal.wasm").expect("Couldn't read Wasm file");
+    let application_configuration = ApplicationConfiguration {
+        wasm_modules: hashmap! { "oak_module".to_string() => minimal_module.clone() },
+        initial_node_configuration: None,
+        module_signatures: vec![],
+    };
+    let proxy = RuntimeProxy::create_runtime(
+        &application_configuration,
+        &PermissionsConfiguration::default(),
+        &SecureServerConfiguration::default(),
+        &SignatureTable::default(),
+        None,
+    );
+    // The signature is valid, but for the minimal module.
+    let signature = load_signature("testdata/minimal.sign");
+    let new_module = parse_str(REPLACEMENT_MODULE).unwrap();
+    assert_eq!(
+        Err(OakStatus::ErrInvalidArgs),
+        proxy
+            .runtime
+            .replace_wasm_module("oak_module", new_module.clone(), &[signature])
+    );
+    assert_eq!(
+        Err(OakStatus::ErrInvalidArgs),
+        proxy
+            .runtime
+            .replace_wasm_module("missing_module", new_module, &[])
+    );
+    assert_eq!(
+        Some(&minimal_module),
+        proxy
+            .runtime
+            .node_factory
+            .application_configuration
+            .read()
+            .unwrap()
+            .wasm_modules
+            .get("oak_module")
+    );
+
+    proxy.runtime.stop();
+}
+
+/// Module whose entrypoint creates a channel and waits on it, and so blocks until the Runtime
+/// terminates, without ever reading from its initial channel.
+const BLOCKING_MODULE: &str = r#"
+    (module
+        (import "oak" "channel_create"
+          (func $channel_create (param i32 i32 i32 i32 i32 i32) (result i32)))
+        (import "oak" "wait_on_channels"
+          (func $wait_on_channels (param i32 i32) (result i32)))
+        (func $oak_main (param $handle i64)
+          (drop (call $channel_create
+            (i32.const 0) (i32.const 16) (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0)))
+          (drop (call $wait_on_channels (i32.const 16) (i32.const 1))))
+        (memory (;0;) 1)
+        (export "memory" (memory 0))
+        (export "oak_main" (func $oak_main)))
+    "#;
+
+/// Creates a Runtime with the [`BLOCKING_MODULE`], and a Node running it whose initial channel
+/// holds a message with the write half of an `"out"` channel. Returns the proxy, along with the
+/// handle to the read half of the `"out"` channel.
+fn start_blocking_node() -> (RuntimeProxy, oak_abi::Handle) {
+    crate::tests::init_logging();
+    let application_configuration = ApplicationConfiguration {
+        wasm_modules: hashmap! {
+            "oak_module".to_string() => parse_str(BLOCKING_MODULE).unwrap(),
+        },
+        initial_node_configuration: None,
+        module_signatures: vec![],
+    };
+    let proxy = RuntimeProxy::create_runtime(
+        &application_configuration,
+        &PermissionsConfiguration::default(),
+        &SecureServerConfiguration::default(),
+        &SignatureTable::default(),
+        None,
+    );
+    let label = Label::public_untrusted();
+    let (in_write_handle, in_read_handle) = proxy.channel_create("in", &label).unwrap();
+    let (out_write_handle, out_read_handle) = proxy.channel_create("out", &label).unwrap();
+    proxy
+        .channel_write(
+            in_write_handle,
+            NodeMessage {
+                bytes: vec![],
+                handles: vec![out_write_handle],
+            },
+        )
+        .unwrap();
+    let result = proxy.node_create(
+        "test",
+        &NodeConfiguration {
+            config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+                wasm_module_name: "oak_module".to_string(),
+                wasm_entrypoint_name: "oak_main".to_string(),
+            })),
+        },
+        &label,
+        in_read_handle,
+    );
+    assert_eq!(Ok(()), result);
+    (proxy, out_read_handle)
+}
+
+#[test]
+fn wasm_respawning_nodes_runs_replaced_module() {
+    let (proxy, out_read_handle) = start_blocking_node();
+    let new_module = parse_str(REPLACEMENT_MODULE).unwrap();
+    assert_eq!(
+        Ok(()),
+        proxy
+            .runtime
+            .replace_wasm_module("oak_module", new_module, &[])
+    );
+    assert_eq!(
+        Ok(vec![]),
+        proxy.runtime.respawn_wasm_module_nodes("other_module")
+    );
+    let new_node_ids = proxy
+        .runtime
+        .respawn_wasm_module_nodes("oak_module")
+        .unwrap();
+    assert_eq!(1, new_node_ids.len());
+
+    // The new Node reads the message queued on the initial channel of the previous Node.
+    proxy.wait_on_channels(&[out_read_handle]).unwrap();
+    let message = proxy.channel_read(out_read_handle).unwrap().unwrap();
+    assert_eq!(b"new".to_vec(), message.bytes);
+
+    proxy.runtime.stop();
+}
+
+#[test]
+fn wasm_replacing_module_without_live_entrypoint_fails() {
+    let (proxy, _out_read_handle) = start_blocking_node();
+    // The new module would be valid for a Node created afterwards with another entrypoint, but
+    // not for respawning the live Node.
+    let new_module =
+        parse_str(REPLACEMENT_MODULE.replace("(export \"oak_main\"", "(export \"other_main\""))
+            .unwrap();
+    assert_eq!(
+        Err(OakStatus::ErrInvalidArgs),
+        proxy
+            .runtime
+            .replace_wasm_module("oak_module", new_module, &[])
+    );
+
+    proxy.runtime.stop();
+}
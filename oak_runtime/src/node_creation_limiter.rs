@@ -0,0 +1,108 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Rate limiting of Node creation.
+//!
+//! A Node that rapidly creates other Nodes is commonly a sign of a bug. When enabled, each Node
+//! gets a token bucket that holds up to a burst of tokens and is refilled at a constant rate.
+//! Creating a Node takes a token from the bucket of the creating Node, and fails with
+//! [`OakStatus::ErrResourceExhausted`](oak_abi::OakStatus::ErrResourceExhausted) if the bucket is
+//! empty, in which case the creation may be retried once the bucket has been refilled.
+
+use crate::NodeId;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::Instant,
+};
+
+/// Configuration of the rate at which each Node may create other Nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeCreationRateLimit {
+    /// Number of Node creations per second that each Node is allowed in the long run.
+    pub rate: f64,
+    /// Number of Node creations that each Node is allowed in a burst, starting from a full
+    /// bucket.
+    pub burst: u32,
+}
+
+/// Token bucket of a single Node.
+struct TokenBucket {
+    /// Number of tokens in the bucket when it was last updated.
+    tokens: f64,
+    /// Time at which the bucket was last updated.
+    updated: Instant,
+}
+
+/// Tracks the token bucket of each Node that has created other Nodes.
+#[derive(Default)]
+pub(crate) struct NodeCreationRateLimiter {
+    limit: RwLock<Option<NodeCreationRateLimit>>,
+    buckets: Mutex<HashMap<NodeId, TokenBucket>>,
+}
+
+impl NodeCreationRateLimiter {
+    /// Enables rate limiting with the provided limit, or disables it if `limit` is `None`.
+    pub(crate) fn set_limit(&self, limit: Option<NodeCreationRateLimit>) {
+        *self.limit.write().unwrap() = limit;
+        self.buckets.lock().unwrap().clear();
+    }
+
+    /// Takes `count` tokens from the bucket of the Node identified by `node_id`, refilling it first
+    /// according to the time elapsed until `now`. Either all the tokens are taken, or none.
+    ///
+    /// Returns whether the Node is allowed to create `count` Nodes, which is always the case if
+    /// rate limiting is disabled.
+    pub(crate) fn try_acquire(&self, node_id: NodeId, count: usize, now: Instant) -> bool {
+        let limit = match *self.limit.read().unwrap() {
+            Some(limit) => limit,
+            None => return true,
+        };
+        let burst = f64::from(limit.burst);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(node_id).or_insert(TokenBucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * limit.rate).min(burst);
+        bucket.updated = now;
+        let count = count as f64;
+        if bucket.tokens >= count {
+            bucket.tokens -= count;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `count` tokens to the bucket of the Node identified by `node_id`, after the creation
+    /// of the Nodes for which they were taken failed.
+    pub(crate) fn release(&self, node_id: NodeId, count: usize) {
+        let limit = match *self.limit.read().unwrap() {
+            Some(limit) => limit,
+            None => return,
+        };
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(&node_id) {
+            bucket.tokens = (bucket.tokens + count as f64).min(f64::from(limit.burst));
+        }
+    }
+
+    /// Forgets the token bucket of the Node identified by `node_id`.
+    pub(crate) fn remove_node(&self, node_id: NodeId) {
+        self.buckets.lock().unwrap().remove(&node_id);
+    }
+}
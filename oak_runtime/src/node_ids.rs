@@ -0,0 +1,123 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Allocation of [`NodeId`]s derived from Node names rather than from the order in which Nodes are
+//! created, so that the same application gets the same [`NodeId`]s across runs (e.g. to compare
+//! graph snapshots).
+//!
+//! The [`NodeId`] of a Node is derived from a hash of its name and of the number of Nodes with the
+//! same name created before it, so only the relative creation order of Nodes with the same name
+//! matters. Collisions with the [`NodeId`]s of live Nodes (including those allocated from the
+//! monotonic counter before the mode was enabled) are resolved by moving on to the next number, up
+//! to a bounded number of attempts after which the monotonic counter is used instead.
+//!
+//! The state kept for this grows with the number of live Nodes and of distinct Node names, but not
+//! with the number of Nodes created over time.
+
+use crate::{NodeId, Runtime};
+use log::warn;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Mutex,
+    },
+};
+
+/// Maximum number of candidate [`NodeId`]s derived for a single Node. Since candidates are 64-bit
+/// hashes, a collision is already extremely unlikely, so this is only a safeguard against looping
+/// forever.
+const MAX_CANDIDATES: usize = 16;
+
+#[derive(Default)]
+struct StableNodeIdsState {
+    /// Number of [`NodeId`]s derived from each Node name so far, with one entry per distinct Node
+    /// name.
+    name_counts: HashMap<String, u64>,
+    /// The [`NodeId`]s allocated to Nodes that have not been removed yet. Since the number of each
+    /// Node name keeps increasing, a removed Node's [`NodeId`] is only derived again for another
+    /// Node in case of a hash collision.
+    allocated: HashSet<u64>,
+}
+
+/// Tracks the [`NodeId`]s derived from Node names.
+#[derive(Default)]
+pub(crate) struct StableNodeIds {
+    enabled: AtomicBool,
+    state: Mutex<StableNodeIdsState>,
+}
+
+/// Returns the candidate [`NodeId`] value for the Node with the provided name and number.
+fn stable_id(node_name: &str, number: u64) -> u64 {
+    let mut input = node_name.as_bytes().to_vec();
+    input.push(0);
+    input.extend_from_slice(&number.to_le_bytes());
+    let hash = oak_sign::get_sha256(&input);
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+// Stable Node ID related methods for the Runtime.
+impl Runtime {
+    /// Enables or disables the derivation of [`NodeId`]s from Node names.
+    pub(crate) fn set_stable_node_ids(&self, enabled: bool) {
+        self.stable_node_ids.enabled.store(enabled, SeqCst);
+    }
+
+    /// Returns a [`NodeId`] derived from `node_name` if enabled, or `None` if [`NodeId`]s should
+    /// be allocated from the monotonic counter instead.
+    pub(crate) fn stable_node_id(&self, node_name: &str) -> Option<NodeId> {
+        if !self.stable_node_ids.enabled.load(SeqCst) {
+            return None;
+        }
+        // Values below the counter may have been allocated from it.
+        let counter = self.next_node_id.load(SeqCst);
+        let mut state = self.stable_node_ids.state.lock().unwrap();
+        let state = &mut *state;
+        let number = state.name_counts.entry(node_name.to_string()).or_insert(0);
+        for _ in 0..MAX_CANDIDATES {
+            let candidate = stable_id(node_name, *number);
+            *number += 1;
+            if candidate >= counter && state.allocated.insert(candidate) {
+                return Some(NodeId(candidate));
+            }
+        }
+        warn!(
+            "could not derive a node ID for node {} after {} attempts",
+            node_name, MAX_CANDIDATES
+        );
+        None
+    }
+
+    /// Releases the [`NodeId`] derived for a Node that has been removed, or that could not be
+    /// added, so that only the [`NodeId`]s of live Nodes are tracked.
+    pub(crate) fn release_stable_node_id(&self, node_id: NodeId) {
+        self.stable_node_ids
+            .state
+            .lock()
+            .unwrap()
+            .allocated
+            .remove(&node_id.0);
+    }
+
+    /// Returns the number of [`NodeId`]s tracked as allocated, and the number of Node names
+    /// tracked.
+    #[cfg(test)]
+    pub(crate) fn stable_node_ids_state_size(&self) -> (usize, usize) {
+        let state = self.stable_node_ids.state.lock().unwrap();
+        (state.allocated.len(), state.name_counts.len())
+    }
+}
@@ -0,0 +1,93 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Tombstones for recently removed Nodes.
+//!
+//! A Node thread may terminate, and the Node be removed from the Runtime, before the Runtime has
+//! finished registering it (see https://github.com/project-oak/oak/issues/1762). When enabled, a
+//! tombstone is kept for each removed Node for a grace period, so that such late registration
+//! steps can be recognized as expected rather than reported as errors.
+
+use crate::NodeId;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Tracks the Nodes removed within the configured grace period.
+#[derive(Default)]
+pub(crate) struct NodeTombstones {
+    /// Grace period in milliseconds, or zero if tombstones are not kept.
+    grace_period_millis: AtomicU64,
+    /// Time at which each recently removed Node was removed.
+    removed: Mutex<HashMap<NodeId, Instant>>,
+}
+
+impl NodeTombstones {
+    /// Enables tombstones, keeping them for the provided grace period, or disables them if
+    /// `grace_period` is `None`.
+    pub(crate) fn set_grace_period(&self, grace_period: Option<Duration>) {
+        self.grace_period_millis.store(
+            grace_period.map_or(0, |grace_period| grace_period.as_millis() as u64),
+            SeqCst,
+        );
+        if grace_period.is_none() {
+            self.removed.lock().unwrap().clear();
+        }
+    }
+
+    /// Records that the Node identified by `node_id` has been removed, if tombstones are enabled.
+    ///
+    /// Expired tombstones are swept at the same time, so that their number stays bounded by the
+    /// rate at which Nodes are removed.
+    pub(crate) fn add(&self, node_id: NodeId) {
+        let grace_period = match self.grace_period_millis.load(SeqCst) {
+            0 => return,
+            millis => Duration::from_millis(millis),
+        };
+        let now = Instant::now();
+        let mut removed = self.removed.lock().unwrap();
+        sweep(&mut removed, grace_period, now);
+        removed.insert(node_id, now);
+    }
+
+    /// Removes the tombstone of the Node identified by `node_id`, returning whether the Node was
+    /// removed within the grace period.
+    ///
+    /// Expired tombstones are swept at the same time, so that they do not linger once Nodes stop
+    /// being removed.
+    pub(crate) fn take(&self, node_id: NodeId) -> bool {
+        let grace_period = Duration::from_millis(self.grace_period_millis.load(SeqCst));
+        let mut removed = self.removed.lock().unwrap();
+        sweep(&mut removed, grace_period, Instant::now());
+        removed.remove(&node_id).is_some()
+    }
+
+    /// Returns whether a tombstone is currently kept for the Node identified by `node_id`.
+    #[cfg(test)]
+    pub(crate) fn contains(&self, node_id: NodeId) -> bool {
+        self.removed.lock().unwrap().contains_key(&node_id)
+    }
+}
+
+/// Drops the tombstones in `removed` that are older than `grace_period` at time `now`.
+fn sweep(removed: &mut HashMap<NodeId, Instant>, grace_period: Duration, now: Instant) {
+    removed.retain(|_, removal_time| now.duration_since(*removal_time) < grace_period);
+}
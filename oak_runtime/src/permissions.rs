@@ -15,9 +15,12 @@
 //
 
 use anyhow::{anyhow, Context};
-use oak_abi::proto::oak::application::{
-    node_configuration::ConfigType, GrpcClientConfiguration, HttpClientConfiguration,
-    NodeConfiguration,
+use oak_abi::{
+    label::Label,
+    proto::oak::application::{
+        node_configuration::ConfigType, GrpcClientConfiguration, HttpClientConfiguration,
+        NodeConfiguration,
+    },
 };
 
 /// Provides a declarative description of the features that are permitted
@@ -37,6 +40,10 @@ pub struct PermissionsConfiguration {
     #[serde(default)]
     pub allow_http_server_nodes: bool,
 
+    /// Whether to enable the use of WebSocketServerNode.
+    #[serde(default)]
+    pub allow_web_socket_server_nodes: bool,
+
     /// Whether to enable the use of LogNode.
     #[serde(default)]
     pub allow_log_nodes: bool,
@@ -53,6 +60,31 @@ pub struct PermissionsConfiguration {
     /// `allow_insecure_http_egress` flag.
     #[serde(default)]
     pub allow_egress_https_authorities: Vec<String>,
+
+    /// Label that Nodes which are not sandboxed, and may therefore communicate externally without
+    /// restriction, must be able to downgrade their label to before they are started. Defaults to
+    /// the "public untrusted" label if not provided; a label with integrity tags imposes a higher
+    /// bar, by also requiring such Nodes to have the privilege to endorse data with these tags.
+    #[serde(default)]
+    pub uncontrolled_node_egress_label: Option<Label>,
+
+    /// Maximum number of 64 KiB pages of linear memory of each WebAssembly Node. WebAssembly
+    /// modules declaring more initial memory than that cannot be instantiated, and the memory of
+    /// the others cannot grow beyond it. Unlimited if not provided.
+    #[serde(default)]
+    pub max_wasm_memory_pages: Option<u32>,
+
+    /// Number of units of fuel that each WebAssembly Node may consume, one per executed
+    /// instruction. A Node that exhausts its fuel is trapped, and terminates. WebAssembly Nodes
+    /// are not metered if not provided, which avoids the overhead of metering.
+    #[serde(default)]
+    pub wasm_fuel: Option<u64>,
+
+    /// Maximum number of handles in the handle table of each Node. Operations that would add
+    /// handles beyond it (cloning a handle, creating a channel, or reading a message carrying
+    /// handles) fail with `ERR_INVALID_ARGS`. Unlimited if not provided.
+    #[serde(default)]
+    pub max_handles_per_node: Option<usize>,
 }
 
 impl PermissionsConfiguration {
@@ -69,6 +101,9 @@ impl PermissionsConfiguration {
                 Some(ConfigType::GrpcClientConfig(config)) => self.allow_grpc_client(config),
                 Some(ConfigType::HttpServerConfig(_config)) => Ok(self.allow_http_server_nodes),
                 Some(ConfigType::HttpClientConfig(config)) => Ok(self.allow_http_client(config)),
+                Some(ConfigType::WebSocketServerConfig(_config)) => {
+                    Ok(self.allow_web_socket_server_nodes)
+                }
                 _ => Ok(true),
             }
         }
@@ -21,6 +21,10 @@ pub mod oak {
         include!(concat!(env!("OUT_DIR"), "/oak.authentication.rs"));
     }
 
+    pub mod dead_letter {
+        include!(concat!(env!("OUT_DIR"), "/oak.dead_letter.rs"));
+    }
+
     pub mod introspection_events {
         include!(concat!(env!("OUT_DIR"), "/oak.introspection_events.rs"));
     }
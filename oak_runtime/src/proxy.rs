@@ -18,22 +18,27 @@
 //! context of a specific Node or pseudo-Node.
 
 use crate::{
-    construct_debug_id, metrics::Metrics, node::ServerNodeFactory,
-    permissions::PermissionsConfiguration, AuxServer, ChannelHalfDirection, Downgrading,
-    LabelReadStatus, NodeId, NodeMessage, NodePrivilege, NodeReadStatus, Runtime,
-    RuntimeConfiguration, SecureServerConfiguration, SignatureTable,
+    construct_debug_id, kms_credentials::KmsCredentials, metrics::Metrics, node::ServerNodeFactory,
+    permissions::PermissionsConfiguration, AuxServer, ChannelHalfDirection, ChannelOptions,
+    ChannelReadOutcome, CloseReason, DowngradeRecord, Downgrading, EnsuredNode, LabelReadStatus,
+    NodeCapacity, NodeId, NodeMessage, NodePrivilege, NodeReadStatus, PeekedMessage, Runtime,
+    RuntimeConfiguration, SecureServerConfiguration, SignatureTable, StartupOrder, SubgraphSpec,
+    DEFAULT_INTROSPECTION_EVENT_QUEUE_CAPACITY, DEFAULT_LOG_RATE_LIMIT_INTERVAL,
+    DEFAULT_MAX_HANDLES_PER_MESSAGE,
 };
-use core::sync::atomic::{AtomicBool, AtomicU64};
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::SeqCst};
 use log::debug;
 use oak_abi::{
     label::Label,
     proto::oak::application::{ApplicationConfiguration, NodeConfiguration},
     ChannelReadStatus, OakStatus,
 };
+use rand::rngs::StdRng;
 use std::{
     cell::RefCell,
     collections::{HashMap, VecDeque},
     sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
 };
 
 #[cfg(test)]
@@ -73,30 +78,73 @@ impl RuntimeProxy {
     ) -> RuntimeProxy {
         let runtime = Arc::new(Runtime {
             terminating: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
             next_channel_id: AtomicU64::new(0),
             node_infos: RwLock::new(HashMap::new()),
             next_node_id: AtomicU64::new(0),
             aux_servers: Mutex::new(Vec::new()),
             introspection_event_queue: Mutex::new(VecDeque::new()),
+            introspection_event_queue_capacity: AtomicUsize::new(
+                DEFAULT_INTROSPECTION_EVENT_QUEUE_CAPACITY,
+            ),
+            introspection_events_dropped: AtomicU64::new(0),
+            disabled_introspection_event_categories: AtomicU64::new(0),
+            #[cfg(feature = "oak-unsafe")]
+            introspection_event_log: Mutex::new(None),
+            introspection_event_subscribers: Mutex::new(Vec::new()),
             metrics_data: Metrics::new(),
+            ensure_node_lock: Mutex::new(()),
+            downgrade_history_limit: AtomicUsize::new(0),
+            tag_message_writers: AtomicBool::new(false),
+            max_handles_per_message: AtomicUsize::new(DEFAULT_MAX_HANDLES_PER_MESSAGE),
+            node_config_defaults: RwLock::new(HashMap::new()),
+            log_rate_limit_interval_millis: AtomicU64::new(
+                DEFAULT_LOG_RATE_LIMIT_INTERVAL.as_millis() as u64,
+            ),
+            log_rate_limiter: Default::default(),
+            channel_queue_lengths: Default::default(),
+            channel_message_rates: Default::default(),
+            channel_label_buckets: Default::default(),
+            require_unique_channel_names: AtomicBool::new(false),
+            channel_names: Mutex::new(HashMap::new()),
+            node_tombstones: Default::default(),
+            exited_node_stoppers: Mutex::new(HashMap::new()),
+            joined_node_failures: Mutex::new(VecDeque::new()),
+            stable_node_ids: Default::default(),
+            rng_seed: Mutex::new(None),
+            shutdown_timeout_millis: AtomicU64::new(0),
+            clock: RwLock::new(Arc::new(crate::time::SystemClock)),
+            dead_letter_channel: RwLock::new(None),
+            introspect_json_labels: AtomicBool::new(false),
+            introspect_auth_token: RwLock::new(None),
+            max_nodes: AtomicUsize::new(usize::MAX),
+            reserved_nodes: AtomicUsize::new(0),
+            node_creation_rate_limiter: Default::default(),
+            track_waiting_nodes: AtomicBool::new(false),
             node_factory: ServerNodeFactory {
-                application_configuration: application_configuration.clone(),
+                application_configuration: RwLock::new(application_configuration.clone()),
                 permissions_configuration: permissions_configuration.clone(),
                 secure_server_configuration: secure_server_configuration.clone(),
-                signature_table: signature_table.clone(),
-                kms_credentials: kms_credentials.map(|p| p.to_path_buf()),
+                signature_table: RwLock::new(signature_table.clone()),
+                kms_credentials: Arc::new(KmsCredentials::new(
+                    kms_credentials.map(|p| p.to_path_buf()),
+                )),
             },
         });
         let new_node_name = "implicit.initial";
         let proxy = runtime.proxy_for_new_node(new_node_name);
         let new_node_id = proxy.node_id;
-        proxy.runtime.node_configure_instance(
-            new_node_id,
-            "implicit",
-            new_node_name,
-            &Label::public_untrusted(),
-            &NodePrivilege::default(),
-        );
+        proxy
+            .runtime
+            .node_configure_instance(
+                new_node_id,
+                "implicit",
+                new_node_name,
+                &Label::public_untrusted(),
+                &NodePrivilege::default(),
+                NodeCapacity::Limit(usize::MAX),
+            )
+            .expect("could not configure the implicit initial node");
         proxy
     }
 
@@ -105,6 +153,9 @@ impl RuntimeProxy {
     /// After starting a [`Runtime`], calling [`Runtime::stop`] will notify all Nodes that they
     /// should terminate, and wait for them to terminate.
     ///
+    /// Auxiliary servers are started relative to the initial Node as specified by
+    /// [`RuntimeConfiguration::startup_order`].
+    ///
     /// Returns a writable [`oak_abi::Handle`] to send messages into the initial Node created from
     /// the configuration.
     pub fn start_runtime(
@@ -115,39 +166,97 @@ impl RuntimeProxy {
             .runtime
             .node_factory
             .application_configuration
+            .read()
+            .unwrap()
             .initial_node_configuration
-            .as_ref()
+            .clone()
             .ok_or(OakStatus::ErrInvalidArgs)?;
 
         self.metrics_data()
             .runtime_metrics
             .runtime_health_check
             .set(1);
-
+        self.runtime
+            .set_downgrade_history_limit(runtime_configuration.downgrade_history_limit);
+        self.runtime.max_handles_per_message.store(
+            runtime_configuration
+                .max_handles_per_message
+                .unwrap_or(DEFAULT_MAX_HANDLES_PER_MESSAGE),
+            SeqCst,
+        );
+        self.runtime.introspection_event_queue_capacity.store(
+            runtime_configuration
+                .introspection_event_queue_capacity
+                .unwrap_or(DEFAULT_INTROSPECTION_EVENT_QUEUE_CAPACITY),
+            SeqCst,
+        );
+        *self.runtime.node_config_defaults.write().unwrap() =
+            runtime_configuration.node_config_defaults.clone();
+        self.runtime.set_log_rate_limit_interval(
+            runtime_configuration
+                .log_rate_limit_interval
+                .unwrap_or(DEFAULT_LOG_RATE_LIMIT_INTERVAL),
+        );
+        self.runtime.set_channel_queue_length_limit(
+            runtime_configuration.channel_queue_length_metric_limit,
+        );
+        self.runtime.set_channel_message_rate_limit(
+            runtime_configuration.channel_message_rate_metric_limit,
+        );
+        self.runtime
+            .set_channel_label_buckets(runtime_configuration.channel_label_buckets.clone());
+        self.runtime
+            .require_unique_channel_names
+            .store(runtime_configuration.require_unique_channel_names, SeqCst);
+        self.runtime
+            .node_tombstones
+            .set_grace_period(runtime_configuration.node_tombstone_grace_period);
+        self.runtime.set_rng_seed(runtime_configuration.rng_seed);
+        self.runtime
+            .set_stable_node_ids(runtime_configuration.stable_node_ids);
+        self.runtime
+            .set_tag_message_writers(runtime_configuration.tag_message_writers);
+        self.runtime
+            .set_shutdown_timeout(runtime_configuration.shutdown_timeout);
+        self.runtime
+            .introspect_json_labels
+            .store(runtime_configuration.introspect_json_labels, SeqCst);
+        *self.runtime.introspect_auth_token.write().unwrap() =
+            runtime_configuration.introspect_auth_token.clone();
+        self.runtime.set_max_nodes(runtime_configuration.max_nodes);
+        self.runtime.track_waiting_nodes.store(
+            runtime_configuration.heartbeat_config.is_some()
+                || runtime_configuration.watchdog_config.is_some(),
+            SeqCst,
+        );
+        self.runtime
+            .node_creation_rate_limiter
+            .set_limit(runtime_configuration.node_creation_rate_limit);
+        if let Some(handle) = runtime_configuration.dead_letter_handle {
+            let half = self.runtime.abi_to_write_half(self.node_id, handle)?;
+            self.runtime.set_dead_letter_channel(Some(half));
+        }
         #[cfg(feature = "oak-unsafe")]
-        if let Some(port) = runtime_configuration.introspect_port {
+        if let Some(path) = &runtime_configuration.introspection_event_log_path {
             self.runtime
-                .aux_servers
-                .lock()
-                .unwrap()
-                .push(AuxServer::new(
-                    "introspect",
-                    port,
-                    self.runtime.clone(),
-                    crate::introspect::serve,
-                ));
+                .open_introspection_event_log(path)
+                .map_err(|err| {
+                    log::error!(
+                        "could not open introspection event log {}: {:?}",
+                        path.display(),
+                        err
+                    );
+                    OakStatus::ErrInternal
+                })?;
         }
-        if let Some(port) = runtime_configuration.metrics_port {
-            self.runtime
-                .aux_servers
-                .lock()
-                .unwrap()
-                .push(AuxServer::new(
-                    "metrics",
-                    port,
-                    self.runtime.clone(),
-                    crate::metrics::server::start_metrics_server,
-                ));
+
+        if runtime_configuration.startup_order != StartupOrder::NodesFirst {
+            self.start_aux_servers(&runtime_configuration);
+        }
+        if runtime_configuration.startup_order == StartupOrder::AuxServersFirst {
+            for aux_server in self.runtime.aux_servers.lock().unwrap().iter_mut() {
+                aux_server.wait_until_bound()?;
+            }
         }
 
         // When first starting, we assign the least privileged label to the channel connecting the
@@ -163,7 +272,7 @@ impl RuntimeProxy {
 
         self.node_create(
             "Initial",
-            node_configuration,
+            &node_configuration,
             // When first starting, we assign the least privileged label to the entrypoint Node.
             &Label::public_untrusted(),
             read_handle,
@@ -171,14 +280,116 @@ impl RuntimeProxy {
         self.channel_close(read_handle)
             .expect("could not close channel");
 
+        if runtime_configuration.startup_order == StartupOrder::NodesFirst {
+            self.start_aux_servers(&runtime_configuration);
+        }
+
         Ok(write_handle)
     }
 
+    /// Starts the auxiliary servers enabled in the provided [`RuntimeConfiguration`], without
+    /// waiting for them to be ready.
+    fn start_aux_servers(&self, runtime_configuration: &RuntimeConfiguration) {
+        #[cfg(feature = "oak-unsafe")]
+        if let Some(address) = runtime_configuration.introspect_address {
+            self.runtime
+                .aux_servers
+                .lock()
+                .unwrap()
+                .push(AuxServer::new(
+                    "introspect",
+                    address,
+                    runtime_configuration.aux_server_tls_config.clone(),
+                    self.runtime.clone(),
+                    crate::introspect::serve,
+                ));
+        }
+        if let Some(heartbeat_config) = runtime_configuration.heartbeat_config.clone() {
+            self.runtime
+                .aux_servers
+                .lock()
+                .unwrap()
+                .push(AuxServer::spawn(
+                    "heartbeat-monitor",
+                    self.runtime.clone(),
+                    move |runtime, termination_notification_receiver| {
+                        crate::heartbeat::monitor_heartbeats(
+                            heartbeat_config,
+                            runtime,
+                            termination_notification_receiver,
+                        )
+                    },
+                ));
+        }
+        if let Some(watchdog_config) = runtime_configuration.watchdog_config.clone() {
+            self.runtime
+                .aux_servers
+                .lock()
+                .unwrap()
+                .push(AuxServer::spawn(
+                    "watchdog",
+                    self.runtime.clone(),
+                    move |runtime, termination_notification_receiver| {
+                        crate::watchdog::monitor_waiting_nodes(
+                            watchdog_config,
+                            runtime,
+                            termination_notification_receiver,
+                        )
+                    },
+                ));
+        }
+        if let Some(address) = runtime_configuration.metrics_address {
+            self.runtime
+                .aux_servers
+                .lock()
+                .unwrap()
+                .push(AuxServer::new(
+                    "metrics",
+                    address,
+                    runtime_configuration.aux_server_tls_config.clone(),
+                    self.runtime.clone(),
+                    crate::metrics::server::start_metrics_server,
+                ));
+        }
+        if let Some(metrics_push) = runtime_configuration.metrics_push.clone() {
+            self.runtime
+                .aux_servers
+                .lock()
+                .unwrap()
+                .push(AuxServer::spawn(
+                    "metrics-push",
+                    self.runtime.clone(),
+                    move |runtime, termination_notification_receiver| {
+                        crate::metrics::push::push_metrics(
+                            metrics_push,
+                            runtime,
+                            termination_notification_receiver,
+                        )
+                    },
+                ));
+        }
+    }
+
     /// See [`Runtime::is_terminating`].
     pub fn is_terminating(&self) -> bool {
         self.runtime.is_terminating()
     }
 
+    /// See [`Runtime::now`].
+    pub(crate) fn now(&self) -> Instant {
+        self.runtime.now()
+    }
+
+    /// See [`Runtime::node_rng`].
+    pub(crate) fn node_rng(&self, node_name: &str) -> Option<StdRng> {
+        self.runtime.node_rng(node_name)
+    }
+
+    /// See [`Runtime::is_draining`].
+    pub fn is_draining(&self) -> bool {
+        self.runtime.is_draining()
+    }
+
     /// Calls [`Runtime::node_create_and_register`] without using the Node's privilege.
     pub fn node_create(
         &self,
@@ -194,14 +405,19 @@ impl RuntimeProxy {
             config,
             label
         );
-        let result = self.runtime.clone().node_create_and_register(
-            self.node_id,
-            name,
-            config,
-            label,
-            initial_handle,
-            Downgrading::No,
-        );
+        let result = self
+            .runtime
+            .clone()
+            .node_create_and_register(
+                self.node_id,
+                name,
+                config,
+                label,
+                initial_handle,
+                None,
+                Downgrading::No,
+            )
+            .map(|_node_id| ());
         debug!(
             "{:?}: node_create({:?}, {:?}, {:?}) -> {:?}",
             self.get_debug_id(),
@@ -225,14 +441,19 @@ impl RuntimeProxy {
             "{:?}: node_create_with_downgrade({:?}, {:?}, {:?})",
             self.node_id, name, config, label
         );
-        let result = self.runtime.clone().node_create_and_register(
-            self.node_id,
-            name,
-            config,
-            label,
-            initial_handle,
-            Downgrading::Yes,
-        );
+        let result = self
+            .runtime
+            .clone()
+            .node_create_and_register(
+                self.node_id,
+                name,
+                config,
+                label,
+                initial_handle,
+                None,
+                Downgrading::Yes,
+            )
+            .map(|_node_id| ());
         debug!(
             "{:?}: node_create_with_downgrade({:?}, {:?}, {:?}) -> {:?}",
             self.node_id, name, config, label, result
@@ -240,184 +461,680 @@ impl RuntimeProxy {
         result
     }
 
-    /// See [`Runtime::node_register`]. This is exposed to facilitate testing.
-    #[cfg(test)]
-    pub fn node_register(
+    /// Calls [`Runtime::node_create_and_register`] without using the Node's privilege, only
+    /// allowing the new Node to create Nodes of the provided types.
+    pub fn node_create_with_creatable_node_types(
         &self,
-        created_node: CreatedNode,
-        node_name: &str,
+        name: &str,
+        config: &NodeConfiguration,
         label: &Label,
         initial_handle: oak_abi::Handle,
+        creatable_node_types: &[&str],
     ) -> Result<(), OakStatus> {
         debug!(
-            "{:?}: register_node_instance(node_name: {:?}, label: {:?})",
+            "{:?}: node_create_with_creatable_node_types({:?}, {:?}, {:?}, {:?})",
             self.get_debug_id(),
-            node_name,
-            label
-        );
-        let result = self.runtime.clone().node_register(
-            self.node_id,
-            created_node,
-            node_name,
+            name,
+            config,
             label,
-            initial_handle,
-            Downgrading::No,
+            creatable_node_types
         );
+        let result = self
+            .runtime
+            .clone()
+            .node_create_and_register(
+                self.node_id,
+                name,
+                config,
+                label,
+                initial_handle,
+                Some(creatable_node_types),
+                Downgrading::No,
+            )
+            .map(|_node_id| ());
         debug!(
-            "{:?}: register_node_instance(node_name: {:?}, label: {:?}) -> {:?}",
+            "{:?}: node_create_with_creatable_node_types({:?}, {:?}, {:?}, {:?}) -> {:?}",
             self.get_debug_id(),
-            node_name,
+            name,
+            config,
             label,
+            creatable_node_types,
             result
         );
         result
     }
 
-    /// Calls [`Runtime::channel_create`] without using the Node's privilege.
-    pub fn channel_create(
+    /// Calls [`Runtime::instantiate_subgraph`] without using the Node's privilege.
+    pub fn instantiate_subgraph(
         &self,
-        name: &str,
-        label: &Label,
-    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
+        spec: &SubgraphSpec,
+    ) -> Result<HashMap<String, NodeId>, OakStatus> {
         debug!(
-            "{:?}: channel_create({:?}, {:?})",
+            "{:?}: instantiate_subgraph({:?})",
             self.get_debug_id(),
-            name,
-            label
+            spec
         );
         let result = self
             .runtime
-            .channel_create(self.node_id, name, label, Downgrading::No);
+            .clone()
+            .instantiate_subgraph(self.node_id, spec, Downgrading::No);
         debug!(
-            "{:?}: channel_create({:?}, {:?}) -> {:?}",
+            "{:?}: instantiate_subgraph({:?}) -> {:?}",
             self.get_debug_id(),
-            name,
-            label,
+            spec,
             result
         );
         result
     }
 
-    /// Calls [`Runtime::channel_create`] using the Node's privilege.
-    pub fn channel_create_with_downgrade(
+    /// Calls [`Runtime::ensure_node`] without using the Node's privilege.
+    pub fn ensure_node(
         &self,
         name: &str,
+        config: &NodeConfiguration,
         label: &Label,
-    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
+        initial_handle: oak_abi::Handle,
+    ) -> Result<EnsuredNode, OakStatus> {
         debug!(
-            "{:?}: channel_create_with_downgrade({:?}, {:?})",
-            self.node_id, name, label
+            "{:?}: ensure_node({:?}, {:?}, {:?})",
+            self.get_debug_id(),
+            name,
+            config,
+            label
+        );
+        let result = self.runtime.clone().ensure_node(
+            self.node_id,
+            name,
+            config,
+            label,
+            initial_handle,
+            Downgrading::No,
         );
-        let result = self
-            .runtime
-            .channel_create(self.node_id, name, label, Downgrading::Yes);
         debug!(
-            "{:?}: channel_create_with_downgrade({:?}, {:?}) -> {:?}",
-            self.node_id, name, label, result
+            "{:?}: ensure_node({:?}, {:?}, {:?}) -> {:?}",
+            self.get_debug_id(),
+            name,
+            config,
+            label,
+            result
         );
         result
     }
 
-    /// Calls [`Runtime::handle_clone`].
-    pub fn handle_clone(&self, handle: oak_abi::Handle) -> Result<oak_abi::Handle, OakStatus> {
-        debug!("{:?}: handle_clone({:?}", self.node_id, handle,);
-        let result = self.runtime.handle_clone(self.node_id, handle);
+    /// Calls [`Runtime::ensure_node`] using the Node's privilege.
+    pub fn ensure_node_with_downgrade(
+        &self,
+        name: &str,
+        config: &NodeConfiguration,
+        label: &Label,
+        initial_handle: oak_abi::Handle,
+    ) -> Result<EnsuredNode, OakStatus> {
         debug!(
-            "{:?}: handle_clone({:?}) -> {:?}",
-            self.node_id, handle, result
+            "{:?}: ensure_node_with_downgrade({:?}, {:?}, {:?})",
+            self.get_debug_id(),
+            name,
+            config,
+            label
+        );
+        let result = self.runtime.clone().ensure_node(
+            self.node_id,
+            name,
+            config,
+            label,
+            initial_handle,
+            Downgrading::Yes,
         );
-        result
-    }
-
-    /// See [`Runtime::channel_close`].
-    pub fn channel_close(&self, handle: oak_abi::Handle) -> Result<(), OakStatus> {
-        debug!("{:?}: channel_close({})", self.get_debug_id(), handle);
-        let result = self.runtime.channel_close(self.node_id, handle);
         debug!(
-            "{:?}: channel_close({}) -> {:?}",
+            "{:?}: ensure_node_with_downgrade({:?}, {:?}, {:?}) -> {:?}",
             self.get_debug_id(),
-            handle,
+            name,
+            config,
+            label,
             result
         );
         result
     }
 
-    /// Calls [`Runtime::wait_on_channels`] without using the Node's privilege.
-    pub fn wait_on_channels(
+    /// Calls [`Runtime::transfer_handles`] without using the target Node's privilege.
+    pub fn transfer_handles(
         &self,
-        read_handles: &[oak_abi::Handle],
-    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        from_node: NodeId,
+        to_node: NodeId,
+    ) -> Result<HashMap<oak_abi::Handle, oak_abi::Handle>, OakStatus> {
         debug!(
-            "{:?}: wait_on_channels(count={})",
+            "{:?}: transfer_handles({:?}, {:?})",
             self.get_debug_id(),
-            read_handles.len()
+            from_node,
+            to_node
         );
-        let result = self
-            .runtime
-            .wait_on_channels(self.node_id, read_handles, Downgrading::No);
+        let result =
+            self.runtime
+                .transfer_handles(self.node_id, from_node, to_node, Downgrading::No);
         debug!(
-            "{:?}: wait_on_channels(count={}) -> {:?}",
+            "{:?}: transfer_handles({:?}, {:?}) -> {:?}",
             self.get_debug_id(),
-            read_handles.len(),
+            from_node,
+            to_node,
             result
         );
         result
     }
 
-    /// Calls [`Runtime::wait_on_channels`] using the Node's privilege.
-    pub fn wait_on_channels_with_downgrade(
+    /// Calls [`Runtime::transfer_handles`] using the target Node's privilege.
+    pub fn transfer_handles_with_downgrade(
         &self,
-        read_handles: &[oak_abi::Handle],
-    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        from_node: NodeId,
+        to_node: NodeId,
+    ) -> Result<HashMap<oak_abi::Handle, oak_abi::Handle>, OakStatus> {
         debug!(
-            "{:?}: wait_on_channels_with_downgrade(count={})",
+            "{:?}: transfer_handles_with_downgrade({:?}, {:?})",
             self.get_debug_id(),
-            read_handles.len()
+            from_node,
+            to_node
         );
-        let result = self
-            .runtime
-            .wait_on_channels(self.node_id, read_handles, Downgrading::Yes);
+        let result =
+            self.runtime
+                .transfer_handles(self.node_id, from_node, to_node, Downgrading::Yes);
         debug!(
-            "{:?}: wait_on_channels_with_downgrade(count={}) -> {:?}",
+            "{:?}: transfer_handles_with_downgrade({:?}, {:?}) -> {:?}",
             self.get_debug_id(),
-            read_handles.len(),
+            from_node,
+            to_node,
             result
         );
         result
     }
 
-    /// Calls [`Runtime::channel_write`] without using the Node's privilege.
-    pub fn channel_write(
+    /// See [`Runtime::node_register`]. This is exposed to facilitate testing.
+    #[cfg(test)]
+    pub fn node_register(
         &self,
-        write_handle: oak_abi::Handle,
-        msg: NodeMessage,
+        created_node: CreatedNode,
+        node_name: &str,
+        label: &Label,
+        initial_handle: oak_abi::Handle,
     ) -> Result<(), OakStatus> {
         debug!(
-            "{:?}: channel_write({}, {:?})",
+            "{:?}: register_node_instance(node_name: {:?}, label: {:?})",
             self.get_debug_id(),
-            write_handle,
-            msg
+            node_name,
+            label
         );
         let result = self
             .runtime
-            .channel_write(self.node_id, write_handle, msg, Downgrading::No);
+            .clone()
+            .node_register(
+                self.node_id,
+                created_node,
+                node_name,
+                label,
+                initial_handle,
+                None,
+                None,
+                Downgrading::No,
+            )
+            .map(|_node_id| ());
         debug!(
-            "{:?}: channel_write({}, ...) -> {:?}",
+            "{:?}: register_node_instance(node_name: {:?}, label: {:?}) -> {:?}",
             self.get_debug_id(),
-            write_handle,
+            node_name,
+            label,
             result
         );
         result
     }
 
-    /// Calls [`Runtime::channel_write`] using the Node's privilege.
-    pub fn channel_write_with_downgrade(
+    /// Calls [`Runtime::channel_create`] without using the Node's privilege.
+    pub fn channel_create(
         &self,
-        write_handle: oak_abi::Handle,
-        msg: NodeMessage,
-    ) -> Result<(), OakStatus> {
+        name: &str,
+        label: &Label,
+    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
         debug!(
-            "{:?}: channel_write_with_downgrade({}, {:?})",
+            "{:?}: channel_create({:?}, {:?})",
+            self.get_debug_id(),
+            name,
+            label
+        );
+        let result = self.runtime.channel_create(
+            self.node_id,
+            name,
+            label,
+            &ChannelOptions::default(),
+            Downgrading::No,
+        );
+        debug!(
+            "{:?}: channel_create({:?}, {:?}) -> {:?}",
+            self.get_debug_id(),
+            name,
+            label,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_create`] with the provided [`ChannelOptions`], without using the
+    /// Node's privilege.
+    pub fn channel_create_with_options(
+        &self,
+        name: &str,
+        label: &Label,
+        options: &ChannelOptions,
+    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
+        debug!(
+            "{:?}: channel_create_with_options({:?}, {:?}, {:?})",
+            self.get_debug_id(),
+            name,
+            label,
+            options
+        );
+        let result =
+            self.runtime
+                .channel_create(self.node_id, name, label, options, Downgrading::No);
+        debug!(
+            "{:?}: channel_create_with_options({:?}, {:?}, {:?}) -> {:?}",
+            self.get_debug_id(),
+            name,
+            label,
+            options,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_create`] using the Node's privilege.
+    pub fn channel_create_with_downgrade(
+        &self,
+        name: &str,
+        label: &Label,
+    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
+        debug!(
+            "{:?}: channel_create_with_downgrade({:?}, {:?})",
+            self.node_id, name, label
+        );
+        let result = self.runtime.channel_create(
+            self.node_id,
+            name,
+            label,
+            &ChannelOptions::default(),
+            Downgrading::Yes,
+        );
+        debug!(
+            "{:?}: channel_create_with_downgrade({:?}, {:?}) -> {:?}",
+            self.node_id, name, label, result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_create_with_message`] without using the Node's privilege.
+    pub fn channel_create_with_message(
+        &self,
+        name: &str,
+        label: &Label,
+        initial: NodeMessage,
+    ) -> Result<(oak_abi::Handle, oak_abi::Handle), OakStatus> {
+        debug!(
+            "{:?}: channel_create_with_message({:?}, {:?}, {:?})",
+            self.get_debug_id(),
+            name,
+            label,
+            initial
+        );
+        let result = self.runtime.channel_create_with_message(
+            self.node_id,
+            name,
+            label,
+            initial,
+            Downgrading::No,
+        );
+        debug!(
+            "{:?}: channel_create_with_message({:?}, {:?}, ...) -> {:?}",
+            self.get_debug_id(),
+            name,
+            label,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::handle_clone`].
+    pub fn handle_clone(&self, handle: oak_abi::Handle) -> Result<oak_abi::Handle, OakStatus> {
+        debug!("{:?}: handle_clone({:?}", self.node_id, handle,);
+        let result = self.runtime.handle_clone(self.node_id, handle);
+        debug!(
+            "{:?}: handle_clone({:?}) -> {:?}",
+            self.node_id, handle, result
+        );
+        result
+    }
+
+    /// See [`Runtime::channel_close`].
+    pub fn channel_close(&self, handle: oak_abi::Handle) -> Result<(), OakStatus> {
+        debug!("{:?}: channel_close({})", self.get_debug_id(), handle);
+        let result = self.runtime.channel_close(self.node_id, handle);
+        debug!(
+            "{:?}: channel_close({}) -> {:?}",
+            self.get_debug_id(),
+            handle,
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::channel_close_idempotent`].
+    pub fn channel_close_idempotent(&self, handle: oak_abi::Handle) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_close_idempotent({})",
+            self.get_debug_id(),
+            handle
+        );
+        let result = self.runtime.channel_close_idempotent(self.node_id, handle);
+        debug!(
+            "{:?}: channel_close_idempotent({}) -> {:?}",
+            self.get_debug_id(),
+            handle,
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::channel_close_with_reason`].
+    pub fn channel_close_with_reason(
+        &self,
+        handle: oak_abi::Handle,
+        reason: CloseReason,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_close_with_reason({}, {:?})",
+            self.get_debug_id(),
+            handle,
+            reason
+        );
+        let result = self
+            .runtime
+            .channel_close_with_reason(self.node_id, handle, reason);
+        debug!(
+            "{:?}: channel_close_with_reason({}, {:?}) -> {:?}",
+            self.get_debug_id(),
+            handle,
+            reason,
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::channel_close_reason`].
+    pub fn channel_close_reason(
+        &self,
+        read_handle: oak_abi::Handle,
+    ) -> Result<Option<CloseReason>, OakStatus> {
+        debug!(
+            "{:?}: channel_close_reason({})",
+            self.get_debug_id(),
+            read_handle
+        );
+        let result = self.runtime.channel_close_reason(self.node_id, read_handle);
+        debug!(
+            "{:?}: channel_close_reason({}) -> {:?}",
+            self.get_debug_id(),
+            read_handle,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::wait_on_channels`] without using the Node's privilege.
+    pub fn wait_on_channels(
+        &self,
+        read_handles: &[oak_abi::Handle],
+    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        debug!(
+            "{:?}: wait_on_channels(count={})",
+            self.get_debug_id(),
+            read_handles.len()
+        );
+        let result = self
+            .runtime
+            .wait_on_channels(self.node_id, read_handles, Downgrading::No);
+        debug!(
+            "{:?}: wait_on_channels(count={}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::wait_on_channels_first`] without using the Node's privilege.
+    pub fn wait_on_channels_first(
+        &self,
+        read_handles: &[oak_abi::Handle],
+    ) -> Result<(usize, ChannelReadStatus), OakStatus> {
+        debug!(
+            "{:?}: wait_on_channels_first(count={})",
+            self.get_debug_id(),
+            read_handles.len()
+        );
+        let result =
+            self.runtime
+                .wait_on_channels_first(self.node_id, read_handles, Downgrading::No);
+        debug!(
+            "{:?}: wait_on_channels_first(count={}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::wait_on_channels`] using the Node's privilege.
+    pub fn wait_on_channels_with_downgrade(
+        &self,
+        read_handles: &[oak_abi::Handle],
+    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        debug!(
+            "{:?}: wait_on_channels_with_downgrade(count={})",
+            self.get_debug_id(),
+            read_handles.len()
+        );
+        let result = self
+            .runtime
+            .wait_on_channels(self.node_id, read_handles, Downgrading::Yes);
+        debug!(
+            "{:?}: wait_on_channels_with_downgrade(count={}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::poll_channels`] without using the Node's privilege.
+    pub fn poll_channels(&self, read_handles: &[oak_abi::Handle]) -> Vec<ChannelReadStatus> {
+        debug!(
+            "{:?}: poll_channels(count={})",
+            self.get_debug_id(),
+            read_handles.len()
+        );
+        let result = self
+            .runtime
+            .poll_channels(self.node_id, read_handles, Downgrading::No);
+        debug!(
+            "{:?}: poll_channels(count={}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::poll_channels`] using the Node's privilege.
+    pub fn poll_channels_with_downgrade(
+        &self,
+        read_handles: &[oak_abi::Handle],
+    ) -> Vec<ChannelReadStatus> {
+        debug!(
+            "{:?}: poll_channels_with_downgrade(count={})",
+            self.get_debug_id(),
+            read_handles.len()
+        );
+        let result = self
+            .runtime
+            .poll_channels(self.node_id, read_handles, Downgrading::Yes);
+        debug!(
+            "{:?}: poll_channels_with_downgrade(count={}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::wait_on_channels_timeout`] without using the Node's privilege.
+    pub fn wait_on_channels_timeout(
+        &self,
+        read_handles: &[oak_abi::Handle],
+        timeout: Duration,
+    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        debug!(
+            "{:?}: wait_on_channels_timeout(count={}, {:?})",
+            self.get_debug_id(),
+            read_handles.len(),
+            timeout
+        );
+        let result = self.runtime.wait_on_channels_timeout(
+            self.node_id,
+            read_handles,
+            timeout,
+            Downgrading::No,
+        );
+        debug!(
+            "{:?}: wait_on_channels_timeout(count={}, {:?}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            timeout,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::wait_on_channels_timeout`] using the Node's privilege.
+    pub fn wait_on_channels_timeout_with_downgrade(
+        &self,
+        read_handles: &[oak_abi::Handle],
+        timeout: Duration,
+    ) -> Result<Vec<ChannelReadStatus>, OakStatus> {
+        debug!(
+            "{:?}: wait_on_channels_timeout_with_downgrade(count={}, {:?})",
+            self.get_debug_id(),
+            read_handles.len(),
+            timeout
+        );
+        let result = self.runtime.wait_on_channels_timeout(
+            self.node_id,
+            read_handles,
+            timeout,
+            Downgrading::Yes,
+        );
+        debug!(
+            "{:?}: wait_on_channels_timeout_with_downgrade(count={}, {:?}) -> {:?}",
+            self.get_debug_id(),
+            read_handles.len(),
+            timeout,
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::node_count`].
+    pub fn node_count(&self, node_type: Option<&str>) -> u64 {
+        debug!("{:?}: node_count({:?})", self.get_debug_id(), node_type);
+        let result = self.runtime.node_count(node_type);
+        debug!(
+            "{:?}: node_count({:?}) -> {:?}",
+            self.get_debug_id(),
+            node_type,
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::downgrade_history`].
+    pub fn downgrade_history(&self) -> Vec<DowngradeRecord> {
+        debug!("{:?}: downgrade_history()", self.get_debug_id());
+        let result = self.runtime.downgrade_history(self.node_id);
+        debug!(
+            "{:?}: downgrade_history() -> {:?}",
+            self.get_debug_id(),
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::last_read_message_writer`].
+    pub fn last_read_message_writer(&self) -> Option<NodeId> {
+        debug!("{:?}: last_read_message_writer()", self.get_debug_id());
+        let result = self.runtime.last_read_message_writer(self.node_id);
+        debug!(
+            "{:?}: last_read_message_writer() -> {:?}",
+            self.get_debug_id(),
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::last_read_message_writers`].
+    pub fn last_read_message_writers(&self) -> Vec<Option<NodeId>> {
+        debug!("{:?}: last_read_message_writers()", self.get_debug_id());
+        let result = self.runtime.last_read_message_writers(self.node_id);
+        debug!(
+            "{:?}: last_read_message_writers() -> {:?}",
+            self.get_debug_id(),
+            result
+        );
+        result
+    }
+
+    /// See [`Runtime::heartbeat`].
+    pub fn heartbeat(&self) -> Result<(), OakStatus> {
+        debug!("{:?}: heartbeat()", self.get_debug_id());
+        let result = self.runtime.heartbeat(self.node_id);
+        debug!("{:?}: heartbeat() -> {:?}", self.get_debug_id(), result);
+        result
+    }
+
+    /// Calls [`Runtime::channel_write`] without using the Node's privilege.
+    pub fn channel_write(
+        &self,
+        write_handle: oak_abi::Handle,
+        msg: NodeMessage,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_write({}, {:?})",
+            self.get_debug_id(),
+            write_handle,
+            msg
+        );
+        let result = self
+            .runtime
+            .channel_write(self.node_id, write_handle, msg, Downgrading::No);
+        debug!(
+            "{:?}: channel_write({}, ...) -> {:?}",
+            self.get_debug_id(),
+            write_handle,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_write`] using the Node's privilege.
+    pub fn channel_write_with_downgrade(
+        &self,
+        write_handle: oak_abi::Handle,
+        msg: NodeMessage,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_write_with_downgrade({}, {:?})",
             self.node_id, write_handle, msg
         );
         let result = self
@@ -430,6 +1147,81 @@ impl RuntimeProxy {
         result
     }
 
+    /// Calls [`Runtime::channel_write_with_ttl`] without using the Node's privilege.
+    pub fn channel_write_with_ttl(
+        &self,
+        write_handle: oak_abi::Handle,
+        msg: NodeMessage,
+        ttl: Duration,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_write_with_ttl({}, {:?}, {:?})",
+            self.get_debug_id(),
+            write_handle,
+            msg,
+            ttl
+        );
+        let result = self.runtime.channel_write_with_ttl(
+            self.node_id,
+            write_handle,
+            msg,
+            ttl,
+            Downgrading::No,
+        );
+        debug!(
+            "{:?}: channel_write_with_ttl({}, ..., {:?}) -> {:?}",
+            self.get_debug_id(),
+            write_handle,
+            ttl,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_write_blocking`] without using the Node's privilege.
+    pub fn channel_write_blocking(
+        &self,
+        write_handle: oak_abi::Handle,
+        msg: NodeMessage,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_write_blocking({}, {:?})",
+            self.get_debug_id(),
+            write_handle,
+            msg
+        );
+        let result =
+            self.runtime
+                .channel_write_blocking(self.node_id, write_handle, msg, Downgrading::No);
+        debug!(
+            "{:?}: channel_write_blocking({}, ...) -> {:?}",
+            self.get_debug_id(),
+            write_handle,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_write_blocking`] using the Node's privilege.
+    pub fn channel_write_blocking_with_downgrade(
+        &self,
+        write_handle: oak_abi::Handle,
+        msg: NodeMessage,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_write_blocking_with_downgrade({}, {:?})",
+            self.node_id, write_handle, msg
+        );
+        let result =
+            self.runtime
+                .channel_write_blocking(self.node_id, write_handle, msg, Downgrading::Yes);
+        debug!(
+            "{:?}: channel_write_blocking_with_downgrade({}, ...) -> {:?}",
+            self.node_id, write_handle, result
+        );
+        result
+    }
+
     /// Calls [`Runtime::channel_read`] without using the Node's privilege.
     pub fn channel_read(
         &self,
@@ -467,6 +1259,141 @@ impl RuntimeProxy {
         result
     }
 
+    /// Calls [`Runtime::channel_read_with_close_reason`] without using the Node's privilege.
+    pub fn channel_read_with_close_reason(
+        &self,
+        read_handle: oak_abi::Handle,
+    ) -> Result<ChannelReadOutcome, OakStatus> {
+        debug!(
+            "{:?}: channel_read_with_close_reason({})",
+            self.get_debug_id(),
+            read_handle
+        );
+        let result =
+            self.runtime
+                .channel_read_with_close_reason(self.node_id, read_handle, Downgrading::No);
+        debug!(
+            "{:?}: channel_read_with_close_reason({}) -> {:?}",
+            self.get_debug_id(),
+            read_handle,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_read_batch`] without using the Node's privilege.
+    pub fn channel_read_batch(
+        &self,
+        read_handle: oak_abi::Handle,
+        max_messages: usize,
+    ) -> Result<Vec<NodeMessage>, OakStatus> {
+        debug!(
+            "{:?}: channel_read_batch({}, {})",
+            self.get_debug_id(),
+            read_handle,
+            max_messages
+        );
+        let result = self.runtime.channel_read_batch(
+            self.node_id,
+            read_handle,
+            max_messages,
+            Downgrading::No,
+        );
+        debug!(
+            "{:?}: channel_read_batch({}, {}) -> {:?}",
+            self.get_debug_id(),
+            read_handle,
+            max_messages,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_read_batch`] using the Node's privilege.
+    pub fn channel_read_batch_with_downgrade(
+        &self,
+        read_handle: oak_abi::Handle,
+        max_messages: usize,
+    ) -> Result<Vec<NodeMessage>, OakStatus> {
+        debug!(
+            "{:?}: channel_read_batch_with_downgrade({}, {})",
+            self.node_id, read_handle, max_messages
+        );
+        let result = self.runtime.channel_read_batch(
+            self.node_id,
+            read_handle,
+            max_messages,
+            Downgrading::Yes,
+        );
+        debug!(
+            "{:?}: channel_read_batch_with_downgrade({}, {}) -> {:?}",
+            self.node_id, read_handle, max_messages, result
+        );
+        result
+    }
+
+    /// See [`Runtime::channel_set_consumer_priority`].
+    pub fn channel_set_consumer_priority(
+        &self,
+        read_handle: oak_abi::Handle,
+        priority: i32,
+    ) -> Result<(), OakStatus> {
+        debug!(
+            "{:?}: channel_set_consumer_priority({}, {})",
+            self.get_debug_id(),
+            read_handle,
+            priority
+        );
+        let result =
+            self.runtime
+                .channel_set_consumer_priority(self.node_id, read_handle, priority);
+        debug!(
+            "{:?}: channel_set_consumer_priority({}, {}) -> {:?}",
+            self.get_debug_id(),
+            read_handle,
+            priority,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_peek`] without using the Node's privilege.
+    pub fn channel_peek(
+        &self,
+        read_handle: oak_abi::Handle,
+    ) -> Result<Option<PeekedMessage>, OakStatus> {
+        debug!("{:?}: channel_peek({})", self.get_debug_id(), read_handle);
+        let result = self
+            .runtime
+            .channel_peek(self.node_id, read_handle, Downgrading::No);
+        debug!(
+            "{:?}: channel_peek({}) -> {:?}",
+            self.get_debug_id(),
+            read_handle,
+            result
+        );
+        result
+    }
+
+    /// Calls [`Runtime::channel_peek`] using the Node's privilege.
+    pub fn channel_peek_with_downgrade(
+        &self,
+        read_handle: oak_abi::Handle,
+    ) -> Result<Option<PeekedMessage>, OakStatus> {
+        debug!(
+            "{:?}: channel_peek_with_downgrade({})",
+            self.node_id, read_handle,
+        );
+        let result = self
+            .runtime
+            .channel_peek(self.node_id, read_handle, Downgrading::Yes);
+        debug!(
+            "{:?}: channel_peek_with_downgrade({}) -> {:?}",
+            self.node_id, read_handle, result
+        );
+        result
+    }
+
     /// Calls [`Runtime::channel_try_read_message`] without the Node's privilege.
     pub fn channel_try_read_message(
         &self,
@@ -0,0 +1,258 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Creation of a set of Nodes and of the channels connecting them in a single operation.
+//!
+//! A [`SubgraphSpec`] declares Nodes by local names, and the edges between them. The whole
+//! subgraph is validated before any Node is started, so that either all the Nodes are created and
+//! connected, or none of them are.
+//!
+//! Each Node of the subgraph receives, on the channel identified by its initial handle, a single
+//! message with no data and with a handle for each edge the Node is an endpoint of, in the order in
+//! which the edges are declared: a write handle if the Node is the source of the edge, followed by
+//! a read handle if the Node is its target.
+
+use crate::{
+    node::{self, NodeFactory},
+    ChannelOptions, Downgrading, NodeCapacity, NodeId, NodeReservation, Runtime,
+};
+use log::warn;
+use oak_abi::{label::Label, proto::oak::application::NodeConfiguration, OakStatus};
+use oak_io::Message as NodeMessage;
+use std::{collections::HashMap, sync::Arc};
+
+/// A Node to be created as part of a [`SubgraphSpec`].
+#[derive(Clone, Debug)]
+pub struct SubgraphNode {
+    /// Name of the Node, which also identifies it within the subgraph and so must be unique in it.
+    pub name: String,
+    pub config: NodeConfiguration,
+    pub label: Label,
+}
+
+/// A channel to be created as part of a [`SubgraphSpec`], from the Node named `source` to the Node
+/// named `target`.
+#[derive(Clone, Debug)]
+pub struct SubgraphEdge {
+    pub source: String,
+    pub target: String,
+    pub label: Label,
+}
+
+/// Specification of a set of Nodes and of the channels connecting them.
+#[derive(Clone, Debug, Default)]
+pub struct SubgraphSpec {
+    pub nodes: Vec<SubgraphNode>,
+    pub edges: Vec<SubgraphEdge>,
+}
+
+impl SubgraphSpec {
+    /// Checks that Node names are unique and that edges only refer to declared Nodes, and returns
+    /// the index of each Node by name.
+    fn node_indices(&self) -> Result<HashMap<&str, usize>, OakStatus> {
+        let mut indices = HashMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            if indices.insert(node.name.as_str(), index).is_some() {
+                warn!("duplicate node {:?} in subgraph", node.name);
+                return Err(OakStatus::ErrInvalidArgs);
+            }
+        }
+        for edge in &self.edges {
+            for name in &[&edge.source, &edge.target] {
+                if !indices.contains_key(name.as_str()) {
+                    warn!("edge refers to unknown node {:?} in subgraph", name);
+                    return Err(OakStatus::ErrInvalidArgs);
+                }
+            }
+        }
+        Ok(indices)
+    }
+}
+
+// Subgraph related methods for the Runtime.
+impl Runtime {
+    /// Creates all the Nodes and channels declared in `spec` on behalf of the Node identified by
+    /// `node_id`, and returns the [`NodeId`] of each created Node by name.
+    ///
+    /// Each Node and channel is subject to the same checks as if the calling Node created it
+    /// individually (see [`Runtime::node_create_and_register`]). In addition, the label of the
+    /// source Node of each edge must flow to the label of the edge, which must in turn flow to the
+    /// label of the target Node, so that the Nodes can actually use the channels they are given.
+    /// The [`RuntimeConfiguration::node_creation_rate_limit`] is checked for all the Nodes at
+    /// once, and capacity for all of them is reserved within the
+    /// [`RuntimeConfiguration::max_nodes`] limit, so that the calling Node either gets to create
+    /// the whole subgraph or none of it, even if other Nodes are created concurrently.
+    /// All the checks are performed before any Node is started, and if any of them fails, all the
+    /// channels created so far are closed, and no Node is created. Once the Nodes start being
+    /// registered, the only remaining failure is the Runtime terminating, which stops the Nodes
+    /// that were already started anyway.
+    ///
+    /// [`RuntimeConfiguration::node_creation_rate_limit`]: crate::RuntimeConfiguration::node_creation_rate_limit
+    /// [`RuntimeConfiguration::max_nodes`]: crate::RuntimeConfiguration::max_nodes
+    pub(crate) fn instantiate_subgraph(
+        self: Arc<Self>,
+        node_id: NodeId,
+        spec: &SubgraphSpec,
+        downgrade: Downgrading,
+    ) -> Result<HashMap<String, NodeId>, OakStatus> {
+        if self.is_terminating() {
+            return Err(OakStatus::ErrTerminated);
+        }
+        let indices = spec.node_indices()?;
+
+        // The calling Node must be able to create all the Nodes and channels.
+        self.validate_can_write_to_label(node_id, &Label::public_untrusted(), downgrade)?;
+        for label in spec
+            .nodes
+            .iter()
+            .map(|node| &node.label)
+            .chain(spec.edges.iter().map(|edge| &edge.label))
+        {
+            self.validate_can_write_to_label(node_id, label, downgrade)?;
+        }
+        for edge in &spec.edges {
+            let source = &spec.nodes[indices[edge.source.as_str()]];
+            let target = &spec.nodes[indices[edge.target.as_str()]];
+            if !source.label.flows_to(&edge.label) || !edge.label.flows_to(&target.label) {
+                warn!(
+                    "{:?}: edge from {:?} to {:?} in subgraph violates IFC",
+                    self.get_node_debug_id(node_id),
+                    edge.source,
+                    edge.target
+                );
+                return Err(OakStatus::ErrPermissionDenied);
+            }
+        }
+
+        // This only creates the Node instances, but does not start them.
+        let mut instances = Vec::with_capacity(spec.nodes.len());
+        for node in &spec.nodes {
+            let config = node::apply_node_config_defaults(
+                &self.node_config_defaults.read().unwrap(),
+                &node.config,
+            );
+            let instance = self
+                .node_factory
+                .create_node(&node.name, &config)
+                .map_err(|err| {
+                    warn!("could not create node: {:?}", err);
+                    OakStatus::ErrInvalidArgs
+                })?;
+            self.validate_can_create_node_type(node_id, instance.instance.node_type())?;
+            self.validate_node_isolation(&instance, &node.name, &node.label)?;
+            instances.push((instance, config));
+        }
+
+        let node_count = spec.nodes.len();
+        self.acquire_node_creations(node_id, node_count, || {
+            format!("subgraph of {} nodes", node_count)
+        })?;
+
+        // All the handles created in the handle table of the calling Node, to be closed once they
+        // have been handed over to the new Nodes, or if anything fails.
+        let mut created_handles = Vec::new();
+        let result = match self.reserve_node_capacity(node_count) {
+            Ok(reservation) => self.clone().instantiate_subgraph_nodes(
+                node_id,
+                spec,
+                &indices,
+                instances,
+                downgrade,
+                &reservation,
+                &mut created_handles,
+            ),
+            Err(status) => {
+                warn!(
+                    "{:?}: cannot create subgraph of {} nodes: limit of live nodes reached",
+                    self.get_node_debug_id(node_id),
+                    node_count
+                );
+                Err(status)
+            }
+        };
+        for handle in created_handles {
+            let _ = self.channel_close(node_id, handle);
+        }
+        if result.is_err() {
+            // The Nodes of a subgraph that could not be instantiated do not count towards the
+            // rate limit.
+            self.node_creation_rate_limiter.release(node_id, node_count);
+        }
+        result
+    }
+
+    /// Creates the channels of the already validated `spec`, and registers the provided Node
+    /// `instances`, along with the configurations they were created from, which must be in the same
+    /// order as the Nodes of `spec`, using the capacity in `reservation`.
+    #[allow(clippy::too_many_arguments)]
+    fn instantiate_subgraph_nodes(
+        self: Arc<Self>,
+        node_id: NodeId,
+        spec: &SubgraphSpec,
+        indices: &HashMap<&str, usize>,
+        instances: Vec<(node::CreatedNode, NodeConfiguration)>,
+        downgrade: Downgrading,
+        reservation: &NodeReservation,
+        created_handles: &mut Vec<oak_abi::Handle>,
+    ) -> Result<HashMap<String, NodeId>, OakStatus> {
+        let options = ChannelOptions::default();
+        let mut node_handles = vec![Vec::new(); spec.nodes.len()];
+        for edge in &spec.edges {
+            let name = format!("{}->{}", edge.source, edge.target);
+            let (write_handle, read_handle) =
+                self.channel_create(node_id, &name, &edge.label, &options, downgrade)?;
+            created_handles.push(write_handle);
+            created_handles.push(read_handle);
+            node_handles[indices[edge.source.as_str()]].push(write_handle);
+            node_handles[indices[edge.target.as_str()]].push(read_handle);
+        }
+
+        let mut initial_handles = Vec::with_capacity(spec.nodes.len());
+        for (node, handles) in spec.nodes.iter().zip(node_handles) {
+            let (write_handle, read_handle) =
+                self.channel_create(node_id, &node.name, &node.label, &options, downgrade)?;
+            created_handles.push(write_handle);
+            created_handles.push(read_handle);
+            let message = NodeMessage {
+                bytes: Vec::new(),
+                handles,
+            };
+            self.channel_write(node_id, write_handle, message, downgrade)?;
+            initial_handles.push(read_handle);
+        }
+
+        let creatable_node_types = self.child_creatable_node_types(node_id, None);
+        let mut node_ids = HashMap::new();
+        for ((node, (instance, config)), initial_handle) in
+            spec.nodes.iter().zip(instances).zip(initial_handles)
+        {
+            // All the checks of `Runtime::node_register` have already been performed for the
+            // whole subgraph.
+            let new_node_id = self.clone().node_start_with_initial_handle(
+                node_id,
+                instance,
+                &node.name,
+                &node.label,
+                initial_handle,
+                creatable_node_types.clone(),
+                Some(config),
+                NodeCapacity::Reserved(reservation),
+            )?;
+            node_ids.insert(node.name.clone(), new_node_id);
+        }
+        Ok(node_ids)
+    }
+}
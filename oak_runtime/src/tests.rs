@@ -15,17 +15,24 @@
 //
 
 use super::*;
+use crate::io::{ReceiverExt, SenderExt};
 use maplit::{hashmap, hashset};
 use oak_abi::{
     label::{
-        confidentiality_label, public_key_identity_tag, tls_endpoint_tag,
-        web_assembly_module_signature_tag, web_assembly_module_tag, Label,
+        confidentiality_label, public_key_identity_tag, tls_endpoint_tag, top,
+        web_assembly_module_signature_tag, web_assembly_module_tag, Label, TagNamespace,
     },
     proto::oak::application::{
-        node_configuration::ConfigType, ApplicationConfiguration, GrpcServerConfiguration,
-        LogConfiguration, NodeConfiguration,
+        node_configuration::ConfigType, ApplicationConfiguration, GrpcClientConfiguration,
+        GrpcServerConfiguration, LogConfiguration, NodeConfiguration, RandomConfiguration,
+        RoughtimeClientConfiguration, RoughtimeServer, TimerConfiguration,
+        WebAssemblyConfiguration,
     },
 };
+use oak_services::proto::oak::{
+    random::{RandomBytes, RandomRequest},
+    timer::{Tick, TickSender},
+};
 use std::sync::mpsc;
 
 pub fn init_logging() {
@@ -37,39 +44,23 @@ type NodeBody = dyn Fn(RuntimeProxy) -> Result<(), OakStatus> + Send + Sync;
 /// Runs the provided function as if it were the body of a [`Node`] implementation, which is
 /// instantiated by the [`Runtime`] with the provided [`Label`].
 fn run_node_body(node_label: &Label, node_privilege: &NodePrivilege, node_body: Box<NodeBody>) {
-    init_logging();
-    let configuration = ApplicationConfiguration {
-        wasm_modules: hashmap! {},
-        initial_node_configuration: None,
-        module_signatures: vec![],
-    };
     let permissions = crate::permissions::PermissionsConfiguration {
         allow_grpc_server_nodes: true,
         allow_log_nodes: true,
         ..Default::default()
     };
-    let signature_table = SignatureTable::default();
-    info!("Create runtime for test");
-    let proxy = crate::RuntimeProxy::create_runtime(
-        &configuration,
-        &permissions,
-        &SecureServerConfiguration {
-            grpc_config: Some(GrpcConfiguration {
-                grpc_server_tls_identity: Some(Identity::from_pem(
-                    include_str!("../../examples/certs/local/local.pem"),
-                    include_str!("../../examples/certs/local/local.key"),
-                )),
-                grpc_client_root_tls_certificate: crate::tls::Certificate::parse(
-                    include_bytes!("../../examples/certs/local/ca.pem").to_vec(),
-                )
-                .ok(),
-                oidc_client_info: None,
-            }),
-            http_config: None,
-        },
-        &signature_table,
-        None,
-    );
+    run_node_body_with_permissions(&permissions, node_label, node_privilege, node_body)
+}
+
+/// Same as [`run_node_body`], but uses the provided [`PermissionsConfiguration`] for the
+/// [`Runtime`].
+fn run_node_body_with_permissions(
+    permissions: &crate::permissions::PermissionsConfiguration,
+    node_label: &Label,
+    node_privilege: &NodePrivilege,
+    node_body: Box<NodeBody>,
+) {
+    let proxy = create_test_runtime(permissions);
 
     struct TestNode {
         node_body: Box<NodeBody>,
@@ -132,6 +123,84 @@ fn run_node_body(node_label: &Label, node_privilege: &NodePrivilege, node_body:
     info!("Stop runtime..done");
 }
 
+/// Creates a [`Runtime`] for testing with the provided [`PermissionsConfiguration`], and returns a
+/// [`RuntimeProxy`] for it.
+fn create_test_runtime(permissions: &crate::permissions::PermissionsConfiguration) -> RuntimeProxy {
+    init_logging();
+    let configuration = ApplicationConfiguration {
+        wasm_modules: hashmap! {},
+        initial_node_configuration: None,
+        module_signatures: vec![],
+    };
+    let signature_table = SignatureTable::default();
+    info!("Create runtime for test");
+    crate::RuntimeProxy::create_runtime(
+        &configuration,
+        permissions,
+        &SecureServerConfiguration {
+            grpc_config: Some(GrpcConfiguration {
+                grpc_server_tls_identity: Some(Identity::from_pem(
+                    include_str!("../../examples/certs/local/local.pem"),
+                    include_str!("../../examples/certs/local/local.key"),
+                )),
+                grpc_server_tls_config: None,
+                grpc_server_client_ca_certificate: None,
+                grpc_server_require_client_certificate: false,
+                grpc_client_root_tls_certificate: crate::tls::Certificate::parse(
+                    include_bytes!("../../examples/certs/local/ca.pem").to_vec(),
+                )
+                .ok(),
+                oidc_client_info: None,
+                oidc_token_validation_config: Default::default(),
+                grpc_reflection_file_descriptor_sets: None,
+            }),
+            http_config: None,
+        },
+        &signature_table,
+        None,
+    )
+}
+
+/// Registers an additional Node with the provided name and [`Label`] in the [`Runtime`], without
+/// running it, and returns a [`RuntimeProxy`] for it. This allows tests to act on behalf of
+/// several Nodes from a single thread.
+fn register_idle_node(runtime: &RuntimeProxy, name: &str, label: &Label) -> RuntimeProxy {
+    register_privileged_idle_node(runtime, name, label, &NodePrivilege::default())
+}
+
+/// Same as [`register_idle_node`], but also grants the provided [`NodePrivilege`] to the Node.
+fn register_privileged_idle_node(
+    runtime: &RuntimeProxy,
+    name: &str,
+    label: &Label,
+    privilege: &NodePrivilege,
+) -> RuntimeProxy {
+    let proxy = runtime.runtime.clone().proxy_for_new_node(name);
+    proxy
+        .runtime
+        .node_configure_instance(
+            proxy.node_id,
+            "idle",
+            name,
+            label,
+            privilege,
+            NodeCapacity::Limit(usize::MAX),
+        )
+        .expect("could not configure idle node");
+    proxy
+}
+
+/// Makes the channel half identified by `handle` in the handle table of the Node of `runtime` also
+/// available to the Node of `node`, returning the new handle for it.
+fn share_handle(
+    runtime: &RuntimeProxy,
+    node: &RuntimeProxy,
+    handle: oak_abi::Handle,
+) -> Result<oak_abi::Handle, OakStatus> {
+    let half = runtime.runtime.abi_to_half(runtime.node_id, handle)?;
+    Ok(runtime.runtime.new_abi_handle(node.node_id, half))
+}
+
 /// Returns a non-trivial label for testing.
 fn test_label() -> Label {
     Label {
@@ -140,6 +209,18 @@ fn test_label() -> Label {
     }
 }
 
+/// A clock that only moves forward when told to.
+struct ManualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl crate::time::Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+}
+
 /// Checks that a panic in the node body actually causes the test case to fail, and does not
 /// accidentally get ignored.
 #[test]
@@ -475,6 +556,61 @@ fn create_node_invalid_configuration_err() {
     );
 }
 
+/// Create a test Node that creates a Wasm Node referencing a module that is not present in the
+/// application configuration, and fails.
+#[test]
+fn create_node_missing_wasm_module_err() {
+    let label = Label::public_untrusted();
+    let label_clone = label.clone();
+    run_node_body(
+        &label,
+        &NodePrivilege::default(),
+        Box::new(move |runtime| {
+            let (_write_handle, read_handle) = runtime.channel_create("", &label_clone)?;
+            let node_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+                    wasm_module_name: "missing".to_string(),
+                    wasm_entrypoint_name: "oak_main".to_string(),
+                })),
+            };
+            let result =
+                runtime.node_create("test", &node_configuration, &label_clone, read_handle);
+            assert_eq!(Err(OakStatus::ErrInvalidArgs), result);
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn validate_app_config_with_dangling_module_reference_err() {
+    init_logging();
+    let app_config = ApplicationConfiguration {
+        wasm_modules: hashmap! { "module".to_string() => vec![] },
+        initial_node_configuration: Some(NodeConfiguration {
+            config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+                wasm_module_name: "missing".to_string(),
+                wasm_entrypoint_name: "oak_main".to_string(),
+            })),
+        }),
+        module_signatures: vec![],
+    };
+    let result = crate::config::validate_app_config(&app_config);
+    assert!(matches!(
+        result,
+        Err(crate::node::ConfigurationError::WebAssemblyModuleNotFound(name)) if name == "missing"
+    ));
+
+    let result = crate::config::configure_and_run(RuntimeConfiguration {
+        app_config,
+        strict_config_validation: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        result,
+        Err(oak_io::OakError::OakStatus(OakStatus::ErrInvalidArgs))
+    ));
+}
+
 /// Create a test Node with a non public_trusted label, which is then unable to create channels
 /// of any sort, regardless of label.
 #[test]
@@ -612,6 +748,268 @@ fn wait_on_channels_blocks_if_all_channels_have_status_not_ready() {
     );
 }
 
+/// Checks that waiting for the first ready channel blocks until a message is written, and then
+/// returns the index of the channel that became ready.
+#[test]
+fn wait_on_channels_first_returns_ready_index() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (_write_handle_0, read_handle_0) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let (write_handle_1, read_handle_1) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+
+            let runtime_copy = runtime.clone();
+            let writer = std::thread::spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(10));
+                runtime_copy.channel_write(
+                    write_handle_1,
+                    NodeMessage {
+                        bytes: vec![],
+                        handles: vec![],
+                    },
+                )
+            });
+
+            assert_eq!(
+                Ok((1, ChannelReadStatus::ReadReady)),
+                runtime.wait_on_channels_first(&[read_handle_0, read_handle_1])
+            );
+            assert_eq!(Ok(()), writer.join().expect("writer thread panicked"));
+            // Invalid handles are reported immediately.
+            assert_eq!(
+                Ok((0, ChannelReadStatus::InvalidChannel)),
+                runtime.wait_on_channels_first(&[write_handle_1, read_handle_0])
+            );
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.wait_on_channels_first(&[])
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that writing and reading a message are counted by the channel throughput metrics.
+#[test]
+fn channel_traffic_metrics_count_round_trip() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![1, 2, 3],
+                    handles: vec![],
+                },
+            )?;
+            assert!(runtime.channel_read(read_handle)?.is_some());
+
+            let runtime_metrics = runtime.metrics_data().runtime_metrics;
+            for &direction in &["enqueue", "dequeue"] {
+                assert_eq!(
+                    1,
+                    runtime_metrics
+                        .runtime_channel_messages_total
+                        .with_label_values(&[direction])
+                        .get()
+                );
+                assert_eq!(
+                    3,
+                    runtime_metrics
+                        .runtime_channel_bytes_total
+                        .with_label_values(&[direction])
+                        .get()
+                );
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a Node blocking while waiting on a channel is counted as parked.
+#[test]
+fn wait_on_channels_counts_parks() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let park_total = || {
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_channel_park_total
+                    .get()
+            };
+            let parks_before = park_total();
+
+            let runtime_copy = runtime.clone();
+            std::thread::spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(10));
+                let result = runtime_copy.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![],
+                        handles: vec![],
+                    },
+                );
+                assert_eq!(Ok(()), result);
+            });
+
+            assert_eq!(
+                Ok(vec![ChannelReadStatus::ReadReady]),
+                runtime.wait_on_channels(&[read_handle])
+            );
+            assert!(park_total() > parks_before);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that polling reports the statuses of channels without blocking, in the same way as
+/// waiting on them.
+#[test]
+fn poll_channels_reports_statuses_without_blocking() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            // A channel more confidential than the Node, which it may create but not read from.
+            let (_confidential_write_handle, denied_handle) =
+                runtime.channel_create("", &test_label())?;
+            let invalid_handle = write_handle;
+
+            let handles = [read_handle, denied_handle, invalid_handle];
+            let expected = vec![
+                ChannelReadStatus::NotReady,
+                ChannelReadStatus::PermissionDenied,
+                ChannelReadStatus::InvalidChannel,
+            ];
+            assert_eq!(expected, runtime.poll_channels(&handles));
+            // Waiting returns immediately with the same statuses, due to the invalid channel.
+            assert_eq!(Ok(expected), runtime.wait_on_channels(&handles));
+
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![],
+                },
+            )?;
+            assert_eq!(
+                vec![ChannelReadStatus::ReadReady],
+                runtime.poll_channels(&[read_handle])
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that waiting with a timeout returns all statuses as not ready once the timeout elapses,
+/// even if the waiting thread is woken spuriously in the meantime.
+#[test]
+fn wait_on_channels_timeout_returns_not_ready_after_timeout() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let timeout = std::time::Duration::from_millis(100);
+
+            let runtime_copy = runtime.clone();
+            let waiter = std::thread::spawn(move || {
+                let start = std::time::Instant::now();
+                let result = runtime_copy.wait_on_channels_timeout(&[read_handle], timeout);
+                (start.elapsed(), result)
+            });
+            // Spuriously wake the waiting thread a few times.
+            for _ in 0..5 {
+                thread::sleep(std::time::Duration::from_millis(10));
+                waiter.thread().unpark();
+            }
+
+            let (elapsed, result) = waiter.join().unwrap();
+            assert!(elapsed >= timeout);
+            assert_eq!(Ok(vec![ChannelReadStatus::NotReady]), result);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that waiting with a timeout returns as soon as a message is available.
+#[test]
+fn wait_on_channels_timeout_returns_when_ready() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+
+            let runtime_copy = runtime.clone();
+            std::thread::spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(10));
+                let result = runtime_copy.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![],
+                        handles: vec![],
+                    },
+                );
+                assert_eq!(Ok(()), result);
+            });
+
+            let timeout = std::time::Duration::from_secs(60);
+            let start = std::time::Instant::now();
+            let result = runtime.wait_on_channels_timeout(&[read_handle], timeout);
+            assert!(start.elapsed() < timeout);
+            assert_eq!(Ok(vec![ChannelReadStatus::ReadReady]), result);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that waiting with a zero timeout just polls the statuses of the channels.
+#[test]
+fn wait_on_channels_zero_timeout_polls() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let zero = std::time::Duration::from_secs(0);
+            assert_eq!(
+                Ok(vec![ChannelReadStatus::NotReady]),
+                runtime.wait_on_channels_timeout(&[read_handle], zero)
+            );
+
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![],
+                },
+            )?;
+            assert_eq!(
+                Ok(vec![ChannelReadStatus::ReadReady]),
+                runtime.wait_on_channels_timeout(&[read_handle], zero)
+            );
+            Ok(())
+        }),
+    );
+}
+
 #[test]
 fn wait_on_channels_immediately_returns_if_any_channel_is_invalid() {
     let label = Label::public_untrusted();
@@ -650,6 +1048,61 @@ fn wait_on_channels_immediately_returns_if_the_input_list_is_empty() {
     );
 }
 
+/// Create a test Node that shares a channel using [`WakeStrategy::WakeOne`] between several
+/// waiting threads, and checks that every message is delivered to exactly one of them.
+#[test]
+fn wait_on_channels_wake_one_delivers_all_messages() {
+    let label = Label::public_untrusted();
+    let label_clone = label.clone();
+    run_node_body(
+        &label,
+        &NodePrivilege::default(),
+        Box::new(move |runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &label_clone,
+                &ChannelOptions {
+                    wake_strategy: WakeStrategy::WakeOne,
+                    ..ChannelOptions::default()
+                },
+            )?;
+
+            let waiters: Vec<_> = (0..3)
+                .map(|_| {
+                    let runtime = runtime.clone();
+                    std::thread::spawn(move || loop {
+                        let result = runtime.wait_on_channels(&[read_handle]);
+                        assert_eq!(Ok(vec![ChannelReadStatus::ReadReady]), result);
+                        // Another waiter may have read the message first, in which case we just
+                        // wait again.
+                        if let Some(message) = runtime.channel_read(read_handle).unwrap() {
+                            return message.bytes;
+                        }
+                    })
+                })
+                .collect();
+
+            for i in 0..3 {
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![i],
+                        handles: vec![],
+                    },
+                )?;
+            }
+
+            let mut received: Vec<u8> = waiters
+                .into_iter()
+                .flat_map(|waiter| waiter.join().unwrap())
+                .collect();
+            received.sort_unstable();
+            assert_eq!(vec![0, 1, 2], received);
+            Ok(())
+        }),
+    );
+}
+
 #[test]
 fn handle_clone_cloned_handle_is_distinct() {
     let label = Label::public_untrusted();
@@ -717,6 +1170,66 @@ fn downgrade_multiple_labels_using_top_privilege() {
         .flows_to(&Label::public_untrusted()));
 }
 
+/// Checks that the top privilege of a sub-lattice only downgrades the tags of that sub-lattice.
+#[test]
+fn downgrade_labels_using_namespace_top_privilege() {
+    init_logging();
+    let identity_top_privilege =
+        NodePrivilege::namespace_top_privilege(TagNamespace::PublicKeyIdentity);
+
+    let public_key_identity_tag = public_key_identity_tag(&[1, 2, 3]);
+    let tls_endpoint_tag = tls_endpoint_tag("google.com");
+
+    let public_key_identity_label = confidentiality_label(public_key_identity_tag.clone());
+    let tls_endpoint_label = confidentiality_label(tls_endpoint_tag.clone());
+    let mixed_label = Label {
+        confidentiality_tags: vec![public_key_identity_tag, tls_endpoint_tag],
+        integrity_tags: vec![],
+    };
+
+    // Identity tags are declassified.
+    assert!(identity_top_privilege
+        .downgrade_label(&public_key_identity_label)
+        .flows_to(&Label::public_untrusted()));
+    // But TLS endpoint tags are not, even alongside identity tags.
+    assert!(!identity_top_privilege
+        .downgrade_label(&tls_endpoint_label)
+        .flows_to(&Label::public_untrusted()));
+    let downgraded_mixed_label = identity_top_privilege.downgrade_label(&mixed_label);
+    assert!(!downgraded_mixed_label.flows_to(&Label::public_untrusted()));
+    assert!(downgraded_mixed_label.flows_to(&tls_endpoint_label));
+    // Nor is the top tag of all the sub-lattices.
+    assert!(!identity_top_privilege
+        .downgrade_label(&confidentiality_label(top()))
+        .flows_to(&Label::public_untrusted()));
+}
+
+/// Checks that a Node with the top privilege of a sub-lattice can read from channels labelled with
+/// tags of that sub-lattice, but not from channels labelled with tags of other sub-lattices.
+#[test]
+fn read_using_namespace_top_privilege() {
+    let identity_label = confidentiality_label(public_key_identity_tag(&[1, 1, 1]));
+    let tls_endpoint_label = confidentiality_label(tls_endpoint_tag("localhost"));
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::namespace_top_privilege(TagNamespace::PublicKeyIdentity),
+        Box::new(move |runtime| {
+            let (_write_handle, read_handle) = runtime.channel_create("", &identity_label)?;
+            assert!(matches!(
+                runtime.channel_read_with_downgrade(read_handle),
+                Ok(None)
+            ));
+
+            let (_write_handle, read_handle) = runtime.channel_create("", &tls_endpoint_label)?;
+            assert!(matches!(
+                runtime.channel_read_with_downgrade(read_handle),
+                Err(OakStatus::ErrPermissionDenied)
+            ));
+            Ok(())
+        }),
+    );
+}
+
 #[test]
 fn downgrade_tls_label_using_tls_privilege() {
     init_logging();
@@ -768,3 +1281,4842 @@ fn downgrade_wasm_label_using_signature_privilege_does_not_do_aything() {
         .downgrade_label(&wasm_label)
         .flows_to(&Label::public_untrusted()));
 }
+
+/// Checks that a Node that stops emitting heartbeats is reported as stuck, while a Node that keeps
+/// emitting heartbeats is not.
+#[test]
+fn check_heartbeats_reports_silent_node() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // Use idle Nodes, so that the test controls exactly when each of them emits heartbeats.
+            let heartbeating =
+                register_idle_node(&runtime, "heartbeating", &Label::public_untrusted());
+            let silent = register_idle_node(&runtime, "silent", &Label::public_untrusted());
+            heartbeating.heartbeat()?;
+            silent.heartbeat()?;
+
+            let interval = std::time::Duration::from_millis(50);
+            let report_config = HeartbeatConfiguration {
+                interval,
+                action: StuckNodeAction::Report,
+            };
+            std::thread::sleep(interval * 2);
+            heartbeating.heartbeat()?;
+            assert_eq!(
+                vec![silent.node_id],
+                runtime.runtime.check_heartbeats(&report_config)
+            );
+            // A stuck Node is only reported once until it emits another heartbeat.
+            assert!(runtime.runtime.check_heartbeats(&report_config).is_empty());
+            assert_eq!(
+                1,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_stuck_nodes_total
+                    .with_label_values(&["idle"])
+                    .get()
+            );
+
+            silent.heartbeat()?;
+            let terminate_config = HeartbeatConfiguration {
+                interval,
+                action: StuckNodeAction::Terminate,
+            };
+            std::thread::sleep(interval * 2);
+            heartbeating.heartbeat()?;
+            assert_eq!(
+                vec![silent.node_id],
+                runtime.runtime.check_heartbeats(&terminate_config)
+            );
+            assert_eq!(Err(OakStatus::ErrTerminated), silent.heartbeat());
+            assert_eq!(Ok(()), heartbeating.heartbeat());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a Node waiting on channels for longer than the watchdog threshold is reported as
+/// potentially stuck, without being interrupted.
+#[test]
+fn watchdog_reports_long_waiting_node() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let waiting = register_idle_node(&runtime, "waiting", &Label::public_untrusted());
+            let waiting_node_id = waiting.node_id;
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let waiting_read_handle = share_handle(&runtime, &waiting, read_handle)?;
+
+            runtime.runtime.track_waiting_nodes.store(true, SeqCst);
+            let threshold = std::time::Duration::from_millis(50);
+            let mut watchdog = crate::watchdog::Watchdog::new(WatchdogConfiguration { threshold });
+            let waiter =
+                std::thread::spawn(move || waiting.wait_on_channels(&[waiting_read_handle]));
+            // Only start measuring the wait once the Node is actually blocked.
+            while runtime
+                .runtime
+                .node_infos
+                .read()
+                .unwrap()
+                .get(&waiting_node_id)
+                .and_then(|node_info| node_info.liveness.waiting_since())
+                .is_none()
+            {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            std::thread::sleep(threshold * 2);
+            assert_eq!(vec![waiting_node_id], watchdog.check(&runtime.runtime));
+            // A Node is reported for as long as it keeps waiting.
+            assert_eq!(vec![waiting_node_id], watchdog.check(&runtime.runtime));
+            assert_eq!(
+                1,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_stuck_waiting_nodes
+                    .get()
+            );
+
+            // The waiting Node is not interrupted, and stops being reported once it is woken up.
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![],
+                },
+            )?;
+            assert_eq!(
+                Ok(vec![ChannelReadStatus::ReadReady]),
+                waiter.join().expect("waiting thread panicked")
+            );
+            assert!(watchdog.check(&runtime.runtime).is_empty());
+            assert_eq!(
+                0,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_stuck_waiting_nodes
+                    .get()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that all the handles of a Node can be transferred to another Node, which can then use
+/// them, and that the source Node is removed.
+#[test]
+fn transfer_handles_success() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let source = register_idle_node(&runtime, "source", &Label::public_untrusted());
+            let target = register_idle_node(&runtime, "target", &Label::public_untrusted());
+            let (write_handle, read_handle) =
+                source.channel_create("", &Label::public_untrusted())?;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+            source.channel_write(write_handle, message.clone())?;
+
+            let handle_map = source.transfer_handles(source.node_id, target.node_id)?;
+            assert_eq!(
+                hashset! {write_handle, read_handle},
+                handle_map.keys().copied().collect()
+            );
+            assert!(!runtime
+                .runtime
+                .node_infos
+                .read()
+                .unwrap()
+                .contains_key(&source.node_id));
+
+            let write_handle = handle_map[&write_handle];
+            let read_handle = handle_map[&read_handle];
+            assert_eq!(Some(message.clone()), target.channel_read(read_handle)?);
+            target.channel_write(write_handle, message.clone())?;
+            assert_eq!(Some(message), target.channel_read(read_handle)?);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that handles are not transferred if any of them may not be used by the target Node.
+#[test]
+fn transfer_handles_err_permission_denied() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let source = register_idle_node(&runtime, "source", &Label::public_untrusted());
+            // The target Node cannot write to a public channel.
+            let target = register_idle_node(&runtime, "target", &test_label());
+            let (write_handle, read_handle) =
+                source.channel_create("", &Label::public_untrusted())?;
+
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                source.transfer_handles(source.node_id, target.node_id)
+            );
+
+            // The source Node still holds its handles.
+            source.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![],
+                },
+            )?;
+            assert!(source.channel_read(read_handle)?.is_some());
+            assert!(runtime
+                .runtime
+                .node_infos
+                .read()
+                .unwrap()
+                .get(&target.node_id)
+                .unwrap()
+                .abi_handles
+                .is_empty());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the handles of a Node may only be transferred by the Node itself or by a Node with
+/// the top privilege.
+#[test]
+fn transfer_handles_err_caller_not_allowed() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let source = register_idle_node(&runtime, "source", &Label::public_untrusted());
+            let target = register_idle_node(&runtime, "target", &Label::public_untrusted());
+            let (write_handle, _read_handle) =
+                source.channel_create("", &Label::public_untrusted())?;
+
+            // A third Node may not move the handles of the source Node.
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                runtime.transfer_handles(source.node_id, target.node_id)
+            );
+            assert!(source
+                .runtime
+                .abi_to_half(source.node_id, write_handle)
+                .is_ok());
+
+            let privileged = register_privileged_idle_node(
+                &runtime,
+                "privileged",
+                &Label::public_untrusted(),
+                &NodePrivilege::top_privilege(),
+            );
+            let handle_map = privileged.transfer_handles(source.node_id, target.node_id)?;
+            assert_eq!(2, handle_map.len());
+            // Handles cannot be transferred twice.
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                privileged.transfer_handles(source.node_id, target.node_id)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the number of live Nodes is reported, both in total and by Node type.
+#[test]
+fn node_count_by_type() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            register_idle_node(&runtime, "idle-1", &Label::public_untrusted());
+            register_idle_node(&runtime, "idle-2", &Label::public_untrusted());
+
+            // Besides the idle Nodes, there are the implicit initial Node and the test Node itself.
+            assert_eq!(4, runtime.node_count(None));
+            assert_eq!(2, runtime.node_count(Some("idle")));
+            assert_eq!(1, runtime.node_count(Some("test")));
+            assert_eq!(0, runtime.node_count(Some("wasm")));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the reason given when closing the last write half reaches the reader.
+#[test]
+fn channel_close_with_reason_propagates_to_reader() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let write_handle_clone = runtime.handle_clone(write_handle)?;
+
+            runtime.channel_close_with_reason(write_handle, CloseReason::Canceled)?;
+            // There is still a writer, so the channel is not orphaned yet.
+            assert_eq!(None, runtime.channel_close_reason(read_handle)?);
+
+            assert_eq!(
+                ChannelReadOutcome::Empty,
+                runtime.channel_read_with_close_reason(read_handle)?
+            );
+
+            runtime.channel_close_with_reason(write_handle_clone, CloseReason::Error(42))?;
+            assert_eq!(
+                Err(OakStatus::ErrChannelClosed),
+                runtime.channel_read(read_handle)
+            );
+            assert_eq!(
+                Some(CloseReason::Error(42)),
+                runtime.channel_close_reason(read_handle)?
+            );
+            // The reason is also returned together with the indication that the channel is closed.
+            assert_eq!(
+                ChannelReadOutcome::Closed(CloseReason::Error(42)),
+                runtime.channel_read_with_close_reason(read_handle)?
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that closing a channel without an explicit reason counts as normal completion.
+#[test]
+fn channel_close_default_reason_is_completed() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            runtime.channel_close(write_handle)?;
+            assert_eq!(
+                Some(CloseReason::Completed),
+                runtime.channel_close_reason(read_handle)?
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that closing a handle twice only fails when not using the idempotent variant.
+#[test]
+fn channel_close_idempotent_tolerates_double_close() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+
+            assert_eq!(Ok(()), runtime.channel_close(write_handle));
+            assert_eq!(
+                Err(OakStatus::ErrBadHandle),
+                runtime.channel_close(write_handle)
+            );
+
+            assert_eq!(Ok(()), runtime.channel_close_idempotent(read_handle));
+            assert_eq!(Ok(()), runtime.channel_close_idempotent(read_handle));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that only channels without recent reads or writes are reported as idle.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn idle_channels_reports_unused_channel() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (_idle_write_handle, idle_read_handle) =
+                runtime.channel_create("idle", &Label::public_untrusted())?;
+            let (active_write_handle, active_read_handle) =
+                runtime.channel_create("active", &Label::public_untrusted())?;
+            let channel_id = |handle| {
+                runtime
+                    .runtime
+                    .abi_to_half(runtime.node_id, handle)
+                    .map(|half| half.get_channel_id())
+            };
+            let idle_channel_id = channel_id(idle_read_handle)?;
+            let active_channel_id = channel_id(active_read_handle)?;
+
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            runtime.channel_write(
+                active_write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![],
+                },
+            )?;
+
+            let idle_channel_ids: Vec<u64> = runtime
+                .runtime
+                .idle_channels(std::time::Duration::from_millis(100))
+                .iter()
+                .map(|idle_channel| idle_channel.channel_id)
+                .collect();
+            assert!(idle_channel_ids.contains(&idle_channel_id));
+            assert!(!idle_channel_ids.contains(&active_channel_id));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the downgrades applied by successive privileged Nodes accumulate in the history of
+/// the data they forward.
+#[test]
+fn downgrade_history_accumulates_across_privileged_nodes() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime.runtime.set_downgrade_history_limit(Some(8));
+            let tag_1 = public_key_identity_tag(&[1, 1, 1]);
+            let tag_2 = public_key_identity_tag(&[2, 2, 2]);
+            let privilege = |tag: &oak_abi::label::Tag| NodePrivilege {
+                can_declassify_confidentiality_tags: hashset! { tag.clone() },
+                can_endorse_integrity_tags: hashset! {},
+            };
+            let hop_1 = register_privileged_idle_node(
+                &runtime,
+                "hop-1",
+                &Label::public_untrusted(),
+                &privilege(&tag_1),
+            );
+            let hop_2 = register_privileged_idle_node(
+                &runtime,
+                "hop-2",
+                &Label::public_untrusted(),
+                &privilege(&tag_2),
+            );
+            let share = |node, handle| share_handle(&runtime, node, handle);
+
+            let (write_handle_1, read_handle_1) =
+                runtime.channel_create("", &confidentiality_label(tag_1.clone()))?;
+            let (write_handle_2, read_handle_2) =
+                runtime.channel_create("", &confidentiality_label(tag_2.clone()))?;
+            let (write_handle_3, read_handle_3) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+            runtime.channel_write(write_handle_1, message.clone())?;
+
+            let message = hop_1
+                .channel_read_with_downgrade(share(&hop_1, read_handle_1)?)?
+                .expect("no message");
+            hop_1.channel_write(share(&hop_1, write_handle_2)?, message)?;
+            let message = hop_2
+                .channel_read_with_downgrade(share(&hop_2, read_handle_2)?)?
+                .expect("no message");
+            hop_2.channel_write(share(&hop_2, write_handle_3)?, message)?;
+
+            assert!(runtime.channel_read(read_handle_3)?.is_some());
+            assert_eq!(
+                vec![
+                    DowngradeRecord {
+                        node_id: hop_1.node_id,
+                        removed_tags: vec![tag_1],
+                        added_tags: vec![],
+                    },
+                    DowngradeRecord {
+                        node_id: hop_2.node_id,
+                        removed_tags: vec![tag_2],
+                        added_tags: vec![],
+                    },
+                ],
+                runtime.downgrade_history()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that ensuring a Node exists creates it only the first time.
+#[test]
+fn ensure_node_creates_missing_node_once() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let node_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let created = runtime.ensure_node(
+                "logger",
+                &node_configuration,
+                &Label::public_untrusted(),
+                read_handle,
+            )?;
+            let node_id = match created {
+                EnsuredNode::Created(node_id) => node_id,
+                EnsuredNode::Existing(node_id) => panic!("unexpected existing node {:?}", node_id),
+            };
+
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            assert_eq!(
+                Ok(EnsuredNode::Existing(node_id)),
+                runtime.ensure_node(
+                    "logger",
+                    &node_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            );
+            assert_eq!(1, runtime.node_count(Some("logger")));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that an existing Node is found even if it was not created through `ensure_node`.
+#[test]
+fn ensure_node_returns_existing_node() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let idle_node = register_idle_node(&runtime, "existing", &Label::public_untrusted());
+            let node_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            assert_eq!(
+                Ok(EnsuredNode::Existing(idle_node.node_id)),
+                runtime.ensure_node(
+                    "existing",
+                    &node_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            );
+            assert_eq!(0, runtime.node_count(Some("logger")));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that messages containing more handles than allowed are rejected.
+#[test]
+fn channel_write_too_many_handles_err() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime.runtime.max_handles_per_message.store(2, SeqCst);
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let message = |handles| NodeMessage {
+                bytes: vec![],
+                handles,
+            };
+
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_write(
+                    write_handle,
+                    message(vec![read_handle, read_handle, read_handle])
+                )
+            );
+            assert_eq!(None, runtime.channel_read(read_handle)?);
+
+            runtime.channel_write(write_handle, message(vec![read_handle, read_handle]))?;
+            assert!(runtime.channel_read(read_handle)?.is_some());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the privilege of a chain of Nodes can downgrade labels that none of the Nodes can
+/// downgrade on its own.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn chain_privilege_combines_node_privileges() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let tag_1 = public_key_identity_tag(&[1, 1, 1]);
+            let tag_2 = public_key_identity_tag(&[2, 2, 2]);
+            let privilege = |tag: &oak_abi::label::Tag| NodePrivilege {
+                can_declassify_confidentiality_tags: hashset! { tag.clone() },
+                can_endorse_integrity_tags: hashset! {},
+            };
+            let node_1 = register_privileged_idle_node(
+                &runtime,
+                "node-1",
+                &Label::public_untrusted(),
+                &privilege(&tag_1),
+            );
+            let node_2 = register_privileged_idle_node(
+                &runtime,
+                "node-2",
+                &Label::public_untrusted(),
+                &privilege(&tag_2),
+            );
+            let label = Label {
+                confidentiality_tags: vec![tag_1.clone(), tag_2.clone()],
+                integrity_tags: vec![],
+            };
+
+            for node_id in &[node_1.node_id, node_2.node_id] {
+                let node_privilege = runtime.runtime.chain_privilege(&[*node_id])?;
+                assert!(!node_privilege
+                    .downgrade_label(&label)
+                    .flows_to(&Label::public_untrusted()));
+            }
+            let chain_privilege = runtime
+                .runtime
+                .chain_privilege(&[node_1.node_id, node_2.node_id])?;
+            assert_eq!(
+                &hashset! { tag_1, tag_2 },
+                chain_privilege.can_declassify_confidentiality_tags()
+            );
+            assert!(chain_privilege
+                .downgrade_label(&label)
+                .flows_to(&Label::public_untrusted()));
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime
+                    .runtime
+                    .chain_privilege(&[NodeId(u64::MAX)])
+                    .map(|_| ())
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that raw configuration bytes are injected unchanged, as a single message.
+#[test]
+fn inject_raw_bytes_ok() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let bytes = br#"{"key": "value"}"#.to_vec();
+            crate::config::inject_raw_bytes(&runtime, write_handle, bytes.clone())
+                .expect("could not inject raw bytes");
+
+            assert_eq!(
+                Some(NodeMessage {
+                    bytes,
+                    handles: vec![]
+                }),
+                runtime.channel_read(read_handle)?
+            );
+            // The initial channel is closed after the injection.
+            assert_eq!(
+                Err(OakStatus::ErrChannelClosed),
+                runtime.channel_read(read_handle)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Module whose entrypoint reads a message from its initial channel, creates a channel named after
+/// the bytes of the message, and then waits on that channel until the Runtime terminates.
+const RAW_CONFIG_MODULE: &str = r#"
+    (module
+        (import "oak" "wait_on_channels"
+          (func $wait_on_channels (param i32 i32) (result i32)))
+        (import "oak" "channel_read"
+          (func $channel_read (param i64 i32 i32 i32 i32 i32 i32) (result i32)))
+        (import "oak" "channel_create"
+          (func $channel_create (param i32 i32 i32 i32 i32 i32) (result i32)))
+        (func $oak_main (param $handle i64)
+          (i64.store (i32.const 8) (local.get $handle))
+          (drop (call $wait_on_channels (i32.const 8) (i32.const 1)))
+          (drop (call $channel_read
+            (local.get $handle) (i32.const 64) (i32.const 256) (i32.const 0)
+            (i32.const 0) (i32.const 0) (i32.const 4)))
+          (drop (call $channel_create
+            (i32.const 24) (i32.const 32) (i32.const 64) (i32.load (i32.const 0))
+            (i32.const 0) (i32.const 0)))
+          (i64.store (i32.const 8) (i64.load (i32.const 32)))
+          (drop (call $wait_on_channels (i32.const 8) (i32.const 1))))
+        (memory (;0;) 1)
+        (export "memory" (memory 0))
+        (export "oak_main" (func $oak_main)))
+    "#;
+
+/// Checks that [`crate::config::configure_and_run_raw`] delivers the raw bytes unchanged to the
+/// initial Node.
+#[test]
+fn configure_and_run_raw_delivers_bytes_to_initial_node() {
+    init_logging();
+    let bytes = br#"{"key": "value"}"#.to_vec();
+    let runtime = crate::config::configure_and_run_raw(
+        RuntimeConfiguration {
+            app_config: ApplicationConfiguration {
+                wasm_modules: hashmap! {
+                    "module".to_string() => wat::parse_str(RAW_CONFIG_MODULE).unwrap(),
+                },
+                initial_node_configuration: Some(NodeConfiguration {
+                    config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+                        wasm_module_name: "module".to_string(),
+                        wasm_entrypoint_name: "oak_main".to_string(),
+                    })),
+                }),
+                module_signatures: vec![],
+            },
+            ..Default::default()
+        },
+        bytes.clone(),
+    )
+    .expect("could not run application");
+
+    // The initial Node names a channel after the bytes it received.
+    let name = String::from_utf8(bytes).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !runtime
+        .node_infos
+        .read()
+        .unwrap()
+        .values()
+        .any(|node_info| {
+            node_info
+                .abi_handles
+                .values()
+                .any(|half| half.get_channel_name() == name)
+        })
+    {
+        assert!(
+            Instant::now() < deadline,
+            "initial node did not receive the raw bytes"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    runtime.stop();
+}
+
+/// Checks that a channel linking two Nodes is found, together with its message counts.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn trace_link_finds_connecting_channel() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let sender = register_idle_node(&runtime, "sender", &Label::public_untrusted());
+            let receiver = register_idle_node(&runtime, "receiver", &Label::public_untrusted());
+            let (write_handle, read_handle) =
+                runtime.channel_create("link", &Label::public_untrusted())?;
+            let sender_write_handle = share_handle(&runtime, &sender, write_handle)?;
+            share_handle(&runtime, &receiver, read_handle)?;
+
+            sender.channel_write(
+                sender_write_handle,
+                NodeMessage {
+                    bytes: vec![14, 12, 88],
+                    handles: vec![],
+                },
+            )?;
+
+            let link_channels = runtime
+                .runtime
+                .trace_link(sender.node_id, receiver.node_id)?;
+            assert_eq!(1, link_channels.len());
+            assert_eq!(1, link_channels[0].messages_written);
+            assert_eq!(1, link_channels[0].messages_pending);
+            // The link only goes in one direction.
+            assert_eq!(
+                Ok(vec![]),
+                runtime.runtime.trace_link(receiver.node_id, sender.node_id)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the capacity reported when probing for the size of a serialized label is enough to
+/// then retrieve it.
+#[test]
+fn get_serialized_label_probe_then_read() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let label = test_label();
+            let (write_handle, _read_handle) = runtime.channel_create("", &label)?;
+            let capacity = match runtime.get_serialized_channel_label(write_handle, 0)? {
+                LabelReadStatus::NeedsCapacity(capacity) => capacity,
+                status => panic!("unexpected status {:?}", status),
+            };
+            match runtime.get_serialized_channel_label(write_handle, capacity)? {
+                LabelReadStatus::Success(bytes) => {
+                    assert_eq!(capacity, bytes.len());
+                    assert_eq!(Ok(label), Label::decode(bytes.as_slice()));
+                }
+                status => panic!("unexpected status {:?}", status),
+            }
+
+            // The public untrusted label of the Node serializes to an empty byte array.
+            match runtime.get_serialized_node_label(0)? {
+                LabelReadStatus::Success(bytes) => assert!(bytes.is_empty()),
+                status => panic!("unexpected status {:?}", status),
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that per-node-type configuration defaults only fill in the settings that are not
+/// explicitly specified for a Node.
+#[test]
+fn node_config_defaults_are_overridden_by_explicit_settings() {
+    let wasm_config = |module: &str, entrypoint: &str| NodeConfiguration {
+        config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
+            wasm_module_name: module.to_string(),
+            wasm_entrypoint_name: entrypoint.to_string(),
+        })),
+    };
+    let defaults = hashmap! {
+        "wasm_config".to_string() => wasm_config("default", "oak_main"),
+    };
+
+    // The entrypoint is not specified, so it is inherited from the defaults.
+    assert_eq!(
+        wasm_config("a", "oak_main"),
+        node::apply_node_config_defaults(&defaults, &wasm_config("a", ""))
+    );
+    // The entrypoint is specified, so it overrides the default one.
+    assert_eq!(
+        wasm_config("b", "custom"),
+        node::apply_node_config_defaults(&defaults, &wasm_config("b", "custom"))
+    );
+    // Defaults for other types of Node are not applied.
+    let log_config = NodeConfiguration {
+        config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+    };
+    assert_eq!(
+        log_config,
+        node::apply_node_config_defaults(&defaults, &log_config)
+    );
+
+    // Repeated fields replace the default ones if specified, rather than being appended to them.
+    let roughtime_config = |servers: &[&str], timeout_seconds: Option<u32>| NodeConfiguration {
+        config_type: Some(ConfigType::RoughtimeClientConfig(
+            RoughtimeClientConfiguration {
+                servers: servers
+                    .iter()
+                    .map(|name| RoughtimeServer {
+                        name: name.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                timeout_seconds,
+                ..Default::default()
+            },
+        )),
+    };
+    let defaults = hashmap! {
+        "roughtime_client_config".to_string() => roughtime_config(&["default"], Some(3)),
+    };
+    assert_eq!(
+        roughtime_config(&["a", "b"], Some(3)),
+        node::apply_node_config_defaults(&defaults, &roughtime_config(&["a", "b"], None))
+    );
+    assert_eq!(
+        roughtime_config(&["default"], Some(5)),
+        node::apply_node_config_defaults(&defaults, &roughtime_config(&[], Some(5)))
+    );
+}
+
+/// Checks that writing to a channel at capacity fails until a reader drains it.
+#[test]
+fn channel_write_at_capacity_err_until_drained() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    capacity: Some(2),
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let message = |byte| NodeMessage {
+                bytes: vec![byte],
+                handles: vec![],
+            };
+
+            assert_eq!(Ok(()), runtime.channel_write(write_handle, message(0)));
+            assert_eq!(Ok(()), runtime.channel_write(write_handle, message(1)));
+            assert_eq!(
+                Err(OakStatus::ErrChannelFull),
+                runtime.channel_write(write_handle, message(2))
+            );
+
+            // Draining a message makes room for a further write.
+            assert_eq!(vec![0], runtime.channel_read(read_handle)?.unwrap().bytes);
+            assert_eq!(Ok(()), runtime.channel_write(write_handle, message(3)));
+            assert_eq!(
+                Err(OakStatus::ErrChannelFull),
+                runtime.channel_write(write_handle, message(4))
+            );
+
+            // The rejected messages were never queued.
+            assert_eq!(vec![1], runtime.channel_read(read_handle)?.unwrap().bytes);
+            assert_eq!(vec![3], runtime.channel_read(read_handle)?.unwrap().bytes);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that channels cannot be created with zero capacity.
+#[test]
+fn channel_create_zero_capacity_err() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let result = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    capacity: Some(0),
+                    ..ChannelOptions::default()
+                },
+            );
+            assert_eq!(Err(OakStatus::ErrInvalidArgs), result);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the privilege of a Node is recorded as an introspection event, right after the Node
+/// is created and before any other event related to it.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn node_privilege_assigned_event_follows_node_created() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let privilege = NodePrivilege {
+                can_declassify_confidentiality_tags: hashset! { public_key_identity_tag(&[1, 1, 1]) },
+                can_endorse_integrity_tags: hashset! { tls_endpoint_tag("localhost") },
+            };
+            let node = register_privileged_idle_node(
+                &runtime,
+                "privileged",
+                &Label::public_untrusted(),
+                &privilege,
+            );
+            let (write_handle, _read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            share_handle(&runtime, &node, write_handle)?;
+
+            let node_events: Vec<EventDetails> = runtime
+                .runtime
+                .introspection_event_queue
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|event| event.event_details.clone())
+                .filter(|details| match details {
+                    EventDetails::NodeCreated(created) => created.node_id == node.node_id.0,
+                    EventDetails::NodePrivilegeAssigned(assigned) => {
+                        assigned.node_id == node.node_id.0
+                    }
+                    EventDetails::HandleCreated(created) => created.node_id == node.node_id.0,
+                    _ => false,
+                })
+                .collect();
+            assert_eq!(3, node_events.len());
+            assert!(matches!(node_events[0], EventDetails::NodeCreated(_)));
+            assert_eq!(
+                EventDetails::NodePrivilegeAssigned(NodePrivilegeAssigned {
+                    node_id: node.node_id.0,
+                    privilege: Some(privilege.into()),
+                }),
+                node_events[1]
+            );
+            assert!(matches!(node_events[2], EventDetails::HandleCreated(_)));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a blocking write to a channel at capacity waits until a reader drains it.
+#[test]
+fn channel_write_blocking_waits_for_capacity() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    capacity: Some(1),
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let message = |byte| NodeMessage {
+                bytes: vec![byte],
+                handles: vec![],
+            };
+            runtime.channel_write(write_handle, message(0))?;
+
+            let (sender, receiver) = mpsc::channel();
+            let writer = {
+                let runtime = runtime.clone();
+                std::thread::spawn(move || {
+                    let result = runtime.channel_write_blocking(write_handle, message(1));
+                    sender.send(()).unwrap();
+                    result
+                })
+            };
+            // The writer stays blocked while the channel is full.
+            assert!(receiver
+                .recv_timeout(std::time::Duration::from_millis(100))
+                .is_err());
+
+            assert_eq!(vec![0], runtime.channel_read(read_handle)?.unwrap().bytes);
+            assert_eq!(Ok(()), writer.join().unwrap());
+            assert_eq!(vec![1], runtime.channel_read(read_handle)?.unwrap().bytes);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a blocking write to a channel at capacity fails once the channel loses its last
+/// reader, rather than blocking forever.
+#[test]
+fn channel_write_blocking_orphaned_err() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    capacity: Some(1),
+                    ..ChannelOptions::default()
+                },
+            )?;
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![0],
+                    handles: vec![],
+                },
+            )?;
+
+            let writer = {
+                let runtime = runtime.clone();
+                std::thread::spawn(move || {
+                    runtime.channel_write_blocking(
+                        write_handle,
+                        NodeMessage {
+                            bytes: vec![1],
+                            handles: vec![],
+                        },
+                    )
+                })
+            };
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            runtime.channel_close(read_handle)?;
+            assert_eq!(Err(OakStatus::ErrChannelClosed), writer.join().unwrap());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that repeated messages of the same kind for the same Node are only logged once per
+/// interval.
+#[test]
+fn repeated_messages_are_rate_limited() {
+    let limiter = log_limiter::LogRateLimiter::default();
+    let node_id = NodeId(1);
+    let interval = Duration::from_secs(60);
+
+    // Only the first occurrence is logged within the interval.
+    assert_eq!(Some(0), limiter.check(node_id, "cannot_write", interval));
+    for _ in 0..4 {
+        assert_eq!(None, limiter.check(node_id, "cannot_write", interval));
+    }
+    assert_eq!(4, limiter.suppressed(node_id, "cannot_write"));
+    // Messages for other Nodes are tracked separately.
+    assert_eq!(0, limiter.suppressed(NodeId(2), "cannot_write"));
+
+    // Once the interval has elapsed, the next occurrence is logged again, together with the number
+    // of suppressed ones.
+    assert_eq!(
+        Some(4),
+        limiter.check(node_id, "cannot_write", Duration::from_millis(0))
+    );
+    assert_eq!(0, limiter.suppressed(node_id, "cannot_write"));
+}
+
+/// Checks that the degree of a channel counts the distinct Nodes holding each of its halves.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn channel_degree_counts_distinct_nodes() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("hotspot", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, write_handle)?
+                .get_channel_id();
+            for name in &["writer-1", "writer-2"] {
+                let writer = register_idle_node(&runtime, name, &Label::public_untrusted());
+                share_handle(&runtime, &writer, write_handle)?;
+            }
+            for name in &["reader-1", "reader-2", "reader-3"] {
+                let reader = register_idle_node(&runtime, name, &Label::public_untrusted());
+                // A Node holding several handles for the same half is only counted once.
+                share_handle(&runtime, &reader, read_handle)?;
+                share_handle(&runtime, &reader, read_handle)?;
+            }
+
+            // The creating Node also holds both halves.
+            assert_eq!((3, 4), runtime.runtime.channel_degree(channel_id));
+
+            runtime.channel_close(write_handle)?;
+            runtime.channel_close(read_handle)?;
+            assert_eq!((2, 3), runtime.runtime.channel_degree(channel_id));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the introspection events are exported as a trace with a track per Node, and a span
+/// for each message processed by a Node.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn chrome_trace_has_node_tracks() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let sender = register_idle_node(&runtime, "sender", &Label::public_untrusted());
+            let receiver = register_idle_node(&runtime, "receiver", &Label::public_untrusted());
+            let (write_handle, read_handle) =
+                runtime.channel_create("link", &Label::public_untrusted())?;
+            let sender_write_handle = share_handle(&runtime, &sender, write_handle)?;
+            let receiver_read_handle = share_handle(&runtime, &receiver, read_handle)?;
+            sender.channel_write(
+                sender_write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![],
+                },
+            )?;
+            receiver.channel_read(receiver_read_handle)?;
+            receiver.channel_close(receiver_read_handle)?;
+
+            let trace: serde_json::Value =
+                serde_json::from_str(&runtime.runtime.chrome_trace()).unwrap();
+            let trace_events = trace["traceEvents"].as_array().unwrap();
+
+            let thread_names: HashMap<u64, &str> = trace_events
+                .iter()
+                .filter(|event| event["name"] == "thread_name" && event["pid"] == 1)
+                .map(|event| {
+                    (
+                        event["tid"].as_u64().unwrap(),
+                        event["args"]["name"].as_str().unwrap(),
+                    )
+                })
+                .collect();
+            assert_eq!(Some(&"sender"), thread_names.get(&sender.node_id.0));
+            assert_eq!(Some(&"receiver"), thread_names.get(&receiver.node_id.0));
+
+            let spans: Vec<&serde_json::Value> = trace_events
+                .iter()
+                .filter(|event| event["ph"] == "X")
+                .collect();
+            assert_eq!(1, spans.len());
+            assert_eq!("process message", spans[0]["name"]);
+            assert_eq!(receiver.node_id.0, spans[0]["tid"].as_u64().unwrap());
+            assert!(spans[0]["dur"].as_f64().unwrap() >= 0.0);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that peeking at a channel returns its front message without dequeuing it.
+#[test]
+fn channel_peek_leaves_message_in_place() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            assert_eq!(Ok(None), runtime.channel_peek(read_handle));
+
+            let (_other_write_handle, other_read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![14, 12, 88],
+                    handles: vec![other_read_handle],
+                },
+            )?;
+
+            let expected = PeekedMessage {
+                bytes: vec![14, 12, 88],
+                handle_count: 1,
+            };
+            assert_eq!(Ok(Some(expected)), runtime.channel_peek(read_handle));
+            assert_eq!(
+                Ok(Some(PeekedMessage {
+                    bytes: vec![14, 12, 88],
+                    handle_count: 1,
+                })),
+                runtime.channel_peek(read_handle)
+            );
+
+            // The message is still available to be read.
+            let message = runtime.channel_read(read_handle)?.unwrap();
+            assert_eq!(vec![14, 12, 88], message.bytes);
+            assert_eq!(1, message.handles.len());
+
+            runtime.channel_close(write_handle)?;
+            assert_eq!(
+                Err(OakStatus::ErrChannelClosed),
+                runtime.channel_peek(read_handle)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a batch read returns up to the requested number of messages, in order, stopping
+/// early if the channel empties.
+#[test]
+fn channel_read_batch_drains_up_to_max_messages() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            for i in 0..5 {
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![i],
+                        handles: vec![],
+                    },
+                )?;
+            }
+            let bytes = |messages: Vec<NodeMessage>| -> Vec<Vec<u8>> {
+                messages.into_iter().map(|message| message.bytes).collect()
+            };
+
+            assert_eq!(
+                vec![vec![0], vec![1], vec![2]],
+                bytes(runtime.channel_read_batch(read_handle, 3)?)
+            );
+            assert_eq!(
+                vec![vec![3], vec![4]],
+                bytes(runtime.channel_read_batch(read_handle, 3)?)
+            );
+            assert_eq!(Ok(vec![]), runtime.channel_read_batch(read_handle, 3));
+
+            runtime.channel_close(write_handle)?;
+            assert_eq!(
+                Err(OakStatus::ErrChannelClosed),
+                runtime.channel_read_batch(read_handle, 3)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a batch read emits an introspection event for each message read.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn channel_read_batch_emits_event_per_message() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            for i in 0..3 {
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![i],
+                        handles: vec![],
+                    },
+                )?;
+            }
+            assert_eq!(3, runtime.channel_read_batch(read_handle, 10)?.len());
+
+            let dequeued_count = runtime
+                .runtime
+                .introspection_event_queue
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| {
+                    matches!(event.event_details, Some(EventDetails::MessageDequeued(_)))
+                })
+                .count();
+            assert_eq!(3, dequeued_count);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that an uncontrolled Node that can downgrade its label to "public untrusted" can only be
+/// registered if it can also downgrade its label to the configured egress label.
+#[test]
+fn uncontrolled_node_egress_label_err() {
+    struct UncontrolledNode;
+
+    impl crate::node::Node for UncontrolledNode {
+        fn node_type(&self) -> &'static str {
+            "uncontrolled"
+        }
+        fn run(
+            self: Box<Self>,
+            _runtime: RuntimeProxy,
+            _handle: oak_abi::Handle,
+            _notify_receiver: oneshot::Receiver<()>,
+        ) {
+        }
+    }
+
+    let register_uncontrolled_node = |runtime: &RuntimeProxy, privilege: &NodePrivilege| {
+        let (_write_handle, read_handle) =
+            runtime.channel_create("", &Label::public_untrusted())?;
+        runtime.node_register(
+            CreatedNode {
+                instance: Box::new(UncontrolledNode),
+                privilege: privilege.clone(),
+            },
+            "uncontrolled",
+            &Label::public_untrusted(),
+            read_handle,
+        )
+    };
+
+    // By default, the Node only needs to be able to downgrade its label to "public untrusted".
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(move |runtime| {
+            assert_eq!(
+                Ok(()),
+                register_uncontrolled_node(&runtime, &NodePrivilege::default())
+            );
+            Ok(())
+        }),
+    );
+
+    // A stricter egress label requires the Node to be able to endorse data.
+    let egress_tag = tls_endpoint_tag("egress.example.com");
+    let permissions = crate::permissions::PermissionsConfiguration {
+        uncontrolled_node_egress_label: Some(Label {
+            confidentiality_tags: vec![],
+            integrity_tags: vec![egress_tag.clone()],
+        }),
+        ..Default::default()
+    };
+    run_node_body_with_permissions(
+        &permissions,
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(move |runtime| {
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                register_uncontrolled_node(&runtime, &NodePrivilege::default())
+            );
+            let endorsing_privilege = NodePrivilege {
+                can_declassify_confidentiality_tags: hashset! {},
+                can_endorse_integrity_tags: hashset! { egress_tag.clone() },
+            };
+            assert_eq!(
+                Ok(()),
+                register_uncontrolled_node(&runtime, &endorsing_privilege)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that comparing state snapshots taken around the creation of a channel reports exactly
+/// the new channel and its two handles.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn state_snapshot_diff_reports_created_channel() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let before = runtime.runtime.state_snapshot();
+            let (write_handle, read_handle) =
+                runtime.channel_create("snapshot", &Label::public_untrusted())?;
+            let after = runtime.runtime.state_snapshot();
+
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, write_handle)?
+                .get_channel_id();
+            let mut added_handles = vec![
+                (runtime.node_id, write_handle),
+                (runtime.node_id, read_handle),
+            ];
+            added_handles.sort();
+            assert_eq!(
+                StateDiff {
+                    added_channels: vec![channel_id],
+                    added_handles,
+                    ..StateDiff::default()
+                },
+                before.diff(&after)
+            );
+            assert_eq!(
+                Some(&ChannelSnapshot {
+                    label: Label::public_untrusted(),
+                    depth: 0,
+                }),
+                after.channels.get(&channel_id)
+            );
+            assert!(after.diff(&after).is_empty());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that channel queue lengths are reported per channel up to the configured limit, and
+/// aggregated beyond it.
+#[test]
+fn channel_queue_length_metric_aggregates_beyond_limit() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime.runtime.set_channel_queue_length_limit(Some(1));
+            let gauge = runtime
+                .metrics_data()
+                .runtime_metrics
+                .runtime_channel_queue_length;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+
+            let (first_write, first_read) =
+                runtime.channel_create("first", &Label::public_untrusted())?;
+            let first_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, first_write)?
+                .get_channel_id()
+                .to_string();
+            runtime.channel_write(first_write, message.clone())?;
+            runtime.channel_write(first_write, message.clone())?;
+            assert_eq!(2, gauge.with_label_values(&[&first_id]).get());
+
+            let (second_write, second_read) =
+                runtime.channel_create("second", &Label::public_untrusted())?;
+            runtime.channel_write(second_write, message.clone())?;
+            assert_eq!(
+                1,
+                gauge
+                    .with_label_values(&[crate::metrics::channel_queue::OTHER_CHANNELS_LABEL])
+                    .get()
+            );
+
+            runtime.channel_read(first_read)?;
+            assert_eq!(1, gauge.with_label_values(&[&first_id]).get());
+
+            // Destroying a channel removes its pending messages from the aggregate.
+            runtime.channel_close(second_write)?;
+            runtime.channel_close(second_read)?;
+            assert_eq!(
+                0,
+                gauge
+                    .with_label_values(&[crate::metrics::channel_queue::OTHER_CHANNELS_LABEL])
+                    .get()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that channels with duplicate names are only rejected if channel names must be unique,
+/// and that the name of a destroyed channel can be reused.
+#[test]
+fn unique_channel_names_rejects_duplicates() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("duplicate", &Label::public_untrusted())?;
+            assert!(runtime
+                .channel_create("duplicate", &Label::public_untrusted())
+                .is_ok());
+            runtime.channel_close(write_handle)?;
+            runtime.channel_close(read_handle)?;
+
+            runtime
+                .runtime
+                .require_unique_channel_names
+                .store(true, SeqCst);
+            let (write_handle, read_handle) =
+                runtime.channel_create("unique", &Label::public_untrusted())?;
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_create("unique", &Label::public_untrusted())
+            );
+            // Empty names are exempt.
+            assert!(runtime
+                .channel_create("", &Label::public_untrusted())
+                .is_ok());
+            assert!(runtime
+                .channel_create("", &Label::public_untrusted())
+                .is_ok());
+
+            runtime.channel_close(write_handle)?;
+            runtime.channel_close(read_handle)?;
+            assert!(runtime
+                .channel_create("unique", &Label::public_untrusted())
+                .is_ok());
+
+            // Channels created by pseudo-Nodes are exempt.
+            let pseudo_node = register_idle_node(&runtime, "pseudo", &Label::public_untrusted());
+            runtime
+                .runtime
+                .node_infos
+                .write()
+                .unwrap()
+                .get_mut(&pseudo_node.node_id)
+                .unwrap()
+                .sandboxed = false;
+            assert!(pseudo_node
+                .channel_create("unique", &Label::public_untrusted())
+                .is_ok());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the names of the channels of a Node are released when the Node exits, as its
+/// remaining handles are closed.
+#[test]
+fn unique_channel_names_are_released_on_node_exit() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime
+                .runtime
+                .require_unique_channel_names
+                .store(true, SeqCst);
+            let node = register_idle_node(&runtime, "idle", &Label::public_untrusted());
+            node.channel_create("owned", &Label::public_untrusted())?;
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_create("owned", &Label::public_untrusted())
+            );
+
+            runtime.runtime.remove_node_id(node.node_id);
+            assert!(runtime
+                .channel_create("owned", &Label::public_untrusted())
+                .is_ok());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the initial handle passed to a Node when starting it is recorded.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn node_initial_handle_is_recorded() {
+    struct ReportingNode {
+        report_sender: mpsc::SyncSender<(NodeId, oak_abi::Handle)>,
+        done_receiver: mpsc::Receiver<()>,
+    }
+
+    impl crate::node::Node for ReportingNode {
+        fn node_type(&self) -> &'static str {
+            "reporting"
+        }
+        fn isolation(&self) -> NodeIsolation {
+            NodeIsolation::Sandboxed
+        }
+        fn run(
+            self: Box<Self>,
+            runtime: RuntimeProxy,
+            handle: oak_abi::Handle,
+            _notify_receiver: oneshot::Receiver<()>,
+        ) {
+            self.report_sender.send((runtime.node_id, handle)).unwrap();
+            // Keep the Node registered until the test is done inspecting it.
+            let _ = self.done_receiver.recv();
+        }
+    }
+
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (report_sender, report_receiver) = mpsc::sync_channel(1);
+            let (done_sender, done_receiver) = mpsc::sync_channel(1);
+            let (_write_handle, read_handle) =
+                runtime.channel_create("bootstrap", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, read_handle)?
+                .get_channel_id();
+            runtime.node_register(
+                CreatedNode {
+                    instance: Box::new(ReportingNode {
+                        report_sender,
+                        done_receiver,
+                    }),
+                    privilege: NodePrivilege::default(),
+                },
+                "reporting",
+                &Label::public_untrusted(),
+                read_handle,
+            )?;
+
+            let (node_id, handle) = report_receiver.recv().unwrap();
+            assert_eq!(
+                Some((handle, channel_id)),
+                runtime.runtime.node_initial_handle(node_id)
+            );
+            assert_eq!(
+                Some(handle),
+                runtime.runtime.state_snapshot().nodes[&node_id].initial_handle
+            );
+            assert_eq!(None, runtime.runtime.node_initial_handle(NodeId(u64::MAX)));
+            done_sender.send(()).unwrap();
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that metrics are encoded in the OpenMetrics format with the expected names and types.
+#[test]
+fn metrics_encode_openmetrics() {
+    let metrics = Metrics::new();
+    metrics
+        .runtime_metrics
+        .runtime_stuck_nodes_total
+        .with_label_values(&["wasm"])
+        .inc();
+    metrics
+        .grpc_client_metrics
+        .observe_response_handling_completed("server", "Method", "OK", 1);
+    metrics
+        .grpc_client_metrics
+        .observe_new_request("server", "Method", 10);
+    metrics.runtime_metrics.runtime_health_check.set(1);
+
+    let encoded = Metrics::encode_openmetrics(&metrics.gather());
+    let lines: Vec<&str> = encoded.lines().collect();
+    for expected_line in &[
+        // Counter names that already have the `_total` suffix are preserved.
+        "# TYPE runtime_stuck_nodes counter",
+        "runtime_stuck_nodes_total{node_type=\"wasm\"} 1.0",
+        // Other counters gain the suffix.
+        "# TYPE grpc_client_completed counter",
+        "grpc_client_completed_total{method_name=\"Method\",server=\"server\",status_code=\"OK\"} 1.0",
+        "# TYPE runtime_health_check gauge",
+        "runtime_health_check 1.0",
+        "# TYPE grpc_client_sent_bytes histogram",
+        "# UNIT grpc_client_sent_bytes bytes",
+        "grpc_client_received_msgs_bucket{method_name=\"Method\",server=\"server\",le=\"+Inf\"} 1.0",
+        "grpc_client_received_msgs_count{method_name=\"Method\",server=\"server\"} 1.0",
+    ] {
+        assert!(
+            lines.contains(expected_line),
+            "missing line {:?} in:\n{}",
+            expected_line,
+            encoded
+        );
+    }
+    assert_eq!(Some(&"# EOF"), lines.last());
+}
+
+/// Checks that metrics are encoded as JSON with their names, types, labels and values.
+#[test]
+fn metrics_encode_json() {
+    let metrics = Metrics::new();
+    metrics
+        .runtime_metrics
+        .runtime_stuck_nodes_total
+        .with_label_values(&["wasm"])
+        .inc_by(2);
+    metrics
+        .grpc_client_metrics
+        .observe_new_request("server", "Method", 10);
+    metrics.runtime_metrics.runtime_health_check.set(1);
+
+    let encoded: serde_json::Value =
+        serde_json::from_str(&Metrics::encode_json(&metrics.gather())).unwrap();
+    let families = encoded.as_array().unwrap();
+    let family = |name: &str| {
+        families
+            .iter()
+            .find(|family| family["name"] == name)
+            .unwrap_or_else(|| panic!("missing metric {:?} in {}", name, encoded))
+    };
+
+    let stuck_nodes = family("runtime_stuck_nodes_total");
+    assert_eq!("counter", stuck_nodes["type"]);
+    assert_eq!(
+        serde_json::json!([{"labels": {"node_type": "wasm"}, "value": 2.0}]),
+        stuck_nodes["metrics"]
+    );
+
+    let health_check = family("runtime_health_check");
+    assert_eq!("gauge", health_check["type"]);
+    assert_eq!(1.0, health_check["metrics"][0]["value"]);
+
+    let sent_bytes = family("grpc_client_sent_bytes");
+    assert_eq!("histogram", sent_bytes["type"]);
+    let value = &sent_bytes["metrics"][0]["value"];
+    assert_eq!(1, value["count"]);
+    assert_eq!(10.0, value["sum"]);
+    assert!(!value["buckets"].as_array().unwrap().is_empty());
+}
+
+/// Checks that counters and gauges are encoded in the StatsD line protocol, with counters sent as
+/// increments since the previous flush.
+#[test]
+fn statsd_encoder_sends_counter_increments() {
+    let metrics = Metrics::new();
+    let stuck_nodes = metrics
+        .runtime_metrics
+        .runtime_stuck_nodes_total
+        .with_label_values(&["wasm"]);
+    stuck_nodes.inc_by(3);
+    metrics.runtime_metrics.runtime_health_check.set(-1);
+
+    let mut encoder = crate::metrics::push::StatsdEncoder::new("oak");
+    let lines = encoder.encode(&metrics.gather());
+    assert!(lines.contains(&"oak.runtime_stuck_nodes_total:3|c|#node_type:wasm".to_string()));
+    // Negative gauges are reset first, as they would otherwise be treated as a decrement.
+    let health_check_lines: Vec<&String> = lines
+        .iter()
+        .filter(|line| line.starts_with("oak.runtime_health_check:"))
+        .collect();
+    assert_eq!(
+        vec![
+            "oak.runtime_health_check:0|g",
+            "oak.runtime_health_check:-1|g"
+        ],
+        health_check_lines
+    );
+
+    // Unchanged counters are not sent again.
+    let lines = encoder.encode(&metrics.gather());
+    assert!(!lines.iter().any(|line| line.contains("stuck_nodes")));
+    stuck_nodes.inc();
+    let lines = encoder.encode(&metrics.gather());
+    assert!(lines.contains(&"oak.runtime_stuck_nodes_total:1|c|#node_type:wasm".to_string()));
+}
+
+/// Checks that the Runtime stops promptly while the heartbeat monitor and the watchdog are
+/// running, even though their interval and threshold are much longer than the stop takes.
+#[test]
+fn stop_does_not_wait_for_periodic_checks() {
+    let runtime = create_test_runtime(&crate::permissions::PermissionsConfiguration::default());
+    let period = std::time::Duration::from_secs(3600);
+    {
+        let mut aux_servers = runtime.runtime.aux_servers.lock().unwrap();
+        aux_servers.push(AuxServer::spawn(
+            "heartbeat-monitor",
+            runtime.runtime.clone(),
+            move |runtime, termination_notification_receiver| {
+                crate::heartbeat::monitor_heartbeats(
+                    HeartbeatConfiguration {
+                        interval: period,
+                        action: StuckNodeAction::Report,
+                    },
+                    runtime,
+                    termination_notification_receiver,
+                )
+            },
+        ));
+        aux_servers.push(AuxServer::spawn(
+            "watchdog",
+            runtime.runtime.clone(),
+            move |runtime, termination_notification_receiver| {
+                crate::watchdog::monitor_waiting_nodes(
+                    WatchdogConfiguration { threshold: period },
+                    runtime,
+                    termination_notification_receiver,
+                )
+            },
+        ));
+    }
+
+    let start = Instant::now();
+    runtime.runtime.stop();
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(10),
+        "stopping took {:?}",
+        start.elapsed()
+    );
+}
+
+/// Checks that metrics are pushed to the configured StatsD server until the push thread is
+/// stopped.
+#[test]
+fn metrics_are_pushed_over_udp() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            socket
+                .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+                .unwrap();
+            let config = MetricsPushConfiguration {
+                address: socket.local_addr().unwrap(),
+                flush_interval: std::time::Duration::from_millis(10),
+                prefix: "".to_string(),
+            };
+            runtime
+                .metrics_data()
+                .runtime_metrics
+                .runtime_health_check
+                .set(1);
+            let push_server = AuxServer::spawn(
+                "metrics-push",
+                runtime.runtime.clone(),
+                move |runtime, termination_notification_receiver| {
+                    crate::metrics::push::push_metrics(
+                        config,
+                        runtime,
+                        termination_notification_receiver,
+                    )
+                },
+            );
+
+            let mut buffer = [0; 2048];
+            let size = socket.recv(&mut buffer).unwrap();
+            let datagram = String::from_utf8_lossy(&buffer[..size]).to_string();
+            assert!(
+                datagram
+                    .lines()
+                    .any(|line| line == "runtime_health_check:1|g"),
+                "unexpected datagram: {}",
+                datagram
+            );
+            // Dropping the server stops the push thread.
+            drop(push_server);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the metrics server serves HTTPS when a TLS identity is provided, even while another
+/// client stalls its TLS handshake.
+#[test]
+fn metrics_server_serves_https() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // Find a free port on the loopback interface.
+            let address = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            let tls_config = crate::tls::TlsConfig::new(
+                "../examples/certs/local/local.pem",
+                "../examples/certs/local/local.key",
+            )
+            .expect("could not load TLS identity");
+            let mut metrics_server = AuxServer::new(
+                "metrics",
+                address,
+                Some(tls_config),
+                runtime.runtime.clone(),
+                crate::metrics::server::start_metrics_server,
+            );
+            metrics_server.wait_until_bound()?;
+            // A client that connects but never starts its TLS handshake.
+            let _stalled_client = std::net::TcpStream::connect(address).unwrap();
+
+            let mut http = hyper::client::HttpConnector::new();
+            http.enforce_http(false);
+            let mut tls = rustls::ClientConfig::new();
+            tls.root_store
+                .add_pem_file(&mut &include_bytes!("../../examples/certs/local/ca.pem")[..])
+                .expect("could not load CA certificate");
+            let client = hyper::Client::builder()
+                .build::<_, hyper::Body>(hyper_rustls::HttpsConnector::from((http, tls)));
+            let uri: hyper::Uri = format!("https://localhost:{}/metrics", address.port())
+                .parse()
+                .unwrap();
+            let body = tokio::runtime::Runtime::new().unwrap().block_on(async {
+                let response = client.get(uri).await.expect("HTTPS scrape failed");
+                assert_eq!(http::StatusCode::OK, response.status());
+                hyper::body::to_bytes(response.into_body()).await.unwrap()
+            });
+            assert!(
+                String::from_utf8_lossy(&body).contains("runtime_health_check"),
+                "unexpected metrics: {:?}",
+                body
+            );
+            drop(metrics_server);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the health check served alongside the metrics reports the Runtime as unavailable
+/// once it is stopped.
+#[test]
+fn metrics_server_serves_health_check() {
+    let runtime = create_test_runtime(&crate::permissions::PermissionsConfiguration::default());
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let mut metrics_server = AuxServer::new(
+        "metrics",
+        address,
+        None,
+        runtime.runtime.clone(),
+        crate::metrics::server::start_metrics_server,
+    );
+    metrics_server.wait_until_bound().unwrap();
+
+    let client = hyper::Client::new();
+    let status = || {
+        let uri: hyper::Uri = format!("http://{}/healthz", address).parse().unwrap();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(client.get(uri))
+            .expect("health check request failed")
+            .status()
+    };
+    assert_eq!(http::StatusCode::OK, status());
+    runtime.runtime.stop();
+    assert_eq!(http::StatusCode::SERVICE_UNAVAILABLE, status());
+    drop(metrics_server);
+}
+
+/// Checks that the introspection server rejects requests without the configured token.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn introspection_server_requires_auth_token() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            *runtime.runtime.introspect_auth_token.write().unwrap() = Some("secret".to_string());
+            let address = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            let mut introspect_server = AuxServer::new(
+                "introspect",
+                address,
+                None,
+                runtime.runtime.clone(),
+                crate::introspect::serve,
+            );
+            introspect_server.wait_until_bound()?;
+
+            let client = hyper::Client::new();
+            let status = |authorization: Option<String>| {
+                let mut request = hyper::Request::get(format!("http://{}/objcount", address));
+                if let Some(authorization) = authorization {
+                    request = request.header(hyper::header::AUTHORIZATION, authorization);
+                }
+                let request = request.body(hyper::Body::empty()).unwrap();
+                tokio::runtime::Runtime::new()
+                    .unwrap()
+                    .block_on(client.request(request))
+                    .expect("introspection request failed")
+                    .status()
+            };
+            assert_eq!(http::StatusCode::UNAUTHORIZED, status(None));
+            assert_eq!(
+                http::StatusCode::UNAUTHORIZED,
+                status(Some("Bearer wrong".to_string()))
+            );
+            assert_eq!(
+                http::StatusCode::UNAUTHORIZED,
+                status(Some("Bearer secret-suffix".to_string()))
+            );
+            assert_eq!(
+                http::StatusCode::OK,
+                status(Some("Bearer secret".to_string()))
+            );
+            assert_eq!(
+                http::StatusCode::OK,
+                status(Some(format!("Basic {}", base64::encode("user:secret"))))
+            );
+            drop(introspect_server);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the stopper of a Node that terminated before being fully started is discarded
+/// cleanly if the Node was removed within the tombstone grace period.
+#[test]
+fn node_tombstone_correlates_late_node_stopper() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let node_stopper = || {
+                let (notify_sender, _notify_receiver) = oneshot::channel::<()>();
+                NodeStopper {
+                    node_name: "short-lived".to_string(),
+                    join_handle: std::thread::spawn(|| {}),
+                    finished: Arc::new(AtomicBool::new(true)),
+                    notify_sender: Some(notify_sender),
+                }
+            };
+
+            // Without tombstones, the removed Node is not remembered.
+            let node = register_idle_node(&runtime, "short-lived", &Label::public_untrusted());
+            runtime.runtime.remove_node_id(node.node_id);
+            assert!(!runtime.runtime.node_tombstones.contains(node.node_id));
+            runtime
+                .runtime
+                .add_node_stopper(node.node_id, node_stopper());
+
+            // Reproduce the race in which the Node thread terminates before its stopper is added.
+            runtime
+                .runtime
+                .node_tombstones
+                .set_grace_period(Some(std::time::Duration::from_secs(60)));
+            let node = register_idle_node(&runtime, "short-lived", &Label::public_untrusted());
+            runtime.runtime.remove_node_id(node.node_id);
+            assert!(runtime.runtime.node_tombstones.contains(node.node_id));
+            runtime
+                .runtime
+                .add_node_stopper(node.node_id, node_stopper());
+            // The tombstone is consumed once the late stopper has been correlated with it.
+            assert!(!runtime.runtime.node_tombstones.contains(node.node_id));
+
+            // Tombstones are swept once the grace period has expired.
+            runtime
+                .runtime
+                .node_tombstones
+                .set_grace_period(Some(std::time::Duration::from_millis(1)));
+            let first = register_idle_node(&runtime, "first", &Label::public_untrusted());
+            runtime.runtime.remove_node_id(first.node_id);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let second = register_idle_node(&runtime, "second", &Label::public_untrusted());
+            runtime.runtime.remove_node_id(second.node_id);
+            assert!(!runtime.runtime.node_tombstones.contains(first.node_id));
+            assert!(runtime.runtime.node_tombstones.contains(second.node_id));
+
+            // Tombstones are also swept when looking one up, even if no more Nodes are removed.
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            assert!(!runtime.runtime.node_tombstones.take(first.node_id));
+            assert!(!runtime.runtime.node_tombstones.contains(second.node_id));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the threads of rapidly exiting Nodes are joined, regardless of whether each Node is
+/// removed before or after its stopper is added.
+#[test]
+fn exited_node_threads_are_joined() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let live_threads = Arc::new(());
+            let node_stopper = || {
+                let (notify_sender, _notify_receiver) = oneshot::channel::<()>();
+                let live_thread = live_threads.clone();
+                let finished = Arc::new(AtomicBool::new(false));
+                let thread_finished = finished.clone();
+                NodeStopper {
+                    node_name: "short-lived".to_string(),
+                    join_handle: std::thread::spawn(move || {
+                        drop(live_thread);
+                        thread_finished.store(true, SeqCst);
+                    }),
+                    finished,
+                    notify_sender: Some(notify_sender),
+                }
+            };
+
+            for i in 0..20 {
+                let node = register_idle_node(&runtime, "short-lived", &Label::public_untrusted());
+                if i % 2 == 0 {
+                    // The Node thread terminates before its stopper is added.
+                    runtime.runtime.remove_node_id(node.node_id);
+                    runtime
+                        .runtime
+                        .add_node_stopper(node.node_id, node_stopper());
+                } else {
+                    // The Node thread terminates after its stopper is added.
+                    runtime
+                        .runtime
+                        .add_node_stopper(node.node_id, node_stopper());
+                    runtime.runtime.remove_node_id(node.node_id);
+                }
+            }
+
+            // The pending threads are joined once they have finished, without stopping the Runtime.
+            for _ in 0..1000 {
+                runtime.runtime.join_exited_nodes();
+                if runtime
+                    .runtime
+                    .exited_node_stoppers
+                    .lock()
+                    .unwrap()
+                    .is_empty()
+                {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            assert!(runtime
+                .runtime
+                .exited_node_stoppers
+                .lock()
+                .unwrap()
+                .is_empty());
+            assert_eq!(1, Arc::strong_count(&live_threads));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that messages rejected by the validator of a channel are not enqueued.
+#[test]
+fn channel_validator_rejects_invalid_messages() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "validated",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    validator: Some(MessageValidator::new(|bytes| {
+                        if bytes.is_empty() {
+                            Err("empty message".to_string())
+                        } else {
+                            Ok(())
+                        }
+                    })),
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let valid_message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+            let invalid_message = NodeMessage {
+                bytes: vec![],
+                handles: vec![],
+            };
+
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_write(write_handle, invalid_message)
+            );
+            runtime.channel_write(write_handle, valid_message.clone())?;
+            assert_eq!(Some(valid_message), runtime.channel_read(read_handle)?);
+            assert_eq!(None, runtime.channel_read(read_handle)?);
+
+            #[cfg(feature = "oak-unsafe")]
+            {
+                let events = runtime.runtime.introspection_event_queue.lock().unwrap();
+                let reasons: Vec<&str> = events
+                    .iter()
+                    .filter_map(|event| match &event.event_details {
+                        Some(EventDetails::MessageRejected(rejected)) => {
+                            Some(rejected.reason.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(vec!["empty message"], reasons);
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that Runtimes seeded with the same value allocate the same handles for the same
+/// operations.
+#[test]
+fn rng_seed_makes_handle_allocation_reproducible() {
+    let handle_sequences = Arc::new(Mutex::new(Vec::new()));
+    for seed in &[42, 42, 43] {
+        let seed = *seed;
+        let handle_sequences = handle_sequences.clone();
+        run_node_body(
+            &Label::public_untrusted(),
+            &NodePrivilege::default(),
+            Box::new(move |runtime| {
+                runtime.runtime.set_rng_seed(Some(seed));
+                let mut handles = Vec::new();
+                for _ in 0..3 {
+                    let (write_handle, read_handle) =
+                        runtime.channel_create("test", &Label::public_untrusted())?;
+                    handles.push(write_handle);
+                    handles.push(read_handle);
+                }
+                handle_sequences.lock().unwrap().push(handles);
+                Ok(())
+            }),
+        );
+    }
+    let handle_sequences = handle_sequences.lock().unwrap();
+    assert_eq!(handle_sequences[0], handle_sequences[1]);
+    assert_ne!(handle_sequences[0], handle_sequences[2]);
+}
+
+/// Checks that migrating a channel moves its queued messages to the new channel in order, and
+/// rebinds the handles of the old channel to the new channel.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn migrate_channel_preserves_queued_messages() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let message = |byte| NodeMessage {
+                bytes: vec![byte],
+                handles: vec![],
+            };
+            let channel_id = |handle| {
+                runtime
+                    .runtime
+                    .abi_to_half(runtime.node_id, handle)
+                    .map(|half| half.get_channel_id())
+            };
+            let (old_write_handle, old_read_handle) = runtime.channel_create_with_options(
+                "old",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    capacity: Some(2),
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let (new_write_handle, new_read_handle) =
+                runtime.channel_create("new", &Label::public_untrusted())?;
+            runtime.channel_write(old_write_handle, message(0))?;
+            runtime.channel_write(old_write_handle, message(1))?;
+            runtime.channel_write(new_write_handle, message(2))?;
+
+            let old_channel_id = channel_id(old_write_handle)?;
+            let new_channel_id = channel_id(new_write_handle)?;
+            assert_eq!(
+                Ok(2),
+                runtime
+                    .runtime
+                    .migrate_channel(old_channel_id, new_channel_id)
+            );
+            assert_eq!(Ok(new_channel_id), channel_id(old_write_handle));
+            assert_eq!(Ok(new_channel_id), channel_id(old_read_handle));
+
+            // The old channel is no longer referred to by any handle.
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime
+                    .runtime
+                    .migrate_channel(old_channel_id, new_channel_id)
+            );
+
+            // Messages written using the rebound handle are queued after the migrated ones.
+            runtime.channel_write(old_write_handle, message(3))?;
+            runtime.channel_write(old_write_handle, message(4))?;
+            for expected in 0..5 {
+                assert_eq!(
+                    vec![expected],
+                    runtime.channel_read(new_read_handle)?.unwrap().bytes
+                );
+            }
+            assert_eq!(None, runtime.channel_read(old_read_handle)?);
+
+            // Messages cannot be migrated to a channel with a different label.
+            let (secret_write_handle, _) = runtime.channel_create("secret", &test_label())?;
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                runtime
+                    .runtime
+                    .migrate_channel(new_channel_id, channel_id(secret_write_handle)?)
+            );
+
+            // Labels with the same tags in a different order are the same label.
+            let (first_tag, second_tag) =
+                (public_key_identity_tag(&[1]), public_key_identity_tag(&[2]));
+            let (ordered_write_handle, _) = runtime.channel_create(
+                "ordered",
+                &Label {
+                    confidentiality_tags: vec![first_tag.clone(), second_tag.clone()],
+                    integrity_tags: vec![],
+                },
+            )?;
+            let (reordered_write_handle, _) = runtime.channel_create(
+                "reordered",
+                &Label {
+                    confidentiality_tags: vec![second_tag, first_tag],
+                    integrity_tags: vec![],
+                },
+            )?;
+            assert_eq!(
+                Ok(0),
+                runtime.runtime.migrate_channel(
+                    channel_id(ordered_write_handle)?,
+                    channel_id(reordered_write_handle)?
+                )
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a Node with an allowlist of creatable Node types can only create Nodes of these
+/// types, and that the allowlists of the Nodes it creates are restricted to it.
+#[test]
+fn creatable_node_types_restrict_node_creation() {
+    let permissions = crate::permissions::PermissionsConfiguration {
+        allow_log_nodes: true,
+        allow_egress_https_authorities: vec!["localhost:8080".to_string()],
+        ..Default::default()
+    };
+    run_node_body_with_permissions(
+        &permissions,
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime
+                .runtime
+                .node_infos
+                .write()
+                .unwrap()
+                .get_mut(&runtime.node_id)
+                .unwrap()
+                .creatable_node_types = Some(hashset! {"wasm".to_string(), "logger".to_string()});
+
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let grpc_client_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::GrpcClientConfig(GrpcClientConfiguration {
+                    uri: "https://localhost:8080".to_string(),
+                })),
+            };
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                runtime.node_create(
+                    "grpc-client",
+                    &grpc_client_configuration,
+                    &Label::public_untrusted(),
+                    read_handle
+                )
+            );
+
+            let log_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            let log_node_id = runtime.runtime.clone().node_create_and_register(
+                runtime.node_id,
+                "log",
+                &log_configuration,
+                &Label::public_untrusted(),
+                read_handle,
+                Some(&["logger", "grpc-client"]),
+                Downgrading::No,
+            )?;
+            assert_eq!(
+                Some(hashset! {"logger".to_string()}),
+                runtime.runtime.node_infos.read().unwrap()[&log_node_id].creatable_node_types
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that Node creation fails once the limit of live Nodes is reached, and succeeds again
+/// once a Node exits.
+#[test]
+fn max_nodes_limits_node_creation() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // The implicit initial Node and the test Node are already live.
+            assert_eq!(2, runtime.runtime.node_count(None));
+            runtime.runtime.set_max_nodes(Some(3));
+            let idle_node = register_idle_node(&runtime, "idle", &Label::public_untrusted());
+            assert_eq!(3, runtime.runtime.node_count(None));
+
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let log_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    read_handle
+                )
+            );
+            assert_eq!(3, runtime.runtime.node_count(None));
+
+            runtime.runtime.remove_node_id(idle_node.node_id);
+            assert_eq!(2, runtime.runtime.node_count(None));
+            runtime.node_create(
+                "log",
+                &log_configuration,
+                &Label::public_untrusted(),
+                read_handle,
+            )?;
+            assert_eq!(3, runtime.runtime.node_count(None));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that Nodes created concurrently cannot exceed the limit of live Nodes.
+#[test]
+fn max_nodes_limits_concurrent_node_creation() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // The implicit initial Node and the test Node are already live.
+            runtime.runtime.set_max_nodes(Some(3));
+            let log_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            let mut read_handles = Vec::new();
+            for _ in 0..8 {
+                let (_write_handle, read_handle) =
+                    runtime.channel_create("", &Label::public_untrusted())?;
+                read_handles.push(read_handle);
+            }
+            let barrier = Arc::new(std::sync::Barrier::new(read_handles.len()));
+            let creators: Vec<_> = read_handles
+                .into_iter()
+                .map(|read_handle| {
+                    let runtime_copy = runtime.clone();
+                    let log_configuration = log_configuration.clone();
+                    let barrier = barrier.clone();
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        runtime_copy.node_create(
+                            "log",
+                            &log_configuration,
+                            &Label::public_untrusted(),
+                            read_handle,
+                        )
+                    })
+                })
+                .collect();
+            let results: Vec<_> = creators
+                .into_iter()
+                .map(|creator| creator.join().expect("creator thread panicked"))
+                .collect();
+            assert_eq!(1, results.iter().filter(|result| result.is_ok()).count());
+            assert!(
+                results
+                    .iter()
+                    .all(|result| *result == Ok(())
+                        || *result == Err(OakStatus::ErrResourceExhausted))
+            );
+            assert_eq!(3, runtime.runtime.node_count(None));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a subgraph is only instantiated if all its Nodes fit within the limit of live Nodes,
+/// so that it is never partially created.
+#[test]
+fn max_nodes_limits_whole_subgraph() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // The implicit initial Node and the test Node are already live.
+            runtime.runtime.set_max_nodes(Some(4));
+            let subgraph = |names: &[&str]| SubgraphSpec {
+                nodes: names
+                    .iter()
+                    .map(|name| SubgraphNode {
+                        name: name.to_string(),
+                        config: NodeConfiguration {
+                            config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+                        },
+                        label: Label::public_untrusted(),
+                    })
+                    .collect(),
+                edges: vec![],
+            };
+
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.instantiate_subgraph(&subgraph(&["first", "second", "third"]))
+            );
+            assert_eq!(2, runtime.runtime.node_count(None));
+
+            // Capacity reserved for a subgraph cannot be taken by other Nodes in the meantime, and
+            // is returned once the subgraph has been instantiated.
+            let reservation = runtime
+                .runtime
+                .reserve_node_capacity(2)
+                .expect("could not reserve capacity");
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let log_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            );
+            drop(reservation);
+            assert_eq!(
+                2,
+                runtime
+                    .instantiate_subgraph(&subgraph(&["first", "second"]))?
+                    .len()
+            );
+            assert_eq!(4, runtime.runtime.node_count(None));
+            assert_eq!(0, runtime.runtime.reserved_nodes.load(SeqCst));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a channel created with an initial message is immediately ready to be read.
+#[test]
+fn channel_create_with_message_is_ready() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+            let (write_handle, read_handle) = runtime.channel_create_with_message(
+                "bootstrap",
+                &Label::public_untrusted(),
+                message.clone(),
+            )?;
+            assert_eq!(
+                vec![ChannelReadStatus::ReadReady],
+                runtime.poll_channels(&[read_handle])
+            );
+            assert_eq!(Ok(Some(message)), runtime.channel_read(read_handle));
+            runtime.channel_close(write_handle)?;
+            runtime.channel_close(read_handle)?;
+
+            // A message that cannot be written does not leave a channel behind.
+            let handle_count = runtime.runtime.list_handles(runtime.node_id).len();
+            let invalid_message = NodeMessage {
+                bytes: vec![],
+                handles: vec![u64::MAX],
+            };
+            assert_eq!(
+                Err(OakStatus::ErrBadHandle),
+                runtime.channel_create_with_message(
+                    "bootstrap",
+                    &Label::public_untrusted(),
+                    invalid_message
+                )
+            );
+            assert_eq!(
+                handle_count,
+                runtime.runtime.list_handles(runtime.node_id).len()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that both halves of a created channel are listed in the handle table of the Node.
+#[test]
+fn list_handles_shows_created_channel() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let node = register_idle_node(&runtime, "idle", &Label::public_untrusted());
+            assert_eq!(
+                Vec::<(oak_abi::Handle, ChannelHalfDirection, u64)>::new(),
+                runtime.runtime.list_handles(node.node_id)
+            );
+
+            let (write_handle, read_handle) =
+                node.channel_create("", &Label::public_untrusted())?;
+            let channel_id = node
+                .runtime
+                .abi_to_half(node.node_id, write_handle)?
+                .get_channel_id();
+            let mut expected = vec![
+                (write_handle, ChannelHalfDirection::Write, channel_id),
+                (read_handle, ChannelHalfDirection::Read, channel_id),
+            ];
+            expected.sort_by_key(|(handle, _, _)| *handle);
+            assert_eq!(expected, runtime.runtime.list_handles(node.node_id));
+            assert_eq!(
+                Vec::<(oak_abi::Handle, ChannelHalfDirection, u64)>::new(),
+                runtime.runtime.list_handles(NodeId(u64::MAX))
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that handles cannot be added to the handle table of a Node beyond the configured limit,
+/// whether by cloning a handle, creating a channel or reading a message carrying handles.
+#[test]
+fn max_handles_per_node_limits_handle_table() {
+    let permissions = crate::permissions::PermissionsConfiguration {
+        max_handles_per_node: Some(4),
+        ..Default::default()
+    };
+    run_node_body_with_permissions(
+        &permissions,
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // The handle table already holds the initial handle of the Node.
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let cloned_handle = runtime.handle_clone(read_handle)?;
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.handle_clone(read_handle)
+            );
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_create("", &Label::public_untrusted())
+            );
+
+            runtime.channel_write(
+                write_handle,
+                NodeMessage {
+                    bytes: vec![],
+                    handles: vec![cloned_handle],
+                },
+            )?;
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_read(read_handle)
+            );
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.channel_read_batch(read_handle, 2)
+            );
+            assert!(matches!(
+                runtime.channel_try_read_message(read_handle, 0, 1),
+                Err(OakStatus::ErrInvalidArgs)
+            ));
+
+            runtime.channel_close(cloned_handle)?;
+            assert!(runtime.channel_read(read_handle)?.is_some());
+
+            // Messages without handles can still be read with a full handle table.
+            for _ in 0..3 {
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![],
+                        handles: vec![],
+                    },
+                )?;
+            }
+            assert_eq!(2, runtime.channel_read_batch(read_handle, 2)?.len());
+            assert!(runtime.channel_read(read_handle)?.is_some());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that handles added to the handle table of a Node concurrently cannot exceed the
+/// configured limit.
+#[test]
+fn max_handles_per_node_limits_concurrent_handle_clones() {
+    let permissions = crate::permissions::PermissionsConfiguration {
+        max_handles_per_node: Some(10),
+        ..Default::default()
+    };
+    run_node_body_with_permissions(
+        &permissions,
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            // The handle table already holds the initial handle of the Node.
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let barrier = Arc::new(std::sync::Barrier::new(16));
+            let cloners: Vec<_> = (0..16)
+                .map(|_| {
+                    let runtime_copy = runtime.clone();
+                    let barrier = barrier.clone();
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        runtime_copy.handle_clone(read_handle)
+                    })
+                })
+                .collect();
+            let cloned = cloners
+                .into_iter()
+                .map(|cloner| cloner.join().expect("cloner thread panicked"))
+                .filter(|result| result.is_ok())
+                .count();
+            assert_eq!(7, cloned);
+            assert_eq!(10, runtime.runtime.list_handles(runtime.node_id).len());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a Node may create a burst of Nodes, after which further creations are throttled
+/// until its token bucket has been refilled.
+#[test]
+fn node_creation_rate_limit_throttles_after_burst() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let clock = Arc::new(ManualClock {
+                start: Instant::now(),
+                elapsed: Mutex::new(Duration::from_secs(0)),
+            });
+            runtime.runtime.set_clock(clock.clone());
+            runtime
+                .runtime
+                .node_creation_rate_limiter
+                .set_limit(Some(NodeCreationRateLimit {
+                    rate: 0.5,
+                    burst: 2,
+                }));
+            let log_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            let create_log_node = || {
+                let (_write_handle, read_handle) =
+                    runtime.channel_create("", &Label::public_untrusted())?;
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            };
+
+            assert_eq!(Ok(()), create_log_node());
+            assert_eq!(Ok(()), create_log_node());
+            assert_eq!(Err(OakStatus::ErrResourceExhausted), create_log_node());
+            assert_eq!(
+                1,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_node_creations_throttled_total
+                    .get()
+            );
+
+            // A single token is refilled every two seconds.
+            *clock.elapsed.lock().unwrap() = Duration::from_secs(2);
+            assert_eq!(Ok(()), create_log_node());
+            assert_eq!(Err(OakStatus::ErrResourceExhausted), create_log_node());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a Node creation that fails after passing the rate limit does not count against it.
+#[test]
+fn node_creation_rate_limit_ignores_failed_registrations() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let clock = Arc::new(ManualClock {
+                start: Instant::now(),
+                elapsed: Mutex::new(Duration::from_secs(0)),
+            });
+            runtime.runtime.set_clock(clock);
+            runtime
+                .runtime
+                .node_creation_rate_limiter
+                .set_limit(Some(NodeCreationRateLimit {
+                    rate: 0.5,
+                    burst: 1,
+                }));
+            let log_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+            };
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+
+            // The initial handle is only checked once the token has been taken.
+            assert_eq!(
+                Err(OakStatus::ErrBadHandle),
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    oak_abi::INVALID_HANDLE,
+                )
+            );
+            // The limit of live Nodes is only checked once the token has been taken.
+            runtime.runtime.set_max_nodes(Some(2));
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            );
+            assert_eq!(
+                0,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_node_creations_throttled_total
+                    .get()
+            );
+
+            runtime.runtime.set_max_nodes(None);
+            assert_eq!(
+                Ok(()),
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            );
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.node_create(
+                    "log",
+                    &log_configuration,
+                    &Label::public_untrusted(),
+                    read_handle,
+                )
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the topology graph of a two-Node pipeline has a single edge for the channel
+/// between the Nodes.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn export_graph_dot_shows_pipeline_edge() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let consumer = register_idle_node(&runtime, "consumer", &Label::public_untrusted());
+            let (_write_handle, read_handle) =
+                runtime.channel_create("pipeline", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, read_handle)?
+                .get_channel_id();
+            share_handle(&runtime, &consumer, read_handle)?;
+            runtime.channel_close(read_handle)?;
+
+            let dot = runtime.runtime.export_graph_dot();
+            assert!(dot.starts_with("digraph Runtime {"));
+            let expected_edge = format!(
+                r###"  {{node{}}} -> {{node{}}} [label="pipeline({})" taillabel="write" headlabel="read"]"###,
+                runtime.node_id.0, consumer.node_id.0, channel_id
+            );
+            assert_eq!(
+                1,
+                dot.lines()
+                    .filter(|line| line.contains(&format!("pipeline({})", channel_id)))
+                    .count()
+            );
+            assert!(dot.lines().any(|line| line == expected_edge), "{}", dot);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a topology snapshot describes the Nodes and the channels between them.
+#[test]
+fn topology_snapshot_describes_nodes_and_channels() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let consumer = register_idle_node(&runtime, "consumer", &Label::public_untrusted());
+            let (_write_handle, read_handle) =
+                runtime.channel_create("pipeline", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, read_handle)?
+                .get_channel_id();
+            share_handle(&runtime, &consumer, read_handle)?;
+            runtime.channel_close(read_handle)?;
+
+            let snapshot = runtime.runtime.topology_snapshot();
+            let consumer_topology = snapshot
+                .nodes
+                .iter()
+                .find(|node| node.id == consumer.node_id.0)
+                .unwrap();
+            assert_eq!("consumer", consumer_topology.name);
+            assert_eq!("idle", consumer_topology.node_type);
+            #[cfg(feature = "oak-unsafe")]
+            assert_eq!(Label::public_untrusted(), consumer_topology.label);
+            assert_eq!(
+                Some(&ChannelTopology {
+                    id: channel_id,
+                    name: "pipeline".to_string(),
+                    reader_node_ids: vec![consumer.node_id.0],
+                    writer_node_ids: vec![runtime.node_id.0],
+                }),
+                snapshot
+                    .channels
+                    .iter()
+                    .find(|channel| channel.id == channel_id)
+            );
+
+            let json = serde_json::to_value(&snapshot).unwrap();
+            assert_eq!(
+                snapshot.nodes.len(),
+                json["nodes"].as_array().unwrap().len()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the message rate of a channel rises under sustained writes and decays once the
+/// channel is idle.
+#[test]
+fn channel_message_rate_rises_and_decays() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime.runtime.set_channel_message_rate_limit(Some(1));
+            let gauge = runtime
+                .metrics_data()
+                .runtime_metrics
+                .runtime_channel_message_rate;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+
+            let (write_handle, _read_handle) =
+                runtime.channel_create("rate", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, write_handle)?
+                .get_channel_id()
+                .to_string();
+            let mut previous_rate = 0.0;
+            for _ in 0..5 {
+                for _ in 0..10 {
+                    runtime.channel_write(write_handle, message.clone())?;
+                }
+                let rate = gauge.with_label_values(&[&channel_id]).get();
+                assert!(rate > previous_rate, "{} <= {}", rate, previous_rate);
+                previous_rate = rate;
+            }
+
+            let idle_time = crate::metrics::channel_rate::RATE_TIME_CONSTANT * 3;
+            runtime
+                .runtime
+                .channel_message_rates
+                .refresh(&gauge, Instant::now() + idle_time);
+            let decayed_rate = gauge.with_label_values(&[&channel_id]).get();
+            assert!(decayed_rate < previous_rate / 10.0);
+            assert!(decayed_rate > 0.0);
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that Node IDs derived from Node names are the same across runs, regardless of the
+/// order in which Nodes with different names are created.
+#[test]
+fn stable_node_ids_are_reproducible() {
+    let node_ids = Arc::new(Mutex::new(Vec::new()));
+    for names in &[["a", "b", "a"], ["b", "a", "a"]] {
+        let node_ids = node_ids.clone();
+        run_node_body(
+            &Label::public_untrusted(),
+            &NodePrivilege::default(),
+            Box::new(move |runtime| {
+                runtime.runtime.set_stable_node_ids(true);
+                let mut ids: HashMap<&str, Vec<NodeId>> = HashMap::new();
+                for name in names {
+                    let node = register_idle_node(&runtime, name, &Label::public_untrusted());
+                    ids.entry(name).or_default().push(node.node_id);
+                }
+                node_ids.lock().unwrap().push(ids);
+                Ok(())
+            }),
+        );
+    }
+    let node_ids = node_ids.lock().unwrap();
+    assert_eq!(node_ids[0], node_ids[1]);
+    let all_ids: HashSet<NodeId> = node_ids[0].values().flatten().copied().collect();
+    assert_eq!(3, all_ids.len());
+}
+
+/// Checks that the state kept for deriving Node IDs from Node names does not grow as Nodes keep
+/// being created and removed.
+#[test]
+fn stable_node_ids_are_released_when_nodes_are_removed() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            runtime.runtime.set_stable_node_ids(true);
+            let mut node_ids = HashSet::new();
+            for _ in 0..100 {
+                let node = register_idle_node(&runtime, "transient", &Label::public_untrusted());
+                assert_eq!((1, 1), runtime.runtime.stable_node_ids_state_size());
+                runtime.runtime.remove_node_id(node.node_id);
+                assert_eq!((0, 1), runtime.runtime.stable_node_ids_state_size());
+                // Node IDs are still not reused for Nodes with the same name.
+                assert!(node_ids.insert(node.node_id));
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that subscribers receive introspection events as they are generated, and are removed
+/// once their receiver is dropped.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn subscribe_events_receives_new_events() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let receiver = runtime.runtime.subscribe_events();
+            runtime.channel_create("subscribed", &Label::public_untrusted())?;
+            let event = receiver
+                .recv_timeout(std::time::Duration::from_secs(1))
+                .unwrap();
+            assert!(matches!(
+                event.event_details,
+                Some(EventDetails::ChannelCreated(ChannelCreated { ref name, .. }))
+                    if name == "subscribed"
+            ));
+
+            drop(receiver);
+            runtime.channel_create("unsubscribed", &Label::public_untrusted())?;
+            assert!(runtime
+                .runtime
+                .introspection_event_subscribers
+                .lock()
+                .unwrap()
+                .is_empty());
+            Ok(())
+        }),
+    );
+}
+
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn introspection_event_queue_drops_oldest_events() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let queued = runtime
+                .runtime
+                .introspection_event_queue
+                .lock()
+                .unwrap()
+                .len();
+            assert_eq!(0, runtime.runtime.introspection_events_dropped());
+
+            runtime
+                .runtime
+                .introspection_event_queue_capacity
+                .store(queued + 1, SeqCst);
+            runtime.channel_create("truncated", &Label::public_untrusted())?;
+
+            let introspection_event_queue =
+                runtime.runtime.introspection_event_queue.lock().unwrap();
+            assert_eq!(queued + 1, introspection_event_queue.len());
+            // Creating a channel generates a `ChannelCreated` event and two `HandleCreated` events.
+            assert_eq!(2, runtime.runtime.introspection_events_dropped());
+            assert!(matches!(
+                introspection_event_queue.back().unwrap().event_details,
+                Some(EventDetails::HandleCreated(_))
+            ));
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn channel_write_fails_during_termination() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("test", &Label::public_untrusted())?;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+
+            runtime.runtime.terminating.store(true, SeqCst);
+            let results = [
+                runtime.channel_write(write_handle, message.clone()),
+                runtime.channel_write_blocking(write_handle, message.clone()),
+                // Termination is checked before the handle is validated.
+                runtime.channel_write(read_handle, message),
+            ];
+            runtime.runtime.terminating.store(false, SeqCst);
+
+            for result in results.iter() {
+                assert_eq!(&Err(OakStatus::ErrTerminated), result);
+            }
+            assert_eq!(Ok(None), runtime.channel_read(read_handle));
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn readers_can_recover_message_writers() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let writer = register_idle_node(&runtime, "writer", &Label::public_untrusted());
+            let (write_handle, read_handle) =
+                runtime.channel_create("test", &Label::public_untrusted())?;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+
+            // Messages are not tagged unless enabled.
+            writer.channel_write(
+                share_handle(&runtime, &writer, write_handle)?,
+                message.clone(),
+            )?;
+            assert!(runtime.channel_read(read_handle)?.is_some());
+            assert_eq!(None, runtime.last_read_message_writer());
+
+            runtime.runtime.set_tag_message_writers(true);
+            writer.channel_write(
+                share_handle(&runtime, &writer, write_handle)?,
+                message.clone(),
+            )?;
+            runtime.channel_write(write_handle, message.clone())?;
+            assert!(runtime.channel_read(read_handle)?.is_some());
+            assert_eq!(Some(writer.node_id), runtime.last_read_message_writer());
+            assert!(runtime.channel_read(read_handle)?.is_some());
+            assert_eq!(Some(runtime.node_id), runtime.last_read_message_writer());
+
+            // A batch read records the writer of each message.
+            writer.channel_write(
+                share_handle(&runtime, &writer, write_handle)?,
+                message.clone(),
+            )?;
+            runtime.channel_write(write_handle, message)?;
+            assert_eq!(2, runtime.channel_read_batch(read_handle, 10)?.len());
+            assert_eq!(
+                vec![Some(writer.node_id), Some(runtime.node_id)],
+                runtime.last_read_message_writers()
+            );
+            assert_eq!(Some(runtime.node_id), runtime.last_read_message_writer());
+            Ok(())
+        }),
+    );
+}
+
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn introspection_event_log_can_be_read_back() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let path = std::env::temp_dir().join(format!(
+                "oak_introspection_event_log_{}.pb",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            runtime
+                .runtime
+                .open_introspection_event_log(&path)
+                .expect("could not open introspection event log");
+            runtime.channel_create("logged", &Label::public_untrusted())?;
+            runtime.runtime.close_introspection_event_log();
+            runtime.channel_create("not-logged", &Label::public_untrusted())?;
+
+            let events = crate::read_introspection_event_log(&path)
+                .expect("could not read introspection event log");
+            std::fs::remove_file(&path).expect("could not remove introspection event log");
+            // Creating a channel generates a `ChannelCreated` event and two `HandleCreated` events.
+            assert_eq!(3, events.len());
+            assert!(matches!(
+                events[0].event_details,
+                Some(EventDetails::ChannelCreated(ChannelCreated { ref name, .. }))
+                    if name == "logged"
+            ));
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn node_cpu_time_is_reported_on_removal() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let node = register_idle_node(&runtime, "busy", &Label::public_untrusted());
+            runtime
+                .runtime
+                .record_node_cpu_time(node.node_id, std::time::Duration::from_millis(1500));
+            runtime.runtime.remove_node_id(node.node_id);
+            assert_eq!(
+                1.5,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_node_cpu_seconds_total
+                    .with_label_values(&["idle", "busy"])
+                    .get()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that messages written to a channel using [`ConsumerPolicy::RoundRobin`] are delivered to
+/// its waiting consumers in turn.
+#[test]
+fn round_robin_consumer_policy_distributes_messages() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    consumer_policy: ConsumerPolicy::RoundRobin,
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let half = runtime.runtime.abi_to_half(runtime.node_id, read_handle)?;
+            let wait_for_consumers = || {
+                while with_reader_channel(&half, |channel| Ok(channel.waiter_count())).unwrap() < 2
+                {
+                    std::thread::yield_now();
+                }
+            };
+
+            let (sender, receiver) = mpsc::channel();
+            let consumers: Vec<_> = ["first", "second"]
+                .iter()
+                .map(|name| {
+                    let consumer = register_idle_node(&runtime, name, &Label::public_untrusted());
+                    let handle = share_handle(&runtime, &consumer, read_handle).unwrap();
+                    let sender = sender.clone();
+                    std::thread::spawn(move || {
+                        while let Ok(statuses) = consumer.wait_on_channels(&[handle]) {
+                            if statuses != vec![ChannelReadStatus::ReadReady] {
+                                return;
+                            }
+                            if let Ok(Some(_)) = consumer.channel_read(handle) {
+                                sender.send(consumer.node_id).unwrap();
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            let mut readers = Vec::new();
+            for i in 0..4 {
+                wait_for_consumers();
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![i],
+                        handles: vec![],
+                    },
+                )?;
+                readers.push(receiver.recv().unwrap());
+            }
+            assert_ne!(readers[0], readers[1]);
+            assert_eq!(readers[0], readers[2]);
+            assert_eq!(readers[1], readers[3]);
+
+            // Orphaning the channel makes the consumers stop.
+            runtime.channel_close(write_handle)?;
+            for consumer in consumers {
+                consumer.join().unwrap();
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the consumers of a channel are unregistered once their Node no longer holds a read
+/// half of it.
+#[test]
+fn consumers_are_unregistered_when_their_node_closes_the_channel() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (_write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    consumer_policy: ConsumerPolicy::Priority,
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let half = runtime.runtime.abi_to_half(runtime.node_id, read_handle)?;
+            let consumer_count =
+                || with_reader_channel(&half, |channel| Ok(channel.consumer_count())).unwrap();
+
+            let consumer = register_idle_node(&runtime, "consumer", &Label::public_untrusted());
+            let handle = share_handle(&runtime, &consumer, read_handle)?;
+            let handle_clone = consumer.handle_clone(handle)?;
+            consumer.channel_set_consumer_priority(handle, 1)?;
+            // Waiting without blocking is enough to register as a consumer.
+            consumer.wait_on_channels_timeout(&[handle], Duration::from_secs(0))?;
+            assert_eq!(1, consumer_count());
+
+            // The Node still holds another read half of the channel.
+            consumer.channel_close(handle)?;
+            assert_eq!(1, consumer_count());
+            consumer.channel_close(handle_clone)?;
+            assert_eq!(0, consumer_count());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that each message written to a channel using [`ConsumerPolicy::Fifo`] wakes exactly one
+/// of its waiters, in the order in which they started waiting.
+#[test]
+fn fifo_consumer_policy_wakes_one_waiter_in_order() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    consumer_policy: ConsumerPolicy::Fifo,
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let half = runtime.runtime.abi_to_half(runtime.node_id, read_handle)?;
+            let wait_for_waiters = |count| {
+                while with_reader_channel(&half, |channel| Ok(channel.waiter_count())).unwrap()
+                    < count
+                {
+                    std::thread::yield_now();
+                }
+            };
+
+            let (sender, receiver) = mpsc::channel();
+            let mut waiters = Vec::new();
+            for i in 0..3 {
+                let runtime = runtime.clone();
+                let sender = sender.clone();
+                waiters.push(std::thread::spawn(move || {
+                    while let Ok(statuses) = runtime.wait_on_channels(&[read_handle]) {
+                        if statuses != vec![ChannelReadStatus::ReadReady] {
+                            return;
+                        }
+                        if let Ok(Some(_)) = runtime.channel_read(read_handle) {
+                            sender.send(i).unwrap();
+                        }
+                    }
+                }));
+                // Start the waiters one at a time, so that their order is known.
+                wait_for_waiters(i + 1);
+            }
+
+            for expected in 0..3 {
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![],
+                        handles: vec![],
+                    },
+                )?;
+                assert_eq!(expected, receiver.recv().unwrap());
+                // The woken waiter goes back to waiting, behind the others.
+                wait_for_waiters(3);
+                assert!(receiver.try_recv().is_err());
+            }
+
+            // Orphaning the channel makes the waiters stop.
+            runtime.channel_close(write_handle)?;
+            for waiter in waiters {
+                waiter.join().unwrap();
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that [`ConsumerPolicy::Fifo`] does not wake waiters for messages already promised to
+/// woken waiters, and that a woken waiter waiting again without reading hands its message over to
+/// the next waiter.
+#[test]
+fn fifo_consumer_policy_tracks_promised_messages() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) = runtime.channel_create_with_options(
+                "",
+                &Label::public_untrusted(),
+                &ChannelOptions {
+                    consumer_policy: ConsumerPolicy::Fifo,
+                    ..ChannelOptions::default()
+                },
+            )?;
+            let half = runtime.runtime.abi_to_half(runtime.node_id, read_handle)?;
+            let channel = with_reader_channel(&half, Ok)?;
+            let write = || {
+                runtime.channel_write(
+                    write_handle,
+                    NodeMessage {
+                        bytes: vec![],
+                        handles: vec![],
+                    },
+                )
+            };
+            let woken = || {
+                channel
+                    .woken_consumers()
+                    .into_iter()
+                    .collect::<HashSet<_>>()
+            };
+
+            // Waiters are registered on behalf of parked threads, so that they never read.
+            let stop = Arc::new(AtomicBool::new(false));
+            let threads: Vec<_> = (0..3)
+                .map(|_| {
+                    let stop = stop.clone();
+                    std::thread::spawn(move || {
+                        while !stop.load(SeqCst) {
+                            std::thread::park();
+                        }
+                    })
+                })
+                .collect();
+            let waiters: Vec<_> = threads
+                .iter()
+                .map(|thread| Arc::new(thread.thread().clone()))
+                .collect();
+            let ids: Vec<_> = waiters.iter().map(|waiter| waiter.id()).collect();
+            for waiter in &waiters {
+                channel.add_waiter(runtime.node_id, waiter);
+            }
+
+            write()?;
+            assert_eq!(hashset! {ids[0]}, woken());
+            // The first waiter checking the channel again within the same wait keeps its claim.
+            channel.add_waiter(runtime.node_id, &waiters[0]);
+            assert_eq!(hashset! {ids[0]}, woken());
+            // Only the message not yet promised to the first waiter wakes another waiter.
+            write()?;
+            assert_eq!(hashset! {ids[0], ids[1]}, woken());
+
+            // The first waiter starts a new wait without reading, so the third waiter is woken
+            // instead.
+            channel.remove_waiter(ids[0]);
+            channel.add_waiter(runtime.node_id, &waiters[0]);
+            assert_eq!(hashset! {ids[1], ids[2]}, woken());
+
+            stop.store(true, SeqCst);
+            for thread in threads {
+                thread.thread().unpark();
+                thread.join().unwrap();
+            }
+            Ok(())
+        }),
+    );
+}
+
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn disabled_introspection_event_categories_produce_no_events() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let receiver = runtime.runtime.subscribe_events();
+            runtime.runtime.set_introspection_event_category_enabled(
+                IntrospectionEventCategory::HandleCreated,
+                false,
+            );
+            assert!(!runtime.runtime.is_introspection_event_category_enabled(
+                IntrospectionEventCategory::HandleCreated
+            ));
+            runtime.channel_create("quiet", &Label::public_untrusted())?;
+            runtime.runtime.set_introspection_event_category_enabled(
+                IntrospectionEventCategory::HandleCreated,
+                true,
+            );
+            runtime.channel_create("noisy", &Label::public_untrusted())?;
+
+            let categories: Vec<_> = receiver
+                .try_iter()
+                .map(|event| match event.event_details {
+                    Some(EventDetails::ChannelCreated(_)) => "ChannelCreated",
+                    Some(EventDetails::HandleCreated(_)) => "HandleCreated",
+                    _ => "other",
+                })
+                .collect();
+            assert_eq!(
+                vec![
+                    "ChannelCreated",
+                    "ChannelCreated",
+                    "HandleCreated",
+                    "HandleCreated"
+                ],
+                categories
+            );
+            Ok(())
+        }),
+    );
+}
+
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn node_panics_are_reported() {
+    struct PanickingNode;
+
+    impl crate::node::Node for PanickingNode {
+        fn node_type(&self) -> &'static str {
+            "panicking"
+        }
+        fn isolation(&self) -> NodeIsolation {
+            NodeIsolation::Sandboxed
+        }
+        fn run(
+            self: Box<Self>,
+            _runtime: RuntimeProxy,
+            _handle: oak_abi::Handle,
+            _notify_receiver: oneshot::Receiver<()>,
+        ) {
+            panic!("test panic");
+        }
+    }
+
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let receiver = runtime.runtime.subscribe_events();
+            let (_write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            runtime.node_register(
+                CreatedNode {
+                    instance: Box::new(PanickingNode),
+                    privilege: NodePrivilege::default(),
+                },
+                "panicking",
+                &Label::public_untrusted(),
+                read_handle,
+            )?;
+
+            let crashed = receiver
+                .iter()
+                .find_map(|event| match event.event_details {
+                    Some(EventDetails::NodeDestroyed(details)) => Some(details.crashed),
+                    _ => None,
+                })
+                .unwrap();
+            assert!(crashed);
+            assert_eq!(
+                1,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_node_panics_total
+                    .with_label_values(&["panicking"])
+                    .get()
+            );
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn channel_metrics_are_aggregated_by_label_bucket() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let secret_label = confidentiality_label(public_key_identity_tag(&[1, 1, 1]));
+            let other_label = confidentiality_label(public_key_identity_tag(&[2, 2, 2]));
+            runtime.runtime.set_channel_label_buckets(vec![
+                LabelBucket {
+                    name: "public".to_string(),
+                    label: Label::public_untrusted(),
+                },
+                LabelBucket {
+                    name: "secret".to_string(),
+                    label: secret_label.clone(),
+                },
+            ]);
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+            for (label, count) in &[
+                (Label::public_untrusted(), 1),
+                (secret_label, 2),
+                (other_label, 3),
+            ] {
+                let (write_handle, _read_handle) = runtime.channel_create("", label)?;
+                for _ in 0..*count {
+                    runtime.channel_write(write_handle, message.clone())?;
+                }
+            }
+
+            let metrics = &runtime.metrics_data().runtime_metrics;
+            for (bucket, count) in &[("public", 1), ("secret", 2), ("other", 3)] {
+                assert_eq!(
+                    *count,
+                    metrics
+                        .runtime_channel_messages_by_label_total
+                        .with_label_values(&[bucket])
+                        .get()
+                );
+                assert_eq!(
+                    *count as i64,
+                    metrics
+                        .runtime_channel_queue_length_by_label
+                        .with_label_values(&[bucket])
+                        .get()
+                );
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the Nodes of a subgraph are connected as specified, and that nothing is created if
+/// the subgraph is invalid.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn instantiate_subgraph_connects_nodes() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let node = |name: &str| SubgraphNode {
+                name: name.to_string(),
+                config: NodeConfiguration {
+                    config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+                },
+                label: Label::public_untrusted(),
+            };
+            let edge = |source: &str, target: &str, label: &Label| SubgraphEdge {
+                source: source.to_string(),
+                target: target.to_string(),
+                label: label.clone(),
+            };
+            let pipeline = SubgraphSpec {
+                nodes: vec![node("first"), node("second"), node("third")],
+                edges: vec![
+                    edge("first", "second", &Label::public_untrusted()),
+                    edge("second", "third", &Label::public_untrusted()),
+                ],
+            };
+            let node_ids = runtime.instantiate_subgraph(&pipeline)?;
+            assert_eq!(3, node_ids.len());
+
+            // Each Node gets its handles once it has read its initial message.
+            let expected = [
+                ("first->second", node_ids["first"], Direction::Write),
+                ("first->second", node_ids["second"], Direction::Read),
+                ("second->third", node_ids["second"], Direction::Write),
+                ("second->third", node_ids["third"], Direction::Read),
+            ];
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                let mut channel_names = HashMap::new();
+                let mut handles = Vec::new();
+                for event in runtime
+                    .runtime
+                    .introspection_event_queue
+                    .lock()
+                    .unwrap()
+                    .iter()
+                {
+                    match &event.event_details {
+                        Some(EventDetails::ChannelCreated(created)) => {
+                            channel_names.insert(created.channel_id, created.name.clone());
+                        }
+                        Some(EventDetails::HandleCreated(created)) => handles.push(created.clone()),
+                        _ => {}
+                    }
+                }
+                let connected = expected.iter().all(|(name, node_id, direction)| {
+                    handles.iter().any(|handle| {
+                        channel_names[&handle.channel_id] == *name
+                            && handle.node_id == node_id.0
+                            && handle.direction == *direction as i32
+                    })
+                });
+                if connected {
+                    break;
+                }
+                assert!(Instant::now() < deadline, "subgraph was not connected");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            // An edge that its target cannot read from is rejected, and nothing is created.
+            let mut bad_edge = SubgraphSpec {
+                nodes: vec![node("source"), node("sink")],
+                edges: vec![edge("source", "sink", &test_label())],
+            };
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                runtime.instantiate_subgraph(&bad_edge)
+            );
+            bad_edge.edges = vec![edge("source", "unknown", &Label::public_untrusted())];
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.instantiate_subgraph(&bad_edge)
+            );
+            assert_eq!(0, runtime.node_count(Some("source")));
+            assert_eq!(0, runtime.node_count(Some("sink")));
+            assert!(runtime
+                .runtime
+                .topology_snapshot()
+                .channels
+                .iter()
+                .all(|channel| channel.name != "source->sink"));
+
+            // A failure once some channels have been created closes them again, and no Node is
+            // created.
+            runtime
+                .runtime
+                .require_unique_channel_names
+                .store(true, SeqCst);
+            let (write_handle, read_handle) =
+                runtime.channel_create("sink", &Label::public_untrusted())?;
+            let pair = SubgraphSpec {
+                nodes: vec![node("source"), node("sink")],
+                edges: vec![edge("source", "sink", &Label::public_untrusted())],
+            };
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.instantiate_subgraph(&pair)
+            );
+            assert_eq!(0, runtime.node_count(Some("source")));
+            assert_eq!(0, runtime.node_count(Some("sink")));
+            runtime.channel_close(write_handle)?;
+            runtime.channel_close(read_handle)?;
+            // The names of the channels created before the failure have been released.
+            assert_eq!(2, runtime.instantiate_subgraph(&pair)?.len());
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the Nodes of a subgraph are counted against the node creation rate limit all at
+/// once, so that a subgraph larger than the remaining burst is not partially created.
+#[test]
+fn instantiate_subgraph_checks_rate_limit_for_all_nodes() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let clock = Arc::new(ManualClock {
+                start: Instant::now(),
+                elapsed: Mutex::new(Duration::from_secs(0)),
+            });
+            runtime.runtime.set_clock(clock);
+            runtime
+                .runtime
+                .node_creation_rate_limiter
+                .set_limit(Some(NodeCreationRateLimit {
+                    rate: 0.5,
+                    burst: 2,
+                }));
+            let subgraph = |names: &[&str]| SubgraphSpec {
+                nodes: names
+                    .iter()
+                    .map(|name| SubgraphNode {
+                        name: name.to_string(),
+                        config: NodeConfiguration {
+                            config_type: Some(ConfigType::LogConfig(LogConfiguration {})),
+                        },
+                        label: Label::public_untrusted(),
+                    })
+                    .collect(),
+                edges: vec![],
+            };
+
+            let node_count = runtime.runtime.node_infos.read().unwrap().len();
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.instantiate_subgraph(&subgraph(&["first", "second", "third"]))
+            );
+            assert_eq!(node_count, runtime.runtime.node_infos.read().unwrap().len());
+
+            // The rejected subgraph did not use any of the burst.
+            assert_eq!(
+                2,
+                runtime
+                    .instantiate_subgraph(&subgraph(&["first", "second"]))?
+                    .len()
+            );
+            assert_eq!(
+                Err(OakStatus::ErrResourceExhausted),
+                runtime.instantiate_subgraph(&subgraph(&["third"]))
+            );
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn stop_drain_delivers_enqueued_messages() {
+    let runtime = create_test_runtime(&crate::permissions::PermissionsConfiguration::default());
+    let (write_handle, read_handle) = runtime
+        .channel_create("", &Label::public_untrusted())
+        .unwrap();
+    let (inner_write_handle, inner_read_handle) = runtime
+        .channel_create("", &Label::public_untrusted())
+        .unwrap();
+    let message = NodeMessage {
+        bytes: vec![14, 12, 88],
+        handles: vec![],
+    };
+    runtime
+        .channel_write(inner_write_handle, message.clone())
+        .unwrap();
+    // The read half of the inner channel is only reachable through a pending message, but the
+    // message queued on the inner channel must still be drained.
+    runtime
+        .channel_write(
+            write_handle,
+            NodeMessage {
+                bytes: vec![],
+                handles: vec![inner_read_handle],
+            },
+        )
+        .unwrap();
+    runtime.channel_close(inner_read_handle).unwrap();
+    assert_eq!(2, runtime.runtime.pending_message_count());
+
+    // Draining waits for the messages to be read, so it must happen in the background.
+    let drained_runtime = runtime.runtime.clone();
+    let drainer = std::thread::spawn(move || drained_runtime.stop_drain(Duration::from_secs(10)));
+    while !runtime.is_draining() {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    assert_eq!(
+        Err(OakStatus::ErrTerminated),
+        runtime.channel_write(write_handle, message.clone())
+    );
+    assert_eq!(
+        Ok(vec![ChannelReadStatus::ReadReady]),
+        runtime.wait_on_channels(&[read_handle])
+    );
+    let outer_message = runtime.channel_read(read_handle).unwrap().unwrap();
+    assert_eq!(1, outer_message.handles.len());
+    assert!(!runtime.is_terminating());
+    assert_eq!(
+        Ok(Some(message)),
+        runtime.channel_read(outer_message.handles[0])
+    );
+
+    // Once all messages have been read, the Runtime stops without waiting for the timeout.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !runtime.is_terminating() {
+        assert!(
+            Instant::now() < deadline,
+            "runtime did not stop after draining"
+        );
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(ShutdownStatus::Completed, drainer.join().unwrap());
+}
+
+#[test]
+fn stop_abandons_nodes_after_shutdown_timeout() {
+    /// A Node that ignores the termination notification, and only terminates once released.
+    struct StubbornNode {
+        release: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl crate::node::Node for StubbornNode {
+        fn node_type(&self) -> &'static str {
+            "stubborn"
+        }
+        fn isolation(&self) -> NodeIsolation {
+            NodeIsolation::Sandboxed
+        }
+        fn run(
+            self: Box<Self>,
+            _runtime: RuntimeProxy,
+            _handle: oak_abi::Handle,
+            _notify_receiver: oneshot::Receiver<()>,
+        ) {
+            let _ = self.release.lock().unwrap().recv();
+        }
+    }
+
+    let runtime = create_test_runtime(&crate::permissions::PermissionsConfiguration::default());
+    let shutdown_timeout = Duration::from_secs(2);
+    runtime.runtime.set_shutdown_timeout(Some(shutdown_timeout));
+    // The time taken to stop the auxiliary servers counts towards the shutdown timeout.
+    runtime
+        .runtime
+        .aux_servers
+        .lock()
+        .unwrap()
+        .push(AuxServer::spawn(
+            "slow-to-stop",
+            runtime.runtime.clone(),
+            move |_runtime, mut termination_notification_receiver| {
+                while let Err(oneshot::error::TryRecvError::Empty) =
+                    termination_notification_receiver.try_recv()
+                {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                std::thread::sleep(shutdown_timeout);
+            },
+        ));
+    let (release_sender, release_receiver) = mpsc::channel();
+    let (_write_handle, read_handle) = runtime
+        .channel_create("", &Label::public_untrusted())
+        .unwrap();
+    runtime
+        .node_register(
+            CreatedNode {
+                instance: Box::new(StubbornNode {
+                    release: Mutex::new(release_receiver),
+                }),
+                privilege: NodePrivilege::default(),
+            },
+            "stubborn",
+            &Label::public_untrusted(),
+            read_handle,
+        )
+        .unwrap();
+
+    let start = Instant::now();
+    assert_eq!(ShutdownStatus::Forced, runtime.runtime.stop());
+    assert!(
+        start.elapsed() < shutdown_timeout * 3 / 2,
+        "stopping took {:?}",
+        start.elapsed()
+    );
+    assert_eq!(
+        1,
+        runtime
+            .metrics_data()
+            .runtime_metrics
+            .runtime_forced_shutdowns_total
+            .get()
+    );
+    release_sender.send(()).unwrap();
+}
+
+/// Checks that Node threads are named after the debug id of their Node.
+#[test]
+fn node_threads_are_named_with_debug_id() {
+    struct ThreadNameNode {
+        thread_name: Mutex<mpsc::Sender<Option<String>>>,
+    }
+
+    impl crate::node::Node for ThreadNameNode {
+        fn node_type(&self) -> &'static str {
+            "thread-name"
+        }
+        fn isolation(&self) -> NodeIsolation {
+            NodeIsolation::Sandboxed
+        }
+        fn run(
+            self: Box<Self>,
+            _runtime: RuntimeProxy,
+            _handle: oak_abi::Handle,
+            _notify_receiver: oneshot::Receiver<()>,
+        ) {
+            let thread_name = std::thread::current().name().map(str::to_string);
+            self.thread_name.lock().unwrap().send(thread_name).unwrap();
+        }
+    }
+
+    let runtime = create_test_runtime(&crate::permissions::PermissionsConfiguration::default());
+    let (thread_name_sender, thread_name_receiver) = mpsc::channel();
+    for _ in 0..2 {
+        let (_write_handle, read_handle) = runtime
+            .channel_create("", &Label::public_untrusted())
+            .unwrap();
+        runtime
+            .node_register(
+                CreatedNode {
+                    instance: Box::new(ThreadNameNode {
+                        thread_name: Mutex::new(thread_name_sender.clone()),
+                    }),
+                    privilege: NodePrivilege::default(),
+                },
+                "named",
+                &Label::public_untrusted(),
+                read_handle,
+            )
+            .unwrap();
+    }
+
+    let thread_names: HashSet<String> = thread_name_receiver
+        .iter()
+        .take(2)
+        .map(|thread_name| thread_name.unwrap())
+        .collect();
+    // Nodes with the same name run on threads with distinct names.
+    assert_eq!(2, thread_names.len());
+    for thread_name in thread_names {
+        assert!(thread_name.starts_with("named("), "{}", thread_name);
+        assert!(thread_name.ends_with(')'), "{}", thread_name);
+    }
+    runtime.runtime.stop();
+}
+
+#[test]
+fn node_thread_names_keep_node_id() {
+    assert_eq!("short(7)", node_thread_name("short", NodeId(7)));
+    if cfg!(target_os = "linux") {
+        assert_eq!(
+            "a-very-lon(123)",
+            node_thread_name("a-very-long-node-name", NodeId(123))
+        );
+        // Names are only shortened at character boundaries.
+        assert_eq!("éééé(1234)", node_thread_name("éééééé", NodeId(1234)));
+    }
+}
+
+/// Checks that a Node panicking on shutdown surfaces its panic through the shutdown report, and
+/// that the results of Nodes exiting normally before shutdown are not kept.
+#[test]
+fn stop_reports_panicked_nodes() {
+    /// Node that exits once released, panicking if released with `true`.
+    struct PanicOnReleaseNode {
+        release: Mutex<mpsc::Receiver<bool>>,
+    }
+
+    impl crate::node::Node for PanicOnReleaseNode {
+        fn node_type(&self) -> &'static str {
+            "panic-on-release"
+        }
+        fn isolation(&self) -> NodeIsolation {
+            NodeIsolation::Sandboxed
+        }
+        fn run(
+            self: Box<Self>,
+            _runtime: RuntimeProxy,
+            _handle: oak_abi::Handle,
+            _notify_receiver: oneshot::Receiver<()>,
+        ) {
+            if self.release.lock().unwrap().recv().unwrap_or(true) {
+                panic!("test panic");
+            }
+        }
+    }
+
+    let runtime = create_test_runtime(&crate::permissions::PermissionsConfiguration::default());
+    let register_node = || {
+        let (release_sender, release_receiver) = mpsc::channel();
+        let (_write_handle, read_handle) = runtime
+            .channel_create("", &Label::public_untrusted())
+            .unwrap();
+        runtime
+            .node_register(
+                CreatedNode {
+                    instance: Box::new(PanicOnReleaseNode {
+                        release: Mutex::new(release_receiver),
+                    }),
+                    privilege: NodePrivilege::default(),
+                },
+                "panic-on-release",
+                &Label::public_untrusted(),
+                read_handle,
+            )
+            .unwrap();
+        release_sender
+    };
+
+    // The thread of a Node that exits normally before the Runtime stops is joined early, and its
+    // result is not kept.
+    register_node().send(false).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while runtime
+        .runtime
+        .exited_node_stoppers
+        .lock()
+        .unwrap()
+        .is_empty()
+    {
+        assert!(Instant::now() < deadline, "node did not exit");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    while !runtime
+        .runtime
+        .exited_node_stoppers
+        .lock()
+        .unwrap()
+        .is_empty()
+    {
+        assert!(Instant::now() < deadline, "exited node was not joined");
+        runtime.runtime.join_exited_nodes();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(runtime
+        .runtime
+        .joined_node_failures
+        .lock()
+        .unwrap()
+        .is_empty());
+
+    // The thread of a Node that panics before the Runtime stops is joined early, but its result is
+    // still reported.
+    register_node().send(true).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while runtime
+        .runtime
+        .joined_node_failures
+        .lock()
+        .unwrap()
+        .is_empty()
+    {
+        assert!(Instant::now() < deadline, "exited node was not joined");
+        runtime.runtime.join_exited_nodes();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    register_node().send(true).unwrap();
+    let report = runtime.runtime.stop_with_report();
+    assert_eq!(ShutdownStatus::Completed, report.status);
+    assert_eq!(2, report.node_results.len());
+    assert_eq!(
+        report
+            .node_results
+            .iter()
+            .map(|(node_id, _)| *node_id)
+            .collect::<Vec<_>>(),
+        report.panicked_nodes()
+    );
+    for (_, result) in report.node_results {
+        assert_eq!(
+            Some(&"test panic"),
+            result.unwrap_err().downcast_ref::<&str>()
+        );
+    }
+}
+
+#[test]
+fn expired_messages_are_skipped() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let clock = Arc::new(ManualClock {
+                start: Instant::now(),
+                elapsed: Mutex::new(Duration::from_secs(0)),
+            });
+            runtime.runtime.set_clock(clock.clone());
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let message = |byte: u8| NodeMessage {
+                bytes: vec![byte],
+                handles: vec![],
+            };
+            runtime.channel_write_with_ttl(write_handle, message(1), Duration::from_secs(1))?;
+            runtime.channel_write_with_ttl(write_handle, message(2), Duration::from_secs(3600))?;
+            runtime.channel_write_with_ttl(write_handle, message(3), Duration::from_secs(1))?;
+            runtime.channel_write(write_handle, message(4))?;
+
+            *clock.elapsed.lock().unwrap() = Duration::from_secs(2);
+            assert_eq!(Ok(Some(message(2))), runtime.channel_read(read_handle));
+            assert!(matches!(
+                runtime.channel_try_read_message(read_handle, 1, 0),
+                Ok(Some(NodeReadStatus::Success(msg))) if msg == message(4)
+            ));
+            assert_eq!(Ok(None), runtime.channel_read(read_handle));
+            assert_eq!(
+                2,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_messages_expired_total
+                    .get()
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a channel holding only expired messages is not reported as ready, so that a Node
+/// waiting on it keeps blocking until a live message arrives.
+#[test]
+fn expired_messages_do_not_wake_waiters() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let clock = Arc::new(ManualClock {
+                start: Instant::now(),
+                elapsed: Mutex::new(Duration::from_secs(0)),
+            });
+            runtime.runtime.set_clock(clock.clone());
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let message = |byte: u8| NodeMessage {
+                bytes: vec![byte],
+                handles: vec![],
+            };
+            runtime.channel_write_with_ttl(write_handle, message(1), Duration::from_secs(1))?;
+            *clock.elapsed.lock().unwrap() = Duration::from_secs(2);
+
+            assert_eq!(
+                vec![ChannelReadStatus::NotReady],
+                runtime.poll_channels(&[read_handle])
+            );
+            assert_eq!(
+                Ok(vec![ChannelReadStatus::NotReady]),
+                runtime.wait_on_channels_timeout(&[read_handle], Duration::from_millis(10))
+            );
+
+            runtime.channel_write_with_ttl(write_handle, message(2), Duration::from_secs(1))?;
+            *clock.elapsed.lock().unwrap() = Duration::from_secs(4);
+            let runtime_copy = runtime.clone();
+            let writer = std::thread::spawn(move || {
+                // Let the waiter block on the expired message first.
+                thread::sleep(Duration::from_millis(50));
+                runtime_copy.channel_write(write_handle, message(3))
+            });
+            assert_eq!(
+                Ok(vec![ChannelReadStatus::ReadReady]),
+                runtime.wait_on_channels(&[read_handle])
+            );
+            assert_eq!(Ok(()), writer.join().expect("writer thread panicked"));
+            assert_eq!(Ok(Some(message(3))), runtime.channel_read(read_handle));
+            assert_eq!(
+                2,
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_messages_expired_total
+                    .get()
+            );
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn writes_to_orphaned_channels_are_forwarded_to_dead_letter_channel() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (dead_letter_write_handle, dead_letter_read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let dead_letter_half = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, dead_letter_write_handle)?;
+            runtime
+                .runtime
+                .set_dead_letter_channel(Some(dead_letter_half));
+
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, write_handle)?
+                .get_channel_id();
+            runtime.channel_close(read_handle)?;
+            let message = NodeMessage {
+                bytes: vec![14, 12, 88],
+                handles: vec![],
+            };
+            assert_eq!(
+                Err(OakStatus::ErrChannelClosed),
+                runtime.channel_write(write_handle, message.clone())
+            );
+            // Blocking writes are forwarded too.
+            assert_eq!(
+                Err(OakStatus::ErrChannelClosed),
+                runtime.channel_write_blocking(write_handle, message)
+            );
+
+            for _ in 0..2 {
+                let forwarded = runtime.channel_read(dead_letter_read_handle)?.unwrap();
+                assert_eq!(
+                    DeadLetter {
+                        source_node_id: runtime.node_id.0,
+                        channel_id,
+                        data: vec![14, 12, 88],
+                    },
+                    DeadLetter::decode(forwarded.bytes.as_slice()).unwrap()
+                );
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that the downgraded label of a Node only removes the confidentiality tags that the Node
+/// may declassify, and adds the integrity tags that it may endorse.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn downgraded_label_applies_node_privilege() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let tag_1 = public_key_identity_tag(&[1, 1, 1]);
+            let tag_2 = public_key_identity_tag(&[2, 2, 2]);
+            let node = register_privileged_idle_node(
+                &runtime,
+                "node",
+                &Label::public_untrusted(),
+                &NodePrivilege {
+                    can_declassify_confidentiality_tags: hashset! { tag_1.clone() },
+                    can_endorse_integrity_tags: hashset! { tag_2.clone() },
+                },
+            );
+            let label = Label {
+                confidentiality_tags: vec![tag_1.clone(), tag_2.clone()],
+                integrity_tags: vec![],
+            };
+
+            assert_eq!(
+                Label {
+                    confidentiality_tags: vec![tag_2.clone()],
+                    integrity_tags: vec![tag_2],
+                },
+                runtime.runtime.downgraded_label(node.node_id, &label)?
+            );
+            // The calling Node has no privilege, so the label is unchanged.
+            assert_eq!(
+                label,
+                runtime.runtime.downgraded_label(runtime.node_id, &label)?
+            );
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.runtime.downgraded_label(NodeId(u64::MAX), &label)
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that labels serialize to JSON that does not depend on the order of their tags, and that
+/// can be parsed back into the same label.
+#[test]
+fn serialize_label_json_is_canonical() {
+    let tag_0 = oak_abi::label::public_key_identity_tag(&[0, 0, 0]);
+    let tag_1 = oak_abi::label::tls_endpoint_tag("localhost");
+    let label = Label {
+        confidentiality_tags: vec![tag_1.clone(), tag_0.clone()],
+        integrity_tags: vec![tag_0.clone()],
+    };
+    let reordered_label = Label {
+        confidentiality_tags: vec![tag_0.clone(), tag_1, tag_0.clone()],
+        integrity_tags: vec![tag_0],
+    };
+
+    let capacity = match serialize_label_json(&label, 0) {
+        LabelReadStatus::NeedsCapacity(capacity) => capacity,
+        status => panic!("unexpected status {:?}", status),
+    };
+    let json = match serialize_label_json(&label, capacity) {
+        LabelReadStatus::Success(json) => json,
+        status => panic!("unexpected status {:?}", status),
+    };
+    assert_eq!(capacity, json.len());
+    match serialize_label_json(&reordered_label, capacity) {
+        LabelReadStatus::Success(reordered_json) => assert_eq!(json, reordered_json),
+        status => panic!("unexpected status {:?}", status),
+    }
+    let parsed: Label = serde_json::from_slice(&json).unwrap();
+    assert_eq!(label.canonical(), parsed);
+}
+
+/// Checks that the introspection server shows labels as JSON if configured to.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn introspection_shows_json_labels() {
+    run_node_body(
+        &test_label(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let json = label_json(&test_label());
+            let html = runtime.runtime.html_for_node(runtime.node_id.0).unwrap();
+            assert!(!html.contains(&json));
+
+            runtime.runtime.introspect_json_labels.store(true, SeqCst);
+            let html = runtime.runtime.html_for_node(runtime.node_id.0).unwrap();
+            assert!(html.contains(&format!("Label={}", json)));
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that each use of the privilege of a Node to downgrade a label is counted, and reported
+/// with the labels involved.
+#[test]
+fn label_downgrades_are_audited() {
+    let tag = public_key_identity_tag(&[1, 1, 1]);
+    let confidential_label = confidentiality_label(tag.clone());
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::new(hashset! { tag }, hashset! {}),
+        Box::new(move |runtime| {
+            #[cfg(feature = "oak-unsafe")]
+            let receiver = runtime.runtime.subscribe_events();
+            let downgrades = || {
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_downgrades_total
+                    .get()
+            };
+            let (_write_handle, read_handle) =
+                runtime.channel_create("confidential", &confidential_label)?;
+            let (_write_handle, public_read_handle) =
+                runtime.channel_create("public", &Label::public_untrusted())?;
+
+            assert_eq!(Ok(None), runtime.channel_read_with_downgrade(read_handle));
+            assert_eq!(1, downgrades());
+            // The privilege of the Node does not change the label of the public channel.
+            assert_eq!(
+                Ok(None),
+                runtime.channel_read_with_downgrade(public_read_handle)
+            );
+            assert_eq!(1, downgrades());
+
+            #[cfg(feature = "oak-unsafe")]
+            {
+                let downgrades: Vec<_> = receiver
+                    .try_iter()
+                    .filter_map(|event| match event.event_details {
+                        Some(EventDetails::LabelDowngraded(downgraded)) => Some(downgraded),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(
+                    vec![LabelDowngraded {
+                        node_id: runtime.node_id.0,
+                        original_label: Some(confidential_label.clone()),
+                        downgraded_label: Some(Label::public_untrusted()),
+                    }],
+                    downgrades
+                );
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that IFC violations are counted by direction, and reported with the offending label.
+#[test]
+fn permission_denied_is_reported() {
+    let confidential_label = confidentiality_label(public_key_identity_tag(&[1, 1, 1]));
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(move |runtime| {
+            #[cfg(feature = "oak-unsafe")]
+            let receiver = runtime.runtime.subscribe_events();
+            let denied = |direction: &str| {
+                runtime
+                    .metrics_data()
+                    .runtime_metrics
+                    .runtime_permission_denied_total
+                    .with_label_values(&[direction])
+                    .get()
+            };
+
+            let (_write_handle, read_handle) =
+                runtime.channel_create("confidential", &confidential_label)?;
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                runtime.channel_read(read_handle)
+            );
+            let secret_node = register_privileged_idle_node(
+                &runtime,
+                "secret",
+                &confidential_label,
+                &NodePrivilege::default(),
+            );
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                secret_node
+                    .channel_create("public", &Label::public_untrusted())
+                    .map(|_| ())
+            );
+            assert_eq!(1, denied("read"));
+            assert_eq!(1, denied("write"));
+
+            #[cfg(feature = "oak-unsafe")]
+            {
+                let denials: Vec<_> = receiver
+                    .try_iter()
+                    .filter_map(|event| match event.event_details {
+                        Some(EventDetails::PermissionDenied(denied)) => Some(denied),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(
+                    vec![
+                        PermissionDenied {
+                            node_id: runtime.node_id.0,
+                            direction: Direction::Read as i32,
+                            label: Some(confidential_label.clone()),
+                        },
+                        PermissionDenied {
+                            node_id: secret_node.node_id.0,
+                            direction: Direction::Write as i32,
+                            label: Some(Label::public_untrusted()),
+                        }
+                    ],
+                    denials
+                );
+            }
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a channel losing its last writer, and then its last reader, is reported once for
+/// each side.
+#[cfg(feature = "oak-unsafe")]
+#[test]
+fn channel_orphaned_events_report_lost_side() {
+    use crate::proto::oak::introspection_events::ChannelOrphaned;
+
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let (write_handle, read_handle) =
+                runtime.channel_create("", &Label::public_untrusted())?;
+            let channel_id = runtime
+                .runtime
+                .abi_to_half(runtime.node_id, write_handle)?
+                .get_channel_id();
+            let cloned_write_handle = runtime.handle_clone(write_handle)?;
+            let receiver = runtime.runtime.subscribe_events();
+
+            // Closing one of the two write handles does not orphan the channel.
+            runtime.channel_close(write_handle)?;
+            runtime.channel_close(cloned_write_handle)?;
+            runtime.channel_close(read_handle)?;
+
+            let orphaned: Vec<_> = receiver
+                .try_iter()
+                .filter_map(|event| match event.event_details {
+                    Some(EventDetails::ChannelOrphaned(orphaned)) => Some(orphaned),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                vec![
+                    ChannelOrphaned {
+                        channel_id,
+                        direction: Direction::Write as i32,
+                    },
+                    ChannelOrphaned {
+                        channel_id,
+                        direction: Direction::Read as i32,
+                    },
+                ],
+                orphaned
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that a timer pseudo-Node writes ticks to its channel at the configured interval, and
+/// may only be created with a label that it can use to communicate externally.
+#[test]
+fn timer_node_emits_ticks() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            const TICKS: u32 = 5;
+            let interval = Duration::from_millis(20);
+            let timer_configuration = NodeConfiguration {
+                config_type: Some(ConfigType::TimerConfig(TimerConfiguration {
+                    interval_millis: interval.as_millis() as u64,
+                })),
+            };
+
+            let (tick_senders, tick_sender_receiver) = crate::io::channel_create::<TickSender>(
+                &runtime,
+                "tick senders",
+                &Label::public_untrusted(),
+            )?;
+            assert_eq!(
+                Err(OakStatus::ErrPermissionDenied),
+                runtime.node_create(
+                    "timer",
+                    &timer_configuration,
+                    &test_label(),
+                    tick_sender_receiver.handle.handle
+                )
+            );
+
+            let start = Instant::now();
+            runtime.node_create(
+                "timer",
+                &timer_configuration,
+                &Label::public_untrusted(),
+                tick_sender_receiver.handle.handle,
+            )?;
+            tick_sender_receiver
+                .close(&runtime)
+                .expect("could not close receiver");
+            let (sender, receiver) =
+                crate::io::channel_create::<Tick>(&runtime, "ticks", &Label::public_untrusted())?;
+            tick_senders
+                .send(
+                    TickSender {
+                        sender: Some(sender.clone()),
+                    },
+                    &runtime,
+                )
+                .expect("could not send tick sender");
+            sender.close(&runtime).expect("could not close sender");
+            tick_senders
+                .close(&runtime)
+                .expect("could not close sender");
+            for count in 1..=TICKS {
+                let tick = receiver.receive(&runtime).expect("could not receive tick");
+                assert_eq!(u64::from(count), tick.count);
+            }
+            // Scheduling delays may only postpone ticks.
+            let elapsed = start.elapsed();
+            assert!(elapsed >= interval * TICKS, "{:?}", elapsed);
+            assert!(
+                elapsed < interval * TICKS + Duration::from_secs(2),
+                "{:?}",
+                elapsed
+            );
+            Ok(())
+        }),
+    );
+}
+
+/// Checks that random pseudo-Nodes with the same name in Runtimes seeded with the same value write
+/// identical bytes, and different bytes with a different seed.
+#[test]
+fn seeded_random_nodes_write_identical_bytes() {
+    let byte_streams = Arc::new(Mutex::new(Vec::new()));
+    for seed in &[42, 42, 43] {
+        let seed = *seed;
+        let byte_streams = byte_streams.clone();
+        run_node_body(
+            &Label::public_untrusted(),
+            &NodePrivilege::default(),
+            Box::new(move |runtime| {
+                runtime.runtime.set_rng_seed(Some(seed));
+                let random_configuration = NodeConfiguration {
+                    config_type: Some(ConfigType::RandomConfig(RandomConfiguration {})),
+                };
+                let (requests, request_receiver) = crate::io::channel_create::<RandomRequest>(
+                    &runtime,
+                    "requests",
+                    &Label::public_untrusted(),
+                )?;
+                runtime.node_create(
+                    "random",
+                    &random_configuration,
+                    &Label::public_untrusted(),
+                    request_receiver.handle.handle,
+                )?;
+                request_receiver
+                    .close(&runtime)
+                    .expect("could not close receiver");
+
+                let mut bytes = Vec::new();
+                for length in &[1, 16, 256] {
+                    let (sender, receiver) = crate::io::channel_create::<RandomBytes>(
+                        &runtime,
+                        "bytes",
+                        &Label::public_untrusted(),
+                    )?;
+                    requests
+                        .send(
+                            RandomRequest {
+                                length: *length,
+                                sender: Some(sender.clone()),
+                            },
+                            &runtime,
+                        )
+                        .expect("could not send request");
+                    sender.close(&runtime).expect("could not close sender");
+                    let response = receiver.receive(&runtime).expect("could not receive bytes");
+                    assert_eq!(*length as usize, response.data.len());
+                    bytes.extend(response.data);
+                    receiver.close(&runtime).expect("could not close receiver");
+                }
+                requests.close(&runtime).expect("could not close sender");
+                byte_streams.lock().unwrap().push(bytes);
+                Ok(())
+            }),
+        );
+    }
+    let byte_streams = byte_streams.lock().unwrap();
+    assert_eq!(byte_streams[0], byte_streams[1]);
+    assert_ne!(byte_streams[0], byte_streams[2]);
+}
+
+#[test]
+fn kms_credentials_can_be_reloaded() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let credentials_path = |generation| {
+                std::env::temp_dir().join(format!(
+                    "oak_kms_credentials_{}_{}.json",
+                    std::process::id(),
+                    generation
+                ))
+            };
+            let (first_path, second_path) = (credentials_path(1), credentials_path(2));
+            std::fs::write(&first_path, b"{}").expect("could not write KMS credentials");
+            std::fs::write(&second_path, b"{}").expect("could not write KMS credentials");
+            let kms_credentials = &runtime.runtime.node_factory.kms_credentials;
+            assert_eq!(None, kms_credentials.path());
+
+            assert_eq!(Ok(()), runtime.runtime.reload_kms_credentials(&first_path));
+            assert_eq!(Some(first_path.clone()), kms_credentials.path());
+            assert_eq!(Ok(()), runtime.runtime.reload_kms_credentials(&second_path));
+            assert_eq!(Some(second_path.clone()), kms_credentials.path());
+
+            // Credentials that cannot be read leave the current ones in use.
+            std::fs::remove_file(&first_path).expect("could not remove KMS credentials");
+            assert_eq!(
+                Err(OakStatus::ErrInvalidArgs),
+                runtime.runtime.reload_kms_credentials(&first_path)
+            );
+            assert_eq!(Some(second_path.clone()), kms_credentials.path());
+            std::fs::remove_file(&second_path).expect("could not remove KMS credentials");
+            Ok(())
+        }),
+    );
+}
+
+#[test]
+fn kms_clients_are_recreated_after_reloading_credentials() {
+    run_node_body(
+        &Label::public_untrusted(),
+        &NodePrivilege::default(),
+        Box::new(|runtime| {
+            let credentials_path = |generation| {
+                std::env::temp_dir().join(format!(
+                    "oak_kms_clients_credentials_{}_{}.json",
+                    std::process::id(),
+                    generation
+                ))
+            };
+            let (first_path, second_path) = (credentials_path(1), credentials_path(2));
+            std::fs::write(&first_path, b"{}").expect("could not write KMS credentials");
+            std::fs::write(&second_path, b"{}").expect("could not write KMS credentials");
+            let kms_credentials = &runtime.runtime.node_factory.kms_credentials;
+            let key_uri = format!("{}test-key", crate::kms_credentials::TEST_KMS_PREFIX);
+            // The test KMS client reports the credentials it was created with.
+            let client_credentials = || {
+                kms_credentials
+                    .get_kms_client(&key_uri)
+                    .expect("could not get KMS client")
+                    .get_aead(&key_uri)
+                    .err()
+                    .expect("test KMS client returned a key")
+                    .to_string()
+            };
+
+            assert_eq!(Ok(()), runtime.runtime.reload_kms_credentials(&first_path));
+            assert!(client_credentials().contains(&format!("{:?}", first_path)));
+            assert_eq!(vec![key_uri.clone()], kms_credentials.client_key_uris());
+
+            assert_eq!(Ok(()), runtime.runtime.reload_kms_credentials(&second_path));
+            assert!(kms_credentials.client_key_uris().is_empty());
+            assert!(client_credentials().contains(&format!("{:?}", second_path)));
+            assert_eq!(vec![key_uri.clone()], kms_credentials.client_key_uris());
+
+            std::fs::remove_file(&first_path).expect("could not remove KMS credentials");
+            std::fs::remove_file(&second_path).expect("could not remove KMS credentials");
+            Ok(())
+        }),
+    );
+}
@@ -24,7 +24,7 @@ use roughenough::{
 };
 use std::{
     net::{SocketAddr, ToSocketAddrs as _},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{net::UdpSocket, runtime::Runtime, time::timeout};
 
@@ -43,6 +43,21 @@ pub const DEFAULT_TIMEOUT_SECONDS: u64 = 3;
 pub const DEFAULT_SERVER_RETRIES: usize = 3;
 const MAX_RESPONSE_SIZE: usize = 1024;
 
+/// Source of the current time for the Runtime, e.g. to expire messages, which may be replaced in
+/// tests to control the passing of time.
+pub(crate) trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] returning the current time of the system.
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
 /// Specifies the details of a Roughtime server.
 ///
 /// Only UDP is supported as a protocol and Ed25519 as a public key type.
@@ -16,11 +16,13 @@
 
 use rustls::{
     internal::pemfile::{certs, rsa_private_keys},
-    NoClientAuth, ServerConfig,
+    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, NoClientAuth,
+    ProtocolVersion, RootCertStore, ServerConfig, SupportedCipherSuite, ALL_CIPHERSUITES,
 };
 use std::{
     fs::File,
-    io::{self, BufReader},
+    io::{self, BufRead, BufReader},
+    str::FromStr,
     sync::Arc,
 };
 
@@ -58,11 +60,129 @@ impl From<Certificate> for tonic::transport::Certificate {
     }
 }
 
+/// Minimum TLS protocol version accepted by servers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsVersion {
+    Tls12,
+    Tls13,
+}
+
+impl Default for TlsVersion {
+    fn default() -> Self {
+        TlsVersion::Tls12
+    }
+}
+
+impl FromStr for TlsVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        match version {
+            "1.2" => Ok(TlsVersion::Tls12),
+            "1.3" => Ok(TlsVersion::Tls13),
+            _ => Err(anyhow::anyhow!(
+                "unsupported TLS version {}, expected 1.2 or 1.3",
+                version
+            )),
+        }
+    }
+}
+
+/// Restrictions on the TLS parameters that servers negotiate with clients. Handshakes with clients
+/// that do not support any of the accepted parameters fail.
+///
+/// The default policy accepts all the TLS versions and cipher suites supported by `rustls`.
+#[derive(Default, Clone, Debug)]
+pub struct TlsPolicy {
+    min_version: TlsVersion,
+    /// Names of the accepted cipher suites, in order of preference, or empty to accept all of
+    /// them.
+    cipher_suites: Vec<String>,
+}
+
+impl TlsPolicy {
+    /// Creates a policy accepting TLS versions from `min_version` onwards, and the cipher suites
+    /// named in `cipher_suites` (e.g. `TLS13_AES_256_GCM_SHA384`) in order of preference, or all
+    /// the cipher suites supported by `rustls` if `cipher_suites` is empty.
+    ///
+    /// Returns an error if a cipher suite is unknown, or if none of the cipher suites can be used
+    /// with the accepted TLS versions.
+    pub fn new(min_version: TlsVersion, cipher_suites: Vec<String>) -> anyhow::Result<Self> {
+        let policy = TlsPolicy {
+            min_version,
+            cipher_suites,
+        };
+        policy.accepted_cipher_suites()?;
+        Ok(policy)
+    }
+
+    fn accepted_versions(&self) -> Vec<ProtocolVersion> {
+        match self.min_version {
+            TlsVersion::Tls12 => vec![ProtocolVersion::TLSv1_3, ProtocolVersion::TLSv1_2],
+            TlsVersion::Tls13 => vec![ProtocolVersion::TLSv1_3],
+        }
+    }
+
+    fn accepted_cipher_suites(&self) -> anyhow::Result<Vec<&'static SupportedCipherSuite>> {
+        let cipher_suites = if self.cipher_suites.is_empty() {
+            ALL_CIPHERSUITES.to_vec()
+        } else {
+            self.cipher_suites
+                .iter()
+                .map(|name| {
+                    ALL_CIPHERSUITES
+                        .iter()
+                        .find(|cipher_suite| format!("{:?}", cipher_suite.suite) == *name)
+                        .copied()
+                        .ok_or_else(|| anyhow::anyhow!("unknown TLS cipher suite {}", name))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        };
+        // Cipher suites are specific to TLS versions, so drop the ones that cannot be used.
+        let versions = self.accepted_versions();
+        let cipher_suites = cipher_suites
+            .into_iter()
+            .filter(|cipher_suite| {
+                versions
+                    .iter()
+                    .any(|version| cipher_suite.usable_for_version(*version))
+            })
+            .collect::<Vec<_>>();
+        anyhow::ensure!(
+            !cipher_suites.is_empty(),
+            "none of the TLS cipher suites can be used with TLS version {:?} or later",
+            self.min_version
+        );
+        Ok(cipher_suites)
+    }
+
+    /// Restricts `config` to the TLS versions and cipher suites accepted by this policy.
+    fn apply(&self, config: &mut ServerConfig) {
+        config.versions = self.accepted_versions();
+        config.ciphersuites = self
+            .accepted_cipher_suites()
+            .expect("TLS policy was validated when it was created");
+    }
+}
+
+/// Verification of the certificates presented by the clients of servers.
+#[derive(Clone)]
+struct ClientVerification {
+    /// Root certificates of the Certificate Authorities that client certificates are verified
+    /// against.
+    roots: RootCertStore,
+    /// Whether clients that do not present a certificate are rejected.
+    required: bool,
+}
+
 /// Represents TLS identity to use for HTTP server pseudo-nodes.
 #[derive(Default, Clone)]
 pub struct TlsConfig {
     certs: Vec<rustls::Certificate>,
     keys: Vec<rustls::PrivateKey>,
+    policy: TlsPolicy,
+    /// Clients are not asked for certificates if `None`.
+    client_verification: Option<ClientVerification>,
 }
 
 impl TlsConfig {
@@ -76,28 +196,101 @@ impl TlsConfig {
             Ok(keys) => keys,
             Err(_) => return None,
         };
-        Some(TlsConfig { certs, keys })
+        Some(TlsConfig {
+            certs,
+            keys,
+            policy: TlsPolicy::default(),
+            client_verification: None,
+        })
+    }
+
+    /// Same as [`TlsConfig::new`], but reads the PEM encoded certificates and private key from
+    /// memory rather than from files.
+    pub fn from_pem(cert_pem: &[u8], key_pem: &[u8]) -> Option<Self> {
+        let certs = match read_certs(&mut io::Cursor::new(cert_pem)) {
+            Ok(certs) => certs,
+            Err(_) => return None,
+        };
+
+        let keys = match read_keys(&mut io::Cursor::new(key_pem)) {
+            Ok(keys) => keys,
+            Err(_) => return None,
+        };
+        Some(TlsConfig {
+            certs,
+            keys,
+            policy: TlsPolicy::default(),
+            client_verification: None,
+        })
+    }
+
+    /// Restricts the TLS parameters negotiated by servers using this configuration.
+    pub fn with_policy(self, policy: TlsPolicy) -> Self {
+        TlsConfig { policy, ..self }
+    }
+
+    /// Makes servers using this configuration verify the certificates presented by clients against
+    /// `client_ca_certificate`, and reject clients that do not present one if `required` (i.e.
+    /// mutual TLS). Clients presenting a certificate that cannot be verified are always rejected.
+    pub fn with_client_verification(
+        self,
+        client_ca_certificate: &Certificate,
+        required: bool,
+    ) -> anyhow::Result<Self> {
+        let mut roots = RootCertStore::empty();
+        let (valid_count, _invalid_count) = roots
+            .add_pem_file(&mut io::Cursor::new(client_ca_certificate.as_ref()))
+            .map_err(|()| anyhow::anyhow!("could not parse client CA certificate"))?;
+        anyhow::ensure!(valid_count > 0, "no valid client CA certificate");
+        Ok(TlsConfig {
+            client_verification: Some(ClientVerification { roots, required }),
+            ..self
+        })
     }
 }
 
 pub(crate) fn to_server_config(tls_config: TlsConfig) -> Arc<ServerConfig> {
-    let mut cfg = ServerConfig::new(NoClientAuth::new());
+    Arc::new(to_rustls_server_config(tls_config))
+}
+
+/// Builds a `rustls` server configuration using the certificates, private key and [`TlsPolicy`]
+/// of `tls_config`.
+pub(crate) fn to_rustls_server_config(tls_config: TlsConfig) -> ServerConfig {
+    let client_cert_verifier = match tls_config.client_verification {
+        None => NoClientAuth::new(),
+        Some(ClientVerification {
+            roots,
+            required: true,
+        }) => AllowAnyAuthenticatedClient::new(roots),
+        Some(ClientVerification {
+            roots,
+            required: false,
+        }) => AllowAnyAnonymousOrAuthenticatedClient::new(roots),
+    };
+    let mut cfg = ServerConfig::new(client_cert_verifier);
     // Select a certificate to use.
     let private_key = tls_config.keys[0].clone();
     if let Err(error) = cfg.set_single_cert(tls_config.certs, private_key) {
         log::warn!("{}", error);
     };
+    tls_config.policy.apply(&mut cfg);
     // Configure ALPN to accept HTTP/2, HTTP/1.1 in that order.
     cfg.set_protocols(&[b"h2".to_vec(), b"http/1.1".to_vec()]);
-    Arc::new(cfg)
+    cfg
 }
 
 fn load_certs(path: &str) -> io::Result<Vec<rustls::Certificate>> {
-    certs(&mut BufReader::new(File::open(path)?))
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid cert"))
+    read_certs(&mut BufReader::new(File::open(path)?))
 }
 
 fn load_keys(path: &str) -> io::Result<Vec<rustls::PrivateKey>> {
-    rsa_private_keys(&mut BufReader::new(File::open(path)?))
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid key"))
+    read_keys(&mut BufReader::new(File::open(path)?))
+}
+
+fn read_certs(reader: &mut dyn BufRead) -> io::Result<Vec<rustls::Certificate>> {
+    certs(reader).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid cert"))
+}
+
+fn read_keys(reader: &mut dyn BufRead) -> io::Result<Vec<rustls::PrivateKey>> {
+    rsa_private_keys(reader).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid key"))
 }
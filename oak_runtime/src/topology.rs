@@ -0,0 +1,106 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Programmatic access to the topology of the Nodes and channels of the Runtime, so that embedders
+//! can inspect it (e.g. to build their own dashboards) without going through the introspection
+//! server.
+//!
+//! Labels are only included when the `oak-unsafe` feature is enabled, since their tags may reveal
+//! sensitive information such as the identities of users.
+
+use crate::{ChannelHalfDirection, Runtime};
+#[cfg(feature = "oak-unsafe")]
+use oak_abi::label::Label;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Description of a Node in a [`TopologySnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeTopology {
+    pub id: u64,
+    pub name: String,
+    pub node_type: &'static str,
+    #[cfg(feature = "oak-unsafe")]
+    pub label: Label,
+}
+
+/// Description of a channel in a [`TopologySnapshot`], identifying the Nodes that hold handles to
+/// each of its halves.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ChannelTopology {
+    pub id: u64,
+    pub name: String,
+    /// IDs of the Nodes holding a read half of the channel, in increasing order.
+    pub reader_node_ids: Vec<u64>,
+    /// IDs of the Nodes holding a write half of the channel, in increasing order.
+    pub writer_node_ids: Vec<u64>,
+}
+
+/// Snapshot of the Nodes of the [`Runtime`] and of the channels they hold handles to, in
+/// increasing order of ID. Channels that are only referred to from queued messages are not
+/// included.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TopologySnapshot {
+    pub nodes: Vec<NodeTopology>,
+    pub channels: Vec<ChannelTopology>,
+}
+
+// Topology related methods for the Runtime.
+impl Runtime {
+    /// Returns a [`TopologySnapshot`] of the current Nodes and channels.
+    ///
+    /// The Node table is only locked once, so that the snapshot is consistent.
+    pub fn topology_snapshot(&self) -> TopologySnapshot {
+        let node_infos = self.node_infos.read().unwrap();
+        let mut nodes = Vec::new();
+        let mut channels = BTreeMap::new();
+        for (node_id, node_info) in node_infos.iter() {
+            nodes.push(NodeTopology {
+                id: node_id.0,
+                name: node_info.name.clone(),
+                node_type: node_info.node_type,
+                #[cfg(feature = "oak-unsafe")]
+                label: node_info.label.clone(),
+            });
+            for half in node_info.abi_handles.values() {
+                let channel =
+                    channels
+                        .entry(half.get_channel_id())
+                        .or_insert_with(|| ChannelTopology {
+                            id: half.get_channel_id(),
+                            name: half.get_channel_name().to_string(),
+                            ..ChannelTopology::default()
+                        });
+                match half.direction {
+                    ChannelHalfDirection::Read => channel.reader_node_ids.push(node_id.0),
+                    ChannelHalfDirection::Write => channel.writer_node_ids.push(node_id.0),
+                }
+            }
+        }
+        nodes.sort_by_key(|node| node.id);
+        let channels = channels
+            .into_values()
+            .map(|mut channel| {
+                for node_ids in &mut [&mut channel.reader_node_ids, &mut channel.writer_node_ids] {
+                    node_ids.sort_unstable();
+                    node_ids.dedup();
+                }
+                channel
+            })
+            .collect();
+        TopologySnapshot { nodes, channels }
+    }
+}
@@ -0,0 +1,194 @@
+//
+// Copyright 2020 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Export of the introspection event timeline in the [Chrome Trace Event format], which can be
+//! loaded in trace viewers such as `chrome://tracing` or Perfetto, or converted to flamegraphs.
+//!
+//! Each Node is shown as a thread track of a "Nodes" process, and each channel as a thread track of
+//! a "Channels" process, with every introspection event shown as an instant event on the track of
+//! the Node (or channel) it relates to. In addition, the time between a Node reading a message and
+//! its next operation is shown as a "process message" span on the track of the Node.
+//!
+//! [Chrome Trace Event format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use crate::{
+    proto::oak::introspection_events::{event::EventDetails, Event, MessageDequeued},
+    Runtime,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Trace process identifier under which Node tracks are grouped.
+const NODES_PID: u64 = 1;
+
+/// Trace process identifier under which channel tracks are grouped.
+const CHANNELS_PID: u64 = 2;
+
+/// A span started by a Node reading a message, which ends at the next event of the Node.
+struct OpenSpan {
+    start: f64,
+    seq: usize,
+    channel_id: u64,
+}
+
+/// Returns the `(pid, tid)` pair identifying the track of the provided event.
+fn track(event_details: &EventDetails) -> (u64, u64) {
+    match event_details {
+        EventDetails::NodeCreated(details) => (NODES_PID, details.node_id),
+        EventDetails::NodeDestroyed(details) => (NODES_PID, details.node_id),
+        EventDetails::NodePrivilegeAssigned(details) => (NODES_PID, details.node_id),
+        EventDetails::NodeStuck(details) => (NODES_PID, details.node_id),
+        EventDetails::LabelDowngraded(details) => (NODES_PID, details.node_id),
+        EventDetails::PermissionDenied(details) => (NODES_PID, details.node_id),
+        EventDetails::HandleCreated(details) => (NODES_PID, details.node_id),
+        EventDetails::HandleDestroyed(details) => (NODES_PID, details.node_id),
+        EventDetails::MessageEnqueued(details) => (NODES_PID, details.node_id),
+        EventDetails::MessageDequeued(details) => (NODES_PID, details.node_id),
+        EventDetails::MessageRejected(details) => (NODES_PID, details.node_id),
+        EventDetails::ChannelCreated(details) => (CHANNELS_PID, details.channel_id),
+        EventDetails::ChannelDestroyed(details) => (CHANNELS_PID, details.channel_id),
+        EventDetails::ChannelMigrated(details) => (CHANNELS_PID, details.old_channel_id),
+        EventDetails::ChannelOrphaned(details) => (CHANNELS_PID, details.channel_id),
+    }
+}
+
+/// Returns the name under which the provided event is shown.
+fn event_name(event_details: &EventDetails) -> &'static str {
+    match event_details {
+        EventDetails::NodeCreated(_) => "NodeCreated",
+        EventDetails::NodeDestroyed(_) => "NodeDestroyed",
+        EventDetails::NodePrivilegeAssigned(_) => "NodePrivilegeAssigned",
+        EventDetails::NodeStuck(_) => "NodeStuck",
+        EventDetails::LabelDowngraded(_) => "LabelDowngraded",
+        EventDetails::PermissionDenied(_) => "PermissionDenied",
+        EventDetails::HandleCreated(_) => "HandleCreated",
+        EventDetails::HandleDestroyed(_) => "HandleDestroyed",
+        EventDetails::MessageEnqueued(_) => "MessageEnqueued",
+        EventDetails::MessageDequeued(_) => "MessageDequeued",
+        EventDetails::MessageRejected(_) => "MessageRejected",
+        EventDetails::ChannelCreated(_) => "ChannelCreated",
+        EventDetails::ChannelDestroyed(_) => "ChannelDestroyed",
+        EventDetails::ChannelMigrated(_) => "ChannelMigrated",
+        EventDetails::ChannelOrphaned(_) => "ChannelOrphaned",
+    }
+}
+
+/// Returns the timestamp of the provided event in microseconds since the UNIX epoch, as expected
+/// by the trace format.
+fn timestamp_micros(event: &Event) -> f64 {
+    event
+        .timestamp
+        .as_ref()
+        .map(|timestamp| timestamp.seconds as f64 * 1e6 + f64::from(timestamp.nanos) / 1e3)
+        .unwrap_or_default()
+}
+
+/// Returns a metadata trace event naming the provided process or thread track.
+fn name_metadata(kind: &str, pid: u64, tid: u64, name: &str) -> Value {
+    json!({
+        "name": kind,
+        "ph": "M",
+        "pid": pid,
+        "tid": tid,
+        "args": { "name": name },
+    })
+}
+
+/// Converts a sequence of introspection events, in chronological order, into a trace in the Chrome
+/// Trace Event format.
+///
+/// Each trace event records the position of the introspection event it was derived from in the
+/// sequence as its `seq` argument. "Process message" spans that are still open at the end of the
+/// sequence are not included.
+pub(crate) fn chrome_trace(events: &[Event]) -> Value {
+    let mut trace_events = vec![
+        name_metadata("process_name", NODES_PID, 0, "Nodes"),
+        name_metadata("process_name", CHANNELS_PID, 0, "Channels"),
+    ];
+    let mut open_spans: HashMap<u64, OpenSpan> = HashMap::new();
+    for (seq, event) in events.iter().enumerate() {
+        let event_details = match &event.event_details {
+            Some(event_details) => event_details,
+            None => continue,
+        };
+        let (pid, tid) = track(event_details);
+        let ts = timestamp_micros(event);
+
+        match event_details {
+            EventDetails::NodeCreated(details) => {
+                trace_events.push(name_metadata("thread_name", pid, tid, &details.name));
+            }
+            EventDetails::ChannelCreated(details) => {
+                trace_events.push(name_metadata("thread_name", pid, tid, &details.name));
+            }
+            _ => {}
+        }
+
+        if pid == NODES_PID {
+            if let Some(span) = open_spans.remove(&tid) {
+                trace_events.push(json!({
+                    "name": "process message",
+                    "ph": "X",
+                    "ts": span.start,
+                    "dur": ts - span.start,
+                    "pid": pid,
+                    "tid": tid,
+                    "args": { "seq": span.seq, "channel_id": span.channel_id },
+                }));
+            }
+            // Expired messages are discarded rather than processed.
+            if let EventDetails::MessageDequeued(details @ MessageDequeued { expired: false, .. }) =
+                event_details
+            {
+                open_spans.insert(
+                    tid,
+                    OpenSpan {
+                        start: ts,
+                        seq,
+                        channel_id: details.channel_id,
+                    },
+                );
+            }
+        }
+
+        trace_events.push(json!({
+            "name": event_name(event_details),
+            "ph": "i",
+            "s": "t",
+            "ts": ts,
+            "pid": pid,
+            "tid": tid,
+            "args": { "seq": seq },
+        }));
+    }
+    json!({ "traceEvents": trace_events, "displayTimeUnit": "ms" })
+}
+
+// Trace export related methods for the Runtime.
+impl Runtime {
+    /// Returns the introspection events recorded so far as a JSON trace in the Chrome Trace Event
+    /// format, see [`chrome_trace`].
+    pub fn chrome_trace(&self) -> String {
+        let events: Vec<Event> = self
+            .introspection_event_queue
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect();
+        chrome_trace(&events).to_string()
+    }
+}
@@ -0,0 +1,113 @@
+//
+// Copyright 2021 The Project Oak Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Detection of Nodes blocked waiting on channels for a long time.
+//!
+//! Whereas heartbeats detect Nodes that are busy without making progress, the watchdog detects
+//! Nodes that have been blocked in [`RuntimeProxy::wait_on_channels`] for longer than a threshold,
+//! which may indicate a deadlock between Nodes waiting on each other. Since waiting for a long
+//! time may also be legitimate, such Nodes are only reported, via logs and the
+//! `runtime_stuck_waiting_nodes` metric, and are never interrupted.
+//!
+//! [`RuntimeProxy::wait_on_channels`]: crate::RuntimeProxy::wait_on_channels
+
+use crate::{NodeId, Runtime};
+use log::warn;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// Configuration options for detecting Nodes blocked waiting on channels.
+#[derive(Clone, Debug)]
+pub struct WatchdogConfiguration {
+    /// Time a Node may spend waiting on channels before it is reported as potentially stuck.
+    pub threshold: Duration,
+}
+
+/// Finds the Nodes that have been waiting on channels for longer than the configured threshold.
+pub(crate) struct Watchdog {
+    config: WatchdogConfiguration,
+    /// Time since which each Node already reported has been waiting, so that each wait is only
+    /// logged once.
+    reported: HashMap<NodeId, Instant>,
+}
+
+impl Watchdog {
+    pub(crate) fn new(config: WatchdogConfiguration) -> Self {
+        Watchdog {
+            config,
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Finds the Nodes in the `runtime` that have been waiting on channels for longer than the
+    /// threshold, logs those not yet reported for their current wait, and sets the
+    /// `runtime_stuck_waiting_nodes` metric to their number.
+    ///
+    /// Returns the [`NodeId`]s of all these Nodes, in increasing order.
+    pub(crate) fn check(&mut self, runtime: &Runtime) -> Vec<NodeId> {
+        let now = Instant::now();
+        let mut reported = HashMap::new();
+        {
+            let node_infos = runtime.node_infos.read().unwrap();
+            for (node_id, node_info) in node_infos.iter() {
+                let waiting_since = match node_info.liveness.waiting_since() {
+                    Some(waiting_since) => waiting_since,
+                    None => continue,
+                };
+                let elapsed = now.duration_since(waiting_since);
+                if elapsed <= self.config.threshold {
+                    continue;
+                }
+                if self.reported.get(node_id) != Some(&waiting_since) {
+                    warn!(
+                        "{:?}: waiting on channels for {:?}, node potentially stuck",
+                        node_info.get_debug_id(*node_id),
+                        elapsed
+                    );
+                }
+                reported.insert(*node_id, waiting_since);
+            }
+        }
+        self.reported = reported;
+        runtime
+            .metrics_data
+            .runtime_metrics
+            .runtime_stuck_waiting_nodes
+            .set(self.reported.len() as i64);
+
+        let mut stuck_nodes = self.reported.keys().copied().collect::<Vec<_>>();
+        stuck_nodes.sort();
+        stuck_nodes
+    }
+}
+
+/// Periodically checks for Nodes in the `runtime` blocked waiting on channels, until notified to
+/// terminate.
+pub(crate) fn monitor_waiting_nodes(
+    config: WatchdogConfiguration,
+    runtime: Arc<Runtime>,
+    termination_notification_receiver: oneshot::Receiver<()>,
+) {
+    let period = config.threshold;
+    let mut watchdog = Watchdog::new(config);
+    crate::aux_server::run_periodically(period, termination_notification_receiver, || {
+        watchdog.check(&runtime);
+    });
+}
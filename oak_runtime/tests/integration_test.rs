@@ -14,10 +14,14 @@
 // limitations under the License.
 //
 
-use oak_runtime::time::{RoughtimeClient, DEFAULT_MAX_RADIUS_MICROSECONDS};
-use std::time::SystemTime;
+use oak_runtime::{
+    time::{RoughtimeClient, DEFAULT_MAX_RADIUS_MICROSECONDS},
+    StartupOrder,
+};
+use std::{net::SocketAddr, time::SystemTime};
 
 const METRICS_PORT: u16 = 9876;
+const AUX_SERVERS_FIRST_METRICS_PORT: u16 = 9877;
 
 fn init_logging() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -32,11 +36,14 @@ mod common {
         WebAssemblyConfiguration,
     };
     use oak_io::OakError;
-    use oak_runtime::{config, Runtime, SecureServerConfiguration, SignatureTable};
-    use std::sync::Arc;
+    use oak_runtime::{config, Runtime, SecureServerConfiguration, SignatureTable, StartupOrder};
+    use std::{net::SocketAddr, sync::Arc};
     use wat::parse_str;
 
-    pub fn start_runtime() -> Result<Arc<Runtime>, OakError> {
+    pub fn start_runtime(
+        metrics_address: SocketAddr,
+        startup_order: StartupOrder,
+    ) -> Result<Arc<Runtime>, OakError> {
         // Loop 100 000 times in the main function to make sure the Wasm node is alive for a while
         // before exiting. This is needed to avoid a race condition causing
         // `test_metrics_gives_the_correct_number_of_nodes` to sometimes fail. If the Wasm node has
@@ -87,14 +94,16 @@ mod common {
 
         info!("Starting the runtime with one node.");
         config::configure_and_run(oak_runtime::RuntimeConfiguration {
-            metrics_port: Some(crate::METRICS_PORT),
-            introspect_port: None,
+            metrics_address: Some(metrics_address),
+            introspect_address: None,
             kms_credentials: None,
             secure_server_configuration: SecureServerConfiguration::default(),
             app_config: application_configuration,
             permissions_config: permissions,
             sign_table: SignatureTable::default(),
             config_map: ConfigMap::default(),
+            startup_order,
+            ..Default::default()
         })
     }
 
@@ -126,7 +135,11 @@ fn test_metrics_gives_the_correct_number_of_nodes() {
     init_logging();
 
     // Start the Runtime, including a metrics server.
-    let runtime = common::start_runtime().expect("Starting the Runtime failed!");
+    let runtime = common::start_runtime(
+        oak_runtime::aux_server_address(METRICS_PORT),
+        StartupOrder::default(),
+    )
+    .expect("Starting the Runtime failed!");
 
     let rt = tokio::runtime::Runtime::new().expect("Couldn't create Tokio runtime");
     let res = rt
@@ -144,6 +157,33 @@ fn test_metrics_gives_the_correct_number_of_nodes() {
     runtime.stop();
 }
 
+#[test]
+fn test_aux_servers_first_binds_metrics_server_before_starting_nodes() {
+    init_logging();
+
+    // Bind the metrics server to the loopback interface only.
+    let metrics_address = SocketAddr::from(([127, 0, 0, 1], AUX_SERVERS_FIRST_METRICS_PORT));
+    let runtime = common::start_runtime(metrics_address, StartupOrder::AuxServersFirst)
+        .expect("Starting the Runtime failed!");
+
+    // The metrics server must already accept connections, without any retries, by the time the
+    // initial Node has been started.
+    std::net::TcpStream::connect(metrics_address).expect("Metrics server is not bound");
+
+    runtime.stop();
+}
+
+#[test]
+fn test_aux_servers_first_fails_if_metrics_server_cannot_bind() {
+    init_logging();
+
+    // Occupy an address, so that the metrics server cannot bind to it.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let metrics_address = listener.local_addr().unwrap();
+
+    assert!(common::start_runtime(metrics_address, StartupOrder::AuxServersFirst).is_err());
+}
+
 #[test]
 #[ignore]
 /// Gets Roughtime from the live default servers with the default settings.
@@ -40,7 +40,9 @@ fn main() {
         &[
             "oak_services/proto/crypto.proto",
             "oak_services/proto/log.proto",
+            "oak_services/proto/random.proto",
             "oak_services/proto/roughtime_service.proto",
+            "oak_services/proto/timer.proto",
         ],
         "..",
         oak_utils::ProtoOptions {
@@ -35,9 +35,17 @@ pub mod oak {
         include!(concat!(env!("OUT_DIR"), "/oak.log.rs"));
     }
 
+    pub mod random {
+        include!(concat!(env!("OUT_DIR"), "/oak.random.rs"));
+    }
+
     pub mod roughtime {
         include!(concat!(env!("OUT_DIR"), "/oak.roughtime.rs"));
     }
+
+    pub mod timer {
+        include!(concat!(env!("OUT_DIR"), "/oak.timer.rs"));
+    }
 }
 
 // These do not contain handles, so a blanket impl is sufficient.
@@ -45,5 +53,6 @@ handle_visit_blanket_impl!(
     oak::encap::GrpcResponse,
     oak::encap::GrpcRequest,
     oak::encap::HttpResponse,
-    oak::encap::HttpRequest
+    oak::encap::HttpRequest,
+    oak::encap::WebSocketMessage
 );
@@ -15,7 +15,7 @@
 //
 
 use anyhow::Context;
-use oak_abi::proto::oak::application::ModuleSignature;
+use oak_abi::proto::oak::application::{self, ModuleSignature};
 use once_cell::sync::Lazy;
 use pem::Pem;
 use ring::{
@@ -82,6 +82,71 @@ static ED_25519_ALG: Lazy<ASN1Block> = Lazy::new(|| {
     )
 });
 
+/// OIDs for elliptic curve public keys, and for the P-256 curve.
+///
+/// See https://tools.ietf.org/html/rfc5480#section-2.1.1
+static OID_EC_PUBLIC_KEY: Lazy<OID> = Lazy::new(|| simple_asn1::oid!(1, 2, 840, 10045, 2, 1));
+static OID_P_256: Lazy<OID> = Lazy::new(|| simple_asn1::oid!(1, 2, 840, 10045, 3, 1, 7));
+static ECDSA_P_256_ALG: Lazy<ASN1Block> = Lazy::new(|| {
+    ASN1Block::Sequence(
+        0,
+        vec![
+            ASN1Block::ObjectIdentifier(0, OID_EC_PUBLIC_KEY.clone()),
+            ASN1Block::ObjectIdentifier(0, OID_P_256.clone()),
+        ],
+    )
+});
+
+/// Algorithm used to sign the hash in a [`SignatureBundle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    Ed25519,
+    /// ECDSA over the P-256 curve, with the SHA-256 hash of the signed data, and the signature
+    /// encoded in ASN.1 DER.
+    EcdsaP256Sha256,
+}
+
+impl Default for SignatureAlgorithm {
+    fn default() -> Self {
+        SignatureAlgorithm::Ed25519
+    }
+}
+
+impl SignatureAlgorithm {
+    /// Returns the algorithm identifier of the public keys for this signature algorithm.
+    fn public_key_algorithm(self) -> &'static ASN1Block {
+        match self {
+            SignatureAlgorithm::Ed25519 => &ED_25519_ALG,
+            SignatureAlgorithm::EcdsaP256Sha256 => &ECDSA_P_256_ALG,
+        }
+    }
+
+    fn verification_algorithm(self) -> &'static dyn signature::VerificationAlgorithm {
+        match self {
+            SignatureAlgorithm::Ed25519 => &signature::ED25519,
+            SignatureAlgorithm::EcdsaP256Sha256 => &signature::ECDSA_P256_SHA256_ASN1,
+        }
+    }
+}
+
+impl From<application::SignatureAlgorithm> for SignatureAlgorithm {
+    fn from(algorithm: application::SignatureAlgorithm) -> Self {
+        match algorithm {
+            application::SignatureAlgorithm::Ed25519 => SignatureAlgorithm::Ed25519,
+            application::SignatureAlgorithm::EcdsaP256Sha256 => SignatureAlgorithm::EcdsaP256Sha256,
+        }
+    }
+}
+
+impl From<SignatureAlgorithm> for application::SignatureAlgorithm {
+    fn from(algorithm: SignatureAlgorithm) -> Self {
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => application::SignatureAlgorithm::Ed25519,
+            SignatureAlgorithm::EcdsaP256Sha256 => application::SignatureAlgorithm::EcdsaP256Sha256,
+        }
+    }
+}
+
 const BITS_PER_BYTE: usize = 8;
 
 impl KeyPair {
@@ -141,10 +206,47 @@ pub fn encode_public_key_der(key_bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
     simple_asn1::to_der(&key_asn_1).context("Could not encode key to DER")
 }
 
-/// Decodes the public key as a binary DER-encoded `SubjectPublicKeyInfo` (see
+/// Decodes the Ed25519 public key as a binary DER-encoded `SubjectPublicKeyInfo` (see
 /// https://tools.ietf.org/html/rfc5280#section-4.1).
 pub fn decode_public_key_der(key_der: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let asn_blocks = simple_asn1::from_der(key_der).unwrap();
+    decode_public_key_der_for(key_der, SignatureAlgorithm::Ed25519)
+}
+
+/// Decodes the public key for `algorithm` as a binary DER-encoded `SubjectPublicKeyInfo`, and fails
+/// if the key is for another algorithm.
+pub fn decode_public_key_der_for(
+    key_der: &[u8],
+    algorithm: SignatureAlgorithm,
+) -> anyhow::Result<Vec<u8>> {
+    let (key_algorithm, key_bytes) = parse_public_key_der(key_der)?;
+    if key_algorithm != *algorithm.public_key_algorithm() {
+        anyhow::bail!(
+            "Invalid algorithm: {:?}, expected public key for {:?}",
+            key_algorithm,
+            algorithm
+        );
+    }
+    Ok(key_bytes)
+}
+
+/// Returns the signature algorithm of the public key as a binary DER-encoded
+/// `SubjectPublicKeyInfo`.
+fn public_key_der_algorithm(key_der: &[u8]) -> anyhow::Result<SignatureAlgorithm> {
+    let (key_algorithm, _) = parse_public_key_der(key_der)?;
+    [
+        SignatureAlgorithm::Ed25519,
+        SignatureAlgorithm::EcdsaP256Sha256,
+    ]
+    .iter()
+    .copied()
+    .find(|algorithm| key_algorithm == *algorithm.public_key_algorithm())
+    .with_context(|| format!("Invalid algorithm: {:?}", key_algorithm))
+}
+
+/// Parses the public key as a binary DER-encoded `SubjectPublicKeyInfo` into its algorithm
+/// identifier and its key bytes.
+fn parse_public_key_der(key_der: &[u8]) -> anyhow::Result<(ASN1Block, Vec<u8>)> {
+    let asn_blocks = simple_asn1::from_der(key_der).context("Could not parse public key DER")?;
     if asn_blocks.len() != 1 {
         anyhow::bail!(
             "Invalid number of ASN.1 blocks: {}, expected: 1",
@@ -155,14 +257,10 @@ pub fn decode_public_key_der(key_der: &[u8]) -> anyhow::Result<Vec<u8>> {
         if v.len() != 2 {
             anyhow::bail!("Invalid length of ASN.1 sequence: {}, expected: 2", v.len());
         }
-        if v[0] == ED_25519_ALG.clone() {
-            if let ASN1Block::BitString(_, _, key_bytes) = &v[1] {
-                Ok(key_bytes.clone())
-            } else {
-                anyhow::bail!("Invalid ASN.1 block: {:?}, expected: BIT STRING", v[1]);
-            }
+        if let ASN1Block::BitString(_, _, key_bytes) = &v[1] {
+            Ok((v[0].clone(), key_bytes.clone()))
         } else {
-            anyhow::bail!("Invalid algorithm: {:?}", v[0]);
+            anyhow::bail!("Invalid ASN.1 block: {:?}, expected: BIT STRING", v[1]);
         }
     } else {
         anyhow::bail!(
@@ -179,6 +277,8 @@ pub struct SignatureBundle {
     pub public_key_der: Vec<u8>,
     pub signed_hash: Vec<u8>,
     pub hash: Vec<u8>,
+    /// Algorithm of the signature, which must match the algorithm of the public key.
+    pub algorithm: SignatureAlgorithm,
 }
 
 impl SignatureBundle {
@@ -189,13 +289,19 @@ impl SignatureBundle {
             public_key_der: key_pair.public_key_der()?,
             signed_hash: key_pair.sign(&hash),
             hash,
+            algorithm: SignatureAlgorithm::Ed25519,
         })
     }
 
-    /// Verifies the signature validity.
+    /// Verifies the signature validity, using the signature algorithm of the bundle.
+    ///
+    /// Fails if the public key is not a key for that algorithm.
     pub fn verify(&self) -> anyhow::Result<()> {
-        let public_key_bytes = decode_public_key_der(&self.public_key_der)?;
-        let public_key = signature::UnparsedPublicKey::new(&signature::ED25519, &public_key_bytes);
+        let public_key_bytes = decode_public_key_der_for(&self.public_key_der, self.algorithm)?;
+        let public_key = signature::UnparsedPublicKey::new(
+            self.algorithm.verification_algorithm(),
+            &public_key_bytes,
+        );
         public_key
             .verify(&self.hash, &self.signed_hash)
             .ok()
@@ -218,6 +324,8 @@ impl SignatureBundle {
 
     /// Parses public key, signature and SHA-256 hash encoded using PEM format.
     /// https://tools.ietf.org/html/rfc1421
+    ///
+    /// The signature algorithm is the algorithm of the public key.
     pub fn from_pem(file: &[u8]) -> anyhow::Result<SignatureBundle> {
         let file_content: HashMap<String, Vec<u8>> = pem::parse_many(file)?
             .iter()
@@ -236,6 +344,7 @@ impl SignatureBundle {
             public_key_der: public_key.to_vec(),
             signed_hash: signed_hash.to_vec(),
             hash: hash.to_vec(),
+            algorithm: public_key_der_algorithm(public_key)?,
         })
     }
 
@@ -252,6 +361,7 @@ impl SignatureBundle {
 
 impl From<ModuleSignature> for SignatureBundle {
     fn from(module_signature: ModuleSignature) -> Self {
+        let algorithm = module_signature.algorithm().into();
         let public_key_der = module_signature.public_key;
         let signed_hash = module_signature.signed_hash;
         let hash = module_signature.module_hash;
@@ -259,6 +369,7 @@ impl From<ModuleSignature> for SignatureBundle {
             public_key_der,
             signed_hash,
             hash,
+            algorithm,
         }
     }
 }
@@ -268,10 +379,12 @@ impl From<SignatureBundle> for ModuleSignature {
         let public_key = signature_bundle.public_key_der;
         let signed_hash = signature_bundle.signed_hash;
         let module_hash = signature_bundle.hash;
+        let algorithm = application::SignatureAlgorithm::from(signature_bundle.algorithm) as i32;
         Self {
             public_key,
             signed_hash,
             module_hash,
+            algorithm,
         }
     }
 }
@@ -15,8 +15,10 @@
 //
 
 use assert_matches::assert_matches;
+use oak_abi::proto::oak::application::ModuleSignature;
 use oak_sign::{
-    decode_public_key_der, encode_public_key_der, get_sha256_hex, KeyPair, SignatureBundle,
+    decode_public_key_der, decode_public_key_der_for, encode_public_key_der, get_sha256_hex,
+    KeyPair, SignatureAlgorithm, SignatureBundle, HASH_TAG, PUBLIC_KEY_TAG, SIGNATURE_TAG,
 };
 
 const DATA: &str = "Test data";
@@ -24,6 +26,26 @@ const TEST_SHA256: &str = "e27c8214be8b7cf5bccc7c08247e3cb0c1514a48ee1f63197fe4e
 const INCORRECT_SIGNATURE: &str =
     "JmtVw8SPMnU/f4fLSBvd+w96C2NAMFn7+rqGVHzWOItsdZ+T+TqBwnEcCNJrJ1wrQWXPwFk+8sRiRFFCOCJVAA==";
 
+// Test vectors generated with OpenSSL, signing the SHA-256 hash of `DATA`.
+const ED25519_PUBLIC_KEY_DER_HEX: &str =
+    "302a300506032b6570032100689b942dbd2ef3184e62475c50a7658a9c6dd872250a0c11a30b9a90b0116905";
+const ED25519_SIGNATURE_HEX: &str = "eac4d4f267b4cb96f494de376a187c78349d2813ca4ea3a59acbe15401b5b7c60014fcfb7b12dc55f3ea61a87a9610588ad5edd25e74b3bca2bfc6861e5cdd0c";
+const ECDSA_P256_PUBLIC_KEY_DER_HEX: &str = "3059301306072a8648ce3d020106082a8648ce3d03010703420004ab5c65422adcfa8552698892e467eb68897c466a7f7645183b3e4f6124e0d190cbb4b7d3606749691562eb55e8b65bf8eab9a3cc38d92ca5e997c53c745c3c99";
+const ECDSA_P256_SIGNATURE_HEX: &str = "3045022100e20415ffa27cfcb7dba400dc76ecd58f042f94603ab69b997be42d82660cad7d02201c552abf6c4280047edca492cf4040647f7c3d28a765917fda31f796847cacac";
+
+fn test_vector(
+    public_key_der_hex: &str,
+    signature_hex: &str,
+    algorithm: SignatureAlgorithm,
+) -> SignatureBundle {
+    SignatureBundle {
+        public_key_der: hex::decode(public_key_der_hex).unwrap(),
+        signed_hash: hex::decode(signature_hex).unwrap(),
+        hash: hex::decode(TEST_SHA256).unwrap(),
+        algorithm,
+    }
+}
+
 #[test]
 fn key_pair_serialization_deserialization_test() {
     let generate_result = KeyPair::generate();
@@ -52,11 +74,102 @@ fn signature_test() {
         public_key_der: signature.public_key_der.clone(),
         signed_hash: INCORRECT_SIGNATURE.as_bytes().to_vec(),
         hash: signature.signed_hash,
+        algorithm: SignatureAlgorithm::Ed25519,
     };
     let incorrect_verify_result = incorrect_signature.verify();
     assert_matches!(incorrect_verify_result, Err(_));
 }
 
+#[test]
+fn signature_test_vectors() {
+    let ed25519 = test_vector(
+        ED25519_PUBLIC_KEY_DER_HEX,
+        ED25519_SIGNATURE_HEX,
+        SignatureAlgorithm::Ed25519,
+    );
+    assert_matches!(ed25519.verify(), Ok(_));
+    let ecdsa_p256 = test_vector(
+        ECDSA_P256_PUBLIC_KEY_DER_HEX,
+        ECDSA_P256_SIGNATURE_HEX,
+        SignatureAlgorithm::EcdsaP256Sha256,
+    );
+    assert_matches!(ecdsa_p256.verify(), Ok(_));
+
+    // The algorithm is preserved when converting to and from a `ModuleSignature`.
+    let module_signature: ModuleSignature = ecdsa_p256.into();
+    let bundle: SignatureBundle = module_signature.into();
+    assert_eq!(SignatureAlgorithm::EcdsaP256Sha256, bundle.algorithm);
+    assert_matches!(bundle.verify(), Ok(_));
+}
+
+#[test]
+fn signature_with_mismatched_algorithm_fails() {
+    let ed25519_key_as_ecdsa = test_vector(
+        ED25519_PUBLIC_KEY_DER_HEX,
+        ED25519_SIGNATURE_HEX,
+        SignatureAlgorithm::EcdsaP256Sha256,
+    );
+    assert_matches!(ed25519_key_as_ecdsa.verify(), Err(_));
+    let ecdsa_key_as_ed25519 = test_vector(
+        ECDSA_P256_PUBLIC_KEY_DER_HEX,
+        ECDSA_P256_SIGNATURE_HEX,
+        SignatureAlgorithm::Ed25519,
+    );
+    assert_matches!(ecdsa_key_as_ed25519.verify(), Err(_));
+}
+
+#[test]
+fn public_key_with_unknown_algorithm_fails() {
+    // Same as the Ed25519 public key, but with the OID of Ed448 (1.3.101.113), which is not
+    // supported.
+    let ed448_public_key_der = hex::decode(
+        "302a300506032b6571032100689b942dbd2ef3184e62475c50a7658a9c6dd872250a0c11a30b9a90b0116905",
+    )
+    .unwrap();
+    assert_matches!(
+        decode_public_key_der_for(&ed448_public_key_der, SignatureAlgorithm::Ed25519),
+        Err(_)
+    );
+    assert_matches!(
+        decode_public_key_der_for(&ed448_public_key_der, SignatureAlgorithm::EcdsaP256Sha256),
+        Err(_)
+    );
+    let signature_file = pem::encode_many(&[
+        pem::Pem {
+            tag: PUBLIC_KEY_TAG.to_string(),
+            contents: ed448_public_key_der,
+        },
+        pem::Pem {
+            tag: SIGNATURE_TAG.to_string(),
+            contents: hex::decode(ED25519_SIGNATURE_HEX).unwrap(),
+        },
+        pem::Pem {
+            tag: HASH_TAG.to_string(),
+            contents: hex::decode(TEST_SHA256).unwrap(),
+        },
+    ]);
+    assert_matches!(SignatureBundle::from_pem(signature_file.as_bytes()), Err(_));
+}
+
+#[test]
+fn signature_bundle_with_malformed_public_key_fails() {
+    // The public key is a DER-encoded SEQUENCE that is missing its contents.
+    const MALFORMED_SIGNATURE_FILE: &str = "-----BEGIN PUBLIC KEY-----
+MAM=
+-----END PUBLIC KEY-----
+-----BEGIN SIGNATURE-----
+AAAA
+-----END SIGNATURE-----
+-----BEGIN HASH-----
+AAAA
+-----END HASH-----
+";
+    assert_matches!(
+        SignatureBundle::from_pem(MALFORMED_SIGNATURE_FILE.as_bytes()),
+        Err(_)
+    );
+}
+
 #[test]
 fn public_key_der_encode_decode() {
     // Ground truth: https://gchq.github.io/CyberChef/#recipe=Parse_ASN.1_hex_string(0,32)&input=MzAyYTMwMDUwNjAzMmI2NTcwMDMyMTAwN2Y4ZDUyMGE1MzZkNDc4OGI4ZWFmZDkzYmExZDVmNDBiNmVkZmQ5YTkxYWY1OTQ0MzVhOGMyNWJkZGEzYzhmZQ
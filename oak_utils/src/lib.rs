@@ -351,10 +351,30 @@ where
             ".oak.identity",
             "#[derive(serde::Deserialize, serde::Serialize)]\n#[serde(rename_all = \"camelCase\")]",
         );
+    // We require label-related types to be serializable and deserializable to and from JSON, and
+    // label-related messages to be comparable and hashable so that they can be used in hash-based
+    // collections (enums already are). Only the most specific matching attribute applies to each
+    // type, so messages must be listed explicitly.
+    prost_config.type_attribute(
+        ".oak.label",
+        "#[derive(serde::Deserialize, serde::Serialize)]\n#[serde(rename_all = \"camelCase\")]",
+    );
+    for message in &[
+        ".oak.label.Label",
+        ".oak.label.Tag",
+        ".oak.label.Tag.tag",
+        ".oak.label.WebAssemblyModuleTag",
+        ".oak.label.WebAssemblyModuleSignatureTag",
+        ".oak.label.TlsEndpointTag",
+        ".oak.label.PublicKeyIdentityTag",
+        ".oak.label.TlsClientCertificateTag",
+        ".oak.label.Top",
+    ] {
+        prost_config.type_attribute(message, "#[derive(Eq, Hash, serde::Deserialize, serde::Serialize)]\n#[serde(rename_all = \"camelCase\")]");
+    }
+    // Labels in JSON format may omit the namespace of top tags, which then covers all tags.
+    prost_config.field_attribute(".oak.label.Top.namespace", "#[serde(default)]");
     prost_config
-        // We require label-related types to be comparable and hashable so that they can be used in
-        // hash-based collections.
-        .type_attribute(".oak.label", "#[derive(Eq, Hash, serde::Deserialize, serde::Serialize)]\n#[serde(rename_all = \"camelCase\")]")
         .compile_protos(inputs, &[repo_root])
         .expect("could not run prost-build");
 }
@@ -245,5 +245,9 @@ pub fn error_from_nonok_status(status: OakStatus) -> io::Error {
         OakStatus::ErrPermissionDenied => {
             io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied")
         }
+        OakStatus::ErrChannelFull => io::Error::new(io::ErrorKind::WouldBlock, "Channel full"),
+        OakStatus::ErrResourceExhausted => {
+            io::Error::new(io::ErrorKind::Other, "Resource exhausted")
+        }
     }
 }
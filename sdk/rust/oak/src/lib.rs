@@ -480,6 +480,15 @@ pub fn node_privilege_read() -> Result<Label, OakStatus> {
     })
 }
 
+/// Get the number of Nodes currently live in the Runtime, only counting Nodes of type `node_type`
+/// (e.g. `"wasm"`) if provided.
+pub fn node_count(node_type: Option<&str>) -> Result<u64, OakStatus> {
+    let type_bytes = node_type.unwrap_or_default().as_bytes();
+    let mut count: u64 = 0;
+    let status = unsafe { oak_abi::node_count(type_bytes.as_ptr(), type_bytes.len(), &mut count) };
+    result_from_status(status as i32, count)
+}
+
 /// Helper function to read a label using `label_fetcher`.
 ///
 /// If the buffer is too small, it will resize the buffer to the `actual_size` and try again.
@@ -19,7 +19,8 @@
 use oak_abi::proto::oak::application::{
     node_configuration::ConfigType, GrpcClientConfiguration, GrpcServerConfiguration,
     HttpClientConfiguration, HttpServerConfiguration, LogConfiguration, NodeConfiguration,
-    WebAssemblyConfiguration,
+    RandomConfiguration, TimerConfiguration, WebAssemblyConfiguration,
+    WebSocketServerConfiguration,
 };
 
 pub fn grpc_client(address: &str) -> NodeConfiguration {
@@ -54,6 +55,30 @@ pub fn http_server(address: &str) -> NodeConfiguration {
     }
 }
 
+pub fn web_socket_server(address: &str) -> NodeConfiguration {
+    NodeConfiguration {
+        config_type: Some(ConfigType::WebSocketServerConfig(
+            WebSocketServerConfiguration {
+                address: address.to_string(),
+            },
+        )),
+    }
+}
+
+pub fn timer(interval_millis: u64) -> NodeConfiguration {
+    NodeConfiguration {
+        config_type: Some(ConfigType::TimerConfig(TimerConfiguration {
+            interval_millis,
+        })),
+    }
+}
+
+pub fn random() -> NodeConfiguration {
+    NodeConfiguration {
+        config_type: Some(ConfigType::RandomConfig(RandomConfiguration {})),
+    }
+}
+
 pub fn wasm(module_name: &str, entrypoint_name: &str) -> NodeConfiguration {
     NodeConfiguration {
         config_type: Some(ConfigType::WasmConfig(WebAssemblyConfiguration {
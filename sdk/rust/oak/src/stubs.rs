@@ -102,6 +102,10 @@ pub extern "C" fn node_privilege_read() {
     panic!("stub function invoked!");
 }
 #[no_mangle]
+pub extern "C" fn node_count() {
+    panic!("stub function invoked!");
+}
+#[no_mangle]
 pub extern "C" fn random_get() {
     panic!("stub function invoked!");
 }
@@ -163,8 +163,8 @@ pub fn runtime_config_wasm(
     sign_table: oak_runtime::SignatureTable,
 ) -> oak_runtime::RuntimeConfiguration {
     oak_runtime::RuntimeConfiguration {
-        metrics_port: Some(9090),
-        introspect_port: Some(1909),
+        metrics_address: Some(oak_runtime::aux_server_address(9090)),
+        introspect_address: Some(oak_runtime::aux_server_address(1909)),
         kms_credentials: None,
         secure_server_configuration: oak_runtime::SecureServerConfiguration {
             grpc_config: Some(oak_runtime::GrpcConfiguration {
@@ -172,11 +172,16 @@ pub fn runtime_config_wasm(
                     include_str!("../certs/local.pem"),
                     include_str!("../certs/local.key"),
                 )),
+                grpc_server_tls_config: None,
+                grpc_server_client_ca_certificate: None,
+                grpc_server_require_client_certificate: false,
                 grpc_client_root_tls_certificate: oak_runtime::tls::Certificate::parse(
                     include_bytes!("../certs/ca.pem").to_vec(),
                 )
                 .ok(),
                 oidc_client_info: None,
+                oidc_token_validation_config: Default::default(),
+                grpc_reflection_file_descriptor_sets: None,
             }),
             http_config: create_http_config(),
         },
@@ -193,6 +198,7 @@ pub fn runtime_config_wasm(
         permissions_config: permissions,
         config_map,
         sign_table,
+        ..Default::default()
     }
 }
 